@@ -24,7 +24,7 @@ impl Command {
     }
 }
 
-const COMMANDS: [Command; 47] = [
+const COMMANDS: [Command; 53] = [
     Command {
         cmd: "oneshot",
         usage_params: "{depends}",
@@ -50,11 +50,21 @@ const COMMANDS: [Command; 47] = [
         usage_params: "<kind> <pubkey> <d> [<relayurl>, ...]",
         desc: "encode an event address (parameterized replaceable event link).",
     },
+    Command {
+        cmd: "cache_usage",
+        usage_params: "",
+        desc: "print how much disk space downloaded files use, by kind.",
+    },
     Command {
         cmd: "clear_timeouts",
         usage_params: "",
         desc: "clear relay avoidance timeouts.",
     },
+    Command {
+        cmd: "daemon",
+        usage_params: "",
+        desc: "run gossip headless (without the GUI), staying online and syncing until SIGTERM or SIGINT.",
+    },
     Command {
         cmd: "decrypt",
         usage_params: "<pubkey> <ciphertext>",
@@ -210,6 +220,16 @@ const COMMANDS: [Command; 47] = [
         usage_params: "",
         desc: "Rebuild all event-related indices",
     },
+    Command {
+        cmd: "rebuild_search_index",
+        usage_params: "",
+        desc: "Rebuild the full-text search index used by local search",
+    },
+    Command {
+        cmd: "relay_stats",
+        usage_params: "[<url>] [<days>]",
+        desc: "Show connection and bandwidth statistics per relay over the last <days> days (default 7), most traffic first, or per day for the given relay",
+    },
     Command {
         cmd: "rename_person_list",
         usage_params: "<number> <newname>",
@@ -240,6 +260,11 @@ const COMMANDS: [Command; 47] = [
         usage_params: "<dark | light>",
         desc: "Start gossip with the selected theme",
     },
+    Command {
+        cmd: "trim_cache",
+        usage_params: "[<megabytes>]",
+        desc: "delete the least recently used downloaded files until they use no more than <megabytes> (default: the cache_max_size_mb setting).",
+    },
     Command {
         cmd: "ungiftwrap",
         usage_params: "<idhex>",
@@ -255,6 +280,11 @@ const COMMANDS: [Command; 47] = [
         usage_params: "<event_json>",
         desc: "Verify if the passed in event JSON's signature is valid",
     },
+    Command {
+        cmd: "verify_zaps",
+        usage_params: "",
+        desc: "re-validate all stored zap receipts per NIP-57 appendix F, flagging the invalid ones so they are left out of zap totals.",
+    },
     Command {
         cmd: "wgpu_renderer",
         usage_params: "true | false",
@@ -283,7 +313,9 @@ pub fn handle_command(mut args: env::Args) -> Result<bool, Error> {
         "backdate_eose" => backdate_eose()?,
         "bech32_decode" => bech32_decode(command, args)?,
         "bech32_encode_naddr" => bech32_encode_naddr(command, args)?,
+        "cache_usage" => cache_usage()?,
         "clear_timeouts" => clear_timeouts()?,
+        "daemon" => crate::daemon::run()?,
         "decrypt" => decrypt(command, args)?,
         "delete_by_kind" => delete_by_kind(command, args)?,
         "delete_spam_by_content" => delete_spam_by_content(command, args)?,
@@ -321,6 +353,8 @@ pub fn handle_command(mut args: env::Args) -> Result<bool, Error> {
         "reaction_stats" => reaction_stats(command, args)?,
         "rebuild_fof" => rebuild_fof()?,
         "rebuild_indices" => rebuild_indices()?,
+        "rebuild_search_index" => rebuild_search_index()?,
+        "relay_stats" => relay_stats(command, args)?,
         "rename_person_list" => rename_person_list(command, args)?,
         "reprocess_recent" => reprocess_recent(command)?,
         "reprocess_relay_lists" => reprocess_relay_lists()?,
@@ -330,9 +364,11 @@ pub fn handle_command(mut args: env::Args) -> Result<bool, Error> {
             set_theme(command, args)?;
             return Ok(false);
         }
+        "trim_cache" => trim_cache(command, args)?,
         "ungiftwrap" => ungiftwrap(command, args)?,
         "verify" => verify(command, args)?,
         "verify_json" => verify_json(command, args)?,
+        "verify_zaps" => verify_zaps()?,
         "wgpu_renderer" => wgpu_renderer(command, args)?,
         other => println!("Unknown command {}", other),
     }
@@ -527,6 +563,25 @@ pub fn bech32_encode_naddr(cmd: Command, mut args: env::Args) -> Result<(), Erro
     Ok(())
 }
 
+pub fn cache_usage() -> Result<(), Error> {
+    let job = tokio::task::spawn(async move { GLOBALS.fetcher.measure_cache().await });
+
+    let usage = GLOBALS.runtime.block_on(job)??;
+    for kind_usage in &usage.kinds {
+        println!(
+            "{}: {} files, {} bytes",
+            kind_usage.kind, kind_usage.files, kind_usage.bytes
+        );
+    }
+    println!("Total: {} files, {} bytes", usage.files(), usage.bytes());
+    println!(
+        "Maximum: {} bytes",
+        GLOBALS.db().read_setting_cache_max_size_mb() * 1_048_576
+    );
+
+    Ok(())
+}
+
 pub fn clear_timeouts() -> Result<(), Error> {
     GLOBALS
         .db()
@@ -699,7 +754,9 @@ pub fn delete_spam_by_content(cmd: Command, mut args: env::Args) -> Result<(), E
             content: "spam".to_owned(),
         };
         // Should we add a pow? Maybe the relay needs it.
-        GLOBALS.identity.sign_event(pre_event)?
+        GLOBALS
+            .runtime
+            .block_on(GLOBALS.identity.sign_event(pre_event))?
     };
     println!("{}", serde_json::to_string(&event).unwrap());
 
@@ -1101,6 +1158,24 @@ pub fn force_migration_level(cmd: Command, mut args: env::Args) -> Result<(), Er
     Ok(())
 }
 
+pub fn trim_cache(cmd: Command, mut args: env::Args) -> Result<(), Error> {
+    let megabytes = match args.next() {
+        Some(integer) => match integer.parse::<u64>() {
+            Ok(mb) => mb,
+            Err(_) => return cmd.usage("Megabytes must be a whole number".to_string()),
+        },
+        None => GLOBALS.db().read_setting_cache_max_size_mb(),
+    };
+
+    let job =
+        tokio::task::spawn(async move { GLOBALS.fetcher.trim_cache(megabytes * 1_048_576).await });
+
+    let (count, freed) = GLOBALS.runtime.block_on(job)??;
+    println!("Removed {} files, freeing {} bytes", count, freed);
+
+    Ok(())
+}
+
 pub fn ungiftwrap(cmd: Command, mut args: env::Args) -> Result<(), Error> {
     let idstr = match args.next() {
         Some(id) => id,
@@ -1177,6 +1252,77 @@ pub fn rebuild_fof() -> Result<(), Error> {
     Ok(())
 }
 
+pub fn relay_stats(cmd: Command, args: env::Args) -> Result<(), Error> {
+    use gossip_lib::RelayStats;
+    use humansize::{format_size, DECIMAL};
+
+    let mut url: Option<RelayUrl> = None;
+    let mut days: u32 = 7;
+    for arg in args {
+        if let Ok(d) = arg.parse::<u32>() {
+            days = d;
+        } else {
+            match RelayUrl::try_from_str(&arg) {
+                Ok(u) => url = Some(u),
+                Err(_) => return cmd.usage(format!("Not a relay url or number of days: {}", arg)),
+            }
+        }
+    }
+
+    fn print_row(stats: &RelayStats, label: &str) {
+        let latency = match stats.mean_eose_latency_ms() {
+            Some(ms) => format!("{}ms", ms),
+            None => "-".to_owned(),
+        };
+        println!(
+            "{:>10} {:>10} {:>10} {:>8} {:>8} {:>6} {:>8} {:>6} {:>6}  {}",
+            format_size(stats.bytes(), DECIMAL),
+            format_size(stats.bytes_sent, DECIMAL),
+            format_size(stats.bytes_received, DECIMAL),
+            stats.events_received,
+            stats.duplicate_events,
+            stats.subscriptions,
+            latency,
+            stats.notices,
+            stats.closed,
+            label
+        );
+    }
+
+    println!(
+        "{:>10} {:>10} {:>10} {:>8} {:>8} {:>6} {:>8} {:>6} {:>6}  {}",
+        "TOTAL",
+        "SENT",
+        "RECEIVED",
+        "EVENTS",
+        "DUPS",
+        "SUBS",
+        "EOSE",
+        "NOTICE",
+        "CLOSED",
+        if url.is_some() { "DAY" } else { "RELAY" }
+    );
+
+    match url {
+        Some(url) => {
+            for stats in gossip_lib::relay_stats::relay_stats_history(&url, days)? {
+                let day = match time::OffsetDateTime::from_unix_timestamp(stats.day_start().0) {
+                    Ok(stamp) => stamp.date().to_string(),
+                    Err(_) => format!("{}", stats.day),
+                };
+                print_row(&stats, &day);
+            }
+        }
+        None => {
+            for (url, stats) in gossip_lib::relay_stats::all_relay_stats_totals(days)? {
+                print_row(&stats, url.as_str());
+            }
+        }
+    }
+
+    Ok(())
+}
+
 pub fn reprocess_recent(_cmd: Command) -> Result<(), Error> {
     login()?;
 
@@ -1292,6 +1438,42 @@ pub fn verify_json(cmd: Command, mut args: env::Args) -> Result<(), Error> {
     Ok(())
 }
 
+pub fn verify_zaps() -> Result<(), Error> {
+    let online = !GLOBALS.db().read_setting_offline();
+    if !online {
+        println!("Offline: the signers of zap receipts will not be checked");
+    }
+
+    let job = tokio::task::spawn(async move {
+        let mut filter = Filter::new();
+        filter.kinds = vec![EventKind::Zap];
+        let receipts = match GLOBALS.db().find_events_by_filter(&filter, |_| true) {
+            Ok(e) => e,
+            Err(e) => {
+                println!("ERROR: {}", e);
+                vec![]
+            }
+        };
+
+        let mut valid: usize = 0;
+        let mut invalid: usize = 0;
+        for receipt in receipts.iter() {
+            match gossip_lib::zaps::verify_zap_receipt(receipt, online).await {
+                Ok(None) => valid += 1,
+                Ok(Some(why)) => {
+                    println!("{}: {}", receipt.id.as_hex_string(), why);
+                    invalid += 1;
+                }
+                Err(e) => println!("ERROR: {}", e),
+            }
+        }
+
+        println!("{} valid, {} invalid zap receipts", valid, invalid);
+    });
+
+    Ok(GLOBALS.runtime.block_on(job)?)
+}
+
 pub fn rebuild_indices() -> Result<(), Error> {
     println!("Login required in order to reindex DMs and GiftWraps");
     login()?;
@@ -1300,6 +1482,13 @@ pub fn rebuild_indices() -> Result<(), Error> {
     Ok(())
 }
 
+pub fn rebuild_search_index() -> Result<(), Error> {
+    GLOBALS.db().rebuild_search_index(None)?;
+    println!("Search index rebuilt");
+
+    Ok(())
+}
+
 pub fn rename_person_list(cmd: Command, mut args: env::Args) -> Result<(), Error> {
     let number: u8 = match args.next() {
         Some(number) => number.parse::<u8>()?,
@@ -1326,23 +1515,30 @@ pub fn rename_person_list(cmd: Command, mut args: env::Args) -> Result<(), Error
 
 pub fn login() -> Result<(), Error> {
     if !GLOBALS.identity.is_unlocked() {
-        let mut password = rpassword::prompt_password("Password: ").unwrap();
-        if !GLOBALS.identity.has_private_key() {
-            let epk = match GLOBALS.db().read_encrypted_private_key()? {
-                Some(epk) => epk,
-                None => return Err(ErrorKind::NoPrivateKey.into()),
-            };
-            GLOBALS.identity.set_encrypted_private_key(epk, &password)?;
-        } else {
-            GLOBALS.identity.unlock(&password)?;
-        }
+        let mut password = rpassword::prompt_password("Password: ")?;
+        let result = unlock(&password);
         password.zeroize();
+        result?;
     } else {
         println!("No private key, skipping login");
     }
     Ok(())
 }
 
+/// Unlock our private key with this passphrase
+pub fn unlock(password: &str) -> Result<(), Error> {
+    if !GLOBALS.identity.has_private_key() {
+        let epk = match GLOBALS.db().read_encrypted_private_key()? {
+            Some(epk) => epk,
+            None => return Err(ErrorKind::NoPrivateKey.into()),
+        };
+        GLOBALS.identity.set_encrypted_private_key(epk, password)?;
+    } else {
+        GLOBALS.identity.unlock(password)?;
+    }
+    Ok(())
+}
+
 pub fn offline() -> Result<(), Error> {
     GLOBALS.db().write_setting_offline(&true, None)?;
     Ok(())
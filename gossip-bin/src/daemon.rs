@@ -0,0 +1,110 @@
+use gossip_lib::{Error, ErrorKind, RunState, GLOBALS};
+use std::env;
+use std::io::IsTerminal;
+use std::sync::atomic::Ordering;
+use std::time::Duration;
+use zeroize::Zeroize;
+
+/// How often the daemon logs its status
+const STATUS_INTERVAL: Duration = Duration::from_secs(60);
+
+/// Names a file holding the passphrase, so the daemon can log in without a terminal
+const PASSPHRASE_FILE_VAR: &str = "GOSSIP_PASSPHRASE_FILE";
+
+/// Run the lib (overlord, minions, seeker and background tasks) without the GUI,
+/// until we get SIGINT, SIGQUIT or SIGTERM.
+pub fn run() -> Result<(), Error> {
+    // The overlord waits for a login before it starts, and there is no GUI to do it
+    if GLOBALS.wait_for_login.load(Ordering::Relaxed) {
+        login()?;
+    }
+
+    tracing::info!("Running headless. Send SIGTERM or SIGINT to shut down.");
+
+    // Log our status periodically
+    std::mem::drop(GLOBALS.runtime.spawn(log_status()));
+
+    // The overlord handles the shutdown signals, and returns once the
+    // minions are shut down and LMDB is synced
+    GLOBALS.runtime.block_on(gossip_lib::run());
+
+    tracing::info!("Gossip daemon end.");
+
+    Ok(())
+}
+
+// Read the passphrase from the file named by GOSSIP_PASSPHRASE_FILE if set, else
+// ask at the terminal if there is one
+fn login() -> Result<(), Error> {
+    if GLOBALS.identity.is_unlocked() {
+        return Ok(());
+    }
+
+    if let Some(path) = env::var_os(PASSPHRASE_FILE_VAR) {
+        let mut contents = std::fs::read_to_string(&path).map_err(|e| {
+            Error::from(ErrorKind::General(format!(
+                "Could not read the passphrase file {}: {}",
+                path.to_string_lossy(),
+                e
+            )))
+        })?;
+        let result = crate::commands::unlock(contents.trim_end_matches(['\r', '\n']));
+        contents.zeroize();
+        return result;
+    }
+
+    if !std::io::stdin().is_terminal() {
+        return Err(ErrorKind::General(format!(
+            "gossip is set to login at startup, but there is no terminal to ask for the passphrase. Set {} to a file holding it.",
+            PASSPHRASE_FILE_VAR
+        ))
+        .into());
+    }
+
+    crate::commands::login()
+}
+
+async fn log_status() {
+    let mut read_runstate = GLOBALS.read_runstate.clone();
+    let mut interval = tokio::time::interval(STATUS_INTERVAL);
+    interval.tick().await; // the first tick completes immediately
+
+    loop {
+        tokio::select! {
+            _ = interval.tick() => log_status_once(),
+            changed = read_runstate.changed() => {
+                if changed.is_err() || *read_runstate.borrow_and_update() == RunState::ShuttingDown {
+                    break;
+                }
+            }
+        }
+    }
+}
+
+fn log_status_once() {
+    tracing::info!(
+        "STATUS: {:?}, {} relays connected, {} subscriptions, {} events processed, {} bytes read",
+        *GLOBALS.read_runstate.borrow(),
+        GLOBALS.connected_relays.len(),
+        GLOBALS.open_subscriptions.load(Ordering::Relaxed),
+        GLOBALS.events_processed.load(Ordering::Relaxed),
+        GLOBALS.bytes_read.load(Ordering::Relaxed),
+    );
+
+    tracing::info!(
+        "STATUS: fetcher {} ({} in flight, {} stalled, {} completed)",
+        GLOBALS.fetcher.stats(),
+        GLOBALS.fetcher.num_requests_in_flight(),
+        GLOBALS.fetcher.num_requests_stalled(),
+        GLOBALS.fetcher.num_requests_completed(),
+    );
+
+    for entry in GLOBALS.connected_relays.iter() {
+        let jobs: Vec<String> = entry
+            .value()
+            .iter()
+            .map(|job| format!("{}", job.reason))
+            .collect();
+        tracing::info!("STATUS: {} jobs: {}", entry.key(), jobs.join(", "));
+    }
+}
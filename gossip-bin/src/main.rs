@@ -7,6 +7,7 @@
 
 mod about;
 mod commands;
+mod daemon;
 mod date_ago;
 mod notecache;
 mod notedata;
@@ -1,5 +1,5 @@
 use gossip_lib::GLOBALS;
-use gossip_lib::{Person, PersonList, PersonTable, Private, Table};
+use gossip_lib::{CountTarget, Label, Person, PersonList, PersonTable, Private, Table};
 use std::collections::HashMap;
 
 use nostr_types::{
@@ -50,6 +50,13 @@ pub(crate) struct NoteData {
     /// Annotations by the author
     pub annotations: Vec<(Unixtime, String)>,
 
+    /// Reports (NIP-56) of this note or its author by people we follow:
+    /// (reporter, report type)
+    pub reports: Vec<(PublicKey, String)>,
+
+    /// Labels (NIP-32) applied to this note
+    pub labels: Vec<Label>,
+
     /// Do we consider this note as being a repost of another?
     pub repost: Option<RepostType>,
 
@@ -65,6 +72,10 @@ pub(crate) struct NoteData {
     /// Has the current user reacted to this post?
     pub our_reaction: Option<char>,
 
+    /// Replies and reactions as counted by relays (NIP-45), if any relay has
+    pub relay_reply_count: Option<usize>,
+    pub relay_reaction_count: Option<usize>,
+
     /// The total amount of MilliSatoshi zapped to this note
     pub zaptotal: MilliSatoshi,
 
@@ -128,6 +139,17 @@ impl NoteData {
             .get_reactions(event.id)
             .unwrap_or((vec![], None));
 
+        let relay_reply_count = gossip_lib::counts::relay_count(CountTarget::Replies(event.id));
+        let relay_reaction_count =
+            gossip_lib::counts::relay_count(CountTarget::Reactions(event.id));
+
+        let reports = GLOBALS
+            .db()
+            .get_reports_by_follows(event.id, event.pubkey)
+            .unwrap_or_default();
+
+        let labels = trusted_labels(&event);
+
         let zaptotal = GLOBALS
             .db()
             .get_zap_total(event.id)
@@ -195,6 +217,8 @@ impl NoteData {
             EventKind::GiftWrap => ("".to_owned(), Some("DECRYPTION FAILED".to_owned())),
             EventKind::ChannelMessage => (event.content.clone(), None),
             EventKind::LiveChatMessage => (event.content.clone(), None),
+            EventKind::GroupChatMessage => (event.content.trim().to_string(), None),
+            EventKind::GroupChatThread => (event.content.trim().to_string(), None),
             EventKind::DraftLongFormContent => (event.content.clone(), None),
             EventKind::Picture => {
                 let mut content: String = String::new();
@@ -322,11 +346,15 @@ impl NoteData {
             lists,
             deletions,
             annotations,
+            reports,
+            labels,
             repost,
             embedded_event,
             mentions,
             reactions,
             our_reaction,
+            relay_reply_count,
+            relay_reaction_count,
             zaptotal,
             seen_on,
             shattered_content,
@@ -349,6 +377,12 @@ impl NoteData {
         self.reactions.append(&mut reactions);
         self.our_reaction = our_reaction;
 
+        // Update relay counts
+        self.relay_reply_count =
+            gossip_lib::counts::relay_count(CountTarget::Replies(self.event.id));
+        self.relay_reaction_count =
+            gossip_lib::counts::relay_count(CountTarget::Reactions(self.event.id));
+
         // Update seen_on
         let mut seen_on = GLOBALS
             .db()
@@ -364,6 +398,15 @@ impl NoteData {
             .get_annotations(&self.event)
             .unwrap_or_default();
 
+        // Update reports
+        self.reports = GLOBALS
+            .db()
+            .get_reports_by_follows(self.event.id, self.event.pubkey)
+            .unwrap_or_default();
+
+        // Update labels
+        self.labels = trusted_labels(&self.event);
+
         // Update zaptotal
         self.zaptotal = GLOBALS
             .db()
@@ -398,3 +441,14 @@ impl NoteData {
         }
     }
 }
+
+/// The labels (NIP-32) on an event that we or people we follow applied
+fn trusted_labels(event: &Event) -> Vec<Label> {
+    GLOBALS
+        .db()
+        .get_labels(event)
+        .unwrap_or_default()
+        .drain(..)
+        .filter(|l| l.is_trusted())
+        .collect()
+}
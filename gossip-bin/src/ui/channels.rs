@@ -0,0 +1,174 @@
+use super::{GossipUi, Page};
+use eframe::egui;
+use egui::{Context, RichText, Ui};
+use gossip_lib::comms::ToOverlordMessage;
+use gossip_lib::{ChannelMetadata, FeedKind, FollowedChannel, GLOBALS};
+use nostr_types::{Id, NostrBech32, RelayUrl};
+use std::time::{Duration, Instant};
+
+/// The state of the public chat channels (NIP-28) page
+#[derive(Debug, Default)]
+pub(super) struct Channels {
+    // A channel typed in to open, as a note1, nevent1 or hex id
+    pub identifier: String,
+
+    // Channels we have seen but do not follow, refreshed now and then
+    known: Vec<(Id, ChannelMetadata)>,
+    next_refresh: Option<Instant>,
+
+    // Channels we follow with their names, refreshed now and then
+    followed: Vec<(FollowedChannel, String)>,
+    followed_next_refresh: Option<Instant>,
+}
+
+/// The channels we follow, sorted by name
+pub(super) fn followed_channels(app: &mut GossipUi) -> Vec<(FollowedChannel, String)> {
+    if !matches!(app.channels.followed_next_refresh, Some(next) if Instant::now() < next) {
+        let mut channels: Vec<(FollowedChannel, String)> = GLOBALS
+            .db()
+            .read_followed_channels()
+            .unwrap_or_default()
+            .into_iter()
+            .map(|c| {
+                let name = c.name();
+                (c, name)
+            })
+            .collect();
+        channels.sort_by(|a, b| a.1.to_lowercase().cmp(&b.1.to_lowercase()));
+        app.channels.followed = channels;
+        app.channels.followed_next_refresh = Some(Instant::now() + Duration::from_secs(5));
+    }
+    app.channels.followed.clone()
+}
+
+// Parse a channel id, with a relay hint if there is one
+fn parse_channel_identifier(s: &str) -> Option<(Id, Option<RelayUrl>)> {
+    let s = s.trim();
+    let s = s.strip_prefix("nostr:").unwrap_or(s);
+    match NostrBech32::try_from_string(s) {
+        Some(NostrBech32::Id(id)) => Some((id, None)),
+        Some(NostrBech32::NEvent(ne)) => {
+            let relay = ne
+                .relays
+                .iter()
+                .filter_map(|u| RelayUrl::try_from_unchecked_url(u).ok())
+                .next();
+            Some((ne.id, relay))
+        }
+        _ => Id::try_from_hex_string(s).ok().map(|id| (id, None)),
+    }
+}
+
+pub(super) fn update(app: &mut GossipUi, ctx: &Context, ui: &mut Ui) {
+    ui.add_space(10.0);
+    ui.heading("Public Chat Channels");
+    ui.add_space(12.0);
+    ui.separator();
+    ui.add_space(10.0);
+
+    ui.horizontal(|ui| {
+        ui.label("Open a channel: ");
+        ui.add(
+            text_edit_line!(app, app.channels.identifier)
+                .hint_text("nevent1..., note1... or hex id")
+                .desired_width(400.0),
+        );
+        if ui.button("Open").clicked() {
+            match parse_channel_identifier(&app.channels.identifier) {
+                Some((id, relay)) => {
+                    if let Some(relay) = relay {
+                        let _ = GLOBALS
+                            .to_overlord
+                            .send(ToOverlordMessage::FetchEvent(id, vec![relay]));
+                    }
+                    app.channels.identifier.clear();
+                    app.set_page(ctx, Page::Feed(FeedKind::Channel(id)));
+                }
+                None => GLOBALS
+                    .status_queue
+                    .write()
+                    .write("Not a channel id".to_owned()),
+            }
+        }
+    });
+    ui.add_space(10.0);
+
+    let followed = followed_channels(app);
+    if !matches!(app.channels.next_refresh, Some(next) if Instant::now() < next) {
+        app.channels.known = gossip_lib::channels::known_channels(100)
+            .unwrap_or_default()
+            .into_iter()
+            .filter(|(id, _)| !followed.iter().any(|(c, _)| c.id == *id))
+            .collect();
+        app.channels.next_refresh = Some(Instant::now() + Duration::from_secs(5));
+    }
+    let known = app.channels.known.clone();
+
+    app.vert_scroll_area()
+        .id_salt("channels_scroll")
+        .show(ui, |ui| {
+            ui.heading("Following");
+            ui.add_space(6.0);
+            if followed.is_empty() {
+                ui.label("You are not following any channels.");
+            }
+            for (channel, name) in followed {
+                ui.horizontal(|ui| {
+                    if ui.link(RichText::new(&name).heading()).clicked() {
+                        app.set_page(ctx, Page::Feed(FeedKind::Channel(channel.id)));
+                    }
+                    if let Some(relay) = &channel.relay {
+                        ui.label(RichText::new(relay.host()).weak());
+                    }
+
+                    ui.with_layout(egui::Layout::right_to_left(egui::Align::Center), |ui| {
+                        if ui.button("Unfollow").clicked() {
+                            let _ = GLOBALS
+                                .to_overlord
+                                .send(ToOverlordMessage::UnfollowChannel(channel.id));
+                        }
+                    });
+                });
+                ui.add_space(6.0);
+            }
+
+            ui.add_space(10.0);
+            ui.horizontal(|ui| {
+                ui.heading("Other channels");
+                if ui.button("Find channels").clicked() {
+                    let _ = GLOBALS.to_overlord.send(ToOverlordMessage::FetchChannels);
+                }
+            });
+            ui.add_space(6.0);
+            if known.is_empty() {
+                ui.label("No other channels seen yet.");
+            }
+            for (id, metadata) in known {
+                let name = match &metadata.name {
+                    Some(name) if !name.is_empty() => name.clone(),
+                    _ => gossip_lib::names::hex_id_short(&id.into()),
+                };
+                ui.horizontal(|ui| {
+                    if ui.link(RichText::new(&name).heading()).clicked() {
+                        app.set_page(ctx, Page::Feed(FeedKind::Channel(id)));
+                    }
+                    if let Some(about) = &metadata.about {
+                        ui.label(RichText::new(about.lines().next().unwrap_or("")).weak());
+                    }
+
+                    ui.with_layout(egui::Layout::right_to_left(egui::Align::Center), |ui| {
+                        if ui.button("Follow").clicked() {
+                            let relay = gossip_lib::channels::channel_relays(id)
+                                .unwrap_or_default()
+                                .into_iter()
+                                .next();
+                            let _ = GLOBALS
+                                .to_overlord
+                                .send(ToOverlordMessage::FollowChannel(id, relay));
+                        }
+                    });
+                });
+                ui.add_space(6.0);
+            }
+        });
+}
@@ -0,0 +1,187 @@
+use super::{GossipUi, Page};
+use eframe::egui;
+use egui::{Context, RichText, Ui};
+use gossip_lib::comms::ToOverlordMessage;
+use gossip_lib::{Draft, FeedKind, LongFormArticle, GLOBALS};
+use nostr_types::{EventKind, Id};
+use std::time::{Duration, Instant};
+
+/// Reload our saved drafts from the database
+pub(super) fn reload(app: &mut GossipUi) {
+    (app.drafts, app.drafts_pending) = match gossip_lib::drafts::load_drafts() {
+        Ok(loaded) => loaded,
+        Err(e) => {
+            tracing::error!("{}", e);
+            (vec![], 0)
+        }
+    };
+    app.drafts_next_reload = Instant::now() + Duration::from_secs(1);
+}
+
+/// Load a saved draft into the post composer
+pub(super) fn resume_draft(app: &mut GossipUi, ctx: &Context, draft: Draft) {
+    app.draft_data.clear();
+    app.draft_data.draft = draft.content.clone();
+    if let Some(subject) = draft.subject() {
+        app.draft_data.include_subject = true;
+        app.draft_data.subject = subject;
+    }
+    if let Some(cw) = draft.content_warning() {
+        app.draft_data.include_content_warning = true;
+        app.draft_data.content_warning = cw;
+    }
+    app.draft_data.is_annotate = draft.is_annotation();
+    app.draft_data.replying_to = draft.in_reply_to();
+
+    let page = match app.draft_data.replying_to {
+        Some(id) => Page::Feed(FeedKind::Thread {
+            id,
+            referenced_by: id,
+            author: None,
+        }),
+        None => match GLOBALS.identity.public_key() {
+            Some(pubkey) => Page::Feed(FeedKind::Person(pubkey)),
+            None => Page::Feed(FeedKind::Global),
+        },
+    };
+
+    app.draft_data.saved_draft = Some(draft);
+    app.show_post_area = true;
+    app.draft_needs_focus = true;
+    app.set_page(ctx, page);
+}
+
+pub(super) fn update(app: &mut GossipUi, ctx: &Context, ui: &mut Ui) {
+    ui.add_space(10.0);
+    ui.horizontal(|ui| {
+        ui.heading("Drafts");
+        ui.add_space(10.0);
+        if ui.button("Refresh").clicked() {
+            reload(app);
+        }
+    });
+    ui.add_space(12.0);
+
+    if !GLOBALS.identity.is_unlocked() {
+        ui.label("Your drafts are encrypted. Unlock your key to see them.");
+        return;
+    }
+
+    if app.drafts_pending > 0 {
+        // Pick up the drafts as the remote signer decrypts them
+        if app.drafts_next_reload < Instant::now() {
+            reload(app);
+        }
+        ui.label(match app.drafts_pending {
+            1 => "1 draft is waiting for your remote signer to decrypt it.".to_owned(),
+            n => format!(
+                "{} drafts are waiting for your remote signer to decrypt them.",
+                n
+            ),
+        });
+        ui.add_space(8.0);
+    }
+
+    if app.drafts.is_empty() {
+        if app.drafts_pending == 0 {
+            ui.label(
+                "You have no saved drafts. Drafts can be saved from the menu of the post composer.",
+            );
+        }
+        return;
+    }
+
+    let mut resume: Option<Draft> = None;
+    let mut delete: Option<Draft> = None;
+    let mut open_thread: Option<Id> = None;
+
+    app.vert_scroll_area()
+        .id_salt("drafts_scroll")
+        .show(ui, |ui| {
+            for draft in app.drafts.iter() {
+                ui.add_space(8.0);
+                ui.separator();
+                ui.add_space(8.0);
+
+                ui.horizontal(|ui| {
+                    ui.label(
+                        RichText::new(crate::date_ago::date_ago(draft.saved_at))
+                            .italics()
+                            .weak(),
+                    );
+
+                    if let Some(id) = draft.in_reply_to() {
+                        ui.add_space(10.0);
+                        if ui.link("reply").clicked() {
+                            open_thread = Some(id);
+                        }
+                    }
+
+                    if let Some(subject) = draft.subject() {
+                        ui.add_space(10.0);
+                        ui.label(RichText::new(subject).strong());
+                    }
+
+                    if let Some(article) = LongFormArticle::from_draft(draft) {
+                        ui.add_space(10.0);
+                        ui.label(RichText::new(format!("Article: {}", article.title)).strong());
+                    }
+                });
+
+                let summary: String = draft
+                    .content
+                    .chars()
+                    .take(200)
+                    .collect::<String>()
+                    .replace('\n', " ");
+                ui.label(summary);
+
+                ui.horizontal(|ui| {
+                    if draft.kind == EventKind::TextNote || draft.kind == EventKind::LongFormContent
+                    {
+                        if ui.button("Resume").clicked() {
+                            resume = Some(draft.clone());
+                        }
+                    } else {
+                        ui.label(
+                            RichText::new(format!(
+                                "Drafts of kind {} cannot be resumed here",
+                                u32::from(draft.kind)
+                            ))
+                            .weak(),
+                        );
+                    }
+                    btn_h_space!(ui);
+                    if ui.button("Delete").clicked() {
+                        delete = Some(draft.clone());
+                    }
+                });
+            }
+        });
+
+    if let Some(draft) = delete {
+        app.drafts.retain(|d| d.identifier != draft.identifier);
+        let _ = GLOBALS
+            .to_overlord
+            .send(ToOverlordMessage::DeleteDraft(draft));
+    }
+
+    if let Some(id) = open_thread {
+        app.set_page(
+            ctx,
+            Page::Feed(FeedKind::Thread {
+                id,
+                referenced_by: id,
+                author: None,
+            }),
+        );
+    }
+
+    if let Some(draft) = resume {
+        if draft.kind == EventKind::LongFormContent {
+            super::long_form::resume_draft(app, ctx, draft);
+        } else {
+            resume_draft(app, ctx, draft);
+        }
+    }
+}
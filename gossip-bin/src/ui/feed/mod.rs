@@ -3,13 +3,14 @@ use eframe::egui::{self, Align, Rect};
 use egui::{Context, RichText, Ui, Vec2};
 use gossip_lib::comms::ToOverlordMessage;
 use gossip_lib::relay::Relay;
-use gossip_lib::DmChannel;
 use gossip_lib::FeedKind;
 use gossip_lib::GLOBALS;
 use nostr_types::Id;
 use std::sync::atomic::Ordering;
+use std::time::{Duration, Instant};
 
 mod note;
+pub(super) use note::render_markdown;
 pub use note::NoteRenderData;
 pub(super) mod post;
 
@@ -61,6 +62,35 @@ pub(super) fn enter_feed(app: &mut GossipUi, ctx: &Context, kind: FeedKind) {
     app.displayed_feed_hash = None;
 }
 
+/// Show only the notes that we or the people we follow labelled (namespace, label),
+/// or go back to the whole feed
+pub(in crate::ui) fn set_label_filter(app: &mut GossipUi, filter: Option<(String, String)>) {
+    app.feed_label_filter = filter;
+    app.feed_label_filter_ids = Vec::new();
+    app.feed_label_filter_next_refresh = Instant::now();
+}
+
+// Keep the notes of the displayed feed that carry the label we filter by. This runs
+// when the displayed feed changes, and every few seconds to pick up new labels.
+fn refresh_label_filter(app: &mut GossipUi, namespace: &str, label: &str) {
+    app.feed_label_filter_ids = app
+        .displayed_feed
+        .iter()
+        .filter(|id| match GLOBALS.db().read_event(**id) {
+            Ok(Some(event)) => GLOBALS
+                .db()
+                .get_labels(&event)
+                .unwrap_or_default()
+                .iter()
+                .any(|l| l.namespace == namespace && l.label == label && l.is_trusted()),
+            _ => false,
+        })
+        .copied()
+        .collect();
+    app.feed_label_filter_hash = app.displayed_feed_hash;
+    app.feed_label_filter_next_refresh = Instant::now() + Duration::from_secs(5);
+}
+
 pub(super) fn update(app: &mut GossipUi, ctx: &Context, ui: &mut Ui) {
     if GLOBALS.ui_invalidate_all.load(Ordering::Relaxed) {
         app.notecache.invalidate_all();
@@ -252,6 +282,141 @@ pub(super) fn update(app: &mut GossipUi, ctx: &Context, ui: &mut Ui) {
 
             render_a_feed(app, ctx, ui, None, &scroll_widget_id, load_more);
         }
+        FeedKind::Group(relay_url, group) => {
+            let metadata = gossip_lib::GroupMetadata::get(&relay_url, &group);
+            let joined = GLOBALS
+                .db()
+                .is_joined_group(&relay_url, &group)
+                .unwrap_or(false);
+
+            ui.add_space(10.0);
+            ui.allocate_ui_with_layout(
+                Vec2::new(ui.available_width(), ui.spacing().interact_size.y),
+                egui::Layout::left_to_right(egui::Align::Center),
+                |ui| {
+                    add_left_space(ui);
+                    ui.heading(metadata.name.as_deref().unwrap_or(&group));
+                    recompute_btn(app, ui);
+
+                    ui.with_layout(egui::Layout::right_to_left(egui::Align::Center), |ui| {
+                        ui.add_space(8.0);
+                        if joined {
+                            if widgets::Button::bordered(&app.theme, "Leave")
+                                .small(true)
+                                .show(ui)
+                                .clicked()
+                            {
+                                let _ = GLOBALS.to_overlord.send(ToOverlordMessage::LeaveGroup(
+                                    relay_url.clone(),
+                                    group.clone(),
+                                ));
+                            }
+                        } else if GLOBALS.identity.is_unlocked()
+                            && widgets::Button::bordered(&app.theme, "Join")
+                                .small(true)
+                                .show(ui)
+                                .clicked()
+                        {
+                            let _ = GLOBALS.to_overlord.send(ToOverlordMessage::JoinGroup(
+                                relay_url.clone(),
+                                group.clone(),
+                            ));
+                        }
+                    });
+                },
+            );
+            ui.horizontal_wrapped(|ui| {
+                add_left_space(ui);
+                ui.label(
+                    RichText::new(format!(
+                        "{}'{} \u{2022} {} \u{2022} {} \u{2022} {} members \u{2022} {} admins",
+                        relay_url.host(),
+                        group,
+                        if metadata.private {
+                            "Private"
+                        } else {
+                            "Public"
+                        },
+                        if metadata.closed { "Closed" } else { "Open" },
+                        metadata.members.len(),
+                        metadata.admins.len(),
+                    ))
+                    .weak(),
+                );
+            });
+            if let Some(about) = &metadata.about {
+                ui.horizontal_wrapped(|ui| {
+                    add_left_space(ui);
+                    ui.label(about);
+                });
+            }
+            ui.add_space(6.0);
+
+            render_a_feed(app, ctx, ui, None, &scroll_widget_id, load_more);
+        }
+        FeedKind::Channel(id) => {
+            let metadata = gossip_lib::ChannelMetadata::get(id);
+            let followed = GLOBALS.db().is_followed_channel(id).unwrap_or(false);
+
+            ui.add_space(10.0);
+            ui.allocate_ui_with_layout(
+                Vec2::new(ui.available_width(), ui.spacing().interact_size.y),
+                egui::Layout::left_to_right(egui::Align::Center),
+                |ui| {
+                    add_left_space(ui);
+                    ui.heading(gossip_lib::channels::channel_name(id));
+                    recompute_btn(app, ui);
+
+                    ui.with_layout(egui::Layout::right_to_left(egui::Align::Center), |ui| {
+                        ui.add_space(8.0);
+                        if followed {
+                            if widgets::Button::bordered(&app.theme, "Unfollow")
+                                .small(true)
+                                .show(ui)
+                                .clicked()
+                            {
+                                let _ = GLOBALS
+                                    .to_overlord
+                                    .send(ToOverlordMessage::UnfollowChannel(id));
+                            }
+                        } else if widgets::Button::bordered(&app.theme, "Follow")
+                            .small(true)
+                            .show(ui)
+                            .clicked()
+                        {
+                            let relay = gossip_lib::channels::channel_relays(id)
+                                .unwrap_or_default()
+                                .into_iter()
+                                .next();
+                            let _ = GLOBALS
+                                .to_overlord
+                                .send(ToOverlordMessage::FollowChannel(id, relay));
+                        }
+                    });
+                },
+            );
+            if let Some(creator) = metadata.creator {
+                ui.horizontal_wrapped(|ui| {
+                    add_left_space(ui);
+                    ui.label(
+                        RichText::new(format!(
+                            "Public chat channel by {}",
+                            gossip_lib::names::best_name_from_pubkey_lookup(&creator)
+                        ))
+                        .weak(),
+                    );
+                });
+            }
+            if let Some(about) = &metadata.about {
+                ui.horizontal_wrapped(|ui| {
+                    add_left_space(ui);
+                    ui.label(about);
+                });
+            }
+            ui.add_space(6.0);
+
+            render_dm_feed(app, ui, &scroll_widget_id, load_more);
+        }
         FeedKind::DmChat(channel) => {
             if !GLOBALS.identity.is_unlocked() {
                 ui.add_space(10.0);
@@ -318,7 +483,7 @@ pub(super) fn update(app: &mut GossipUi, ctx: &Context, ui: &mut Ui) {
             );
 
             ui.add_space(6.0);
-            render_dm_feed(app, ui, channel);
+            render_dm_feed(app, ui, &channel.name(), false);
         }
     }
 
@@ -345,7 +510,30 @@ fn render_a_feed(
         return;
     }
 
-    let feed = app.displayed_feed.clone();
+    let mut feed = app.displayed_feed.clone();
+
+    if parent.is_none() {
+        if let Some((namespace, label)) = app.feed_label_filter.clone() {
+            ui.horizontal(|ui| {
+                add_left_space(ui);
+                ui.label(format!(
+                    "Only showing notes labelled {}:{}",
+                    namespace, label
+                ));
+                if ui.link("Clear").clicked() {
+                    set_label_filter(app, None);
+                }
+            });
+            ui.add_space(6.0);
+
+            if app.feed_label_filter_hash != app.displayed_feed_hash
+                || app.feed_label_filter_next_refresh < Instant::now()
+            {
+                refresh_label_filter(app, &namespace, &label);
+            }
+            feed = app.feed_label_filter_ids.clone();
+        }
+    }
 
     app.vert_scroll_area()
         .auto_shrink(false)
@@ -444,9 +632,9 @@ fn render_a_feed(
         });
 }
 
-fn render_dm_feed(app: &mut GossipUi, ui: &mut Ui, channel: DmChannel) {
+// Render a feed chat-style, as used for DMs and public chat channels
+fn render_dm_feed(app: &mut GossipUi, ui: &mut Ui, scroll_area_id: &str, load_more: bool) {
     let feed = app.displayed_feed.clone();
-    let scroll_area_id = channel.name();
     let feed_newest_at_bottom = GLOBALS.db().read_setting_dm_feed_newest_at_bottom();
     let iterator: Box<dyn Iterator<Item = &Id>> = if feed_newest_at_bottom {
         Box::new(feed.iter().rev())
@@ -459,6 +647,12 @@ fn render_dm_feed(app: &mut GossipUi, ui: &mut Ui, channel: DmChannel) {
         .stick_to_bottom(feed_newest_at_bottom)
         .id_salt(scroll_area_id)
         .show(ui, |ui| {
+            // Older messages are loaded at the old end of the feed
+            if load_more && feed_newest_at_bottom {
+                ui.add_space(20.0);
+                render_load_more(app, ui);
+                ui.add_space(20.0);
+            }
             for id in iterator {
                 note::render_dm_note(
                     app,
@@ -471,6 +665,11 @@ fn render_dm_feed(app: &mut GossipUi, ui: &mut Ui, channel: DmChannel) {
                     },
                 );
             }
+            if load_more && !feed_newest_at_bottom {
+                ui.add_space(20.0);
+                render_load_more(app, ui);
+                ui.add_space(20.0);
+            }
         });
 }
 
@@ -0,0 +1,343 @@
+use super::{media, GossipUi, Page};
+use eframe::egui;
+use egui::{Margin, RichText, Ui};
+use gossip_lib::comms::ToOverlordMessage;
+use gossip_lib::{FeedKind, GLOBALS};
+use nostr_types::{Id, NAddr, NEvent, NostrBech32, PublicKey};
+use pulldown_cmark::{Event, HeadingLevel, Options, Parser, Tag, TagEnd};
+
+const INDENT: f32 = 18.0;
+
+// A piece of text within a block, with its style already applied
+enum Inline {
+    Text(RichText),
+    Link(RichText, String),
+    Image(String),
+    LineBreak,
+}
+
+#[derive(Default)]
+struct State {
+    strong: usize,
+    emphasis: usize,
+    strikethrough: usize,
+    heading: Option<HeadingLevel>,
+    link: Option<String>,
+    image: Option<String>,
+    code_block: Option<String>,
+    quote_depth: usize,
+    // A counter for each open list (None for bullet lists)
+    lists: Vec<Option<u64>>,
+}
+
+impl State {
+    fn indent(&self) -> f32 {
+        (self.lists.len() + self.quote_depth) as f32 * INDENT
+    }
+
+    fn style(&self, text: &str) -> RichText {
+        let mut rt = RichText::new(text);
+        if let Some(level) = self.heading {
+            let size = match level {
+                HeadingLevel::H1 => 22.0,
+                HeadingLevel::H2 => 19.0,
+                HeadingLevel::H3 => 17.0,
+                _ => 15.0,
+            };
+            rt = rt.size(size).strong();
+        }
+        if self.strong > 0 {
+            rt = rt.strong();
+        }
+        if self.emphasis > 0 {
+            rt = rt.italics();
+        }
+        if self.strikethrough > 0 {
+            rt = rt.strikethrough();
+        }
+        if self.quote_depth > 0 {
+            rt = rt.weak();
+        }
+        rt
+    }
+}
+
+/// Render Markdown text, such as the content of a NIP-23 long-form article.
+///
+/// `referenced_by` is the event the markdown came from, if any, and is used when
+/// following links to other events.
+pub(in crate::ui) fn render_markdown(
+    app: &mut GossipUi,
+    ui: &mut Ui,
+    markdown: &str,
+    referenced_by: Option<Id>,
+) {
+    let options = Options::ENABLE_STRIKETHROUGH | Options::ENABLE_TASKLISTS;
+    let parser = Parser::new_ext(markdown, options);
+
+    let mut state = State::default();
+    let mut block: Vec<Inline> = Vec::new();
+
+    ui.vertical(|ui| {
+        for event in parser {
+            match event {
+                Event::Start(tag) => match tag {
+                    Tag::Heading { level, .. } => {
+                        flush(app, ui, &mut block, state.indent(), referenced_by);
+                        ui.add_space(4.0);
+                        state.heading = Some(level);
+                    }
+                    Tag::BlockQuote(_) => {
+                        flush(app, ui, &mut block, state.indent(), referenced_by);
+                        state.quote_depth += 1;
+                    }
+                    Tag::CodeBlock(_) => {
+                        flush(app, ui, &mut block, state.indent(), referenced_by);
+                        state.code_block = Some(String::new());
+                    }
+                    Tag::List(start) => {
+                        flush(app, ui, &mut block, state.indent(), referenced_by);
+                        state.lists.push(start);
+                    }
+                    Tag::Item => {
+                        flush(app, ui, &mut block, state.indent(), referenced_by);
+                        let marker = match state.lists.last_mut() {
+                            Some(Some(n)) => {
+                                *n += 1;
+                                format!("{}. ", *n - 1)
+                            }
+                            _ => "• ".to_owned(),
+                        };
+                        block.push(Inline::Text(RichText::new(marker)));
+                    }
+                    Tag::Emphasis => state.emphasis += 1,
+                    Tag::Strong => state.strong += 1,
+                    Tag::Strikethrough => state.strikethrough += 1,
+                    Tag::Link { dest_url, .. } => state.link = Some(dest_url.to_string()),
+                    Tag::Image { dest_url, .. } => state.image = Some(dest_url.to_string()),
+                    _ => {}
+                },
+                Event::End(tag) => match tag {
+                    TagEnd::Paragraph | TagEnd::Item => {
+                        flush(app, ui, &mut block, state.indent(), referenced_by);
+                    }
+                    TagEnd::Heading(_) => {
+                        flush(app, ui, &mut block, state.indent(), referenced_by);
+                        state.heading = None;
+                    }
+                    TagEnd::BlockQuote(_) => {
+                        flush(app, ui, &mut block, state.indent(), referenced_by);
+                        state.quote_depth = state.quote_depth.saturating_sub(1);
+                    }
+                    TagEnd::CodeBlock => {
+                        if let Some(code) = state.code_block.take() {
+                            render_code_block(ui, code.trim_end(), state.indent());
+                        }
+                    }
+                    TagEnd::List(_) => {
+                        flush(app, ui, &mut block, state.indent(), referenced_by);
+                        state.lists.pop();
+                    }
+                    TagEnd::Emphasis => state.emphasis = state.emphasis.saturating_sub(1),
+                    TagEnd::Strong => state.strong = state.strong.saturating_sub(1),
+                    TagEnd::Strikethrough => {
+                        state.strikethrough = state.strikethrough.saturating_sub(1)
+                    }
+                    TagEnd::Link => state.link = None,
+                    TagEnd::Image => {
+                        if let Some(url) = state.image.take() {
+                            block.push(Inline::Image(url));
+                        }
+                    }
+                    _ => {}
+                },
+                Event::Text(text) => {
+                    if let Some(code) = state.code_block.as_mut() {
+                        code.push_str(&text);
+                    } else if state.image.is_some() {
+                        // alt text, not shown
+                    } else if let Some(url) = &state.link {
+                        block.push(Inline::Link(state.style(&text), url.clone()));
+                    } else {
+                        block.push(Inline::Text(state.style(&text)));
+                    }
+                }
+                Event::Code(code) => {
+                    block.push(Inline::Text(state.style(&code).code()));
+                }
+                Event::Html(html) | Event::InlineHtml(html) => {
+                    // We do not render HTML, but we don't hide it either
+                    block.push(Inline::Text(state.style(&html).weak()));
+                }
+                Event::SoftBreak => block.push(Inline::Text(RichText::new(" "))),
+                Event::HardBreak => block.push(Inline::LineBreak),
+                Event::Rule => {
+                    flush(app, ui, &mut block, state.indent(), referenced_by);
+                    ui.separator();
+                }
+                Event::TaskListMarker(checked) => {
+                    let marker = if checked { "☑ " } else { "☐ " };
+                    block.push(Inline::Text(RichText::new(marker)));
+                }
+                _ => {}
+            }
+        }
+
+        flush(app, ui, &mut block, state.indent(), referenced_by);
+    });
+}
+
+// Lay out the inline pieces of one block, wrapping as needed
+fn flush(
+    app: &mut GossipUi,
+    ui: &mut Ui,
+    block: &mut Vec<Inline>,
+    indent: f32,
+    referenced_by: Option<Id>,
+) {
+    if block.is_empty() {
+        return;
+    }
+
+    ui.horizontal_wrapped(|ui| {
+        ui.spacing_mut().item_spacing.x = 0.0;
+        ui.add_space(indent);
+        for inline in block.drain(..) {
+            match inline {
+                Inline::Text(text) => {
+                    ui.label(text);
+                }
+                Inline::Link(text, url) => render_link(app, ui, text, &url, referenced_by),
+                Inline::Image(url) => {
+                    if let Some(nurl) = app.try_check_url(&url) {
+                        media::show_image(app, ui, nurl, false, false, None, None);
+                    } else {
+                        ui.hyperlink_to("[ Image ]", url);
+                    }
+                }
+                Inline::LineBreak => {
+                    ui.end_row();
+                    ui.add_space(indent);
+                }
+            }
+        }
+    });
+
+    ui.add_space(6.0);
+}
+
+fn render_code_block(ui: &mut Ui, code: &str, indent: f32) {
+    ui.horizontal(|ui| {
+        ui.add_space(indent);
+        egui::Frame::none()
+            .fill(ui.visuals().extreme_bg_color)
+            .inner_margin(Margin::same(6.0))
+            .show(ui, |ui| {
+                ui.label(RichText::new(code).monospace());
+            });
+    });
+    ui.add_space(6.0);
+}
+
+fn render_link(
+    app: &mut GossipUi,
+    ui: &mut Ui,
+    text: RichText,
+    url: &str,
+    referenced_by: Option<Id>,
+) {
+    // Links to nostr entities stay within gossip
+    if let Some(nb32) = url
+        .strip_prefix("nostr:")
+        .and_then(NostrBech32::try_from_string)
+    {
+        match nb32 {
+            NostrBech32::Pubkey(pubkey) => {
+                if ui.link(text).clicked() {
+                    app.set_page(ui.ctx(), Page::Person(pubkey));
+                }
+                return;
+            }
+            NostrBech32::Profile(profile) => {
+                if ui.link(text).clicked() {
+                    app.set_page(ui.ctx(), Page::Person(profile.pubkey));
+                }
+                return;
+            }
+            NostrBech32::Id(id) => {
+                render_event_link(app, ui, text, id, None, referenced_by);
+                return;
+            }
+            NostrBech32::NEvent(NEvent { id, author, .. }) => {
+                render_event_link(app, ui, text, id, author, referenced_by);
+                return;
+            }
+            NostrBech32::NAddr(naddr) => {
+                render_addr_link(app, ui, text, naddr, referenced_by);
+                return;
+            }
+            _ => {}
+        }
+    }
+
+    ui.hyperlink_to(text, url).on_hover_text(url);
+}
+
+fn render_event_link(
+    app: &mut GossipUi,
+    ui: &mut Ui,
+    text: RichText,
+    id: Id,
+    author: Option<PublicKey>,
+    referenced_by: Option<Id>,
+) {
+    if ui.link(text).clicked() {
+        render_event_link_clicked(app, ui, id, author, referenced_by);
+    }
+}
+
+fn render_event_link_clicked(
+    app: &mut GossipUi,
+    ui: &mut Ui,
+    id: Id,
+    author: Option<PublicKey>,
+    referenced_by: Option<Id>,
+) {
+    app.set_page(
+        ui.ctx(),
+        Page::Feed(FeedKind::Thread {
+            id,
+            referenced_by: referenced_by.unwrap_or(id),
+            author,
+        }),
+    );
+}
+
+fn render_addr_link(
+    app: &mut GossipUi,
+    ui: &mut Ui,
+    text: RichText,
+    naddr: NAddr,
+    referenced_by: Option<Id>,
+) {
+    if ui.link(text).clicked() {
+        if let Ok(Some(event)) =
+            GLOBALS
+                .db()
+                .get_replaceable_event(naddr.kind, naddr.author, &naddr.d)
+        {
+            render_event_link_clicked(app, ui, event.id, Some(event.pubkey), referenced_by);
+        } else {
+            // Disclose failure
+            GLOBALS
+                .status_queue
+                .write()
+                .write("Parameterized event not found.".to_owned());
+
+            // Start fetch
+            let _ = GLOBALS
+                .to_overlord
+                .send(ToOverlordMessage::FetchNAddr(naddr));
+        }
+    }
+}
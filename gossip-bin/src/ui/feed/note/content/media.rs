@@ -3,7 +3,7 @@ use eframe::{egui, epaint};
 use egui::{Image, Response, RichText, Ui};
 use epaint::Vec2;
 use gossip_lib::{MediaLoadingResult, GLOBALS};
-use nostr_types::{FileMetadata, Url};
+use nostr_types::{FileMetadata, PublicKey, Url};
 
 pub fn show_image(
     app: &mut GossipUi,
@@ -12,6 +12,7 @@ pub fn show_image(
     privacy_issue: bool,
     volatile: bool,
     file_metadata: Option<FileMetadata>,
+    author: Option<PublicKey>,
 ) {
     // insert a newline if the current line has text
     if ui.cursor().min.x > ui.max_rect().min.x {
@@ -22,7 +23,7 @@ pub fn show_image(
 
     // Show image or loading placeholder
     if show(app, &url, privacy_issue) {
-        if try_render_image(app, ui, url.clone(), volatile, file_metadata) {
+        if try_render_image(app, ui, url.clone(), volatile, file_metadata, author) {
             show_link = false;
         }
     }
@@ -85,6 +86,7 @@ pub fn show_video(
     privacy_issue: bool,
     volatile: bool,
     file_metadata: Option<FileMetadata>,
+    author: Option<PublicKey>,
 ) {
     // insert a newline if the current line has text
     if ui.cursor().min.x > ui.max_rect().min.x {
@@ -95,7 +97,7 @@ pub fn show_video(
 
     // Show video player or loading placeholder
     if show(app, &url, privacy_issue) {
-        if try_render_video(app, ui, url.clone(), volatile, file_metadata) {
+        if try_render_video(app, ui, url.clone(), volatile, file_metadata, author) {
             show_link = false;
         }
     }
@@ -162,8 +164,15 @@ fn try_render_image(
     url: Url,
     volatile: bool,
     file_metadata: Option<FileMetadata>,
+    author: Option<PublicKey>,
 ) -> bool {
-    match app.try_get_media(ui.ctx(), url.clone(), volatile, file_metadata.as_ref()) {
+    match app.try_get_media(
+        ui.ctx(),
+        url.clone(),
+        volatile,
+        file_metadata.as_ref(),
+        author,
+    ) {
         MediaLoadingResult::Disabled => {
             // will render link
             false
@@ -254,9 +263,16 @@ fn try_render_video(
     url: Url,
     volatile: bool,
     file_metadata: Option<FileMetadata>,
+    author: Option<PublicKey>,
 ) -> bool {
     let show_full_width = app.media_full_width_list.contains(&url);
-    match app.try_get_player(ui.ctx(), url.clone(), volatile, file_metadata.as_ref()) {
+    match app.try_get_player(
+        ui.ctx(),
+        url.clone(),
+        volatile,
+        file_metadata.as_ref(),
+        author,
+    ) {
         MediaLoadingResult::Disabled => {
             // will render link
             false
@@ -345,6 +361,7 @@ fn try_render_video(
     _url: Url,
     _volatile: bool,
     _file_metadata: Option<FileMetadata>,
+    _author: Option<PublicKey>,
 ) -> bool {
     false
 }
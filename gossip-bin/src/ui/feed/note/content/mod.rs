@@ -1,14 +1,17 @@
+mod markdown;
 mod media;
 
+pub(in crate::ui) use markdown::render_markdown;
+
 use super::{GossipUi, NoteData, Page, RepostType};
 use eframe::egui;
 use egui::{Button, Color32, Margin, Pos2, RichText, Stroke, Ui};
 use gossip_lib::comms::ToOverlordMessage;
 use gossip_lib::FeedKind;
-use gossip_lib::GLOBALS;
+use gossip_lib::{LongFormArticle, GLOBALS};
 use nostr_types::{
-    ContentSegment, FileMetadata, Id, NAddr, NEvent, NostrBech32, NostrUrl, ParsedTag, PublicKey,
-    RelayUrl, Span,
+    ContentSegment, EventKind, FileMetadata, Id, NAddr, NEvent, NostrBech32, NostrUrl, ParsedTag,
+    PublicKey, RelayUrl, Span,
 };
 use std::{
     cell::{Ref, RefCell},
@@ -35,6 +38,12 @@ pub(super) fn render_content(
             // fall through in case there is also shattered content to display
         }
 
+        if note.event.kind == EventKind::LongFormContent {
+            render_long_form(app, ui, &note);
+            ui.reset_style();
+            return;
+        }
+
         let content_start = ui.next_widget_position();
 
         for segment in note.shattered_content.segments.iter() {
@@ -224,6 +233,53 @@ pub(super) fn render_content(
     ui.reset_style();
 }
 
+// NIP-23 articles show their image and summary until opened, and are then
+// rendered as Markdown
+fn render_long_form(app: &mut GossipUi, ui: &mut Ui, note: &Ref<NoteData>) {
+    let article = match LongFormArticle::from_event(&note.event) {
+        Some(article) => article,
+        None => return,
+    };
+
+    if !article.image.is_empty() {
+        if let Some(url) = app.try_check_url(&article.image) {
+            media::show_image(
+                app,
+                ui,
+                url,
+                false,
+                note.volatile,
+                None,
+                Some(note.event.pubkey),
+            );
+        }
+    }
+
+    if app.opened.contains(&note.event.id) {
+        ui.end_row();
+        render_markdown(app, ui, &article.content, Some(note.event.id));
+        ui.end_row();
+        if ui.button("Show less ▲").clicked() {
+            app.opened.remove(&note.event.id);
+        }
+    } else {
+        if !article.summary.is_empty() {
+            ui.label(RichText::new(&article.summary).italics());
+            ui.end_row();
+        }
+        ui.end_row();
+        let text_color = if app.theme.dark_mode {
+            Color32::WHITE
+        } else {
+            Color32::BLACK
+        };
+        let button = Button::new("Read article ▼").stroke(Stroke::new(1.0, text_color));
+        if ui.add(button).clicked() {
+            app.opened.insert(note.event.id);
+        }
+    }
+}
+
 pub(super) fn render_hyperlink(
     app: &mut GossipUi,
     ui: &mut Ui,
@@ -262,9 +318,25 @@ pub(super) fn render_hyperlink(
     if let (Ok(url), Some(nurl)) = (url::Url::try_from(link), app.try_check_url(link)) {
         if let Some(mimetype) = gossip_lib::media_url_mimetype(url.path()) {
             if mimetype.starts_with("image/") {
-                media::show_image(app, ui, nurl, privacy_issue, note.volatile, file_metadata);
+                media::show_image(
+                    app,
+                    ui,
+                    nurl,
+                    privacy_issue,
+                    note.volatile,
+                    file_metadata,
+                    Some(note.event.pubkey),
+                );
             } else if mimetype.starts_with("video/") {
-                media::show_video(app, ui, nurl, privacy_issue, note.volatile, file_metadata);
+                media::show_video(
+                    app,
+                    ui,
+                    nurl,
+                    privacy_issue,
+                    note.volatile,
+                    file_metadata,
+                    Some(note.event.pubkey),
+                );
             }
         } else {
             crate::ui::widgets::break_anywhere_hyperlink_to(ui, link, link);
@@ -1,4 +1,5 @@
 mod content;
+pub(in crate::ui) use content::render_markdown;
 
 use std::cell::RefCell;
 use std::ops::Add;
@@ -251,7 +252,8 @@ pub fn render_dm_note(app: &mut GossipUi, ui: &mut Ui, feed_note_params: FeedNot
                 can_load_thread: false,
                 thread_position: indent as i32,
                 hide_footer: false,
-                hide_nameline: true,
+                // Public chat channels have many authors, so show who wrote what
+                hide_nameline: !matches!(app.page, Page::Feed(FeedKind::Channel(_))),
             };
 
             let inner_response =
@@ -547,6 +549,15 @@ pub fn render_note_inside_framing(
                             );
                         }
 
+                        if !note.reports.is_empty() {
+                            let color = app.theme.warning_marker_text_color();
+                            ui.label(
+                                RichText::new("REPORTED")
+                                    .color(color)
+                                    .text_style(TextStyle::Small),
+                            );
+                        }
+
                         if note.repost.is_some() {
                             let color = app.theme.notice_marker_text_color();
                             ui.label(
@@ -804,6 +815,14 @@ pub fn render_note_inside_framing(
                                             app.draft_data.include_subject = true;
                                         }
                                     }
+
+                                    // Reply count, as counted by relays (NIP-45)
+                                    if let Some(count) = note.relay_reply_count {
+                                        if count > 0 {
+                                            ui.label(format!("{}", count))
+                                                .on_hover_text("Replies counted by relays");
+                                        }
+                                    }
                                 };
 
                                 ui.add_space(24.0);
@@ -1050,6 +1069,14 @@ pub fn render_note_inside_framing(
                                             _ => app.note_showing_reactions = Some(note.event.id),
                                         }
                                     }
+
+                                    // Relays may know of more reactions than we have (NIP-45)
+                                    if let Some(count) = note.relay_reaction_count {
+                                        if count > like_count + reaction_count {
+                                            ui.label(RichText::new(format!("({})", count)).weak())
+                                                .on_hover_text("Reactions counted by relays");
+                                        }
+                                    }
                                 }
 
                                 if GLOBALS.delayed_posts.contains(&note.event.id) {
@@ -1163,12 +1190,12 @@ pub fn render_note_inside_framing(
                                     .on_hover_text("Copy Invoice")
                                     .clicked()
                                 {
-                                    ui.output_mut(|o| {
-                                        if let ZapState::ReadyToPay(_id, ref invoice) =
-                                            app.zap_state
-                                        {
+                                    ui.output_mut(|o| match app.zap_state {
+                                        ZapState::ReadyToPay(_id, ref invoice)
+                                        | ZapState::Failed(_id, ref invoice, _) => {
                                             o.copied_text = invoice.to_owned();
                                         }
+                                        _ => {}
                                     });
                                 }
                             }
@@ -1261,6 +1288,11 @@ fn render_note_between_header_and_footer(
             .inner_margin(content_inner_margin)
             .outer_margin(content_outer_margin)
             .show(ui, |ui| {
+                if !note.reports.is_empty() {
+                    widgets::reports_banner(ui, &app.theme, &note.reports);
+                    ui.add_space(4.0);
+                }
+
                 ui.horizontal_wrapped(|ui| {
                     let feed_kind = GLOBALS.feed.get_feed_kind();
                     if note.muted() && !matches!(feed_kind, FeedKind::Person(_)) {
@@ -1365,10 +1397,60 @@ fn render_note_between_header_and_footer(
                         );
                     }
                 });
+
+                if !note.labels.is_empty() {
+                    ui.add_space(4.0);
+                    if let Some(label) = widgets::label_chips(ui, &app.theme, &note.labels) {
+                        super::set_label_filter(app, Some(label));
+                    }
+                }
             });
     }
 }
 
+fn label_dialog(ui: &mut Ui, app: &mut GossipUi) {
+    let id = match app.label_target {
+        Some(id) => id,
+        None => return,
+    };
+
+    ui.vertical(|ui| {
+        ui.heading("Label note");
+        ui.add_space(10.0);
+
+        ui.label("Namespace");
+        ui.add(
+            text_edit_line!(app, app.label_namespace)
+                .hint_text(gossip_lib::labels::UGC_NAMESPACE)
+                .desired_width(f32::INFINITY),
+        );
+        ui.add_space(6.0);
+
+        ui.label("Label");
+        let response = ui.add(
+            text_edit_line!(app, app.label_text)
+                .hint_text("e.g. spam, nsfw, bitcoin")
+                .desired_width(f32::INFINITY),
+        );
+        ui.add_space(10.0);
+
+        let enter = response.lost_focus() && ui.input(|i| i.key_pressed(egui::Key::Enter));
+        let can_apply = !app.label_text.trim().is_empty();
+        let clicked = ui
+            .add_enabled(can_apply, egui::Button::new("Apply"))
+            .clicked();
+        if can_apply && (clicked || enter) {
+            let _ = GLOBALS.to_overlord.send(ToOverlordMessage::Label(
+                id,
+                app.label_namespace.clone(),
+                app.label_text.clone(),
+            ));
+            app.label_target.take();
+            app.modal.take();
+        }
+    });
+}
+
 fn render_repost(
     app: &mut GossipUi,
     ui: &mut Ui,
@@ -1564,6 +1646,16 @@ fn note_actions(
                 }),
             )));
 
+            // Edit Button for long-form articles
+            if note.event.kind == EventKind::LongFormContent {
+                my_items.push(MoreMenuItem::Button(MoreMenuButton::new(
+                    "Edit Article",
+                    Box::new(|ui, app| {
+                        crate::ui::long_form::edit_article(app, ui.ctx(), &note.event);
+                    }),
+                )));
+            }
+
             // Chance to post our note again to relays it missed
             // (not applicable for NIP-17 DMs)
             if note.encryption != EncryptionType::Giftwrap {
@@ -1621,6 +1713,93 @@ fn note_actions(
         )));
     } // end Bookmark
 
+    // ---- Report ----
+    if let Some(our_pubkey) = GLOBALS.identity.public_key() {
+        if note.event.pubkey != our_pubkey && !note.event.kind.is_direct_message_related() {
+            let mut report_items: Vec<MoreMenuItem> = Vec::new();
+            for report_type in gossip_lib::REPORT_TYPES {
+                let id = note.event.id;
+                let pubkey = note.event.pubkey;
+                let mut label = report_type.to_owned();
+                label[..1].make_ascii_uppercase();
+                report_items.push(MoreMenuItem::Button(
+                    MoreMenuButton::new(
+                        label,
+                        Box::new(move |_, _| {
+                            let _ = GLOBALS.to_overlord.send(ToOverlordMessage::Report(
+                                id,
+                                pubkey,
+                                report_type.to_owned(),
+                            ));
+                        }),
+                    )
+                    .enabled(GLOBALS.identity.is_unlocked()),
+                ));
+            }
+            items.push(MoreMenuItem::SubMenu(MoreMenuSubMenu::new(
+                "Report",
+                report_items,
+                &menu,
+            )));
+        }
+    } // end Report
+
+    // ---- Channel moderation (NIP-28) ----
+    if let Some(our_pubkey) = GLOBALS.identity.public_key() {
+        if note.event.pubkey != our_pubkey && note.event.kind == EventKind::ChannelMessage {
+            let id = note.event.id;
+            let pubkey = note.event.pubkey;
+            items.push(MoreMenuItem::Button(
+                MoreMenuButton::new(
+                    "Hide message",
+                    Box::new(move |_, _| {
+                        let _ = GLOBALS
+                            .to_overlord
+                            .send(ToOverlordMessage::HideChannelMessage(id, String::new()));
+                    }),
+                )
+                .enabled(GLOBALS.identity.is_unlocked()),
+            ));
+            items.push(MoreMenuItem::Button(
+                MoreMenuButton::new(
+                    "Mute user",
+                    Box::new(move |_, _| {
+                        let _ = GLOBALS
+                            .to_overlord
+                            .send(ToOverlordMessage::MuteChannelUser(pubkey, String::new()));
+                    }),
+                )
+                .enabled(GLOBALS.identity.is_unlocked()),
+            ));
+        }
+    } // end Channel moderation
+
+    // ---- Label ----
+    if GLOBALS.identity.public_key().is_some() && !note.event.kind.is_direct_message_related() {
+        items.push(MoreMenuItem::Button(
+            MoreMenuButton::new(
+                "Label...",
+                Box::new(|ui, app| {
+                    app.label_target = Some(note.event.id);
+                    app.label_namespace = String::new();
+                    app.label_text = String::new();
+                    app.modal = Some(Rc::new(ModalEntry {
+                        min_size: vec2(300.0, 200.0),
+                        max_size: vec2(400.0, 300.0).min(ui.ctx().screen_rect().size()),
+                        content: Rc::new(|ui, app| {
+                            label_dialog(ui, app);
+                        }),
+                        on_close: Rc::new(|app| {
+                            app.label_target.take();
+                            app.modal.take();
+                        }),
+                    }));
+                }),
+            )
+            .enabled(GLOBALS.identity.is_unlocked()),
+        ));
+    } // end Label
+
     // ---- Open with ----
     if !note.event.kind.is_direct_message_related() {
         let mut my_items: Vec<MoreMenuItem> = Vec::new();
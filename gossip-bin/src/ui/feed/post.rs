@@ -9,10 +9,22 @@ use egui_winit::egui::text::{CCursor, CCursorRange};
 use egui_winit::egui::text_edit::TextEditOutput;
 use egui_winit::egui::{vec2, AboveOrBelow, Id};
 use gossip_lib::comms::ToOverlordMessage;
-use gossip_lib::{DmChannel, PersonTable, Relay, Table, GLOBALS};
+use gossip_lib::{DmChannel, Draft, PersonTable, Relay, Table, GLOBALS};
 use memoize::memoize;
-use nostr_types::{ContentSegment, NostrBech32, NostrUrl, ParsedTag, ShatteredContent, Tag};
+use nostr_types::{
+    ContentSegment, NostrBech32, NostrUrl, ParsedTag, ShatteredContent, Tag, Unixtime,
+};
 use std::collections::HashMap;
+use std::time::Duration;
+
+// Choices offered for how long until a note expires (NIP-40)
+const EXPIRATION_CHOICES: [(u64, &str); 5] = [
+    (60 * 60, "1 hour"),
+    (60 * 60 * 24, "1 day"),
+    (60 * 60 * 24 * 7, "1 week"),
+    (60 * 60 * 24 * 30, "30 days"),
+    (60 * 60 * 24 * 365, "1 year"),
+];
 
 #[memoize]
 pub fn textarea_highlighter(theme: Theme, text: String, interests: Vec<String>) -> LayoutJob {
@@ -397,6 +409,11 @@ fn dm_posting_area(
         if app.dm_draft_data.include_subject {
             tags.push(ParsedTag::Subject(app.dm_draft_data.subject.clone()).into_tag());
         }
+        for file_metadata in &app.dm_draft_data.attachments {
+            if app.dm_draft_data.draft.contains(&file_metadata.url.0) {
+                tags.push(file_metadata.to_imeta_tag());
+            }
+        }
 
         let _ = GLOBALS.to_overlord.send(ToOverlordMessage::Post {
             content: app.dm_draft_data.draft.clone(),
@@ -404,6 +421,7 @@ fn dm_posting_area(
             in_reply_to: None,
             annotation: app.dm_draft_data.is_annotate,
             dm_channel: Some(dm_channel.to_owned()),
+            expiration: None,
         });
 
         app.reset_draft();
@@ -498,6 +516,20 @@ fn real_posting_area(app: &mut GossipUi, ctx: &Context, ui: &mut Ui) {
                     ui.add_space(10.0);
                 }
 
+                if matches!(app.page, Page::Feed(FeedKind::Group(_, _)))
+                    && app.draft_data.replying_to.is_none()
+                {
+                    ui.horizontal(|ui| {
+                        ui.label("Thread title: ");
+                        ui.add(
+                            text_edit_line!(app, app.groups.thread_title)
+                                .hint_text("Leave empty to post a chat message")
+                                .desired_width(f32::INFINITY),
+                        );
+                    });
+                    ui.add_space(10.0);
+                }
+
                 if app.draft_data.include_content_warning {
                     ui.horizontal(|ui| {
                         ui.label("Content Warning: ");
@@ -510,6 +542,31 @@ fn real_posting_area(app: &mut GossipUi, ctx: &Context, ui: &mut Ui) {
                     ui.add_space(10.0);
                 }
 
+                if app.draft_data.include_expiration {
+                    ui.horizontal(|ui| {
+                        ui.label("Expires after: ");
+                        let selected = EXPIRATION_CHOICES
+                            .iter()
+                            .find(|(secs, _)| *secs == app.draft_data.expiration_secs)
+                            .map(|(_, name)| *name)
+                            .unwrap_or("");
+                        egui::ComboBox::from_id_salt(Id::from("PostExpirationCombo"))
+                            .selected_text(selected)
+                            .show_ui(ui, |ui| {
+                                for (secs, name) in EXPIRATION_CHOICES.iter() {
+                                    ui.selectable_value(
+                                        &mut app.draft_data.expiration_secs,
+                                        *secs,
+                                        *name,
+                                    );
+                                }
+                            });
+                    })
+                    .response
+                    .on_hover_text("Supporting relays and clients drop the note after this");
+                    ui.add_space(10.0);
+                }
+
                 // if we are tagging, we will consume arrow presses and enter key
                 let enter_key;
                 (app.draft_data.tagging_search_selected, enter_key) =
@@ -664,6 +721,32 @@ fn real_posting_area(app: &mut GossipUi, ctx: &Context, ui: &mut Ui) {
                     )));
                 }
 
+                if app.draft_data.include_expiration {
+                    items.push(MoreMenuItem::Button(MoreMenuButton::new(
+                        "Remove Expiration",
+                        Box::new(|_, app| {
+                            app.draft_data.include_expiration = false;
+                        }),
+                    )));
+                } else {
+                    items.push(MoreMenuItem::Button(MoreMenuButton::new(
+                        "Add Expiration",
+                        Box::new(|_, app| {
+                            app.draft_data.include_expiration = true;
+                        }),
+                    )));
+                }
+
+                items.push(MoreMenuItem::Button(
+                    MoreMenuButton::new(
+                        "Save Draft",
+                        Box::new(|_, app| {
+                            save_draft(app);
+                        }),
+                    )
+                    .enabled(!app.draft_data.draft.is_empty()),
+                ));
+
                 items.push(MoreMenuItem::Button(
                     MoreMenuButton::new(
                         "Show raw preview",
@@ -706,6 +789,14 @@ fn real_posting_area(app: &mut GossipUi, ctx: &Context, ui: &mut Ui) {
                             app.draft_data.are_you_sure_cancel = false;
                         }
 
+                        if widgets::Button::primary(&app.theme, "Save Draft")
+                            .show(ui)
+                            .clicked()
+                        {
+                            save_draft(app);
+                            app.reset_draft();
+                        }
+
                         if widgets::Button::primary(&app.theme, "Erase Draft")
                             .show(ui)
                             .clicked()
@@ -775,17 +866,62 @@ fn real_posting_area(app: &mut GossipUi, ctx: &Context, ui: &mut Ui) {
         if app.draft_data.include_subject {
             tags.push(ParsedTag::Subject(app.draft_data.subject.clone()).into_tag());
         }
-        match app.draft_data.replying_to {
-            Some(replying_to_id) => {
+        for file_metadata in &app.draft_data.attachments {
+            if replaced.contains(&file_metadata.url.0) {
+                tags.push(file_metadata.to_imeta_tag());
+            }
+        }
+        let expiration = if app.draft_data.include_expiration {
+            Some(Unixtime::now() + Duration::from_secs(app.draft_data.expiration_secs))
+        } else {
+            None
+        };
+        let group = match &app.page {
+            Page::Feed(FeedKind::Group(relay_url, group)) => {
+                Some((relay_url.clone(), group.clone()))
+            }
+            _ => None,
+        };
+        let channel = match &app.page {
+            Page::Feed(FeedKind::Channel(id)) => Some(*id),
+            _ => None,
+        };
+        match (group.clone(), channel, app.draft_data.replying_to) {
+            (_, Some(channel), in_reply_to) if app.draft_data.repost.is_none() => {
+                let _ = GLOBALS.to_overlord.send(ToOverlordMessage::PostChannel {
+                    channel,
+                    content: replaced,
+                    tags,
+                    in_reply_to,
+                });
+            }
+            (Some((relay_url, group)), _, in_reply_to) if app.draft_data.repost.is_none() => {
+                let title = app.groups.thread_title.trim().to_owned();
+                let _ = GLOBALS.to_overlord.send(ToOverlordMessage::PostGroup {
+                    relay_url,
+                    group,
+                    content: replaced,
+                    tags,
+                    in_reply_to,
+                    title: if title.is_empty() || in_reply_to.is_some() {
+                        None
+                    } else {
+                        Some(title)
+                    },
+                });
+                app.groups.thread_title.clear();
+            }
+            (_, _, Some(replying_to_id)) => {
                 let _ = GLOBALS.to_overlord.send(ToOverlordMessage::Post {
                     content: replaced,
                     tags,
                     in_reply_to: Some(replying_to_id),
                     annotation: app.draft_data.is_annotate,
                     dm_channel: None,
+                    expiration,
                 });
             }
-            None => {
+            (_, _, None) => {
                 if let Some(event_id) = app.draft_data.repost {
                     let _ = GLOBALS
                         .to_overlord
@@ -797,17 +933,26 @@ fn real_posting_area(app: &mut GossipUi, ctx: &Context, ui: &mut Ui) {
                         in_reply_to: None,
                         annotation: app.draft_data.is_annotate,
                         dm_channel: None,
+                        expiration,
                     });
                 }
             }
         }
 
+        // The draft has been sent, so it is no longer needed
+        if let Some(saved_draft) = app.draft_data.saved_draft.take() {
+            let _ = GLOBALS
+                .to_overlord
+                .send(ToOverlordMessage::DeleteDraft(saved_draft));
+        }
+
         let is_reply = app.draft_data.replying_to.is_some();
 
         app.reset_draft();
 
         // So they can see it rendered and see the "Undo Send" button
-        if !is_reply {
+        // (group and channel posts show up in their own feed)
+        if !is_reply && group.is_none() && channel.is_none() {
             if let Some(pubkey) = GLOBALS.identity.public_key() {
                 app.set_page(ctx, Page::Feed(FeedKind::Person(pubkey)));
             }
@@ -839,6 +984,37 @@ fn real_posting_area(app: &mut GossipUi, ctx: &Context, ui: &mut Ui) {
     }
 }
 
+// Save the draft as a NIP-37 draft, replacing the one it was resumed from if any
+fn save_draft(app: &mut GossipUi) {
+    let content = do_replacements(&app.draft_data.draft, &app.draft_data.replacements);
+    let subject = if app.draft_data.include_subject {
+        Some(app.draft_data.subject.clone())
+    } else {
+        None
+    };
+    let content_warning = if app.draft_data.include_content_warning {
+        Some(app.draft_data.content_warning.clone())
+    } else {
+        None
+    };
+
+    let mut draft = Draft::new_text_note(
+        content,
+        subject,
+        content_warning,
+        app.draft_data.replying_to,
+        app.draft_data.is_annotate,
+    );
+    if let Some(saved_draft) = &app.draft_data.saved_draft {
+        draft.identifier = saved_draft.identifier.clone();
+    }
+
+    app.draft_data.saved_draft = Some(draft.clone());
+    let _ = GLOBALS
+        .to_overlord
+        .send(ToOverlordMessage::SaveDraft(draft));
+}
+
 fn calc_tagging_search(app: &mut GossipUi) {
     // show tagging slector tooltip
     if let Some(search) = &app.draft_data.tagging_search_substring {
@@ -1061,27 +1237,32 @@ fn offer_attachment(app: &mut GossipUi, ctx: &Context, ui: &mut Ui, dm: bool) {
     if let Some(pathbuf) = &app.uploading {
         if let Some(result) = GLOBALS.blossom_uploads.get(pathbuf) {
             match result.value() {
-                Ok(bd) => {
-                    if dm {
-                        app.dm_draft_data.draft.push(' ');
-                        app.dm_draft_data.draft.push_str(&bd.url);
-                        if bd.url.len() > 5 && !bd.url[bd.url.len() - 5..].contains('.') {
-                            if let Some(ext) = pathbuf.extension() {
-                                app.dm_draft_data.draft.push('.');
-                                app.dm_draft_data.draft.push_str(&ext.to_string_lossy());
-                            }
-                        }
-                    } else {
-                        app.draft_data.draft.push(' ');
-                        app.draft_data.draft.push_str(&bd.url);
-                        if bd.url.len() > 5 && !bd.url[bd.url.len() - 5..].contains('.') {
-                            if let Some(ext) = pathbuf.extension() {
-                                app.draft_data.draft.push('.');
-                                app.draft_data.draft.push_str(&ext.to_string_lossy());
-                            }
+                Ok(file_metadata) => {
+                    // Offer to describe it before it goes into the draft
+                    ui.add(
+                        egui::TextEdit::singleline(&mut app.upload_alt)
+                            .hint_text("Describe it (optional)")
+                            .desired_width(160.0),
+                    );
+                    if ui.button("Attach").clicked() {
+                        let mut file_metadata = file_metadata.clone();
+                        let alt = app.upload_alt.trim();
+                        if !alt.is_empty() {
+                            file_metadata.alt = Some(alt.to_owned());
                         }
+
+                        let draft_data = if dm {
+                            &mut app.dm_draft_data
+                        } else {
+                            &mut app.draft_data
+                        };
+                        draft_data.draft.push(' ');
+                        draft_data.draft.push_str(&file_metadata.url.0);
+                        draft_data.attachments.push(file_metadata);
+
+                        app.upload_alt.clear();
+                        clear_uploading = true;
                     }
-                    clear_uploading = true;
                 }
                 Err(e) => {
                     if ui
@@ -0,0 +1,118 @@
+use super::{GossipUi, Page};
+use eframe::egui;
+use egui::{Context, RichText, Ui};
+use gossip_lib::comms::ToOverlordMessage;
+use gossip_lib::{FeedKind, JoinedGroup, GLOBALS};
+use std::time::{Duration, Instant};
+
+/// The state of the relay-based groups (NIP-29) page
+#[derive(Debug, Default)]
+pub(super) struct Groups {
+    // A group identifier typed in to join, host'group-id
+    pub identifier: String,
+
+    // The title of a new thread, when posting into a group
+    pub thread_title: String,
+
+    // Groups we joined with their names, refreshed now and then
+    joined: Vec<(JoinedGroup, String)>,
+    joined_next_refresh: Option<Instant>,
+}
+
+/// The groups we joined, sorted by name
+pub(super) fn joined_groups(app: &mut GossipUi) -> Vec<(JoinedGroup, String)> {
+    if !matches!(app.groups.joined_next_refresh, Some(next) if Instant::now() < next) {
+        let mut groups: Vec<(JoinedGroup, String)> = GLOBALS
+            .db()
+            .read_joined_groups()
+            .unwrap_or_default()
+            .into_iter()
+            .map(|g| {
+                let name = g.name();
+                (g, name)
+            })
+            .collect();
+        groups.sort_by(|a, b| a.1.to_lowercase().cmp(&b.1.to_lowercase()));
+        app.groups.joined = groups;
+        app.groups.joined_next_refresh = Some(Instant::now() + Duration::from_secs(5));
+    }
+    app.groups.joined.clone()
+}
+
+pub(super) fn update(app: &mut GossipUi, ctx: &Context, ui: &mut Ui) {
+    ui.add_space(10.0);
+    ui.heading("Groups");
+    ui.add_space(12.0);
+    ui.separator();
+    ui.add_space(10.0);
+
+    if GLOBALS.identity.public_key().is_none() {
+        ui.horizontal(|ui| {
+            ui.label("You need to");
+            if ui.link("setup an identity").clicked() {
+                app.set_page(ctx, Page::YourKeys);
+            }
+            ui.label("to join groups.");
+        });
+        return;
+    }
+
+    ui.horizontal(|ui| {
+        ui.label("Join a group: ");
+        ui.add(
+            text_edit_line!(app, app.groups.identifier)
+                .hint_text("groups.example.com'group-id")
+                .desired_width(400.0),
+        );
+        if ui
+            .add_enabled(GLOBALS.identity.is_unlocked(), egui::Button::new("Join"))
+            .on_disabled_hover_text("Your key must be unlocked to join a group")
+            .clicked()
+        {
+            match gossip_lib::groups::parse_group_identifier(&app.groups.identifier) {
+                Ok((relay_url, group)) => {
+                    let _ = GLOBALS.to_overlord.send(ToOverlordMessage::JoinGroup(
+                        relay_url.clone(),
+                        group.clone(),
+                    ));
+                    app.groups.identifier.clear();
+                    app.set_page(ctx, Page::Feed(FeedKind::Group(relay_url, group)));
+                }
+                Err(e) => GLOBALS.status_queue.write().write(format!("{}", e)),
+            }
+        }
+    });
+    ui.add_space(10.0);
+
+    let groups = joined_groups(app);
+    if groups.is_empty() {
+        ui.label("You have not joined any groups.");
+        return;
+    }
+
+    app.vert_scroll_area()
+        .id_salt("groups_scroll")
+        .show(ui, |ui| {
+            for (group, name) in groups {
+                ui.horizontal(|ui| {
+                    if ui.link(RichText::new(&name).heading()).clicked() {
+                        app.set_page(
+                            ctx,
+                            Page::Feed(FeedKind::Group(group.relay.clone(), group.id.clone())),
+                        );
+                    }
+                    ui.label(RichText::new(format!("{}'{}", group.relay.host(), group.id)).weak());
+
+                    ui.with_layout(egui::Layout::right_to_left(egui::Align::Center), |ui| {
+                        if ui.button("Leave").clicked() {
+                            let _ = GLOBALS.to_overlord.send(ToOverlordMessage::LeaveGroup(
+                                group.relay.clone(),
+                                group.id.clone(),
+                            ));
+                        }
+                    });
+                });
+                ui.add_space(6.0);
+            }
+        });
+}
@@ -90,12 +90,24 @@ pub(super) fn update(app: &mut GossipUi, _ctx: &Context, _frame: &mut eframe::Fr
         ));
         ui.add_space(6.0);
 
+        ui.label(format!(
+            "Event Expirations: {} records",
+            GLOBALS.db().get_event_expiration_len().unwrap_or(0)
+        ));
+        ui.add_space(6.0);
+
         ui.label(format!(
             "Hashtags: {} records",
             GLOBALS.db().get_hashtags_len().unwrap_or(0)
         ));
         ui.add_space(6.0);
 
+        ui.label(format!(
+            "Search Index: {} records",
+            GLOBALS.db().get_search_index_len().unwrap_or(0)
+        ));
+        ui.add_space(6.0);
+
         ui.label(format!(
             "Relays: {} records",
             GLOBALS.db().get_relays_len().unwrap_or(0)
@@ -120,6 +132,12 @@ pub(super) fn update(app: &mut GossipUi, _ctx: &Context, _frame: &mut eframe::Fr
         ));
         ui.add_space(6.0);
 
+        ui.label(format!(
+            "Person-Reports: {} records",
+            GLOBALS.db().get_person_reports_len().unwrap_or(0)
+        ));
+        ui.add_space(6.0);
+
         ui.label(format!(
             "Event Relationships By Id: {} records",
             GLOBALS.db().get_relationships_by_id_len().unwrap_or(0)
@@ -132,12 +150,48 @@ pub(super) fn update(app: &mut GossipUi, _ctx: &Context, _frame: &mut eframe::Fr
         ));
         ui.add_space(6.0);
 
+        ui.label(format!(
+            "Blossom Blobs: {} records",
+            GLOBALS.db().get_blossom_blobs_len().unwrap_or(0)
+        ));
+        ui.add_space(6.0);
+
+        ui.label(format!(
+            "Invalid Zaps: {} records",
+            GLOBALS.db().get_invalid_zaps_len().unwrap_or(0)
+        ));
+        ui.add_space(6.0);
+
+        ui.label(format!(
+            "Followed Channels: {} records",
+            GLOBALS.db().get_followed_channels_len().unwrap_or(0)
+        ));
+        ui.add_space(6.0);
+
+        ui.label(format!(
+            "Joined Groups: {} records",
+            GLOBALS.db().get_joined_groups_len().unwrap_or(0)
+        ));
+        ui.add_space(6.0);
+
+        ui.label(format!(
+            "Relay Stats: {} records",
+            GLOBALS.db().get_relay_stats_len().unwrap_or(0)
+        ));
+        ui.add_space(6.0);
+
         ui.label(format!(
             "Nip46 Servers: {} records",
             GLOBALS.db().get_nip46servers_len().unwrap_or(0)
         ));
         ui.add_space(6.0);
 
+        ui.label(format!(
+            "Nip46 Audit Log: {} records",
+            GLOBALS.db().get_nip46_audit_len().unwrap_or(0)
+        ));
+        ui.add_space(6.0);
+
         ui.label(format!(
             "Followings: {} records",
             FollowingsTable::num_records().unwrap_or(0)
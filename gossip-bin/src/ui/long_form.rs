@@ -0,0 +1,323 @@
+use super::{feed, GossipUi, Page};
+use eframe::egui;
+use egui::{Context, RichText, Ui};
+use gossip_lib::comms::ToOverlordMessage;
+use gossip_lib::{Draft, LongFormArticle, GLOBALS};
+use nostr_types::{Event, EventKind, NostrBech32};
+
+/// The state of the long-form article (NIP-23) editor
+#[derive(Debug, Default)]
+pub(super) struct LongFormEditor {
+    pub article: LongFormArticle,
+
+    // Hashtags as typed, separated by commas or spaces
+    pub hashtags: String,
+
+    // An naddr typed in to load an existing article for editing
+    pub naddr: String,
+
+    pub preview: bool,
+
+    // The NIP-37 draft this was last saved as, or resumed from
+    pub saved_draft: Option<Draft>,
+}
+
+impl LongFormEditor {
+    fn load(&mut self, article: LongFormArticle) {
+        self.hashtags = article.hashtags.join(", ");
+        self.article = article;
+        self.preview = false;
+        self.saved_draft = None;
+    }
+
+    fn clear(&mut self) {
+        *self = LongFormEditor::default();
+    }
+
+    fn is_editing_existing(&self) -> bool {
+        !self.article.identifier.is_empty()
+    }
+
+    // The article with the hashtags as typed
+    fn edited_article(&self) -> LongFormArticle {
+        let mut article = self.article.clone();
+        article.hashtags = self
+            .hashtags
+            .split(|c: char| c == ',' || c.is_whitespace())
+            .filter(|s| !s.is_empty())
+            .map(|s| s.to_owned())
+            .collect();
+        article
+    }
+}
+
+// Save the article as a NIP-37 draft, replacing the one it was last saved as if any
+fn save_draft(app: &mut GossipUi) {
+    let mut draft = app.long_form.edited_article().to_draft();
+    if let Some(saved_draft) = &app.long_form.saved_draft {
+        draft.identifier = saved_draft.identifier.clone();
+    }
+
+    app.long_form.saved_draft = Some(draft.clone());
+    let _ = GLOBALS
+        .to_overlord
+        .send(ToOverlordMessage::SaveDraft(draft));
+}
+
+/// Open the editor on a saved draft of an article
+pub(super) fn resume_draft(app: &mut GossipUi, ctx: &Context, draft: Draft) {
+    if let Some(article) = LongFormArticle::from_draft(&draft) {
+        app.long_form.load(article);
+        app.long_form.saved_draft = Some(draft);
+        app.set_page(ctx, Page::LongFormEditor);
+    }
+}
+
+/// Open the editor on an existing article
+pub(super) fn edit_article(app: &mut GossipUi, ctx: &Context, event: &Event) {
+    if let Some(article) = LongFormArticle::from_event(event) {
+        app.long_form.load(article);
+        app.set_page(ctx, Page::LongFormEditor);
+    }
+}
+
+pub(super) fn update(app: &mut GossipUi, ctx: &Context, ui: &mut Ui) {
+    ui.add_space(10.0);
+    ui.horizontal(|ui| {
+        if app.long_form.is_editing_existing() {
+            ui.heading("Edit Article");
+        } else {
+            ui.heading("Write Article");
+        }
+    });
+    ui.add_space(12.0);
+    ui.separator();
+    ui.add_space(10.0);
+
+    let author = match GLOBALS.identity.public_key() {
+        Some(pk) => pk,
+        None => {
+            ui.horizontal(|ui| {
+                ui.label("You need to");
+                if ui.link("setup an identity").clicked() {
+                    app.set_page(ctx, Page::YourKeys);
+                }
+                ui.label("to write articles.");
+            });
+            return;
+        }
+    };
+
+    // Load an existing article by its address
+    ui.horizontal(|ui| {
+        ui.label("Edit existing article: ");
+        ui.add(
+            text_edit_line!(app, app.long_form.naddr)
+                .hint_text("naddr1...")
+                .desired_width(400.0),
+        );
+        if ui.button("Load").clicked() {
+            load_naddr(app, author);
+        }
+    });
+    ui.add_space(10.0);
+
+    ui.horizontal(|ui| {
+        if ui
+            .selectable_label(!app.long_form.preview, "Edit")
+            .clicked()
+        {
+            app.long_form.preview = false;
+        }
+        if ui
+            .selectable_label(app.long_form.preview, "Preview")
+            .clicked()
+        {
+            app.long_form.preview = true;
+        }
+    });
+    ui.add_space(10.0);
+
+    app.vert_scroll_area()
+        .id_salt("long_form_editor_scroll")
+        .show(ui, |ui| {
+            if app.long_form.preview {
+                show_preview(app, ui);
+            } else {
+                show_editor(app, ui);
+            }
+
+            ui.add_space(18.0);
+
+            ui.horizontal(|ui| {
+                let can_publish = GLOBALS.identity.is_unlocked()
+                    && !app.long_form.article.title.trim().is_empty()
+                    && !app.long_form.article.content.trim().is_empty();
+
+                let label = if app.long_form.is_editing_existing() {
+                    "Republish"
+                } else {
+                    "Publish"
+                };
+
+                if ui
+                    .add_enabled(can_publish, egui::Button::new(label))
+                    .on_disabled_hover_text(
+                        "An unlocked key, a title and some content are required",
+                    )
+                    .clicked()
+                {
+                    let article = app.long_form.edited_article();
+                    let _ = GLOBALS
+                        .to_overlord
+                        .send(ToOverlordMessage::PostLongForm(article));
+
+                    // The article has been published, so its draft is no longer needed
+                    if let Some(saved_draft) = app.long_form.saved_draft.take() {
+                        let _ = GLOBALS
+                            .to_overlord
+                            .send(ToOverlordMessage::DeleteDraft(saved_draft));
+                    }
+
+                    app.long_form.clear();
+                    app.set_page(ui.ctx(), Page::Feed(gossip_lib::FeedKind::Person(author)));
+                }
+
+                btn_h_space!(ui);
+
+                let can_save = GLOBALS.identity.is_unlocked()
+                    && (!app.long_form.article.title.trim().is_empty()
+                        || !app.long_form.article.content.trim().is_empty());
+                if ui
+                    .add_enabled(can_save, egui::Button::new("Save Draft"))
+                    .on_hover_text("Save an encrypted draft that syncs via your relays")
+                    .on_disabled_hover_text(
+                        "An unlocked key and a title or some content are required",
+                    )
+                    .clicked()
+                {
+                    save_draft(app);
+                }
+
+                btn_h_space!(ui);
+
+                if ui.button("Clear").clicked() {
+                    app.long_form.clear();
+                }
+            });
+
+            ui.add_space(18.0);
+        });
+}
+
+fn show_editor(app: &mut GossipUi, ui: &mut Ui) {
+    ui.label("Title");
+    ui.add(
+        text_edit_line!(app, app.long_form.article.title)
+            .hint_text("Title of the article")
+            .desired_width(f32::INFINITY),
+    );
+    ui.add_space(10.0);
+
+    ui.label("Summary");
+    ui.add(
+        text_edit_line!(app, app.long_form.article.summary)
+            .hint_text("A short summary shown before the article is opened")
+            .desired_width(f32::INFINITY),
+    );
+    ui.add_space(10.0);
+
+    ui.label("Image");
+    ui.add(
+        text_edit_line!(app, app.long_form.article.image)
+            .hint_text("https://...")
+            .desired_width(f32::INFINITY),
+    );
+    ui.add_space(10.0);
+
+    ui.label("Hashtags");
+    ui.add(
+        text_edit_line!(app, app.long_form.hashtags)
+            .hint_text("nostr, writing")
+            .desired_width(f32::INFINITY),
+    );
+    ui.add_space(10.0);
+
+    ui.label("Content (Markdown)");
+    ui.add(
+        text_edit_multiline!(app, app.long_form.article.content)
+            .code_editor()
+            .desired_rows(20)
+            .desired_width(f32::INFINITY),
+    );
+}
+
+fn show_preview(app: &mut GossipUi, ui: &mut Ui) {
+    let title = app.long_form.article.title.clone();
+    let summary = app.long_form.article.summary.clone();
+    let content = app.long_form.article.content.clone();
+
+    ui.label(RichText::new(title).text_style(egui::TextStyle::Name("subject".into())));
+    ui.add_space(6.0);
+    if !summary.is_empty() {
+        ui.label(RichText::new(summary).italics());
+        ui.add_space(6.0);
+    }
+    ui.separator();
+    ui.add_space(10.0);
+
+    feed::render_markdown(app, ui, &content, None);
+}
+
+fn load_naddr(app: &mut GossipUi, author: nostr_types::PublicKey) {
+    let text = app.long_form.naddr.trim();
+    let text = text.strip_prefix("nostr:").unwrap_or(text);
+
+    let naddr = match NostrBech32::try_from_string(text) {
+        Some(NostrBech32::NAddr(naddr)) => naddr,
+        _ => {
+            GLOBALS
+                .status_queue
+                .write()
+                .write("That is not an naddr.".to_owned());
+            return;
+        }
+    };
+
+    if naddr.kind != EventKind::LongFormContent {
+        GLOBALS
+            .status_queue
+            .write()
+            .write("That naddr is not a long-form article.".to_owned());
+        return;
+    }
+
+    if naddr.author != author {
+        GLOBALS
+            .status_queue
+            .write()
+            .write("You can only edit your own articles.".to_owned());
+        return;
+    }
+
+    match GLOBALS
+        .db()
+        .get_replaceable_event(naddr.kind, naddr.author, &naddr.d)
+    {
+        Ok(Some(event)) => {
+            if let Some(article) = LongFormArticle::from_event(&event) {
+                app.long_form.load(article);
+            }
+        }
+        Ok(None) => {
+            GLOBALS
+                .status_queue
+                .write()
+                .write("Article not found locally, fetching it. Try again shortly.".to_owned());
+            let _ = GLOBALS
+                .to_overlord
+                .send(ToOverlordMessage::FetchNAddr(naddr));
+        }
+        Err(e) => tracing::error!("{}", e),
+    }
+}
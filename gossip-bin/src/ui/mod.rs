@@ -34,11 +34,15 @@ macro_rules! write_setting {
 }
 
 mod assets;
+mod channels;
 mod dm_chat_list;
+mod drafts;
 mod emojis;
 mod feed;
+mod groups;
 mod handler;
 mod help;
+mod long_form;
 mod notifications;
 mod people;
 mod relays;
@@ -69,15 +73,17 @@ use egui_winit::egui::Rect;
 use egui_winit::egui::Response;
 use egui_winit::egui::ViewportBuilder;
 use gossip_lib::comms::ToOverlordMessage;
+use gossip_lib::nostr_connect_server::Nip46AuditEntry;
 use gossip_lib::{
-    DmChannel, DmChannelData, Error, FeedKind, MediaLoadingResult, Person, PersonList, Private,
-    RunState, ZapState, GLOBALS,
+    CountTarget, DmChannel, DmChannelData, Draft, Error, FeedKind, MediaLoadingResult, Person,
+    PersonList, Private, RunState, ZapState, GLOBALS,
 };
 use handler::Handlers;
 use nostr_types::ContentSegment;
 use nostr_types::RelayUrl;
 use nostr_types::{
-    EventKind, FileMetadata, Id, Metadata, MilliSatoshi, Profile, PublicKey, UncheckedUrl, Url,
+    EventKind, FileMetadata, Id, Metadata, MilliSatoshi, Profile, PublicKey, UncheckedUrl,
+    Unixtime, Url,
 };
 use widgets::ModalEntry;
 
@@ -155,10 +161,14 @@ pub fn run() -> Result<(), Error> {
 
 #[derive(Debug, Clone, PartialEq)]
 enum Page {
+    Channels,
     DmChatList,
+    Drafts,
     Feed(FeedKind),
+    Groups,
     HandlerKinds,
     Handlers(EventKind),
+    LongFormEditor,
     Notifications,
     PeopleLists,
     PeopleList(PersonList),
@@ -169,6 +179,7 @@ enum Page {
     YourMetadata,
     YourDelegation,
     YourNostrConnect,
+    YourUploads,
     RelaysActivityMonitor,
     RelaysCoverage,
     RelaysMine,
@@ -198,10 +209,14 @@ impl Page {
 impl Page {
     pub fn to_readable(&self) -> (&'static str /* Category */, String /* Name */) {
         match self {
+            Page::Channels => (SubMenu::Channels.as_str(), "Manage Channels".into()),
             Page::DmChatList => (SubMenu::Feeds.as_str(), "Private msgs".into()),
+            Page::Drafts => ("Drafts", "Drafts".into()),
             Page::Feed(feedkind) => ("Feed", feedkind.to_string()),
+            Page::Groups => (SubMenu::Groups.as_str(), "Manage Groups".into()),
             Page::HandlerKinds => ("Event Handlers", "Event Handlers".into()),
             Page::Handlers(kind) => ("Event Handler", format!("{:?}", kind)),
+            Page::LongFormEditor => ("Article", "Write Article".into()),
             Page::Notifications => ("Notifications", "Notifications".into()),
             Page::PeopleLists => ("Lists", "Lists".into()),
             Page::PeopleList(list) => {
@@ -228,6 +243,7 @@ impl Page {
             Page::YourMetadata => (SubMenu::Account.as_str(), "Profile".into()),
             Page::YourDelegation => (SubMenu::Account.as_str(), "Delegation".into()),
             Page::YourNostrConnect => (SubMenu::Account.as_str(), "Nostr Connect".into()),
+            Page::YourUploads => (SubMenu::Account.as_str(), "My Uploads".into()),
             Page::RelaysActivityMonitor => (SubMenu::Relays.as_str(), "Active Relays".into()),
             Page::RelaysCoverage => (SubMenu::Relays.as_str(), "Coverage Report".into()),
             Page::RelaysMine => (SubMenu::Relays.as_str(), "My Relays".into()),
@@ -270,9 +286,11 @@ impl Page {
             Page::Person(_) => name_cat(self),
             Page::PersonFollows(_) => name_cat(self),
             Page::PersonFollowers(_) => name_cat(self),
-            Page::YourKeys | Page::YourMetadata | Page::YourDelegation | Page::YourNostrConnect => {
-                cat_name(self)
-            }
+            Page::YourKeys
+            | Page::YourMetadata
+            | Page::YourDelegation
+            | Page::YourNostrConnect
+            | Page::YourUploads => cat_name(self),
             Page::Wizard(_) => name_cat(self),
             _ => name(self),
         }
@@ -283,6 +301,8 @@ impl Page {
 enum SubMenu {
     Feeds,
     Search,
+    Groups,
+    Channels,
     Relays,
     Account,
     Help,
@@ -293,6 +313,8 @@ impl SubMenu {
         match self {
             SubMenu::Feeds => "Feeds",
             SubMenu::Search => "Search",
+            SubMenu::Groups => "Groups",
+            SubMenu::Channels => "Channels",
             SubMenu::Relays => "Relays",
             SubMenu::Account => "Account",
             SubMenu::Help => "Help",
@@ -303,6 +325,8 @@ impl SubMenu {
         match self {
             SubMenu::Feeds => "feeds_submenu_id",
             SubMenu::Search => "search_submenu_id",
+            SubMenu::Groups => "groups_submenu_id",
+            SubMenu::Channels => "channels_submenu_id",
             SubMenu::Account => "account_submenu_id",
             SubMenu::Relays => "relays_submenu_id",
             SubMenu::Help => "help_submenu_id",
@@ -357,6 +381,10 @@ pub struct DraftData {
     pub include_content_warning: bool,
     pub content_warning: String,
 
+    // NIP-40 expiration, in seconds from when the note is sent
+    pub include_expiration: bool,
+    pub expiration_secs: u64,
+
     // Data for normal draft
     pub repost: Option<Id>,
     pub replying_to: Option<Id>,
@@ -372,6 +400,12 @@ pub struct DraftData {
 
     // If this is an annotation
     pub is_annotate: bool,
+
+    // The saved NIP-37 draft this was resumed from or last saved as
+    pub saved_draft: Option<Draft>,
+
+    // NIP-94 descriptions of our uploads in the draft, for NIP-92 imeta tags
+    pub attachments: Vec<FileMetadata>,
 }
 
 impl Default for DraftData {
@@ -387,6 +421,8 @@ impl Default for DraftData {
             subject: "".to_owned(),
             include_content_warning: false,
             content_warning: "".to_owned(),
+            include_expiration: false,
+            expiration_secs: 60 * 60 * 24,
 
             // The following are ignored for DMs
             repost: None,
@@ -400,6 +436,9 @@ impl Default for DraftData {
             tagging_search_results: Vec::new(),
 
             is_annotate: false,
+
+            saved_draft: None,
+            attachments: Vec::new(),
         }
     }
 }
@@ -416,6 +455,8 @@ impl DraftData {
         self.subject = "".to_owned();
         self.include_content_warning = false;
         self.content_warning = "".to_owned();
+        self.include_expiration = false;
+        self.expiration_secs = 60 * 60 * 24;
         self.repost = None;
         self.replying_to = None;
         self.are_you_sure_cancel = false;
@@ -424,6 +465,8 @@ impl DraftData {
         self.tagging_search_searched = None;
         self.tagging_search_results.clear();
         self.is_annotate = false;
+        self.saved_draft = None;
+        self.attachments.clear();
     }
 }
 
@@ -474,6 +517,11 @@ struct GossipUi {
     render_raw: Option<(Id, String)>,
     render_qr: Option<Id>,
     approved: HashSet<Id>, // content warning posts
+
+    // Labelling (NIP-32) a note
+    label_target: Option<Id>,
+    label_namespace: String,
+    label_text: String,
     feed_note_height: HashMap<Id, f32>,
 
     // Person page rendering ('npub', 'nprofile', or 'lud06')
@@ -492,6 +540,13 @@ struct GossipUi {
     displayed_feed: Vec<Id>,
     displayed_feed_hash: Option<Id>,
     update_once_immediately: bool,
+    // Only show notes with this (namespace, label)
+    feed_label_filter: Option<(String, String)>,
+    // The notes of the displayed feed labelled that way, the feed they were taken
+    // from, and when to look again
+    feed_label_filter_ids: Vec<Id>,
+    feed_label_filter_hash: Option<Id>,
+    feed_label_filter_next_refresh: Instant,
 
     // General Data
     assets: Assets,
@@ -518,6 +573,12 @@ struct GossipUi {
     previous_draft_data: DraftData,
     dm_draft_data: DraftData,
     dm_draft_data_target: Option<DmChannel>,
+    long_form: long_form::LongFormEditor,
+    groups: groups::Groups,
+    channels: channels::Channels,
+    drafts: Vec<Draft>,
+    drafts_pending: usize,
+    drafts_next_reload: Instant,
 
     // User entry: metadata
     editing_metadata: bool,
@@ -535,6 +596,7 @@ struct GossipUi {
     new_metadata_fieldname: String,
     import_priv: String,
     import_pub: String,
+    bunker_token: String,
     search: String,
     entering_a_search_page: bool,
     search_started: bool,
@@ -550,6 +612,8 @@ struct GossipUi {
     nostr_connect_name: String,
     nostr_connect_relay1: String,
     nostr_connect_relay2: String,
+    nostr_connect_policy: Option<you::Nip46PolicyForm>,
+    blossom_delete_confirm: Option<String>,
 
     // search result
     search_note_height: HashMap<Id, f32>,
@@ -587,8 +651,17 @@ struct GossipUi {
     dm_channel_next_refresh: Instant,
     dm_channel_error: Option<String>,
 
+    // Cached reports by our follows about the person being viewed
+    person_reports_cache: Option<(PublicKey, Vec<(PublicKey, String)>)>,
+    person_reports_next_refresh: Instant,
+
+    // Cached NIP-46 audit logs of the services we sign for
+    nip46_audit_cache: HashMap<PublicKey, Vec<Nip46AuditEntry>>,
+    nip46_audit_next_refresh: Instant,
+
     file_dialog: FileDialog,
     uploading: Option<PathBuf>,
+    upload_alt: String,
 }
 
 impl Drop for GossipUi {
@@ -625,6 +698,11 @@ impl GossipUi {
         let mut submenu_ids: HashMap<SubMenu, egui::Id> = HashMap::new();
         submenu_ids.insert(SubMenu::Feeds, egui::Id::new(SubMenu::Feeds.as_id_str()));
         submenu_ids.insert(SubMenu::Search, egui::Id::new(SubMenu::Search.as_id_str()));
+        submenu_ids.insert(SubMenu::Groups, egui::Id::new(SubMenu::Groups.as_id_str()));
+        submenu_ids.insert(
+            SubMenu::Channels,
+            egui::Id::new(SubMenu::Channels.as_id_str()),
+        );
         submenu_ids.insert(
             SubMenu::Account,
             egui::Id::new(SubMenu::Account.as_id_str()),
@@ -766,6 +844,9 @@ impl GossipUi {
             render_raw: None,
             render_qr: None,
             approved: HashSet::new(),
+            label_target: None,
+            label_namespace: String::new(),
+            label_text: String::new(),
             feed_note_height: HashMap::new(),
             person_qr: None,
             setting_active_person: false,
@@ -778,6 +859,10 @@ impl GossipUi {
             displayed_feed: Vec::new(),
             displayed_feed_hash: None,
             update_once_immediately: false,
+            feed_label_filter: None,
+            feed_label_filter_ids: Vec::new(),
+            feed_label_filter_hash: None,
+            feed_label_filter_next_refresh: Instant::now(),
 
             // load Assets, but load again when DPI changes
             assets,
@@ -799,6 +884,12 @@ impl GossipUi {
             previous_draft_data: DraftData::default(),
             dm_draft_data: DraftData::default(),
             dm_draft_data_target: None,
+            long_form: Default::default(),
+            groups: Default::default(),
+            channels: Default::default(),
+            drafts: Vec::new(),
+            drafts_pending: 0,
+            drafts_next_reload: Instant::now(),
             editing_metadata: false,
             metadata: Metadata::new(),
             delegatee_tag_str: "".to_owned(),
@@ -810,6 +901,7 @@ impl GossipUi {
             new_metadata_fieldname: String::new(),
             import_priv: "".to_owned(),
             import_pub: "".to_owned(),
+            bunker_token: "".to_owned(),
             search: "".to_owned(),
             entering_a_search_page: false,
             search_started: false,
@@ -825,6 +917,8 @@ impl GossipUi {
             nostr_connect_name: "".to_owned(),
             nostr_connect_relay1: "".to_owned(),
             nostr_connect_relay2: "".to_owned(),
+            nostr_connect_policy: None,
+            blossom_delete_confirm: None,
             search_note_height: HashMap::new(),
             search_person_height: HashMap::new(),
             collapsed: vec![],
@@ -841,9 +935,14 @@ impl GossipUi {
             theme_test: Default::default(),
             dm_channel_cache: vec![],
             dm_channel_next_refresh: Instant::now(),
+            person_reports_cache: None,
+            person_reports_next_refresh: Instant::now(),
+            nip46_audit_cache: HashMap::new(),
+            nip46_audit_next_refresh: Instant::now(),
             dm_channel_error: None,
             file_dialog: FileDialog::new(),
             uploading: None,
+            upload_alt: String::new(),
         }
     }
 
@@ -931,14 +1030,26 @@ impl GossipUi {
         match &page {
             Page::Feed(feed_kind) => {
                 let is_list = matches!(feed_kind, FeedKind::List(_, _));
+                let is_group = matches!(feed_kind, FeedKind::Group(_, _));
+                let is_channel = matches!(feed_kind, FeedKind::Channel(_));
                 GLOBALS.feed.switch_feed(feed_kind.clone());
                 feed::enter_feed(self, ctx, feed_kind.to_owned());
                 if is_list {
                     self.open_menu(ctx, SubMenu::Feeds);
+                } else if is_group {
+                    self.open_menu(ctx, SubMenu::Groups);
+                } else if is_channel {
+                    self.open_menu(ctx, SubMenu::Channels);
                 } else {
                     self.close_all_menus_except_feeds(ctx);
                 }
             }
+            Page::Groups => {
+                self.open_menu(ctx, SubMenu::Groups);
+            }
+            Page::Channels => {
+                self.open_menu(ctx, SubMenu::Channels);
+            }
             Page::PeopleLists => {
                 people::enter_page(self);
                 self.close_all_menus_except_feeds(ctx);
@@ -950,6 +1061,10 @@ impl GossipUi {
                 let _ = GLOBALS
                     .to_overlord
                     .send(ToOverlordMessage::UpdateMetadata(*pubkey));
+                // Ask relays that can count (NIP-45) how many follow them
+                let _ = GLOBALS.to_overlord.send(ToOverlordMessage::Count(vec![
+                    CountTarget::Followers(*pubkey),
+                ]));
             }
             Page::PersonFollows(pubkey) => {
                 self.close_all_menus_except_feeds(ctx);
@@ -980,6 +1095,10 @@ impl GossipUi {
             Page::YourKeys | Page::YourMetadata | Page::YourDelegation | Page::YourNostrConnect => {
                 self.open_menu(ctx, SubMenu::Account);
             }
+            Page::YourUploads => {
+                let _ = GLOBALS.to_overlord.send(ToOverlordMessage::BlossomList);
+                self.open_menu(ctx, SubMenu::Account);
+            }
             Page::RelaysActivityMonitor | Page::RelaysCoverage | Page::RelaysMine => {
                 self.relays.enter_page(None);
                 self.open_menu(ctx, SubMenu::Relays);
@@ -1004,6 +1123,10 @@ impl GossipUi {
             Page::HelpHelp | Page::HelpStats | Page::HelpAbout => {
                 self.open_menu(ctx, SubMenu::Help);
             }
+            Page::Drafts => {
+                drafts::reload(self);
+                self.close_all_menus_except_feeds(ctx);
+            }
             Page::Notifications => {
                 let _ = GLOBALS.pending.compute_pending();
                 self.close_all_menus_except_feeds(ctx);
@@ -1037,6 +1160,7 @@ impl GossipUi {
                     } else {
                         ui.add_space(10.0);
                     }
+                    self.render_remote_signer_pending(ui);
                     self.render_status_queue_area(ui);
                     ui.add_space(10.0);
                     self.add_offline_switch(ui);
@@ -1087,6 +1211,8 @@ impl GossipUi {
                 self.add_global_feed(ui, ctx);
                 self.add_personal_notes(ui, ctx);
                 self.add_private_chats(ui, ctx);
+                self.add_groups_submenu(ui, ctx);
+                self.add_channels_submenu(ui, ctx);
                 self.add_search_submenu(ui, ctx);
 
                 ui.add_space(10.0);
@@ -1246,6 +1372,20 @@ impl GossipUi {
             {
                 self.set_page(ctx, Page::Feed(FeedKind::Bookmarks));
             }
+
+            if self
+                .add_selected_label(ui, self.page == Page::Drafts, "Drafts")
+                .clicked()
+            {
+                self.set_page(ctx, Page::Drafts);
+            }
+
+            if self
+                .add_selected_label(ui, self.page == Page::LongFormEditor, "Write Article")
+                .clicked()
+            {
+                self.set_page(ctx, Page::LongFormEditor);
+            }
         }
     }
 
@@ -1288,6 +1428,43 @@ impl GossipUi {
         }
     }
 
+    fn add_groups_submenu(&mut self, ui: &mut Ui, ctx: &Context) {
+        if GLOBALS.identity.public_key().is_none() {
+            return;
+        }
+        let joined = groups::joined_groups(self);
+        let (mut cstate, header_response) = self.get_openable_menu(ui, ctx, SubMenu::Groups);
+        cstate.show_body_indented(&header_response, ui, |ui| {
+            for (group, name) in joined {
+                self.add_menu_item_page(
+                    ui,
+                    Page::Feed(FeedKind::Group(group.relay, group.id)),
+                    Some(&name),
+                    true,
+                );
+            }
+            self.add_menu_item_page(ui, Page::Groups, None, true);
+        });
+        self.after_openable_menu(ui, &cstate);
+    }
+
+    fn add_channels_submenu(&mut self, ui: &mut Ui, ctx: &Context) {
+        let followed = channels::followed_channels(self);
+        let (mut cstate, header_response) = self.get_openable_menu(ui, ctx, SubMenu::Channels);
+        cstate.show_body_indented(&header_response, ui, |ui| {
+            for (channel, name) in followed {
+                self.add_menu_item_page(
+                    ui,
+                    Page::Feed(FeedKind::Channel(channel.id)),
+                    Some(&name),
+                    true,
+                );
+            }
+            self.add_menu_item_page(ui, Page::Channels, None, true);
+        });
+        self.after_openable_menu(ui, &cstate);
+    }
+
     fn add_search_submenu(&mut self, ui: &mut Ui, ctx: &Context) {
         let (mut cstate, header_response) = self.get_openable_menu(ui, ctx, SubMenu::Search);
         cstate.show_body_indented(&header_response, ui, |ui| {
@@ -1323,6 +1500,7 @@ impl GossipUi {
             self.add_menu_item_page(ui, Page::YourKeys, None, true);
             self.add_menu_item_page(ui, Page::YourDelegation, None, true);
             self.add_menu_item_page(ui, Page::YourNostrConnect, None, true);
+            self.add_menu_item_page(ui, Page::YourUploads, None, true);
         });
         self.after_openable_menu(ui, &cstate);
     }
@@ -1545,6 +1723,7 @@ impl GossipUi {
             && self.person_qr.is_none()
             && self.render_qr.is_none()
             && self.render_raw.is_none()
+            && self.label_target.is_none()
     }
 
     fn begin_ui(&self, ui: &mut Ui) {
@@ -1745,6 +1924,7 @@ impl GossipUi {
         url: Url,
         volatile: bool,
         file_metadata: Option<&FileMetadata>,
+        author: Option<PublicKey>,
     ) -> MediaLoadingResult<TextureHandle> {
         // Do not keep retrying if failed
         if let Some(failure) = GLOBALS.media.has_failed(&url.to_unchecked_url()) {
@@ -1756,7 +1936,10 @@ impl GossipUi {
             return MediaLoadingResult::Ready(th.to_owned());
         }
 
-        match GLOBALS.media.get_image(&url, volatile, file_metadata) {
+        match GLOBALS
+            .media
+            .get_image(&url, volatile, file_metadata, author)
+        {
             MediaLoadingResult::Disabled => MediaLoadingResult::Disabled,
             MediaLoadingResult::Loading => {
                 if let Some(fm) = file_metadata {
@@ -1816,6 +1999,7 @@ impl GossipUi {
         url: Url,
         volatile: bool,
         file_metadata: Option<&FileMetadata>,
+        author: Option<PublicKey>,
     ) -> MediaLoadingResult<Rc<RefCell<egui_video::Player>>> {
         // Do not keep retrying if failed
         if let Some(failure) = GLOBALS.media.has_failed(&url.to_unchecked_url()) {
@@ -1827,7 +2011,10 @@ impl GossipUi {
             return MediaLoadingResult::Ready(player.to_owned());
         }
 
-        match GLOBALS.media.get_data(&url, volatile, file_metadata) {
+        match GLOBALS
+            .media
+            .get_data(&url, volatile, file_metadata, author)
+        {
             MediaLoadingResult::Disabled => MediaLoadingResult::Disabled,
             MediaLoadingResult::Loading => MediaLoadingResult::Loading,
             MediaLoadingResult::Ready(bytes) => {
@@ -2147,6 +2334,40 @@ impl GossipUi {
                 // we have to copy it and get out of the borrow first
                 qr_string = Some(invoice.to_owned());
             }
+            ZapState::Paying(_id, _, msats) => {
+                ui.label(format!("Paying {} sats...", msats.0 / 1000));
+                if ui.button("Close").clicked() {
+                    *GLOBALS.current_zap.write() = ZapState::None;
+                }
+            }
+            ZapState::Paid(_id, msats) => {
+                ui.label(format!(
+                    "Paid {} sats. Waiting for the zap receipt...",
+                    msats.0 / 1000
+                ));
+                if ui.button("Close").clicked() {
+                    *GLOBALS.current_zap.write() = ZapState::None;
+                }
+            }
+            ZapState::Receipted(id, _receipt, msats) => {
+                ui.label(format!(
+                    "Zapped {} sats. The zap receipt is in.",
+                    msats.0 / 1000
+                ));
+                if ui.link("Show zaps").clicked() {
+                    self.note_showing_zaps = Some(id);
+                    *GLOBALS.current_zap.write() = ZapState::None;
+                }
+                if ui.button("Close").clicked() {
+                    *GLOBALS.current_zap.write() = ZapState::None;
+                }
+            }
+            ZapState::Failed(_id, ref invoice, ref error) => {
+                ui.label(format!(
+                    "Payment failed: {error}. You can pay the invoice yourself:"
+                ));
+                qr_string = Some(invoice.to_owned());
+            }
         };
 
         if let Some(qr) = qr_string {
@@ -2209,6 +2430,28 @@ impl GossipUi {
         ui.label(spinner);
     }
 
+    fn render_remote_signer_pending(&self, ui: &mut Ui) {
+        let count = GLOBALS.nip46_client_requests.len();
+        if count == 0 {
+            return;
+        }
+
+        let now = Unixtime::now();
+        let mut waiting: Vec<String> = GLOBALS
+            .nip46_client_requests
+            .iter()
+            .map(|r| format!("{} ({}s)", r.method, now.0 - r.sent_at.0))
+            .collect();
+        waiting.sort();
+
+        ui.label(
+            RichText::new(format!("🔑 waiting on remote signer ({count})"))
+                .color(self.theme.warning_marker_text_color()),
+        )
+        .on_hover_text(waiting.join("\n"));
+        ui.add_space(10.0);
+    }
+
     fn render_status_queue_area(&self, ui: &mut Ui) {
         let messages = GLOBALS.status_queue.read().read_all();
         if ui
@@ -2498,13 +2741,7 @@ impl eframe::App for GossipUi {
 
         // Prepare local zap data once per frame for easier compute at render time
         self.zap_state = (*GLOBALS.current_zap.read()).clone();
-        self.note_being_zapped = match self.zap_state {
-            ZapState::None => None,
-            ZapState::CheckingLnurl(id, _, _) => Some(id),
-            ZapState::SeekingAmount(id, _, _, _) => Some(id),
-            ZapState::LoadingInvoice(id, _) => Some(id),
-            ZapState::ReadyToPay(id, _) => Some(id),
-        };
+        self.note_being_zapped = self.zap_state.zapped_id();
 
         egui::CentralPanel::default()
             .frame({
@@ -2519,10 +2756,14 @@ impl eframe::App for GossipUi {
             .show(ctx, |ui| {
                 self.begin_ui(ui);
                 match self.page {
+                    Page::Channels => channels::update(self, ctx, ui),
                     Page::DmChatList => dm_chat_list::update(self, ctx, frame, ui),
+                    Page::Drafts => drafts::update(self, ctx, ui),
                     Page::Feed(_) => feed::update(self, ctx, ui),
+                    Page::Groups => groups::update(self, ctx, ui),
                     Page::HandlerKinds => handler::update_all_kinds(self, ctx, ui),
                     Page::Handlers(kind) => handler::update_kind(self, ctx, ui, kind),
+                    Page::LongFormEditor => long_form::update(self, ctx, ui),
                     Page::Notifications => notifications::update(self, ui),
                     Page::PeopleLists
                     | Page::PeopleList(_)
@@ -2532,7 +2773,8 @@ impl eframe::App for GossipUi {
                     Page::YourKeys
                     | Page::YourMetadata
                     | Page::YourDelegation
-                    | Page::YourNostrConnect => you::update(self, ctx, frame, ui),
+                    | Page::YourNostrConnect
+                    | Page::YourUploads => you::update(self, ctx, frame, ui),
                     Page::RelaysActivityMonitor
                     | Page::RelaysCoverage
                     | Page::RelaysMine
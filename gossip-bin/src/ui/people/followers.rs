@@ -1,7 +1,7 @@
 use super::GossipUi;
 use eframe::egui;
 use egui::{Context, RichText, Ui};
-use gossip_lib::{Person, PersonTable, Table, GLOBALS};
+use gossip_lib::{CountTarget, Person, PersonTable, Table, GLOBALS};
 use nostr_types::PublicKey;
 
 pub(super) fn update(
@@ -51,7 +51,18 @@ pub(super) fn update(
         }
 
         let count = followers.set.len();
-        ui.heading(format!("{} Followers", count));
+        ui.horizontal(|ui| {
+            ui.heading(format!("{} Followers", count));
+
+            // Relays that can count (NIP-45) may know of more than we have downloaded
+            if let Some(relay_count) =
+                gossip_lib::counts::relay_count(CountTarget::Followers(pubkey))
+            {
+                if relay_count > count {
+                    ui.label(RichText::new(format!("({} counted by relays)", relay_count)).weak());
+                }
+            }
+        });
 
         let height: f32 = 48.0;
 
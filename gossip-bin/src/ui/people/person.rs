@@ -12,11 +12,12 @@ use egui_winit::egui::Response;
 use egui_winit::egui::Widget;
 use gossip_lib::comms::ToOverlordMessage;
 use gossip_lib::{
-    DmChannel, FeedKind, Freshness, People, Person, PersonList, PersonTable, Private, Table,
-    GLOBALS,
+    CountTarget, DmChannel, FeedKind, Freshness, Label, People, Person, PersonList, PersonTable,
+    Private, Table, GLOBALS,
 };
 use nostr_types::PublicKey;
 use serde_json::Value;
+use std::time::{Duration, Instant};
 
 const ITEM_V_SPACE: f32 = 2.0;
 const AVATAR_COL_WIDTH: f32 = AVATAR_SIZE_F32 * 3.0;
@@ -49,6 +50,30 @@ pub(super) fn update(app: &mut GossipUi, ctx: &Context, _frame: &mut eframe::Fra
         );
     });
 
+    let reports = person_reports(app, pubkey);
+    if !reports.is_empty() {
+        ui.add_space(6.0);
+        ui.horizontal(|ui| {
+            ui.add_space(10.0);
+            widgets::reports_banner(ui, &app.theme, &reports);
+        });
+    }
+
+    let labels: Vec<Label> = GLOBALS
+        .db()
+        .get_person_labels(pubkey)
+        .unwrap_or_default()
+        .drain(..)
+        .filter(|l| l.is_trusted())
+        .collect();
+    if !labels.is_empty() {
+        ui.add_space(6.0);
+        ui.horizontal(|ui| {
+            ui.add_space(10.0);
+            let _ = widgets::label_chips(ui, &app.theme, &labels);
+        });
+    }
+
     app.vert_scroll_area()
         .id_salt("person page")
         .max_width(f32::INFINITY)
@@ -58,6 +83,28 @@ pub(super) fn update(app: &mut GossipUi, ctx: &Context, _frame: &mut eframe::Fra
         });
 }
 
+// The reports by our follows about this person, refreshed every few seconds
+// rather than read from the database every frame
+fn person_reports(app: &mut GossipUi, pubkey: PublicKey) -> Vec<(PublicKey, String)> {
+    let stale = match &app.person_reports_cache {
+        Some((cached_pubkey, _)) => *cached_pubkey != pubkey,
+        None => true,
+    };
+    if stale || app.person_reports_next_refresh < Instant::now() {
+        let reports = GLOBALS
+            .db()
+            .get_person_reports_by_follows(pubkey)
+            .unwrap_or_default();
+        app.person_reports_cache = Some((pubkey, reports));
+        app.person_reports_next_refresh = Instant::now() + Duration::new(5, 0);
+    }
+
+    match &app.person_reports_cache {
+        Some((_, reports)) => reports.clone(),
+        None => vec![],
+    }
+}
+
 fn content(app: &mut GossipUi, ctx: &Context, ui: &mut Ui, pubkey: PublicKey, person: Person) {
     let npub = pubkey.as_bech32_string();
     let mut lud06 = "unable to get lud06".to_owned();
@@ -550,7 +597,13 @@ fn content(app: &mut GossipUi, ctx: &Context, ui: &mut Ui, pubkey: PublicKey, pe
 
                             ui.add_space(BTN_SPACING);
 
-                            if widgets::Button::primary(&app.theme, "Their Followers")
+                            let followers_label = match gossip_lib::counts::relay_count(
+                                CountTarget::Followers(pubkey),
+                            ) {
+                                Some(count) => format!("Their Followers ({})", count),
+                                None => "Their Followers".to_owned(),
+                            };
+                            if widgets::Button::primary(&app.theme, followers_label)
                                 .show(ui)
                                 .clicked()
                             {
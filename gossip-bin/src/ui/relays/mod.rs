@@ -248,6 +248,14 @@ pub(super) fn relay_scroll_list(app: &mut GossipUi, ui: &mut Ui, relays: Vec<Rel
                 if let Some(ref assignment) = GLOBALS.relay_picker.get_relay_assignment(&db_url) {
                     widget.set_user_count(assignment.pubkeys.len());
                 }
+                if edit || app.relays.show_details {
+                    if let Ok(stats) = gossip_lib::relay_stats::relay_stats_total(
+                        &db_url,
+                        super::widgets::RELAY_STATS_DAYS,
+                    ) {
+                        widget.set_stats(stats);
+                    }
+                }
                 let response = ui.add_enabled_ui(enabled, |ui| widget.show(app, ui)).inner;
                 if response.clicked() {
                     if !edit {
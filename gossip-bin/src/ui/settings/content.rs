@@ -59,6 +59,14 @@ pub(super) fn update(app: &mut GossipUi, _ctx: &Context, _frame: &mut eframe::Fr
         reset_button!(app, ui, enable_zap_receipts);
     });
 
+    ui.horizontal(|ui| {
+        ui.checkbox(
+            &mut app.unsaved_settings.check_zap_receipt_signers,
+            "Check who signed zap receipts (asks each recipient's lightning service)",
+        );
+        reset_button!(app, ui, check_zap_receipt_signers);
+    });
+
     ui.horizontal(|ui| {
         ui.checkbox(
             &mut app.unsaved_settings.enable_picture_events,
@@ -184,6 +192,20 @@ pub(super) fn update(app: &mut GossipUi, _ctx: &Context, _frame: &mut eframe::Fr
         reset_button!(app, ui, show_media);
     });
 
+    ui.horizontal(|ui| {
+        ui.label("Largest size to show images at")
+            .on_hover_text("Larger images are downsized once, and the smaller copy is kept on disk and used from then on. This makes scrolling through media faster.");
+        ui.add(Slider::new(&mut app.unsaved_settings.media_max_dimension, 400..=4800).text("points"));
+        reset_button!(app, ui, media_max_dimension);
+    });
+
+    ui.horizontal(|ui| {
+        ui.label("Memory for images waiting to be shown")
+            .on_hover_text("Images are prepared in the background. If more than this is waiting, the oldest are dropped and prepared again if needed.");
+        ui.add(Slider::new(&mut app.unsaved_settings.media_memory_cap_mb, 64..=4096).logarithmic(true).text("MB"));
+        reset_button!(app, ui, media_memory_cap_mb);
+    });
+
     ui.horizontal(|ui| {
         ui.checkbox(&mut app.unsaved_settings.approve_content_warning, "Approve all content-warning tagged media automatically")
             .on_hover_text("If off, you have to click to show content-warning tagged media. If on, all content-warning tagged media in your feed will be rendered.");
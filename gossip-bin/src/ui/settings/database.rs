@@ -27,6 +27,55 @@ pub(super) fn update(app: &mut GossipUi, _ctx: &Context, _frame: &mut eframe::Fr
         reset_button!(app, ui, cache_prune_period_days);
     });
 
+    ui.horizontal(|ui| {
+        ui.label("Maximum size of downloaded files").on_hover_text(
+            "When the cache grows larger than this, the least recently used files are deleted",
+        );
+        ui.add(
+            Slider::new(&mut app.unsaved_settings.cache_max_size_mb, 64..=32768)
+                .logarithmic(true)
+                .text("MB"),
+        );
+        reset_button!(app, ui, cache_max_size_mb);
+    });
+
+    ui.add_space(20.0);
+
+    ui.heading("Downloaded Files");
+
+    ui.add_space(10.0);
+
+    match GLOBALS.fetcher.cache_usage() {
+        Some(usage) => {
+            egui::Grid::new("cache_usage_grid")
+                .num_columns(3)
+                .striped(true)
+                .show(ui, |ui| {
+                    for kind_usage in &usage.kinds {
+                        ui.label(kind_usage.kind.to_string());
+                        ui.label(format!("{} files", kind_usage.files));
+                        ui.label(format_megabytes(kind_usage.bytes));
+                        ui.end_row();
+                    }
+                    ui.label("Total");
+                    ui.label(format!("{} files", usage.files()));
+                    ui.label(format_megabytes(usage.bytes()));
+                    ui.end_row();
+                });
+            ui.label(format!(
+                "Measured {} ago",
+                crate::date_ago::date_ago(usage.measured_at)
+            ));
+        }
+        None => {
+            ui.label("Not measured yet");
+        }
+    }
+
+    if ui.button("Measure").clicked() {
+        let _ = GLOBALS.to_overlord.send(ToOverlordMessage::MeasureCache);
+    }
+
     // Only let them prune after they have saved
     let stored_settings = UnsavedSettings::load();
     if stored_settings == app.unsaved_settings {
@@ -41,10 +90,19 @@ pub(super) fn update(app: &mut GossipUi, _ctx: &Context, _frame: &mut eframe::Fr
         }
 
         ui.add_space(20.0);
-        if ui.button("Delete Old Downloaded Files").on_hover_text("This will delete cache files with modification times older than the period specified above (unfortunately access times are often unavailable and/or unreliable). Note that this will eventually delete everybody's avatar, even if those are in heavy use.").clicked() {
+        if ui.button("Delete Old Downloaded Files").on_hover_text("This will delete cache files that have not been used for longer than the period specified above.").clicked() {
             let _ = GLOBALS.to_overlord.send(ToOverlordMessage::PruneCache);
         }
+
+        ui.add_space(20.0);
+        if ui.button("Trim Downloaded Files Now").on_hover_text("This will delete the least recently used cache files until the cache is no larger than the maximum size specified above. This also happens automatically every ten minutes.").clicked() {
+            let _ = GLOBALS.to_overlord.send(ToOverlordMessage::TrimCache);
+        }
     }
 
     ui.add_space(20.0);
 }
+
+fn format_megabytes(bytes: u64) -> String {
+    format!("{:.1} MB", bytes as f64 / 1_048_576.0)
+}
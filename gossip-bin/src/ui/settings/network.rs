@@ -1,7 +1,7 @@
 use crate::ui::{GossipUi, Page};
 use eframe::egui;
 use egui::widgets::Slider;
-use egui::{Context, Ui};
+use egui::{Context, TextEdit, Ui};
 
 pub(super) fn update(app: &mut GossipUi, ctx: &Context, _frame: &mut eframe::Frame, ui: &mut Ui) {
     ui.heading("Network Settings");
@@ -213,6 +213,20 @@ pub(super) fn update(app: &mut GossipUi, ctx: &Context, _frame: &mut eframe::Fra
         reset_button!(app, ui, websocket_ping_frequency_sec);
     });
 
+    ui.add_space(10.0);
+    ui.heading("Proxy Settings");
+    ui.add_space(10.0);
+
+    ui.horizontal(|ui| {
+        ui.label("SOCKS5 proxy").on_hover_text("Connect to relays and fetch over HTTP through this SOCKS5 proxy, such as Tor. Hostnames are resolved by the proxy. Leave empty to connect directly. Relays pick this up when they reconnect; media and other HTTP fetches take effect on restart.");
+        ui.add(TextEdit::singleline(&mut app.unsaved_settings.socks5_proxy).hint_text("127.0.0.1:9050"));
+        reset_button!(app, ui, socks5_proxy);
+    });
+    ui.horizontal(|ui| {
+        ui.checkbox(&mut app.unsaved_settings.proxy_only_onion, "Only use the proxy for .onion hosts").on_hover_text("If selected, other hosts are connected to directly. Relays at .onion hosts can only be reached through a proxy.");
+        reset_button!(app, ui, proxy_only_onion);
+    });
+
     ui.add_space(10.0);
     ui.heading("Stale Time Settings");
     ui.add_space(10.0);
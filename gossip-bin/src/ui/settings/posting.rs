@@ -46,6 +46,19 @@ pub(super) fn update(app: &mut GossipUi, _ctx: &Context, _frame: &mut eframe::Fr
 
     ui.add_space(20.0);
 
+    ui.horizontal(|ui| {
+        ui.label("Zap payment command: ")
+            .on_hover_text("A command that pays a lightning invoice, such as a CLI wallet. The invoice replaces {invoice}, or is added at the end. It must print the payment preimage. If empty, you pay zap invoices yourself.");
+        ui.add(
+            TextEdit::singleline(&mut app.unsaved_settings.zap_payment_command)
+                .hint_text("lncli payinvoice --force --json {invoice}")
+                .desired_width(f32::INFINITY),
+        );
+        reset_button!(app, ui, zap_payment_command);
+    });
+
+    ui.add_space(20.0);
+
     ui.horizontal(|ui| {
         ui.label("Blossom servers: ")
             .on_hover_text("Specify your blossom servers (just the host and port if it is not 443). Separate then by spaces or newlines");
@@ -1,4 +1,5 @@
 mod avatar;
+use std::collections::BTreeMap;
 use std::sync::Arc;
 
 pub(crate) use avatar::{paint_avatar, paint_avatar_only, AvatarSize};
@@ -22,8 +23,9 @@ use egui_winit::egui::{
 pub use nav_item::NavItem;
 
 mod relay_entry;
-use nostr_types::RelayUrl;
-pub use relay_entry::RelayEntry;
+use gossip_lib::Label;
+use nostr_types::{PublicKey, RelayUrl};
+pub use relay_entry::{RelayEntry, RELAY_STATS_DAYS};
 
 mod modal_popup;
 pub use modal_popup::{modal_popup, modal_popup_dyn, ModalEntry};
@@ -115,6 +117,70 @@ pub fn truncated_label(ui: &mut Ui, text: impl Into<WidgetText>, max_width: f32)
     ui.label(galley)
 }
 
+/// Warn that people we follow have reported (NIP-56) something,
+/// given (reporter, report type) pairs
+pub fn reports_banner(ui: &mut Ui, theme: &Theme, reports: &[(PublicKey, String)]) -> Response {
+    let mut report_types: Vec<&str> = reports.iter().map(|(_, t)| t.as_str()).collect();
+    report_types.sort();
+    report_types.dedup();
+
+    let mut reporters: Vec<String> = reports
+        .iter()
+        .map(|(pk, _)| gossip_lib::names::best_name_from_pubkey_lookup(pk))
+        .collect();
+    reporters.sort();
+    reporters.dedup();
+
+    ui.label(
+        RichText::new(format!(
+            "⚠ Reported as {} by people you follow",
+            report_types.join(", ")
+        ))
+        .color(theme.warning_marker_text_color()),
+    )
+    .on_hover_text(format!("Reported by {}", reporters.join(", ")))
+}
+
+/// Show labels (NIP-32) as chips grouped by namespace.
+/// Returns the (namespace, label) of a chip that was clicked.
+pub fn label_chips(ui: &mut Ui, theme: &Theme, labels: &[Label]) -> Option<(String, String)> {
+    // namespace -> label -> who applied it
+    let mut grouped: BTreeMap<&str, BTreeMap<&str, Vec<PublicKey>>> = BTreeMap::new();
+    for l in labels {
+        let by = grouped
+            .entry(&l.namespace)
+            .or_default()
+            .entry(&l.label)
+            .or_default();
+        if !by.contains(&l.by) {
+            by.push(l.by);
+        }
+    }
+
+    let mut clicked: Option<(String, String)> = None;
+    ui.horizontal_wrapped(|ui| {
+        for (namespace, labels) in grouped.iter() {
+            ui.label(RichText::new(format!("{}:", namespace)).weak().small());
+            for (label, by) in labels.iter() {
+                let names: Vec<String> = by
+                    .iter()
+                    .map(gossip_lib::names::best_name_from_pubkey_lookup)
+                    .collect();
+                if Button::bordered(theme, *label)
+                    .small(true)
+                    .show(ui)
+                    .on_hover_text(format!("Labelled by {}", names.join(", ")))
+                    .clicked()
+                {
+                    clicked = Some((namespace.to_string(), label.to_string()));
+                }
+            }
+            ui.add_space(6.0);
+        }
+    });
+    clicked
+}
+
 /// Display a relay-URL
 pub fn relay_url(ui: &mut Ui, theme: &Theme, url: &RelayUrl) -> Response {
     let (symbol, color, spacer) = if url.as_url_crate_url().scheme() != "wss" {
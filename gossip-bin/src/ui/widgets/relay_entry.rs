@@ -4,7 +4,8 @@ use nostr_types::{PublicKeyHex, RelayUrl, Unixtime};
 use std::fmt;
 
 use crate::ui::{widgets, GossipUi, Theme};
-use gossip_lib::{comms::ToOverlordMessage, Relay, GLOBALS};
+use gossip_lib::{comms::ToOverlordMessage, relay_rejection, Relay, RelayStats, GLOBALS};
+use humansize::{format_size, DECIMAL};
 
 use super::{
     list_entry::{
@@ -69,6 +70,12 @@ const STATS_COL_3_X: f32 = 120.0;
 const STATS_COL_4_X: f32 = 120.0;
 /// 5. stat column x offset
 const STATS_COL_5_X: f32 = 150.0;
+/// 6. stat column x offset
+const STATS_COL_6_X: f32 = 120.0;
+/// 7. stat column x offset
+const STATS_COL_7_X: f32 = 160.0;
+/// Number of days of relay statistics to show
+pub const RELAY_STATS_DAYS: u32 = 7;
 
 const READ_HOVER_TEXT: &str = "Where you actually read events from (including those tagging you, but also for other purposes).";
 const INBOX_HOVER_TEXT: &str = "Where you tell others you read from. You should also check Read. These relays shouldn't require payment. It is recommended to have a few.";
@@ -193,6 +200,7 @@ pub struct RelayEntry {
     timeout_until: Option<i64>,
     reasons: String,
     user_count: Option<usize>,
+    stats: Option<RelayStats>,
     usage: UsageBits,
     accent: Color32,
     accent_hover: Color32,
@@ -219,6 +227,7 @@ impl RelayEntry {
             timeout_until: None,
             reasons: "".into(),
             user_count: None,
+            stats: None,
             usage,
             accent,
             accent_hover,
@@ -259,6 +268,10 @@ impl RelayEntry {
         self.user_count = Some(count);
     }
 
+    pub fn set_stats(&mut self, stats: RelayStats) {
+        self.stats = Some(stats);
+    }
+
     pub fn set_connected(&mut self, connected: bool) {
         self.connected = connected;
     }
@@ -542,6 +555,70 @@ impl RelayEntry {
                 Some(ui.visuals().text_color()),
                 None,
             );
+
+            // ---- Traffic ----
+            let pos = pos + vec2(STATS_COL_6_X, 0.0);
+            if let Some(stats) = &self.stats {
+                let text = RichText::new(format!(
+                    "Traffic ({}d): {}",
+                    RELAY_STATS_DAYS,
+                    format_size(stats.bytes(), DECIMAL)
+                ));
+                let (galley, response) =
+                    allocate_text_at(ui, pos, text.into(), Align::LEFT, self.make_id("traffic"));
+                draw_text_galley_at(ui, pos, galley, Some(ui.visuals().text_color()), None);
+                let latency = match stats.mean_eose_latency_ms() {
+                    Some(ms) => format!("{} ms", ms),
+                    None => "?".to_owned(),
+                };
+                response.on_hover_text(format!(
+                    "Over the last {} days:\n\
+                     Sent: {}\n\
+                     Received: {}\n\
+                     Events: {} ({} duplicates)\n\
+                     Subscriptions: {}\n\
+                     Average time to EOSE: {}\n\
+                     NOTICEs: {}\n\
+                     CLOSEDs: {}",
+                    RELAY_STATS_DAYS,
+                    format_size(stats.bytes_sent, DECIMAL),
+                    format_size(stats.bytes_received, DECIMAL),
+                    stats.events_received,
+                    stats.duplicate_events,
+                    stats.subscriptions,
+                    latency,
+                    stats.notices,
+                    stats.closed
+                ));
+            }
+
+            // ---- Last refusal ----
+            if let Some(rejection) = relay_rejection::relay_rejection(&self.relay.url) {
+                let pos = pos + vec2(STATS_COL_7_X, 0.0);
+                let prefix = if rejection.prefix.is_empty() {
+                    "unknown"
+                } else {
+                    rejection.prefix.as_str()
+                };
+                let text = RichText::new(format!("Refused: {}", prefix));
+                let (galley, response) =
+                    allocate_text_at(ui, pos, text.into(), Align::LEFT, self.make_id("refused"));
+                draw_text_galley_at(ui, pos, galley, Some(ui.visuals().warn_fg_color), None);
+                let mut hover = format!(
+                    "Refused our {} {}:\n{}",
+                    if rejection.posting {
+                        "event"
+                    } else {
+                        "subscription"
+                    },
+                    crate::date_ago::date_ago(rejection.when),
+                    rejection.message
+                );
+                if relay_rejection::is_write_restricted(&self.relay.url) {
+                    hover.push_str("\nNot posting there again until restarted.");
+                }
+                response.on_hover_text(hover);
+            }
         }
     }
 
@@ -752,13 +829,45 @@ impl RelayEntry {
                 }
             }
             let pos = pos + vec2(0.0, NIP11_Y_SPACING);
+            let mut limits_pos = pos;
             if !doc.supported_nips.is_empty() {
                 let mut text = "NIPS: ".to_string();
                 for nip in &doc.supported_nips {
                     text.push_str(format!(" {},", *nip).as_str());
                 }
                 text.truncate(text.len() - 1); // safe because we built the string
-                draw_text_at(ui, pos, text.into(), align, None, None);
+                let rect = draw_text_at(ui, pos, text.into(), align, None, None);
+                limits_pos = pos + vec2(rect.width() + ui.spacing().item_spacing.x * 2.0, 0.0);
+            }
+            let notes = self.relay.limitation_notes();
+            if !notes.is_empty() {
+                let (galley, response) = allocate_text_at(
+                    ui,
+                    limits_pos,
+                    "Limits".into(),
+                    align,
+                    self.make_id("nip11_limits"),
+                );
+                let rect = draw_text_galley_at(ui, limits_pos, galley, None, None);
+                response.on_hover_text(notes.join("\n"));
+
+                if let Some(skipped) = relay_rejection::skipped_write(&self.relay.url) {
+                    let pos =
+                        limits_pos + vec2(rect.width() + ui.spacing().item_spacing.x * 2.0, 0.0);
+                    let (galley, response) = allocate_text_at(
+                        ui,
+                        pos,
+                        "Skipped a post".into(),
+                        align,
+                        self.make_id("nip11_skipped"),
+                    );
+                    draw_text_galley_at(ui, pos, galley, Some(ui.visuals().warn_fg_color), None);
+                    response.on_hover_text(format!(
+                        "Did not post there {} ago, as {}",
+                        crate::date_ago::date_ago(skipped.when),
+                        skipped.reason
+                    ));
+                }
             }
 
             if let Some(entry) = GLOBALS.relay_tests.get(&self.relay.url) {
@@ -1,15 +1,18 @@
 use super::{GossipUi, Page};
 use crate::ui::widgets::CopyButton;
 use eframe::egui::{self, Margin};
-use egui::{Color32, Context, Frame, Stroke, Ui};
+use egui::{Color32, Context, Frame, RichText, Stroke, Ui};
 use gossip_lib::comms::ToOverlordMessage;
 use gossip_lib::{Globals, GLOBALS};
-use nostr_types::{KeySecurity, PublicKeyHex};
+use nostr_types::{KeySecurity, PublicKeyHex, Unixtime};
 use zeroize::Zeroize;
 
 mod delegation;
 mod metadata;
 mod nostr_connect;
+mod uploads;
+
+pub(super) use nostr_connect::Nip46PolicyForm;
 
 pub(super) fn update(app: &mut GossipUi, ctx: &Context, _frame: &mut eframe::Frame, ui: &mut Ui) {
     if app.page == Page::YourKeys {
@@ -21,7 +24,21 @@ pub(super) fn update(app: &mut GossipUi, ctx: &Context, _frame: &mut eframe::Fra
         ui.add_space(10.0);
 
         app.vert_scroll_area().id_salt("your_keys").show(ui, |ui| {
-            if GLOBALS.identity.is_unlocked() {
+            if GLOBALS.identity.is_remote() {
+                ui.heading("Signing with a remote signer");
+
+                ui.add_space(10.0);
+                ui.separator();
+                ui.add_space(10.0);
+
+                show_pub_key_detail(app, ui);
+
+                ui.add_space(10.0);
+                ui.separator();
+                ui.add_space(10.0);
+
+                show_remote_signer_detail(app, ui);
+            } else if GLOBALS.identity.is_unlocked() {
                 ui.heading("Ready to sign events");
 
                 ui.add_space(10.0);
@@ -94,6 +111,12 @@ pub(super) fn update(app: &mut GossipUi, ctx: &Context, _frame: &mut eframe::Fra
                 ui.separator();
                 ui.add_space(10.0);
 
+                offer_remote_signer_login(app, ui);
+
+                ui.add_space(10.0);
+                ui.separator();
+                ui.add_space(10.0);
+
                 offer_delete_or_import_pub_key(app, ui);
             } else {
                 offer_generate(app, ui);
@@ -108,6 +131,12 @@ pub(super) fn update(app: &mut GossipUi, ctx: &Context, _frame: &mut eframe::Fra
                 ui.separator();
                 ui.add_space(10.0);
 
+                offer_remote_signer_login(app, ui);
+
+                ui.add_space(10.0);
+                ui.separator();
+                ui.add_space(10.0);
+
                 offer_delete_or_import_pub_key(app, ui);
             }
         });
@@ -117,6 +146,8 @@ pub(super) fn update(app: &mut GossipUi, ctx: &Context, _frame: &mut eframe::Fra
         delegation::update(app, ctx, _frame, ui);
     } else if app.page == Page::YourNostrConnect {
         nostr_connect::update(app, ctx, _frame, ui);
+    } else if app.page == Page::YourUploads {
+        uploads::update(app, ctx, _frame, ui);
     }
 }
 
@@ -421,6 +452,79 @@ fn offer_delete_or_import_pub_key(app: &mut GossipUi, ui: &mut Ui) {
     }
 }
 
+fn show_remote_signer_detail(app: &mut GossipUi, ui: &mut Ui) {
+    let remote = match GLOBALS.identity.remote_signer() {
+        Some(remote) => remote,
+        None => return,
+    };
+
+    ui.heading("Remote Signer");
+    ui.add_space(10.0);
+
+    ui.label(format!(
+        "Remote signer: {}",
+        remote.remote_pubkey.as_bech32_string()
+    ));
+    for relay in &remote.relays {
+        ui.label(format!("Relay: {}", relay));
+    }
+
+    ui.add_space(10.0);
+    let now = Unixtime::now();
+    if GLOBALS.nip46_client_requests.is_empty() {
+        ui.label("No requests waiting on the remote signer.");
+    } else {
+        ui.label("Waiting on the remote signer for:");
+        for request in GLOBALS.nip46_client_requests.iter() {
+            ui.label(format!(
+                "  {} (sent {}s ago)",
+                request.method,
+                now.0 - request.sent_at.0
+            ));
+        }
+    }
+
+    ui.add_space(10.0);
+    warn_client_key_stored(app, ui);
+
+    ui.add_space(10.0);
+    ui.label("Disconnecting keeps your public key, so you can still browse.");
+    if ui.button("Disconnect from the remote signer").clicked() {
+        let _ = GLOBALS.to_overlord.send(ToOverlordMessage::Nip46Logout);
+    }
+}
+
+fn offer_remote_signer_login(app: &mut GossipUi, ui: &mut Ui) {
+    ui.heading("Log in with a Remote Signer");
+    ui.add_space(10.0);
+
+    ui.label("A remote signer (NIP-46) keeps your private key and signs for gossip. Paste the bunker:// token it gives you.");
+    warn_client_key_stored(app, ui);
+
+    ui.horizontal_wrapped(|ui| {
+        ui.label("Enter your bunker token");
+        ui.add(
+            text_edit_line!(app, app.bunker_token)
+                .hint_text("bunker://")
+                .desired_width(f32::INFINITY),
+        );
+        if ui.button("Log in").clicked() {
+            let _ = GLOBALS
+                .to_overlord
+                .send(ToOverlordMessage::Nip46Login(app.bunker_token.clone()));
+            app.bunker_token.zeroize();
+            app.bunker_token = "".to_owned();
+        }
+    });
+}
+
+fn warn_client_key_stored(app: &GossipUi, ui: &mut Ui) {
+    ui.label(
+        RichText::new("Gossip keeps the key it uses to talk to your remote signer unencrypted on this computer. Anyone who copies it can ask your remote signer to sign as you, until you revoke this connection at the remote signer.")
+            .color(app.theme.warning_marker_text_color()),
+    );
+}
+
 pub(super) fn offer_delete(app: &mut GossipUi, ui: &mut Ui) {
     ui.heading("DELETE This Identity");
 
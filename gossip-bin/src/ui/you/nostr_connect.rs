@@ -3,8 +3,10 @@ use crate::ui::widgets::CopyButton;
 use eframe::egui;
 use egui::{Context, Ui};
 use gossip_lib::comms::ToOverlordMessage;
-use gossip_lib::{Nip46UnconnectedServer, GLOBALS};
-use nostr_types::RelayUrl;
+use gossip_lib::nostr_connect_server::{Approval, Nip46Policy};
+use gossip_lib::{Nip46Server, Nip46UnconnectedServer, GLOBALS};
+use nostr_types::{PublicKey, RelayUrl, Unixtime};
+use std::time::{Duration, Instant};
 
 pub(super) fn update(app: &mut GossipUi, _ctx: &Context, _frame: &mut eframe::Frame, ui: &mut Ui) {
     ui.add_space(10.0);
@@ -13,7 +15,13 @@ pub(super) fn update(app: &mut GossipUi, _ctx: &Context, _frame: &mut eframe::Fr
     });
 
     ui.add_space(10.0);
-    ui.label("NOTE: Gossip currently only acts as a signing service, using the key you have configured in gossip.");
+    ui.label("NOTE: This is gossip acting as a signing service for other clients, using the key you have configured in gossip.");
+
+    if GLOBALS.identity.is_remote() {
+        ui.add_space(10.0);
+        ui.label("This is not available while gossip itself signs with a remote signer.");
+        return;
+    }
 
     // Show status of unconnected server
     if let Ok(Some(unconnected_server)) = GLOBALS.db().read_nip46_unconnected_server() {
@@ -62,12 +70,263 @@ pub(super) fn update(app: &mut GossipUi, _ctx: &Context, _frame: &mut eframe::Fr
             if ui.button("Disconnect").clicked() {
                 let _ = GLOBALS.db().delete_nip46server(server.peer_pubkey, None);
             }
+
+            egui::CollapsingHeader::new("Permissions")
+                .id_salt(("nip46_permissions", server.peer_pubkey.as_hex_string()))
+                .show(ui, |ui| {
+                    show_permissions(app, ui, server);
+                });
+
+            egui::CollapsingHeader::new("Audit log")
+                .id_salt(("nip46_audit", server.peer_pubkey.as_hex_string()))
+                .show(ui, |ui| {
+                    show_audit_log(app, ui, server);
+                });
+
+            ui.add_space(10.0);
         }
     }
 
     ui.separator();
 }
 
+/// The policy of a connected service, being edited as text
+pub(in crate::ui) struct Nip46PolicyForm {
+    peer_pubkey: PublicKey,
+    allowed_kinds: String,
+    max_requests_per_minute: String,
+    from_hour: String,
+    until_hour: String,
+    expires_in_days: String,
+}
+
+impl Nip46PolicyForm {
+    fn new(server: &Nip46Server) -> Nip46PolicyForm {
+        let policy = &server.policy;
+        let expires_in_days = match policy.expires_at {
+            Some(when) => format!("{}", (when.0 - Unixtime::now().0).max(0) / 86400),
+            None => "".to_owned(),
+        };
+        Nip46PolicyForm {
+            peer_pubkey: server.peer_pubkey,
+            allowed_kinds: policy
+                .allowed_kinds
+                .iter()
+                .map(|k| k.to_string())
+                .collect::<Vec<String>>()
+                .join(", "),
+            max_requests_per_minute: policy.max_requests_per_minute.to_string(),
+            from_hour: policy.active_hours.0.to_string(),
+            until_hour: policy.active_hours.1.to_string(),
+            expires_in_days,
+        }
+    }
+
+    fn to_policy(&self) -> Result<Nip46Policy, String> {
+        let mut allowed_kinds: Vec<u32> = Vec::new();
+        for kind in self.allowed_kinds.split(',') {
+            let kind = kind.trim();
+            if kind.is_empty() {
+                continue;
+            }
+            match kind.parse::<u32>() {
+                Ok(k) => allowed_kinds.push(k),
+                Err(_) => return Err(format!("Not an event kind: {}", kind)),
+            }
+        }
+
+        let max_requests_per_minute = match self.max_requests_per_minute.trim() {
+            "" => 0,
+            s => s
+                .parse::<u32>()
+                .map_err(|_| "Requests per minute must be a number".to_owned())?,
+        };
+
+        let parse_hour = |s: &str| -> Result<u8, String> {
+            match s.trim().parse::<u8>() {
+                Ok(h) if h < 24 => Ok(h),
+                _ => Err("Hours must be from 0 to 23".to_owned()),
+            }
+        };
+        let active_hours = (parse_hour(&self.from_hour)?, parse_hour(&self.until_hour)?);
+
+        let expires_at = match self.expires_in_days.trim() {
+            "" => None,
+            s => {
+                let days = s
+                    .parse::<u64>()
+                    .map_err(|_| "Expiry must be a number of days".to_owned())?;
+                Some(Unixtime::now() + Duration::from_secs(days * 86400))
+            }
+        };
+
+        Ok(Nip46Policy {
+            allowed_kinds,
+            max_requests_per_minute,
+            active_hours,
+            expires_at,
+        })
+    }
+}
+
+fn show_permissions(app: &mut GossipUi, ui: &mut Ui, server: &Nip46Server) {
+    // Per-method approvals take effect immediately
+    let mut changed = server.clone();
+    approval_row(ui, "Sign events", &mut changed.sign_approval);
+    approval_row(ui, "NIP-04 encrypt", &mut changed.nip04_encrypt_approval);
+    approval_row(ui, "NIP-04 decrypt", &mut changed.nip04_decrypt_approval);
+    approval_row(ui, "NIP-44 encrypt", &mut changed.nip44_encrypt_approval);
+    approval_row(ui, "NIP-44 decrypt", &mut changed.nip44_decrypt_approval);
+    if changed.sign_approval != server.sign_approval
+        || changed.nip04_encrypt_approval != server.nip04_encrypt_approval
+        || changed.nip04_decrypt_approval != server.nip04_decrypt_approval
+        || changed.nip44_encrypt_approval != server.nip44_encrypt_approval
+        || changed.nip44_decrypt_approval != server.nip44_decrypt_approval
+    {
+        let _ = GLOBALS.db().write_nip46server(&changed, None);
+    }
+
+    ui.add_space(10.0);
+
+    let editing =
+        matches!(&app.nostr_connect_policy, Some(form) if form.peer_pubkey == server.peer_pubkey);
+    if !editing {
+        let policy = &server.policy;
+        if policy.allowed_kinds.is_empty() {
+            ui.label("Event kinds: any");
+        } else {
+            ui.label(format!("Event kinds: {:?}", policy.allowed_kinds));
+        }
+        if policy.max_requests_per_minute == 0 {
+            ui.label("Rate limit: none");
+        } else {
+            ui.label(format!(
+                "Rate limit: {} requests per minute",
+                policy.max_requests_per_minute
+            ));
+        }
+        if policy.active_hours.0 == policy.active_hours.1 {
+            ui.label("Active hours: any time");
+        } else {
+            ui.label(format!(
+                "Active hours: {}:00 to {}:00 UTC",
+                policy.active_hours.0, policy.active_hours.1
+            ));
+        }
+        match policy.expires_at {
+            Some(when) if policy.is_expired_at(Unixtime::now()) => {
+                ui.label(format!("Expired: {}", format_time(when)));
+            }
+            Some(when) => {
+                ui.label(format!("Expires: {}", format_time(when)));
+            }
+            None => {
+                ui.label("Expires: never");
+            }
+        }
+        if ui.button("Edit policy").clicked() {
+            app.nostr_connect_policy = Some(Nip46PolicyForm::new(server));
+        }
+        return;
+    }
+
+    let mut save = false;
+    let mut cancel = false;
+    if let Some(form) = &mut app.nostr_connect_policy {
+        ui.horizontal(|ui| {
+            ui.label("Event kinds (comma separated, empty for any):");
+            ui.text_edit_singleline(&mut form.allowed_kinds);
+        });
+        ui.horizontal(|ui| {
+            ui.label("Requests per minute (0 for unlimited):");
+            ui.text_edit_singleline(&mut form.max_requests_per_minute);
+        });
+        ui.horizontal(|ui| {
+            ui.label("Active from hour");
+            ui.add(egui::TextEdit::singleline(&mut form.from_hour).desired_width(30.0));
+            ui.label("until hour");
+            ui.add(egui::TextEdit::singleline(&mut form.until_hour).desired_width(30.0));
+            ui.label("UTC (equal for any time)");
+        });
+        ui.horizontal(|ui| {
+            ui.label("Expires in days (empty for never):");
+            ui.text_edit_singleline(&mut form.expires_in_days);
+        });
+        ui.horizontal(|ui| {
+            save = ui.button("Save").clicked();
+            cancel = ui.button("Cancel").clicked();
+        });
+    }
+
+    if save {
+        if let Some(form) = &app.nostr_connect_policy {
+            match form.to_policy() {
+                Ok(policy) => {
+                    let mut server = server.clone();
+                    server.policy = policy;
+                    let _ = GLOBALS.db().write_nip46server(&server, None);
+                    app.nostr_connect_policy = None;
+                }
+                Err(e) => GLOBALS.status_queue.write().write(e),
+            }
+        }
+    } else if cancel {
+        app.nostr_connect_policy = None;
+    }
+}
+
+fn approval_row(ui: &mut Ui, label: &str, approval: &mut Approval) {
+    ui.horizontal(|ui| {
+        ui.label(format!("{}:", label));
+        ui.radio_value(approval, Approval::Ask, "Ask");
+        ui.radio_value(approval, Approval::Always, "Always");
+        ui.radio_value(approval, Approval::None, "Never");
+        if let Approval::Until(when) = approval {
+            ui.label(format!("(approved until {})", format_time(*when)));
+        }
+    });
+}
+
+fn show_audit_log(app: &mut GossipUi, ui: &mut Ui, server: &Nip46Server) {
+    if app.nip46_audit_next_refresh < Instant::now() {
+        app.nip46_audit_cache.clear();
+        app.nip46_audit_next_refresh = Instant::now() + Duration::new(5, 0);
+    }
+    let entries = app
+        .nip46_audit_cache
+        .entry(server.peer_pubkey)
+        .or_insert_with(|| {
+            GLOBALS
+                .db()
+                .read_nip46_audit_log(server.peer_pubkey)
+                .unwrap_or_default()
+        });
+    if entries.is_empty() {
+        ui.label("No requests yet.");
+        return;
+    }
+
+    // Newest first
+    for entry in entries.iter().rev().take(100) {
+        ui.label(format!(
+            "{}  {} {}  {}",
+            format_time(entry.when),
+            entry.method,
+            entry.detail,
+            entry.outcome
+        ));
+    }
+}
+
+fn format_time(when: Unixtime) -> String {
+    match time::OffsetDateTime::from_unix_timestamp(when.0) {
+        Ok(stamp) => stamp
+            .format(&time::format_description::well_known::Rfc2822)
+            .unwrap_or_else(|_| format!("{}", when.0)),
+        Err(_) => format!("{}", when.0),
+    }
+}
+
 fn setup_unconnected_service(app: &mut GossipUi, ui: &mut Ui) {
     ui.separator();
     ui.add_space(10.0);
@@ -0,0 +1,172 @@
+use super::GossipUi;
+use crate::ui::widgets::CopyButton;
+use eframe::egui;
+use egui::{Context, RichText, Ui};
+use gossip_lib::comms::ToOverlordMessage;
+use gossip_lib::GLOBALS;
+
+pub(super) fn update(app: &mut GossipUi, _ctx: &Context, _frame: &mut eframe::Frame, ui: &mut Ui) {
+    ui.add_space(10.0);
+    ui.horizontal_wrapped(|ui| {
+        ui.heading("My Uploads");
+        if ui.button("Refresh").clicked() {
+            let _ = GLOBALS.to_overlord.send(ToOverlordMessage::BlossomList);
+        }
+    });
+
+    ui.add_space(10.0);
+    ui.label("The files you uploaded to each of your blossom servers (see Settings > Posting).");
+    ui.add_space(10.0);
+
+    let servers = GLOBALS.db().read_setting_blossom_servers();
+    if servers.split_whitespace().next().is_none() {
+        ui.label("You have no blossom servers configured.");
+        return;
+    }
+
+    let mut results: Vec<(
+        String,
+        Result<Vec<gossip_lib::blossom::BlobDescriptor>, String>,
+    )> = GLOBALS
+        .blossom_blobs
+        .iter()
+        .map(|e| (e.key().clone(), e.value().clone()))
+        .collect();
+    results.sort_by(|a, b| a.0.cmp(&b.0));
+
+    app.vert_scroll_area().id_salt("my_uploads").show(ui, |ui| {
+        show_replication(ui);
+
+        if results.is_empty() {
+            ui.label("Loading...");
+        }
+
+        for (base_url, result) in results.iter_mut() {
+            ui.separator();
+            ui.heading(base_url.as_str());
+            ui.add_space(6.0);
+
+            let blobs = match result {
+                Ok(blobs) => blobs,
+                Err(e) => {
+                    ui.label(RichText::new(format!("Could not list: {}", e)).italics());
+                    continue;
+                }
+            };
+
+            if blobs.is_empty() {
+                ui.label("Nothing uploaded here.");
+                continue;
+            }
+
+            // Newest first
+            blobs.sort_by(|a, b| b.uploaded_at().cmp(&a.uploaded_at()));
+
+            for blob in blobs.iter() {
+                ui.horizontal_wrapped(|ui| {
+                    ui.label(&blob.url);
+                    if ui
+                        .add(CopyButton::new())
+                        .on_hover_text("Copy URL")
+                        .clicked()
+                    {
+                        ui.output_mut(|o| o.copied_text = blob.url.clone());
+                    }
+                });
+
+                ui.horizontal_wrapped(|ui| {
+                    ui.label(
+                        RichText::new(format!(
+                            "{}, {}, uploaded {}",
+                            format_size(blob.size),
+                            blob.mime_type.as_deref().unwrap_or("unknown type"),
+                            format_date(blob.uploaded_at())
+                        ))
+                        .weak(),
+                    );
+
+                    let key = format!("{} {}", base_url, blob.sha256);
+                    if app.blossom_delete_confirm.as_ref() == Some(&key) {
+                        if ui.button("Really delete").clicked() {
+                            let _ = GLOBALS.to_overlord.send(ToOverlordMessage::BlossomDelete(
+                                base_url.clone(),
+                                blob.sha256.clone(),
+                            ));
+                            app.blossom_delete_confirm = None;
+                        }
+                        if ui.button("Cancel").clicked() {
+                            app.blossom_delete_confirm = None;
+                        }
+                    } else if ui.button("Delete").clicked() {
+                        app.blossom_delete_confirm = Some(key);
+                    }
+                });
+                ui.add_space(6.0);
+            }
+        }
+    });
+}
+
+// Which of our uploads are missing from some of our servers (BUD-04 mirroring)
+fn show_replication(ui: &mut Ui) {
+    ui.separator();
+    ui.horizontal_wrapped(|ui| {
+        ui.heading("Mirroring");
+        if ui.button("Check now").clicked() {
+            let _ = GLOBALS.to_overlord.send(ToOverlordMessage::BlossomRepair);
+        }
+    });
+    ui.add_space(6.0);
+
+    if GLOBALS.blossom_replication.is_empty() {
+        ui.label("Your uploads have not been checked yet.");
+        ui.add_space(6.0);
+        return;
+    }
+
+    let under_replicated = gossip_lib::blossom::under_replicated_blobs();
+    if under_replicated.is_empty() {
+        ui.label("All of your uploads are on all of your blossom servers.");
+        ui.add_space(6.0);
+        return;
+    }
+
+    ui.label(format!(
+        "{} of your uploads are missing from some of your blossom servers:",
+        under_replicated.len()
+    ));
+    ui.add_space(6.0);
+    for replication in under_replicated.iter() {
+        ui.label(&replication.blob.url);
+        ui.indent(("missing", &replication.blob.sha256), |ui| {
+            ui.label(RichText::new(format!("on {} servers", replication.present.len())).weak());
+            for (server, reason) in replication.missing.iter() {
+                ui.label(RichText::new(format!("missing from {}: {}", server, reason)).weak());
+            }
+        });
+        ui.add_space(6.0);
+    }
+}
+
+fn format_size(size: u64) -> String {
+    if size >= 1024 * 1024 {
+        format!("{:.1} MiB", size as f64 / (1024.0 * 1024.0))
+    } else if size >= 1024 {
+        format!("{:.1} KiB", size as f64 / 1024.0)
+    } else {
+        format!("{} bytes", size)
+    }
+}
+
+fn format_date(when: Option<u64>) -> String {
+    let when = match when {
+        Some(when) => when,
+        None => return "at an unknown time".to_owned(),
+    };
+    match time::OffsetDateTime::from_unix_timestamp(when as i64) {
+        Ok(stamp) => stamp
+            .format(&time::format_description::well_known::Rfc2822)
+            .unwrap_or_else(|_| format!("{}", when)),
+        Err(_) => format!("{}", when),
+    }
+}
@@ -66,7 +66,11 @@ pub struct UnsavedSettings {
     pub hide_mutes_entirely: bool,
     pub reactions: bool,
     pub enable_zap_receipts: bool,
+    pub check_zap_receipt_signers: bool,
+    pub zap_payment_command: String,
     pub show_media: bool,
+    pub media_max_dimension: u32,
+    pub media_memory_cap_mb: u64,
     pub approve_content_warning: bool,
     pub show_deleted_events: bool,
     pub avoid_spam_on_unsafe_relays: bool,
@@ -118,6 +122,10 @@ pub struct UnsavedSettings {
     pub websocket_connect_timeout_sec: u64,
     pub websocket_ping_frequency_sec: u64,
 
+    // Proxy settings
+    pub socks5_proxy: String,
+    pub proxy_only_onion: bool,
+
     // HTTP settings
     pub fetcher_connect_timeout_sec: u64,
     pub fetcher_timeout_sec: u64,
@@ -129,6 +137,7 @@ pub struct UnsavedSettings {
     // Database settings
     pub prune_period_days: u64,
     pub cache_prune_period_days: u64,
+    pub cache_max_size_mb: u64,
 
     pub blossom_servers: String,
 
@@ -163,7 +172,11 @@ impl Default for UnsavedSettings {
             hide_mutes_entirely: default_setting!(hide_mutes_entirely),
             reactions: default_setting!(reactions),
             enable_zap_receipts: default_setting!(enable_zap_receipts),
+            check_zap_receipt_signers: default_setting!(check_zap_receipt_signers),
+            zap_payment_command: default_setting!(zap_payment_command),
             show_media: default_setting!(show_media),
+            media_max_dimension: default_setting!(media_max_dimension),
+            media_memory_cap_mb: default_setting!(media_memory_cap_mb),
             approve_content_warning: default_setting!(approve_content_warning),
             show_deleted_events: default_setting!(show_deleted_events),
             avoid_spam_on_unsafe_relays: default_setting!(avoid_spam_on_unsafe_relays),
@@ -214,6 +227,8 @@ impl Default for UnsavedSettings {
             websocket_accept_unmasked_frames: default_setting!(websocket_accept_unmasked_frames),
             websocket_connect_timeout_sec: default_setting!(websocket_connect_timeout_sec),
             websocket_ping_frequency_sec: default_setting!(websocket_ping_frequency_sec),
+            socks5_proxy: default_setting!(socks5_proxy),
+            proxy_only_onion: default_setting!(proxy_only_onion),
             fetcher_connect_timeout_sec: default_setting!(fetcher_connect_timeout_sec),
             fetcher_timeout_sec: default_setting!(fetcher_timeout_sec),
             fetcher_max_requests_per_host: default_setting!(fetcher_max_requests_per_host),
@@ -228,6 +243,7 @@ impl Default for UnsavedSettings {
             ),
             prune_period_days: default_setting!(prune_period_days),
             cache_prune_period_days: default_setting!(prune_period_days),
+            cache_max_size_mb: default_setting!(cache_max_size_mb),
             blossom_servers: default_setting!(blossom_servers),
             undo_send_seconds: default_setting!(undo_send_seconds),
         }
@@ -260,7 +276,11 @@ impl UnsavedSettings {
             hide_mutes_entirely: load_setting!(hide_mutes_entirely),
             reactions: load_setting!(reactions),
             enable_zap_receipts: load_setting!(enable_zap_receipts),
+            check_zap_receipt_signers: load_setting!(check_zap_receipt_signers),
+            zap_payment_command: load_setting!(zap_payment_command),
             show_media: load_setting!(show_media),
+            media_max_dimension: load_setting!(media_max_dimension),
+            media_memory_cap_mb: load_setting!(media_memory_cap_mb),
             approve_content_warning: load_setting!(approve_content_warning),
             show_deleted_events: load_setting!(show_deleted_events),
             avoid_spam_on_unsafe_relays: load_setting!(avoid_spam_on_unsafe_relays),
@@ -307,6 +327,8 @@ impl UnsavedSettings {
             websocket_accept_unmasked_frames: load_setting!(websocket_accept_unmasked_frames),
             websocket_connect_timeout_sec: load_setting!(websocket_connect_timeout_sec),
             websocket_ping_frequency_sec: load_setting!(websocket_ping_frequency_sec),
+            socks5_proxy: load_setting!(socks5_proxy),
+            proxy_only_onion: load_setting!(proxy_only_onion),
             fetcher_connect_timeout_sec: load_setting!(fetcher_connect_timeout_sec),
             fetcher_timeout_sec: load_setting!(fetcher_timeout_sec),
             fetcher_max_requests_per_host: load_setting!(fetcher_max_requests_per_host),
@@ -321,6 +343,7 @@ impl UnsavedSettings {
             ),
             prune_period_days: load_setting!(prune_period_days),
             cache_prune_period_days: load_setting!(cache_prune_period_days),
+            cache_max_size_mb: load_setting!(cache_max_size_mb),
             blossom_servers: load_setting!(blossom_servers),
             undo_send_seconds: load_setting!(undo_send_seconds),
         }
@@ -351,7 +374,11 @@ impl UnsavedSettings {
         save_setting!(hide_mutes_entirely, self, txn);
         save_setting!(reactions, self, txn);
         save_setting!(enable_zap_receipts, self, txn);
+        save_setting!(check_zap_receipt_signers, self, txn);
+        save_setting!(zap_payment_command, self, txn);
         save_setting!(show_media, self, txn);
+        save_setting!(media_max_dimension, self, txn);
+        save_setting!(media_memory_cap_mb, self, txn);
         save_setting!(approve_content_warning, self, txn);
         save_setting!(show_deleted_events, self, txn);
         save_setting!(avoid_spam_on_unsafe_relays, self, txn);
@@ -394,6 +421,8 @@ impl UnsavedSettings {
         save_setting!(websocket_accept_unmasked_frames, self, txn);
         save_setting!(websocket_connect_timeout_sec, self, txn);
         save_setting!(websocket_ping_frequency_sec, self, txn);
+        save_setting!(socks5_proxy, self, txn);
+        save_setting!(proxy_only_onion, self, txn);
         save_setting!(fetcher_connect_timeout_sec, self, txn);
         save_setting!(fetcher_timeout_sec, self, txn);
         save_setting!(fetcher_max_requests_per_host, self, txn);
@@ -402,6 +431,7 @@ impl UnsavedSettings {
         save_setting!(fetcher_host_exclusion_on_high_error_secs, self, txn);
         save_setting!(prune_period_days, self, txn);
         save_setting!(cache_prune_period_days, self, txn);
+        save_setting!(cache_max_size_mb, self, txn);
         save_setting!(blossom_servers, self, txn);
         save_setting!(undo_send_seconds, self, txn);
         txn.commit()?;
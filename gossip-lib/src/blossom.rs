@@ -1,19 +1,23 @@
+use crate::comms::ToOverlordMessage;
 use crate::error::{Error, ErrorKind};
 use crate::globals::GLOBALS;
 use base64::Engine;
 use memmap2::Mmap;
 use mime::Mime;
-use nostr_types::{EventKind, ParsedTag, PreEvent, Tag, Unixtime};
+use nostr_types::{EventKind, NAddr, ParsedTag, PreEvent, PublicKey, Tag, Unixtime};
 use reqwest::header::{AUTHORIZATION, CONTENT_LENGTH, CONTENT_TYPE};
 use reqwest::{Body, Client, Response};
 use serde::{Deserialize, Serialize};
 use sha2::{Digest, Sha256};
+use speedy::{Readable, Writable};
 use std::fmt;
 use std::fs::File;
 use std::path::Path;
+use std::sync::atomic::{AtomicBool, Ordering};
 use std::time::Duration;
 
 /// A simple type for a SHA-256 hash output of 32 bytes
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub struct HashOutput([u8; 32]);
 
 impl HashOutput {
@@ -28,6 +32,31 @@ impl HashOutput {
 
         Ok(HashOutput(sha256hash.into()))
     }
+
+    pub fn from_bytes(bytes: &[u8]) -> HashOutput {
+        let mut hasher = Sha256::new();
+        hasher.update(bytes);
+        HashOutput(hasher.finalize().into())
+    }
+
+    /// The hash from a blossom URL, whose last path segment is the hex hash
+    /// with an optional file extension
+    pub fn from_url(url: &str) -> Option<HashOutput> {
+        let path = url.split(['?', '#']).next()?;
+        let segment = path.rsplit('/').next()?;
+        let stem = segment.split('.').next()?;
+        if stem.len() != 64 {
+            return None;
+        }
+        HashOutput::from_hex(stem).ok()
+    }
+
+    pub fn from_hex(hexstr: &str) -> Result<HashOutput, Error> {
+        match hex::decode(hexstr).map(<[u8; 32]>::try_from) {
+            Ok(Ok(array)) => Ok(HashOutput(array)),
+            _ => Err(ErrorKind::BlossomError(format!("Not a SHA-256 hash: {}", hexstr)).into()),
+        }
+    }
 }
 
 impl fmt::Display for HashOutput {
@@ -63,7 +92,7 @@ impl fmt::Display for BlossomVerb {
     }
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, Readable, Writable)]
 pub struct BlobDescriptor {
     /// A URL that it can be downloaded from
     pub url: String,
@@ -88,6 +117,13 @@ pub struct BlobDescriptor {
     pub created: Option<u64>,
 }
 
+impl BlobDescriptor {
+    /// When uploaded, under either name servers use for it
+    pub fn uploaded_at(&self) -> Option<u64> {
+        self.uploaded.or(self.created)
+    }
+}
+
 pub struct Blossom {
     client: Client,
 }
@@ -98,7 +134,7 @@ impl Blossom {
             Duration::new(GLOBALS.db().read_setting_fetcher_connect_timeout_sec(), 0);
         let timeout = Duration::new(GLOBALS.db().read_setting_fetcher_timeout_sec(), 0);
 
-        let client = Client::builder()
+        let client = crate::proxy::http_client_builder()?
             .gzip(false)
             .brotli(false)
             .deflate(false)
@@ -126,17 +162,18 @@ impl Blossom {
                 "Check if exists".to_owned(),
                 Unixtime::now() + Duration::new(60, 0),
                 vec![],
-            )?;
+            )
+            .await?;
 
             req_builder = req_builder.header(AUTHORIZATION, format!("Nostr {}", authorization))
         };
 
         let response = req_builder.send().await?;
 
-        if response.status().as_u16() == 200 {
-            Ok(true)
-        } else {
-            Err(get_error(&response))
+        match response.status().as_u16() {
+            200 => Ok(true),
+            404 => Ok(false),
+            _ => Err(get_error(&response)),
         }
     }
 
@@ -158,7 +195,8 @@ impl Blossom {
                 "Download".to_owned(),
                 Unixtime::now() + Duration::new(60, 0),
                 vec![],
-            )?;
+            )
+            .await?;
 
             req_builder = req_builder.header(AUTHORIZATION, format!("Nostr {}", authorization))
         };
@@ -191,7 +229,8 @@ impl Blossom {
             "Upload".to_owned(),
             Unixtime::now() + Duration::new(60, 0),
             vec![hash],
-        )?;
+        )
+        .await?;
 
         let url = format!("{}upload", base_url);
         let response = self
@@ -219,24 +258,374 @@ impl Blossom {
         }
     }
 
-    // BUD-04  PUT /mirror
-    //pub async fn mirror() {
-    //
-    //}
+    /// BUD-04  PUT /mirror
+    /// Ask the blossom server to fetch the blob from the url (usually on another
+    /// blossom server) and store it
+    pub async fn mirror(
+        &self,
+        base_url: String,
+        source_url: String,
+        hash: HashOutput,
+    ) -> Result<BlobDescriptor, Error> {
+        let authorization = authorization(
+            BlossomVerb::Upload,
+            "Mirror".to_owned(),
+            Unixtime::now() + Duration::new(60, 0),
+            vec![hash],
+        )
+        .await?;
 
-    // BUD-02  GET /list/<pubkey>
-    //pub async fn list() {
-    //    unimplemented!()
-    //}
+        let url = format!("{}mirror", base_url);
+        let response = self
+            .client
+            .put(url)
+            .header(AUTHORIZATION, format!("Nostr {}", authorization))
+            .header(CONTENT_TYPE, "application/json")
+            .body(serde_json::json!({ "url": source_url }).to_string())
+            .send()
+            .await?;
 
-    // BUD-02  DELETE /<sha256>
-    //pub async fn delete() {
-    //    unimplemented!()
-    //}
+        if response.status().as_u16() < 300 {
+            let full = response.bytes().await?;
+            match serde_json::from_slice::<BlobDescriptor>(&full) {
+                Ok(bd) => Ok(bd),
+                Err(e) => {
+                    let text = String::from_utf8_lossy(&full);
+                    tracing::error!("Failed to deserialize Blossom Blob Descriptor: {}", text);
+                    Err(e.into())
+                }
+            }
+        } else {
+            Err(get_error(&response))
+        }
+    }
+
+    /// BUD-02  GET /list/<pubkey>
+    /// List the blobs the pubkey uploaded to the blossom server
+    pub async fn list(
+        &self,
+        base_url: String,
+        pubkey: PublicKey,
+    ) -> Result<Vec<BlobDescriptor>, Error> {
+        let authorization = authorization(
+            BlossomVerb::List,
+            "List my uploads".to_owned(),
+            Unixtime::now() + Duration::new(60, 0),
+            vec![],
+        )
+        .await?;
+
+        let url = format!("{}list/{}", base_url, pubkey.as_hex_string());
+        let response = self
+            .client
+            .get(url)
+            .header(AUTHORIZATION, format!("Nostr {}", authorization))
+            .send()
+            .await?;
+
+        if response.status().as_u16() < 300 {
+            let full = response.bytes().await?;
+            match serde_json::from_slice::<Vec<BlobDescriptor>>(&full) {
+                Ok(list) => Ok(list),
+                Err(e) => {
+                    let text = String::from_utf8_lossy(&full);
+                    tracing::error!("Failed to deserialize Blossom blob list: {}", text);
+                    Err(e.into())
+                }
+            }
+        } else {
+            Err(get_error(&response))
+        }
+    }
+
+    /// BUD-02  DELETE /<sha256>
+    pub async fn delete(&self, base_url: String, hash: HashOutput) -> Result<(), Error> {
+        let url = format!("{}{}", base_url, hash);
+        let authorization = authorization(
+            BlossomVerb::Delete,
+            "Delete".to_owned(),
+            Unixtime::now() + Duration::new(60, 0),
+            vec![hash],
+        )
+        .await?;
+
+        let response = self
+            .client
+            .delete(url)
+            .header(AUTHORIZATION, format!("Nostr {}", authorization))
+            .send()
+            .await?;
+
+        if response.status().as_u16() < 300 {
+            Ok(())
+        } else {
+            Err(get_error(&response))
+        }
+    }
+}
+
+/// The blossom server URL with any path removed, and https if no scheme was given
+pub fn base_url(server: &str) -> Result<String, Error> {
+    use http::uri::{Parts, PathAndQuery, Scheme};
+    use http::Uri;
+
+    let uri = server.parse::<Uri>()?;
+    let mut parts: Parts = uri.into_parts();
+    parts.path_and_query = Some(PathAndQuery::from_static("/")); // Force no path
+    if parts.scheme.is_none() {
+        // Default to https
+        parts.scheme = Some(Scheme::HTTPS);
+    }
+    let uri = Uri::from_parts(parts)?;
+    Ok(format!("{}", uri))
+}
+
+/// The shared blossom client, created on first use
+pub(crate) fn client() -> Result<&'static Blossom, Error> {
+    match GLOBALS.blossom.get() {
+        Some(b) => Ok(b),
+        None => {
+            let blossom = Blossom::new()?;
+            let _ = GLOBALS.blossom.set(blossom);
+            Ok(GLOBALS.blossom.get().unwrap())
+        }
+    }
+}
+
+/// The base urls of the blossom servers in our user server list (kind 10063)
+pub fn our_servers() -> Vec<String> {
+    GLOBALS
+        .db()
+        .read_setting_blossom_servers()
+        .split_whitespace()
+        .filter_map(|s| base_url(s).ok())
+        .collect()
+}
+
+/// The base urls of the blossom servers in someone's user server list (kind 10063),
+/// if we have it
+pub fn servers_of(pubkey: PublicKey) -> Result<Vec<String>, Error> {
+    let event = match GLOBALS
+        .db()
+        .get_replaceable_event(EventKind::UserServerList, pubkey, "")?
+    {
+        Some(event) => event,
+        None => return Ok(vec![]),
+    };
+
+    Ok(event
+        .tags
+        .iter()
+        .filter(|tag| tag.tagname() == "server")
+        .filter_map(|tag| base_url(tag.value()).ok())
+        .collect())
+}
+
+/// Download a blob from the blossom servers of its author, verifying the hash.
+///
+/// If we don't have their server list we ask their outbox relays for it and give
+/// it some time to arrive.
+pub(crate) async fn download_from_servers_of(
+    author: PublicKey,
+    hash: HashOutput,
+) -> Result<Vec<u8>, Error> {
+    let mut servers = servers_of(author)?;
+    if servers.is_empty() {
+        let relays = crate::relay::get_some_pubkey_outboxes(author)?;
+        let _ = GLOBALS
+            .to_overlord
+            .send(ToOverlordMessage::FetchNAddr(NAddr {
+                d: "".to_owned(),
+                relays: relays.iter().map(|r| r.to_unchecked_url()).collect(),
+                kind: EventKind::UserServerList,
+                author,
+            }));
+
+        for _ in 0..10 {
+            tokio::time::sleep(Duration::from_secs(1)).await;
+            servers = servers_of(author)?;
+            if !servers.is_empty() {
+                break;
+            }
+        }
+        if servers.is_empty() {
+            return Err(ErrorKind::BlossomError("Author has no blossom servers".to_owned()).into());
+        }
+    }
+
+    let blossom = client()?;
+    let mut last_error: Option<Error> = None;
+    for server in servers {
+        let bytes = match blossom.download(server.clone(), hash, false).await {
+            Ok(response) => match response.bytes().await {
+                Ok(bytes) => bytes,
+                Err(e) => {
+                    last_error = Some(e.into());
+                    continue;
+                }
+            },
+            Err(e) => {
+                last_error = Some(e);
+                continue;
+            }
+        };
+
+        GLOBALS.bytes_read.fetch_add(bytes.len(), Ordering::Relaxed);
+
+        if HashOutput::from_bytes(&bytes) != hash {
+            tracing::warn!("Blossom: {} served the wrong data for {}", server, hash);
+            last_error =
+                Some(ErrorKind::BlossomError(format!("Hash mismatch from {}", server)).into());
+            continue;
+        }
+
+        return Ok(bytes.to_vec());
+    }
+
+    Err(last_error.unwrap_or_else(|| ErrorKind::BlossomError("Not found".to_owned()).into()))
+}
+
+/// Which of our blossom servers have one of our blobs
+#[derive(Debug, Clone)]
+pub struct BlobReplication {
+    /// The blob
+    pub blob: BlobDescriptor,
+
+    /// Base urls of the servers that have it
+    pub present: Vec<String>,
+
+    /// Base urls of the servers that don't, and why
+    pub missing: Vec<(String, String)>,
+
+    /// When we checked
+    pub checked_at: Unixtime,
+}
+
+impl BlobReplication {
+    /// Whether some of our servers are missing the blob
+    pub fn is_under_replicated(&self) -> bool {
+        !self.missing.is_empty()
+    }
+}
+
+/// Our blobs that are missing from some of our blossom servers, as of the last check
+pub fn under_replicated_blobs() -> Vec<BlobReplication> {
+    let mut output: Vec<BlobReplication> = GLOBALS
+        .blossom_replication
+        .iter()
+        .filter(|r| r.value().is_under_replicated())
+        .map(|r| r.value().clone())
+        .collect();
+    output.sort_by(|a, b| b.blob.uploaded_at().cmp(&a.blob.uploaded_at()));
+    output
+}
+
+/// Check which of the servers have the blob, and mirror it (BUD-04) to those that
+/// don't from one that does.
+pub(crate) async fn replicate(
+    blossom: &Blossom,
+    blob: &BlobDescriptor,
+    servers: &[String],
+) -> Result<BlobReplication, Error> {
+    let hash = HashOutput::from_hex(&blob.sha256)?;
+
+    let mut present: Vec<String> = Vec::new();
+    let mut absent: Vec<String> = Vec::new();
+    let mut missing: Vec<(String, String)> = Vec::new();
+    for server in servers {
+        match blossom.check_exists(server.clone(), hash, false).await {
+            Ok(true) => present.push(server.clone()),
+            Ok(false) => absent.push(server.clone()),
+            Err(e) => missing.push((server.clone(), format!("{}", e))),
+        }
+    }
+
+    // Mirror from a server we know has it, else from where we uploaded it
+    let source_url = match present.first() {
+        Some(server) => format!("{}{}", server, blob.sha256),
+        None => blob.url.clone(),
+    };
+
+    for server in absent {
+        match blossom
+            .mirror(server.clone(), source_url.clone(), hash)
+            .await
+        {
+            Ok(_) => present.push(server),
+            Err(e) => missing.push((server, format!("{}", e))),
+        }
+    }
+
+    let replication = BlobReplication {
+        blob: blob.clone(),
+        present,
+        missing,
+        checked_at: Unixtime::now(),
+    };
+    GLOBALS
+        .blossom_replication
+        .insert(blob.sha256.clone(), replication.clone());
+
+    Ok(replication)
+}
+
+static REPAIRING: AtomicBool = AtomicBool::new(false);
+
+/// Make sure every blob we uploaded is on every one of our blossom servers
+pub(crate) async fn repair_blobs() -> Result<(), Error> {
+    // Only one repair at a time
+    if REPAIRING.swap(true, Ordering::SeqCst) {
+        return Ok(());
+    }
+    let result = repair_blobs_inner().await;
+    REPAIRING.store(false, Ordering::SeqCst);
+    result
+}
+
+async fn repair_blobs_inner() -> Result<(), Error> {
+    let servers = our_servers();
+    if servers.is_empty() {
+        return Ok(());
+    }
+
+    let blossom = client()?;
+
+    let blobs = GLOBALS.db().read_blossom_blobs()?;
+    GLOBALS
+        .blossom_replication
+        .retain(|sha256, _| blobs.iter().any(|b| &b.sha256 == sha256));
+
+    let mut under_replicated: usize = 0;
+    for blob in &blobs {
+        match replicate(blossom, blob, &servers).await {
+            Ok(replication) => {
+                if replication.is_under_replicated() {
+                    under_replicated += 1;
+                }
+            }
+            Err(e) => tracing::warn!("Blossom: could not check {}: {}", blob.sha256, e),
+        }
+    }
+
+    if under_replicated > 0 {
+        tracing::warn!(
+            "Blossom: {} of {} blobs are missing from some of our servers",
+            under_replicated,
+            blobs.len()
+        );
+    } else {
+        tracing::info!(
+            "Blossom: all {} blobs are on all {} servers",
+            blobs.len(),
+            servers.len()
+        );
+    }
+
+    Ok(())
 }
 
 // This returns the base64 encoded authorization event
-fn authorization(
+async fn authorization(
     verb: BlossomVerb,
     purpose: String,
     expiration: Unixtime,
@@ -263,7 +652,7 @@ fn authorization(
         content: purpose,
     };
 
-    let event = GLOBALS.identity.sign_event(pre_event)?;
+    let event = GLOBALS.identity.sign_event(pre_event).await?;
     let event_json = serde_json::to_string(&event)?;
     let base64 = base64::engine::general_purpose::STANDARD.encode(&event_json);
 
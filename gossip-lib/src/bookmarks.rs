@@ -6,15 +6,31 @@ use nostr_types::{
 };
 use std::collections::BTreeMap;
 
-pub struct BookmarkList(Vec<(EventReference, bool)>);
+#[derive(Clone)]
+pub struct BookmarkList {
+    list: Vec<(EventReference, bool)>,
+
+    // A remote signer is still decrypting the private part, so the list is incomplete
+    private_pending: bool,
+}
 
 impl BookmarkList {
     pub fn empty() -> Self {
-        Self(Vec::new())
+        Self {
+            list: Vec::new(),
+            private_pending: false,
+        }
     }
 
     pub fn clear(&mut self) {
-        self.0 = Vec::new();
+        self.list = Vec::new();
+        self.private_pending = false;
+    }
+
+    /// Whether a remote signer is still decrypting our private bookmarks. Until it
+    /// is done the list must not be published, or they would be lost.
+    pub fn is_private_pending(&self) -> bool {
+        self.private_pending
     }
 
     fn add_tags(&mut self, tags: &[Tag], private: bool) -> Result<(), Error> {
@@ -45,27 +61,27 @@ impl BookmarkList {
                 _ => continue,
             };
 
-            self.0.push((bookmark, private));
+            self.list.push((bookmark, private));
         }
 
         Ok(())
     }
 
     pub fn add(&mut self, er: EventReference, private: bool) -> Result<bool, Error> {
-        let index = self.0.iter().position(|(thiser, _)| *thiser == er);
+        let index = self.list.iter().position(|(thiser, _)| *thiser == er);
         if index.is_some() {
             return Ok(false);
         }
-        self.0.push((er, private));
+        self.list.push((er, private));
         Ok(true)
     }
 
     pub fn remove(&mut self, er: EventReference) -> Result<bool, Error> {
-        let index = self.0.iter().position(|(thiser, _)| *thiser == er);
+        let index = self.list.iter().position(|(thiser, _)| *thiser == er);
         match index {
             None => Ok(false),
             Some(index) => {
-                self.0.remove(index);
+                self.list.remove(index);
                 Ok(true)
             }
         }
@@ -87,21 +103,29 @@ impl BookmarkList {
 
         let mut bml = Self::empty();
         bml.add_tags(event.tags.as_ref(), false)?;
-        if let Ok(json_string) = GLOBALS.identity.decrypt(&public_key, &event.content) {
-            if let Ok(vectags) = serde_json::from_str::<Vec<Tag>>(&json_string) {
-                bml.add_tags(vectags.as_ref(), true)?;
+        match GLOBALS.identity.decrypt(&public_key, &event.content) {
+            Ok(json_string) => {
+                if let Ok(vectags) = serde_json::from_str::<Vec<Tag>>(&json_string) {
+                    bml.add_tags(vectags.as_ref(), true)?;
+                }
             }
+            Err(e) if matches!(e.kind, ErrorKind::Nip46Pending) => bml.private_pending = true,
+            Err(_) => (),
         }
 
         Ok(bml)
     }
 
-    pub fn into_event(&self) -> Result<Event, Error> {
+    pub async fn into_event(&self) -> Result<Event, Error> {
         let public_key = match GLOBALS.identity.public_key() {
             None => return Err(ErrorKind::NoPublicKey.into()),
             Some(pk) => pk,
         };
 
+        if self.private_pending {
+            return Err(ErrorKind::Nip46Pending.into());
+        }
+
         let er_to_tag = |er: &EventReference| -> Tag {
             match er {
                 EventReference::Id { id, relays, .. } => ParsedTag::Event {
@@ -120,7 +144,7 @@ impl BookmarkList {
         };
 
         let tags: Vec<Tag> = self
-            .0
+            .list
             .iter()
             .filter_map(
                 |(er, private)| {
@@ -135,7 +159,7 @@ impl BookmarkList {
 
         let content = {
             let private: Vec<Tag> = self
-                .0
+                .list
                 .iter()
                 .filter_map(
                     |(er, private)| {
@@ -148,11 +172,14 @@ impl BookmarkList {
                 )
                 .collect();
             let private_json = serde_json::to_string(&private)?;
-            GLOBALS.identity.encrypt(
-                &public_key,
-                &private_json,
-                ContentEncryptionAlgorithm::Nip44v2,
-            )?
+            GLOBALS
+                .identity
+                .encrypt(
+                    &public_key,
+                    &private_json,
+                    ContentEncryptionAlgorithm::Nip44v2,
+                )
+                .await?
         };
 
         let pre_event = PreEvent {
@@ -163,13 +190,13 @@ impl BookmarkList {
             content,
         };
 
-        GLOBALS.identity.sign_event(pre_event)
+        GLOBALS.identity.sign_event(pre_event).await
     }
 
     pub fn get_bookmark_feed(&self) -> Result<Vec<Id>, Error> {
         let mut map: BTreeMap<Unixtime, Id> = BTreeMap::new();
 
-        for (eref, _) in &self.0 {
+        for (eref, _) in &self.list {
             match eref {
                 EventReference::Id { id, .. } => {
                     if let Some(event) = GLOBALS.db().read_event(*id)? {
@@ -191,3 +218,17 @@ impl BookmarkList {
         Ok(feed)
     }
 }
+
+/// Rebuild `GLOBALS.bookmarks` from our latest bookmark list event
+pub(crate) fn rebuild_bookmarks() -> Result<(), Error> {
+    if let Some(pk) = GLOBALS.identity.public_key() {
+        if let Some(event) = GLOBALS
+            .db()
+            .get_replaceable_event(EventKind::BookmarkList, pk, "")?
+        {
+            *GLOBALS.bookmarks.write_arc() = BookmarkList::from_event(&event)?;
+            GLOBALS.recompute_current_bookmarks.notify_one();
+        }
+    }
+    Ok(())
+}
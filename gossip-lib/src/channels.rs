@@ -0,0 +1,240 @@
+use crate::error::Error;
+use crate::globals::GLOBALS;
+use nostr_types::{Event, EventKind, Filter, Id, ParsedTag, PublicKey, RelayUrl, Unixtime};
+use speedy::{Readable, Writable};
+use std::collections::HashSet;
+
+/// A public chat channel (NIP-28) that we follow
+#[derive(Debug, Clone, PartialEq, Eq, Readable, Writable)]
+pub struct FollowedChannel {
+    /// The id of the event that created the channel (kind 40)
+    pub id: Id,
+
+    /// A relay where the channel can be found, if we know one
+    pub relay: Option<RelayUrl>,
+
+    /// When we started following
+    pub followed_at: Unixtime,
+}
+
+impl FollowedChannel {
+    /// The name of the channel, from its metadata, else a short form of its id
+    pub fn name(&self) -> String {
+        channel_name(self.id)
+    }
+}
+
+/// What the creator of a channel says about it
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ChannelMetadata {
+    pub name: Option<String>,
+    pub about: Option<String>,
+    pub picture: Option<String>,
+
+    /// Relays the creator recommends for the channel
+    pub relays: Vec<RelayUrl>,
+
+    /// Who created the channel, if we have the creation event
+    pub creator: Option<PublicKey>,
+
+    /// When the channel was created, if we have the creation event
+    pub created_at: Option<Unixtime>,
+}
+
+impl ChannelMetadata {
+    /// The metadata of a channel, loading it from storage only if it is not
+    /// cached. The cache is cleared as channel creation (kind 40) and metadata
+    /// (kind 41) events come in.
+    pub fn get(id: Id) -> ChannelMetadata {
+        if let Some(metadata) = GLOBALS.channel_metadata.get(&id) {
+            return metadata.clone();
+        }
+        match Self::load(id) {
+            Ok(metadata) => {
+                GLOBALS.channel_metadata.insert(id, metadata.clone());
+                metadata
+            }
+            Err(e) => {
+                tracing::error!("Loading channel metadata: {}", e);
+                ChannelMetadata::default()
+            }
+        }
+    }
+
+    /// Load what we have of the metadata of a channel from storage. This is
+    /// the creation event (kind 40) updated by the latest metadata event
+    /// (kind 41) of the creator.
+    pub fn load(id: Id) -> Result<ChannelMetadata, Error> {
+        let creation = match GLOBALS.db().read_event(id)? {
+            Some(event) if event.kind == EventKind::ChannelCreation => event,
+            _ => return Ok(ChannelMetadata::default()),
+        };
+
+        let mut metadata = ChannelMetadata {
+            creator: Some(creation.pubkey),
+            created_at: Some(creation.created_at),
+            ..Default::default()
+        };
+        metadata.apply(&creation.content);
+
+        // Only the creator can change the metadata
+        let mut filter = Filter::new();
+        filter.authors = vec![creation.pubkey];
+        filter.kinds = vec![EventKind::ChannelMetadata];
+        filter.add_tag_value('e', id.as_hex_string());
+        filter.limit = Some(1);
+        let updates = GLOBALS.db().find_events_by_filter(&filter, |_| true)?;
+        if let Some(latest) = updates.first() {
+            metadata.apply(&latest.content);
+        }
+
+        Ok(metadata)
+    }
+
+    // Apply the JSON content of a creation or metadata event. Fields that are
+    // missing are left as they were.
+    fn apply(&mut self, content: &str) {
+        let value: serde_json::Value = match serde_json::from_str(content) {
+            Ok(value) => value,
+            Err(_) => return,
+        };
+
+        let field = |name: &str| {
+            value
+                .get(name)
+                .and_then(|v| v.as_str())
+                .map(|s| s.trim().to_owned())
+        };
+
+        if let Some(name) = field("name") {
+            self.name = Some(name);
+        }
+        if let Some(about) = field("about") {
+            self.about = Some(about);
+        }
+        if let Some(picture) = field("picture") {
+            self.picture = Some(picture);
+        }
+        if let Some(relays) = value.get("relays").and_then(|v| v.as_array()) {
+            self.relays = relays
+                .iter()
+                .filter_map(|r| r.as_str())
+                .filter_map(|r| RelayUrl::try_from_str(r).ok())
+                .collect();
+        }
+    }
+}
+
+/// The name of a channel, from its metadata, else a short form of its id
+pub fn channel_name(id: Id) -> String {
+    name_or_id(id, &ChannelMetadata::get(id))
+}
+
+/// The name of a channel if its metadata is cached, else a short form of its
+/// id. This does not read storage.
+pub fn cached_channel_name(id: Id) -> String {
+    match GLOBALS.channel_metadata.get(&id) {
+        Some(metadata) => name_or_id(id, &metadata),
+        None => crate::names::hex_id_short(&id.into()),
+    }
+}
+
+fn name_or_id(id: Id, metadata: &ChannelMetadata) -> String {
+    match &metadata.name {
+        Some(name) if !name.is_empty() => name.clone(),
+        _ => crate::names::hex_id_short(&id.into()),
+    }
+}
+
+/// Forget the cached metadata of the channel that a creation (kind 40) or
+/// metadata (kind 41) event is about
+pub(crate) fn forget_channel_metadata(event: &Event) {
+    let id = match event.kind {
+        EventKind::ChannelCreation => Some(event.id),
+        EventKind::ChannelMetadata => message_channel(event),
+        _ => None,
+    };
+    if let Some(id) = id {
+        GLOBALS.channel_metadata.remove(&id);
+    }
+}
+
+/// The channels we know of, newest first, as (id, metadata)
+pub fn known_channels(limit: usize) -> Result<Vec<(Id, ChannelMetadata)>, Error> {
+    let mut filter = Filter::new();
+    filter.kinds = vec![EventKind::ChannelCreation];
+    filter.limit = Some(limit);
+
+    let mut output: Vec<(Id, ChannelMetadata)> = Vec::new();
+    for event in GLOBALS.db().find_events_by_filter(&filter, |_| true)? {
+        output.push((event.id, ChannelMetadata::get(event.id)));
+    }
+    Ok(output)
+}
+
+/// The channel a message (kind 42) was posted into: the `e` tag marked
+/// "root", else the first `e` tag (the deprecated positional form)
+pub fn message_channel(event: &Event) -> Option<Id> {
+    let mut first: Option<Id> = None;
+    for tag in &event.tags {
+        if let Ok(ParsedTag::Event { id, marker, .. }) = tag.parse() {
+            if marker.as_deref() == Some("root") {
+                return Some(id);
+            }
+            if first.is_none() {
+                first = Some(id);
+            }
+        }
+    }
+    first
+}
+
+/// The channel messages we have hidden (kind 43)
+pub fn hidden_messages() -> Result<HashSet<Id>, Error> {
+    let pubkey = match GLOBALS.identity.public_key() {
+        Some(pk) => pk,
+        None => return Ok(HashSet::new()),
+    };
+
+    let mut filter = Filter::new();
+    filter.authors = vec![pubkey];
+    filter.kinds = vec![EventKind::ChannelHideMessage];
+
+    let mut hidden: HashSet<Id> = HashSet::new();
+    for event in GLOBALS.db().find_events_by_filter(&filter, |_| true)? {
+        for tag in &event.tags {
+            if let Ok(ParsedTag::Event { id, .. }) = tag.parse() {
+                hidden.insert(id);
+            }
+        }
+    }
+    Ok(hidden)
+}
+
+/// The relays where a channel can be found: those the creator recommends, the
+/// one we remembered when following it, and those we saw its creation on. This
+/// may be empty.
+pub fn channel_relays(id: Id) -> Result<Vec<RelayUrl>, Error> {
+    let mut relays = ChannelMetadata::get(id).relays;
+
+    if let Some(followed) = GLOBALS
+        .db()
+        .read_followed_channels()?
+        .into_iter()
+        .find(|c| c.id == id)
+    {
+        relays.extend(followed.relay);
+    }
+
+    relays.extend(
+        GLOBALS
+            .db()
+            .get_event_seen_on_relay(id)?
+            .into_iter()
+            .map(|(url, _)| url),
+    );
+
+    relays.sort();
+    relays.dedup();
+    Ok(relays)
+}
@@ -1,8 +1,11 @@
+use crate::counts::CountTarget;
 use crate::dm_channel::DmChannel;
+use crate::drafts::Draft;
 use crate::filter_set::FilterSet;
 use crate::misc::Private;
 use crate::nostr_connect_server::{Approval, ParsedCommand};
 use crate::people::PersonList;
+use crate::post::LongFormArticle;
 use crate::relay::Relay;
 use nostr_types::{
     Event, EventKind, EventReference, Id, Metadata, MilliSatoshi, NAddr, Profile, PublicKey,
@@ -36,6 +39,18 @@ pub enum ToOverlordMessage {
     /// pass 'true' as the second parameter for a permanent approval
     AuthDeclined(RelayUrl, bool),
 
+    /// Calls [blossom_delete](crate::Overlord::blossom_delete)
+    /// Deletes our blob (by sha256 hex) from the blossom server (by base url)
+    BlossomDelete(String, String),
+
+    /// Calls [blossom_list](crate::Overlord::blossom_list)
+    /// Lists our blobs on each configured blossom server
+    BlossomList,
+
+    /// Calls [blossom_repair](crate::Overlord::blossom_repair)
+    /// Mirrors our blobs to any of our blossom servers that are missing them
+    BlossomRepair,
+
     /// Calls [blossom_upload](crate::Overlord::blossom_upload)
     /// Uploads the local file to a blossom server
     BlossomUpload(PathBuf),
@@ -62,9 +77,16 @@ pub enum ToOverlordMessage {
     /// pass 'true' as the second parameter for a permanent approval
     ConnectDeclined(RelayUrl, bool),
 
+    /// Calls [count](crate::Overlord::count)
+    /// Asks relays that support NIP-45 to count these for us
+    Count(Vec<CountTarget>),
+
     /// Calls [delegation_reset](crate::Overlord::delegation_reset)
     DelegationReset,
 
+    /// Calls [delete_draft](crate::Overlord::delete_draft)
+    DeleteDraft(Draft),
+
     /// Calls [delete_person_list](crate::Overlord::delete_person_list)
     DeletePersonList(PersonList),
 
@@ -80,12 +102,19 @@ pub enum ToOverlordMessage {
     /// Calls [drop_relay](crate::Overlord::drop_relay)
     DropRelay(RelayUrl),
 
+    /// Calls [fetch_channels](crate::Overlord::fetch_channels)
+    FetchChannels,
+
     /// Calls [fetch_event](crate::Overlord::fetch_event)
     FetchEvent(Id, Vec<RelayUrl>),
 
     /// Calls [fetch_naddr](crate::Overlord::fetch_naddr)
     FetchNAddr(NAddr),
 
+    /// Calls [follow_channel](crate::Overlord::follow_channel)
+    /// Follows a public chat channel (channel id, relay hint)
+    FollowChannel(Id, Option<RelayUrl>),
+
     /// Calls [follow_pubkey](crate::Overlord::follow_pubkey)
     FollowPubkey(PublicKey, PersonList, Private),
 
@@ -98,6 +127,10 @@ pub enum ToOverlordMessage {
     /// Calls [generate_private_key](crate::Overlord::generate_private_key)
     GeneratePrivateKey(String),
 
+    /// Calls [hide_channel_message](crate::Overlord::hide_channel_message)
+    /// Hides a message in a public chat channel (message id, reason)
+    HideChannelMessage(Id, String),
+
     /// Calls [hide_or_show_relay](crate::Overlord::hide_or_show_relay)
     HideOrShowRelay(RelayUrl, bool),
 
@@ -111,15 +144,39 @@ pub enum ToOverlordMessage {
     /// Calls [import_pub](crate::Overlord::import_pub)
     ImportPub(String),
 
+    /// Calls [join_group](crate::Overlord::join_group)
+    /// Asks the relay to let us into the group (relay, group id)
+    JoinGroup(RelayUrl, String),
+
+    /// Calls [label](crate::Overlord::label)
+    Label(Id, String, String),
+
+    /// Calls [leave_group](crate::Overlord::leave_group)
+    /// Asks the relay to take us out of the group (relay, group id)
+    LeaveGroup(RelayUrl, String),
+
     /// Calls [load_more_current_feed](crate::Overlord::load_more_current_feed)
     LoadMoreCurrentFeed,
 
+    /// Calls [measure_cache](crate::Overlord::measure_cache)
+    MeasureCache,
+
     /// internal (minions use this channel too)
     MinionJobComplete(RelayUrl, u64),
 
     /// internal (minions use this channel too)
     MinionJobUpdated(RelayUrl, u64, u64),
 
+    /// Calls [mute_channel_user](crate::Overlord::mute_channel_user)
+    /// Mutes a person in public chat channels (person, reason)
+    MuteChannelUser(PublicKey, String),
+
+    /// Calls [nip46_login](crate::Overlord::nip46_login)
+    Nip46Login(String),
+
+    /// Calls [nip46_logout](crate::Overlord::nip46_logout)
+    Nip46Logout,
+
     /// Calls [nip46_server_op_approval_response](crate::Overlord::nip46_server_op_approval_response)
     Nip46ServerOpApprovalResponse(PublicKey, ParsedCommand, Approval),
 
@@ -130,17 +187,44 @@ pub enum ToOverlordMessage {
         in_reply_to: Option<Id>,
         annotation: bool,
         dm_channel: Option<DmChannel>,
+        expiration: Option<Unixtime>,
     },
 
     /// Calls [post_again](crate::Overlord::post_again)
     PostAgain(Event),
 
+    /// Calls [post_channel](crate::Overlord::post_channel)
+    /// Posts a message into a public chat channel
+    PostChannel {
+        channel: Id,
+        content: String,
+        tags: Vec<Tag>,
+        in_reply_to: Option<Id>,
+    },
+
+    /// Calls [post_group](crate::Overlord::post_group)
+    /// Posts a chat message, or a thread if it has a title, into a group
+    PostGroup {
+        relay_url: RelayUrl,
+        group: String,
+        content: String,
+        tags: Vec<Tag>,
+        in_reply_to: Option<Id>,
+        title: Option<String>,
+    },
+
+    /// Calls [post_long_form](crate::Overlord::post_long_form)
+    PostLongForm(LongFormArticle),
+
     /// Calls [post_cancel](crate::Overlord::post_cancel)
     PostCancel,
 
     /// Calls [post_nip46_event](crate::Overlord::post_nip46_event)
     PostNip46Event(Event, Vec<RelayUrl>),
 
+    /// Calls [post_with_pow](crate::Overlord::post_with_pow)
+    PostWithPow(RelayUrl, Box<Event>, u8),
+
     /// Calls [prune_cache](crate::Overlord::prune_cache)
     PruneCache,
 
@@ -174,9 +258,15 @@ pub enum ToOverlordMessage {
     /// Calls [reresh_subscribed_metadata](crate::Overlord::refresh_subscribed_metadata)
     RefreshSubscribedMetadata,
 
+    /// Calls [report](crate::Overlord::report)
+    Report(Id, PublicKey, String),
+
     /// Calls [repost](crate::Overlord::repost)
     Repost(Id),
 
+    /// Calls [save_draft](crate::Overlord::save_draft)
+    SaveDraft(Draft),
+
     /// Calls [search](crate::Overlord::search_locally)
     SearchLocally(String),
 
@@ -186,12 +276,18 @@ pub enum ToOverlordMessage {
     /// Calls [set_active_person](crate::Overlord::set_active_person)
     SetActivePerson(PublicKey),
 
+    /// internal
+    SetChannelFeed(Id, Unixtime),
+
     /// internal
     SetDmChannel(DmChannel),
 
     /// internal
     SetGlobalFeed(Unixtime),
 
+    /// internal
+    SetGroupFeed(RelayUrl, String, Unixtime),
+
     /// internal
     SetPersonFeed(PublicKey, Unixtime),
 
@@ -232,6 +328,12 @@ pub enum ToOverlordMessage {
     /// Calls [track_follows](crate::Overlord::track_follows)
     TrackFollows(PublicKey),
 
+    /// Calls [trim_cache](crate::Overlord::trim_cache)
+    TrimCache,
+
+    /// Calls [unfollow_channel](crate::Overlord::unfollow_channel)
+    UnfollowChannel(Id),
+
     /// Calls [unlock_key](crate::Overlord::unlock_key)
     UnlockKey(String),
 
@@ -291,6 +393,8 @@ pub(crate) enum ToMinionPayloadDetail {
     AdvertiseRelayList(Box<Event>, Box<Event>),
     AuthApproved,
     AuthDeclined,
+    AuthSigned(Option<Box<Event>>),
+    Count(Vec<CountTarget>),
     FetchEvent(Id),
     FetchNAddr(NAddr),
     PostEvents(Vec<Event>),
@@ -326,6 +430,8 @@ pub enum RelayConnectionReason {
     Search,
     SubscribePerson,
     SubscribeGlobal,
+    SubscribeGroup,
+    SubscribeChannel,
 }
 
 impl fmt::Display for RelayConnectionReason {
@@ -363,6 +469,8 @@ impl RelayConnectionReason {
             Search => "Search",
             SubscribePerson => "Subscribe to the events of a person",
             SubscribeGlobal => "Subscribe to the global feed on a relay",
+            SubscribeGroup => "Subscribe to a group on its relay",
+            SubscribeChannel => "Subscribe to public chat channels",
         }
     }
 
@@ -393,6 +501,8 @@ impl RelayConnectionReason {
             Search => false,
             SubscribePerson => false,
             SubscribeGlobal => false,
+            SubscribeGroup => false,
+            SubscribeChannel => false,
         }
     }
 }
@@ -0,0 +1,96 @@
+use crate::globals::GLOBALS;
+use nostr_types::{EventKind, Filter, Id, PublicKey, Unixtime};
+use std::time::Duration;
+
+/// The NIP that defines the COUNT verb
+pub const NIP_COUNT: u32 = 45;
+
+// How long a count stays good before we ask relays for it again
+const COUNT_TTL: Duration = Duration::from_secs(60 * 10);
+
+/// Something we can ask relays to count for us (NIP-45)
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum CountTarget {
+    /// Contact lists that follow this person
+    Followers(PublicKey),
+
+    /// Replies to this event
+    Replies(Id),
+
+    /// Reactions to this event
+    Reactions(Id),
+}
+
+impl CountTarget {
+    /// The filter that selects the events to be counted
+    pub fn filter(&self) -> Filter {
+        let mut filter = Filter::new();
+        match self {
+            CountTarget::Followers(pubkey) => {
+                filter.kinds = vec![EventKind::ContactList];
+                filter.set_tag_values('p', vec![pubkey.as_hex_string()]);
+            }
+            CountTarget::Replies(id) => {
+                filter.kinds = vec![EventKind::TextNote, EventKind::Comment];
+                filter.set_tag_values('e', vec![id.as_hex_string()]);
+            }
+            CountTarget::Reactions(id) => {
+                filter.kinds = vec![EventKind::Reaction];
+                filter.set_tag_values('e', vec![id.as_hex_string()]);
+            }
+        }
+        filter
+    }
+}
+
+/// The highest count any relay has reported for the target, if any relay has
+pub fn relay_count(target: CountTarget) -> Option<usize> {
+    GLOBALS.relay_counts.get(&target).map(|c| *c)
+}
+
+/// The targets that relays have not been asked to count recently. These are
+/// marked as asked now.
+pub(crate) fn targets_needing_count(targets: Vec<CountTarget>) -> Vec<CountTarget> {
+    let now = Unixtime::now();
+    targets
+        .into_iter()
+        .filter(|target| {
+            if let Some(asked) = GLOBALS.relay_counts_asked.get(target) {
+                if now - *asked < COUNT_TTL {
+                    return false;
+                }
+            }
+            GLOBALS.relay_counts_asked.insert(*target, now);
+            true
+        })
+        .collect()
+}
+
+/// Record a count reported by a relay.
+///
+/// Each relay only counts the events it has, so we keep the highest count reported.
+pub(crate) fn record_relay_count(target: CountTarget, count: usize) {
+    let mut changed = false;
+    GLOBALS
+        .relay_counts
+        .entry(target)
+        .and_modify(|c| {
+            if count > *c {
+                *c = count;
+                changed = true;
+            }
+        })
+        .or_insert_with(|| {
+            changed = true;
+            count
+        });
+
+    if !changed {
+        return;
+    }
+
+    match target {
+        CountTarget::Followers(_) => GLOBALS.notify_ui_redraw.notify_waiters(),
+        CountTarget::Replies(id) | CountTarget::Reactions(id) => GLOBALS.ui_invalidate_note(id),
+    }
+}
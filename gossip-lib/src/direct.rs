@@ -86,7 +86,7 @@ impl Connection {
 
         let (websocket, _response) = tokio::time::timeout(
             Duration::new(5, 0),
-            tokio_tungstenite::connect_async(request),
+            crate::proxy::connect_websocket(request, None),
         )
         .await??;
 
@@ -122,7 +122,7 @@ impl Connection {
 
         let (websocket, _response) = tokio::time::timeout(
             Duration::new(5, 0),
-            tokio_tungstenite::connect_async(request),
+            crate::proxy::connect_websocket(request, None),
         )
         .await??;
 
@@ -257,7 +257,7 @@ impl Connection {
                 ],
                 content: "".to_string(),
             };
-            let event = GLOBALS.identity.sign_event(pre_event)?;
+            let event = GLOBALS.identity.sign_event(pre_event).await?;
             self.auth_state = AuthState::InProgress(event.id);
             self.send_message(ClientMessage::Auth(Box::new(event)))
                 .await?;
@@ -456,7 +456,6 @@ fn url_to_host_and_uri(url: &str) -> Result<(String, Uri), Error> {
 
 pub async fn fetch_nip11(relay_url: &str) -> Result<serde_json::Value, Error> {
     use reqwest::redirect::Policy;
-    use reqwest::Client;
     use std::time::Duration;
 
     let (host, uri) = url_to_host_and_uri(relay_url)?;
@@ -469,7 +468,7 @@ pub async fn fetch_nip11(relay_url: &str) -> Result<serde_json::Value, Error> {
         None => panic!("Relay URL has no scheme."),
     };
     let url = format!("{}://{}{}", scheme, host, uri.path());
-    let client = Client::builder()
+    let client = crate::proxy::http_client_builder()?
         .redirect(Policy::none())
         .connect_timeout(Duration::from_secs(60))
         .timeout(Duration::from_secs(60))
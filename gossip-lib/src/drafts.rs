@@ -0,0 +1,230 @@
+use crate::error::{Error, ErrorKind};
+use crate::globals::GLOBALS;
+use nostr_types::{
+    ContentEncryptionAlgorithm, Event, EventKind, Filter, Id, ParsedTag, PreEvent, Tag, Unixtime,
+};
+
+/// The event kind of NIP-37 draft wraps
+pub fn draft_event_kind() -> EventKind {
+    EventKind::from(31234)
+}
+
+/// A draft of a post, saved as a NIP-37 draft wrap (kind 31234).
+///
+/// The draft itself is an unsigned event which is encrypted to ourself with NIP-44 and
+/// stored in the content of the wrap, so that it syncs between devices via our write relays
+/// without revealing what we are writing.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Draft {
+    /// The `d` tag of the draft wrap
+    pub identifier: String,
+
+    /// The kind of event being drafted
+    pub kind: EventKind,
+
+    pub content: String,
+
+    /// Tags of the drafted event, carrying the subject, content warning and reply context
+    pub tags: Vec<Tag>,
+
+    /// When the draft was last saved
+    pub saved_at: Unixtime,
+}
+
+impl Draft {
+    /// Create a new draft of a text note
+    pub fn new_text_note(
+        content: String,
+        subject: Option<String>,
+        content_warning: Option<String>,
+        in_reply_to: Option<Id>,
+        annotation: bool,
+    ) -> Draft {
+        let mut tags: Vec<Tag> = Vec::new();
+        if let Some(subject) = subject {
+            tags.push(ParsedTag::Subject(subject).into_tag());
+        }
+        if let Some(cw) = content_warning {
+            tags.push(ParsedTag::ContentWarning(Some(cw)).into_tag());
+        }
+        if let Some(id) = in_reply_to {
+            tags.push(
+                ParsedTag::Event {
+                    id,
+                    recommended_relay_url: None,
+                    marker: Some("reply".to_owned()),
+                    author_pubkey: None,
+                }
+                .into_tag(),
+            );
+        }
+        if annotation {
+            tags.push(Tag::new(&["annotation"]));
+        }
+
+        Draft {
+            identifier: format!("{:016x}", rand::random::<u64>()),
+            kind: EventKind::TextNote,
+            content,
+            tags,
+            saved_at: Unixtime::now(),
+        }
+    }
+
+    /// The event this draft replies to
+    pub fn in_reply_to(&self) -> Option<Id> {
+        for tag in &self.tags {
+            if let Ok(ParsedTag::Event { id, marker, .. }) = tag.parse() {
+                if marker.as_deref() == Some("reply") {
+                    return Some(id);
+                }
+            }
+        }
+        None
+    }
+
+    pub fn subject(&self) -> Option<String> {
+        self.tags
+            .iter()
+            .find(|t| t.tagname() == "subject")
+            .map(|t| t.value().to_owned())
+    }
+
+    pub fn content_warning(&self) -> Option<String> {
+        self.tags
+            .iter()
+            .find(|t| t.tagname() == "content-warning")
+            .map(|t| t.value().to_owned())
+    }
+
+    pub fn is_annotation(&self) -> bool {
+        self.tags.iter().any(|t| t.tagname() == "annotation")
+    }
+
+    /// Read a draft from a draft wrap event.
+    ///
+    /// Returns `Ok(None)` if the draft has been deleted (its content is blank).
+    pub fn from_event(event: &Event) -> Result<Option<Draft>, Error> {
+        let public_key = match GLOBALS.identity.public_key() {
+            None => return Err(ErrorKind::NoPublicKey.into()),
+            Some(pk) => pk,
+        };
+
+        if event.kind != draft_event_kind() {
+            return Err(ErrorKind::WrongEventKind.into());
+        }
+
+        if event.pubkey != public_key {
+            return Err(ErrorKind::General("Event by wrong author".to_string()).into());
+        }
+
+        if event.content.is_empty() {
+            return Ok(None);
+        }
+
+        let identifier = match event.parameter() {
+            Some(d) => d,
+            None => return Err(ErrorKind::General("Draft has no d tag".to_string()).into()),
+        };
+
+        let json_string = GLOBALS.identity.decrypt(&public_key, &event.content)?;
+        let inner: PreEvent = serde_json::from_str(&json_string)?;
+
+        Ok(Some(Draft {
+            identifier,
+            kind: inner.kind,
+            content: inner.content,
+            tags: inner.tags,
+            saved_at: event.created_at,
+        }))
+    }
+
+    /// Create the signed draft wrap event for this draft
+    pub async fn to_event(&self) -> Result<Event, Error> {
+        let public_key = match GLOBALS.identity.public_key() {
+            None => return Err(ErrorKind::NoPublicKey.into()),
+            Some(pk) => pk,
+        };
+
+        let inner = PreEvent {
+            pubkey: public_key,
+            created_at: self.saved_at,
+            kind: self.kind,
+            tags: self.tags.clone(),
+            content: self.content.clone(),
+        };
+        let inner_json = serde_json::to_string(&inner)?;
+        let content = GLOBALS
+            .identity
+            .encrypt(
+                &public_key,
+                &inner_json,
+                ContentEncryptionAlgorithm::Nip44v2,
+            )
+            .await?;
+
+        Self::sign_wrap(&self.identifier, self.kind, content).await
+    }
+
+    /// Create the signed draft wrap event that deletes a draft, which per NIP-37 is the
+    /// same wrap with blank content
+    pub async fn deletion_event(identifier: &str, kind: EventKind) -> Result<Event, Error> {
+        Self::sign_wrap(identifier, kind, "".to_owned()).await
+    }
+
+    async fn sign_wrap(identifier: &str, kind: EventKind, content: String) -> Result<Event, Error> {
+        let public_key = match GLOBALS.identity.public_key() {
+            None => return Err(ErrorKind::NoPublicKey.into()),
+            Some(pk) => pk,
+        };
+
+        let pre_event = PreEvent {
+            pubkey: public_key,
+            created_at: Unixtime::now(),
+            kind: draft_event_kind(),
+            tags: vec![
+                ParsedTag::Identifier(identifier.to_owned()).into_tag(),
+                Tag::new(&["k", &format!("{}", u32::from(kind))]),
+            ],
+            content,
+        };
+
+        GLOBALS.identity.sign_event(pre_event).await
+    }
+}
+
+/// Load all of our saved drafts from local storage, most recently saved first, along
+/// with how many drafts a remote signer is still decrypting.
+///
+/// Drafts that we cannot decrypt (e.g. because the identity is locked) are skipped.
+pub fn load_drafts() -> Result<(Vec<Draft>, usize), Error> {
+    let public_key = match GLOBALS.identity.public_key() {
+        None => return Ok((vec![], 0)),
+        Some(pk) => pk,
+    };
+
+    let filter = Filter {
+        authors: vec![public_key],
+        kinds: vec![draft_event_kind()],
+        ..Default::default()
+    };
+
+    let mut drafts: Vec<Draft> = Vec::new();
+    let mut pending: usize = 0;
+    for event in GLOBALS
+        .db()
+        .find_events_by_filter(&filter, |_| true)?
+        .iter()
+    {
+        match Draft::from_event(event) {
+            Ok(Some(draft)) => drafts.push(draft),
+            Ok(None) => (),
+            Err(e) if matches!(e.kind, ErrorKind::Nip46Pending) => pending += 1,
+            Err(_) => (),
+        }
+    }
+
+    drafts.sort_by(|a, b| b.saved_at.cmp(&a.saved_at));
+
+    Ok((drafts, pending))
+}
@@ -33,7 +33,11 @@ pub enum ErrorKind {
     Nip46Denied,
     Nip46NeedApproval,
     Nip46ParsingError(String, String),
+    Nip46Pending,
+    Nip46PolicyDenied(String),
     Nip46RelayNeeded,
+    Nip46RemoteError(String),
+    Nip46Unsupported(String),
     Nostr(nostr_types::Error),
     NoPublicKey,
     NoPrivateKey,
@@ -64,6 +68,7 @@ pub enum ErrorKind {
     Offline,
     ParseInt(std::num::ParseIntError),
     ParseBool(std::str::ParseBoolError),
+    ProxyRequired(String),
     RecordIsNotNewable,
     Regex(regex::Error),
     RelayRejectedUs,
@@ -71,6 +76,7 @@ pub enum ErrorKind {
     SerdeJson(serde_json::Error),
     ShuttingDown,
     SliceError(std::array::TryFromSliceError),
+    Socks(tokio_socks::Error),
     Speedy(speedy::Error),
     Svg(usvg::Error),
     TagNotIndexed(String),
@@ -140,7 +146,11 @@ impl std::fmt::Display for Error {
             Nip46Denied => write!(f, "NIP-46 command denied"),
             Nip46NeedApproval => write!(f, "NIP-46 approval needed"),
             Nip46ParsingError(_id, e) => write!(f, "NIP-46 parse error: {e}"),
+            Nip46Pending => write!(f, "NIP-46 waiting for the remote signer"),
+            Nip46PolicyDenied(s) => write!(f, "NIP-46 command denied by policy: {s}"),
             Nip46RelayNeeded => write!(f, "NIP-46 relay needed to respond."),
+            Nip46RemoteError(e) => write!(f, "NIP-46 remote signer: {e}"),
+            Nip46Unsupported(s) => write!(f, "NIP-46 remote signer cannot {s}"),
             Nostr(e) => write!(f, "Nostr: {e}"),
             NoPublicKey => write!(f, "No public key identity available."),
             NoPrivateKey => write!(f, "No private key available."),
@@ -173,6 +183,7 @@ impl std::fmt::Display for Error {
             Offline => write!(f, "Offline"),
             ParseInt(e) => write!(f, "Bad integer: {e}"),
             ParseBool(e) => write!(f, "Bad bool: {e}"),
+            ProxyRequired(host) => write!(f, "A proxy is required to reach {host}"),
             RecordIsNotNewable => write!(f, "Record is not newable"),
             Regex(e) => write!(f, "Regex: {e}"),
             RelayRejectedUs => write!(f, "Relay rejected us."),
@@ -180,6 +191,7 @@ impl std::fmt::Display for Error {
             SerdeJson(e) => write!(f, "SerdeJson Error: {e}"),
             ShuttingDown => write!(f, "Shutting down"),
             SliceError(e) => write!(f, "Slice: {e}"),
+            Socks(e) => write!(f, "SOCKS proxy: {e}"),
             Speedy(e) => write!(f, "Speedy: {e}"),
             Svg(e) => write!(f, "SVG: {e}"),
             TagNotIndexed(s) => write!(f, "Tag not indexed: {s}"),
@@ -351,6 +363,12 @@ impl From<usvg::Error> for ErrorKind {
     }
 }
 
+impl From<tokio_socks::Error> for ErrorKind {
+    fn from(e: tokio_socks::Error) -> ErrorKind {
+        ErrorKind::Socks(e)
+    }
+}
+
 impl From<tokio::time::error::Elapsed> for ErrorKind {
     fn from(e: tokio::time::error::Elapsed) -> ErrorKind {
         ErrorKind::Timeout(e)
@@ -20,6 +20,8 @@ pub enum FeedKind {
     DmChat(DmChannel),
     Global,
     Relay(RelayUrl),
+    Group(RelayUrl, String), // NIP-29 group: relay, group id
+    Channel(Id),             // NIP-28 channel, by the id of its creation event
 }
 
 impl std::fmt::Display for FeedKind {
@@ -40,6 +42,10 @@ impl std::fmt::Display for FeedKind {
             FeedKind::DmChat(channel) => write!(f, "{}", channel.name()),
             FeedKind::Global => write!(f, "Global"),
             FeedKind::Relay(relayurl) => write!(f, "{}", relayurl),
+            FeedKind::Group(relayurl, id) => {
+                write!(f, "{}", crate::groups::cached_group_name(relayurl, id))
+            }
+            FeedKind::Channel(id) => write!(f, "{}", crate::channels::cached_channel_name(*id)),
         }
     }
 }
@@ -56,6 +62,8 @@ impl FeedKind {
             Self::DmChat(_) => "dmchat".to_owned(),
             Self::Global => "global".to_owned(),
             Self::Relay(relayurl) => format!("relay {}", relayurl),
+            Self::Group(relayurl, id) => format!("group {}'{}", relayurl, id),
+            Self::Channel(id) => format!("channel {}", id.as_hex_string()),
         }
     }
 
@@ -69,6 +77,8 @@ impl FeedKind {
             Self::DmChat(_) => false, // always full
             Self::Global => true,
             Self::Relay(_) => true,
+            Self::Group(_, _) => true,
+            Self::Channel(_) => true,
         }
     }
 
@@ -149,6 +149,36 @@ impl Feed {
             });
         }
 
+        // If not in a Group feed
+        if !matches!(feed_kind, FeedKind::Group(_, _)) {
+            // Stop listening to Group events
+            let _ = GLOBALS.to_minions.send(ToMinionMessage {
+                target: "all".to_string(),
+                payload: ToMinionPayload {
+                    job_id: 0,
+                    detail: ToMinionPayloadDetail::Unsubscribe(FilterSet::GroupFeedFuture {
+                        group: "".to_owned(),    // does not matter
+                        anchor: Unixtime::now(), // does not matter
+                    }),
+                },
+            });
+        }
+
+        // If not in a Channel feed
+        if !matches!(feed_kind, FeedKind::Channel(_)) {
+            // Stop listening to Channel events
+            let _ = GLOBALS.to_minions.send(ToMinionMessage {
+                target: "all".to_string(),
+                payload: ToMinionPayload {
+                    job_id: 0,
+                    detail: ToMinionPayloadDetail::Unsubscribe(FilterSet::ChannelFeedFuture {
+                        channel: Id([0; 32]),    // does not matter
+                        anchor: Unixtime::now(), // does not matter
+                    }),
+                },
+            });
+        }
+
         // If not in a Relay feed
         if let FeedKind::Relay(relay_url) = feed_kind {
             // Stop listening to Global events
@@ -257,6 +287,18 @@ impl Feed {
                     .to_overlord
                     .send(ToOverlordMessage::SetRelayFeed(relay_url.clone(), anchor));
             }
+            FeedKind::Group(relay_url, group) => {
+                let _ = GLOBALS.to_overlord.send(ToOverlordMessage::SetGroupFeed(
+                    relay_url.clone(),
+                    group.clone(),
+                    anchor,
+                ));
+            }
+            FeedKind::Channel(id) => {
+                let _ = GLOBALS
+                    .to_overlord
+                    .send(ToOverlordMessage::SetChannelFeed(*id, anchor));
+            }
             _ => (),
         }
     }
@@ -477,6 +519,43 @@ impl Feed {
                 let events = GLOBALS.db().load_volatile_events(screen);
                 *self.current_feed_events.write_arc() = events.iter().map(|e| e.id).collect();
             }
+            FeedKind::Group(relay_url, group) => {
+                let filter = {
+                    let mut filter = Filter::new();
+                    filter.kinds = crate::groups::group_message_event_kinds();
+                    filter.add_tag_value('h', group);
+                    filter
+                };
+
+                // Group ids are only unique per relay
+                let screen = |e: &Event| crate::groups::seen_on(e, &relay_url);
+
+                let events = Self::load_event_range(anchor, filter, true, screen).await?;
+
+                *self.current_feed_events.write_arc() = events;
+            }
+            FeedKind::Channel(id) => {
+                let filter = {
+                    let mut filter = Filter::new();
+                    filter.kinds = vec![EventKind::ChannelMessage];
+                    filter.add_tag_value('e', id.as_hex_string());
+                    filter
+                };
+
+                // Leave out messages we hid, and messages from people we muted
+                let hidden = crate::channels::hidden_messages()?;
+                let screen = |e: &Event| {
+                    crate::channels::message_channel(e) == Some(id)
+                        && !hidden.contains(&e.id)
+                        && !GLOBALS
+                            .people
+                            .is_person_in_list(&e.pubkey, PersonList::Muted)
+                };
+
+                let events = Self::load_event_range(anchor, filter, true, screen).await?;
+
+                *self.current_feed_events.write_arc() = events;
+            }
         }
 
         // We recompute the inbox always, because we need to watch for changes so we can update
@@ -592,6 +671,7 @@ fn basic_screen(e: &Event, include_replies: bool, dismissed: &[Id]) -> bool {
     let now = Unixtime::now();
 
     e.created_at <= now
+        && !crate::misc::event_is_expired(e, now)
         && (include_replies || e.replies_to().is_none())
         && e.kind != EventKind::EncryptedDirectMessage
         && e.kind != EventKind::DmChat
@@ -652,7 +732,7 @@ pub fn enabled_event_kinds() -> Vec<EventKind> {
             // || *k == EventKind::GitStatusDraft
             // || *k == EventKind::ProblemTracker
             // || *k == EventKind::Reporting
-            // || *k == EventKind::Label
+                || *k == EventKind::Label
             // EventKind::RelayReviews
             // EventKind::AiEmbeddings
             // EventKind::Torrent
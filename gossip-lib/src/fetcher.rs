@@ -7,6 +7,7 @@ use nostr_types::{Unixtime, Url};
 use reqwest::header::ETAG;
 use reqwest::{Client, StatusCode};
 use sha2::Digest;
+use std::fmt;
 use std::path::PathBuf;
 use std::sync::atomic::{AtomicBool, Ordering};
 use std::sync::{Arc, RwLock};
@@ -101,11 +102,14 @@ impl Fetcher {
     }
 
     /// This is where a client attempts to get data synchronously
-    pub fn try_get(&self, url: Url, use_cache: bool) -> Result<FetchResult, Error> {
-        // Maybe initialize
-        if self.client.read().unwrap().is_none() {
-            self.init()?;
-        }
+    pub fn try_get(
+        &self,
+        url: Url,
+        kind: CacheKind,
+        use_cache: bool,
+    ) -> Result<FetchResult, Error> {
+        // Maybe initialize, or rebuild the client if the proxy settings changed
+        self.init()?;
 
         // Lock this url record
         let mut refmut = self.url_data.entry(url.clone()).or_insert(UrlData {
@@ -113,6 +117,7 @@ impl Fetcher {
             bytes: None,
             error: None,
             use_cache,
+            kind,
         });
 
         match refmut.value().state {
@@ -160,11 +165,14 @@ impl Fetcher {
     /// This is where a client attempts to get data asynchronously
     ///
     /// This should never return FetchResult::Processing
-    pub async fn get(&self, url: Url, use_cache: bool) -> Result<FetchResult, Error> {
-        // Maybe initialize
-        if self.client.read().unwrap().is_none() {
-            self.init()?;
-        }
+    pub async fn get(
+        &self,
+        url: Url,
+        kind: CacheKind,
+        use_cache: bool,
+    ) -> Result<FetchResult, Error> {
+        // Maybe initialize, or rebuild the client if the proxy settings changed
+        self.init()?;
 
         // Create UrlData if missing
         let mut start = !self.url_data.contains_key(&url);
@@ -176,6 +184,7 @@ impl Fetcher {
                     bytes: None,
                     error: None,
                     use_cache,
+                    kind,
                 });
             }
         }
@@ -238,40 +247,286 @@ impl Fetcher {
         self.url_data.remove(&url);
     }
 
+    /// Make data we got some other way available (and cached) as if it was fetched
+    /// from the url. This replaces any failure for the url.
+    pub(crate) async fn put(&self, url: Url, kind: CacheKind, bytes: Vec<u8>) -> Result<(), Error> {
+        // Maybe initialize, or rebuild the client if the proxy settings changed
+        self.init()?;
+
+        let cache_file = self.cache_file(&url, kind);
+        let etag_file = cache_file.with_extension("etag");
+
+        // ignore any error in caching
+        let _ = tokio::fs::write(cache_file.as_path(), &bytes).await;
+        let _ = tokio::fs::remove_file(etag_file.as_path()).await;
+
+        self.url_data.insert(
+            url,
+            UrlData {
+                state: FetchState::Ready,
+                bytes: Some(bytes),
+                error: None,
+                use_cache: true,
+                kind,
+            },
+        );
+
+        Ok(())
+    }
+
+    /// Read the thumbnail we made of the image at the url for the given size, if any
+    pub(crate) async fn read_thumbnail(
+        &self,
+        url: &Url,
+        size: u32,
+    ) -> Result<Option<Vec<u8>>, Error> {
+        self.init()?;
+
+        let thumbnail_file = self.thumbnail_file(url, size);
+        match tokio::fs::read(thumbnail_file.as_path()).await {
+            Ok(bytes) => {
+                touch(&thumbnail_file);
+                Ok(Some(bytes))
+            }
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(None),
+            Err(e) => Err(e.into()),
+        }
+    }
+
+    /// Save a thumbnail of the image at the url for the given size
+    pub(crate) async fn write_thumbnail(
+        &self,
+        url: &Url,
+        size: u32,
+        bytes: &[u8],
+    ) -> Result<(), Error> {
+        self.init()?;
+
+        let thumbnail_file = self.thumbnail_file(url, size);
+        tokio::fs::write(thumbnail_file.as_path(), bytes).await?;
+        Ok(())
+    }
+
     /// Prune
     pub(crate) async fn prune(&self, age: Duration) -> Result<usize, Error> {
+        self.init()?;
+
+        let mut count: usize = 0;
+        for file in self.cache_files().await? {
+            let file_age = match SystemTime::now().duration_since(file.accessed) {
+                Ok(dur) => dur,
+                Err(_) => continue,
+            };
+            if file_age > age {
+                tokio::fs::remove_file(file.path.as_path()).await?;
+                let _ = tokio::fs::remove_file(file.path.with_extension("etag")).await;
+                count += 1;
+            }
+        }
+
+        let _ = self.measure_cache().await;
+
+        Ok(count)
+    }
+
+    /// Remove the least recently used files until the cache is no larger than
+    /// `max_bytes`. Returns how many files were removed and how many bytes were freed.
+    pub async fn trim_cache(&self, max_bytes: u64) -> Result<(usize, u64), Error> {
+        self.init()?;
+
+        let mut files = self.cache_files().await?;
+        let mut total: u64 = files.iter().map(|f| f.size).sum();
+        files.sort_by_key(|f| f.accessed);
+
         let mut count: usize = 0;
+        let mut freed: u64 = 0;
+        for file in files {
+            if total <= max_bytes {
+                break;
+            }
+            if tokio::fs::remove_file(file.path.as_path()).await.is_ok() {
+                let _ = tokio::fs::remove_file(file.path.with_extension("etag")).await;
+                total -= file.size;
+                freed += file.size;
+                count += 1;
+            }
+        }
+
+        let _ = self.measure_cache().await;
+
+        Ok((count, freed))
+    }
+
+    /// Measure how much of the disk cache each kind of file uses
+    pub async fn measure_cache(&self) -> Result<CacheUsage, Error> {
+        self.init()?;
+
+        let mut usage = CacheUsage {
+            kinds: CacheKind::ALL
+                .iter()
+                .map(|kind| CacheKindUsage {
+                    kind: *kind,
+                    files: 0,
+                    bytes: 0,
+                })
+                .collect(),
+            measured_at: Unixtime::now(),
+        };
+        for file in self.cache_files().await? {
+            if let Some(kind_usage) = usage.kinds.iter_mut().find(|k| k.kind == file.kind) {
+                kind_usage.files += 1;
+                kind_usage.bytes += file.size;
+            }
+        }
+
+        *self.cache_usage.write().unwrap() = Some(usage.clone());
+
+        Ok(usage)
+    }
+
+    /// How much of the disk cache is used, as last measured
+    pub fn cache_usage(&self) -> Option<CacheUsage> {
+        self.cache_usage.read().unwrap().clone()
+    }
+
+    // Every file in the disk cache. Sizes include the etag file.
+    async fn cache_files(&self) -> Result<Vec<CachedFile>, Error> {
         let cache_path = self.cache_dir.read().unwrap().to_owned();
-        let mut entries = tokio::fs::read_dir(cache_path.as_path()).await?;
-        while let Some(entry) = entries.next_entry().await? {
-            if let Ok(metadata) = entry.metadata().await {
+
+        let mut output: Vec<CachedFile> = Vec::new();
+        for kind in CacheKind::ALL {
+            let dir = match kind.subdir() {
+                Some(subdir) => cache_path.join(subdir),
+                None => cache_path.clone(),
+            };
+            let mut entries = match tokio::fs::read_dir(dir.as_path()).await {
+                Ok(entries) => entries,
+                Err(_) => continue,
+            };
+            while let Some(entry) = entries.next_entry().await? {
+                let metadata = match entry.metadata().await {
+                    Ok(md) => md,
+                    Err(_) => continue,
+                };
                 if metadata.is_dir() {
                     continue;
                 }
-                // FIXME - many filesystems do not track access times. We may want
-                //         to track these in LMDB (along with etags)
-                let file_time = match metadata.accessed() {
+                let path = entry.path();
+                if path.extension().map(|e| e == "etag").unwrap_or(false) {
+                    continue;
+                }
+
+                // We set access times ourselves when we use a cached file (see
+                // touch()), so these work even where the filesystem doesn't keep them
+                let accessed = match metadata.accessed() {
                     Ok(st) => st,
                     Err(_) => match metadata.modified() {
                         Ok(st) => st,
                         Err(_) => metadata.created()?,
                     },
                 };
-                let file_age = match SystemTime::now().duration_since(file_time) {
-                    Ok(dur) => dur,
-                    Err(_) => continue,
-                };
-                if file_age > age {
-                    tokio::fs::remove_file(entry.path().as_path()).await?;
-                    count += 1;
+
+                let mut size = metadata.len();
+                if let Ok(md) = tokio::fs::metadata(path.with_extension("etag")).await {
+                    size += md.len();
                 }
+
+                output.push(CachedFile {
+                    path,
+                    kind,
+                    size,
+                    accessed,
+                });
             }
         }
 
-        Ok(count)
+        Ok(output)
+    }
+}
+
+/// What a file in the disk cache was fetched for
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash)]
+pub enum CacheKind {
+    /// Profile pictures
+    Avatar,
+
+    /// Images and videos in notes
+    #[default]
+    Media,
+
+    /// Downsized images from notes
+    Thumbnail,
+
+    /// Files cached before we kept track of what they were for
+    Other,
+}
+
+impl CacheKind {
+    pub const ALL: [CacheKind; 4] = [
+        CacheKind::Avatar,
+        CacheKind::Media,
+        CacheKind::Thumbnail,
+        CacheKind::Other,
+    ];
+
+    // The subdirectory of the cache these are kept in
+    fn subdir(&self) -> Option<&'static str> {
+        match *self {
+            CacheKind::Avatar => Some("avatars"),
+            CacheKind::Media => Some("media"),
+            CacheKind::Thumbnail => Some("thumbnails"),
+            CacheKind::Other => None,
+        }
+    }
+}
+
+impl fmt::Display for CacheKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match *self {
+            CacheKind::Avatar => write!(f, "Avatars"),
+            CacheKind::Media => write!(f, "Media"),
+            CacheKind::Thumbnail => write!(f, "Thumbnails"),
+            CacheKind::Other => write!(f, "Other"),
+        }
+    }
+}
+
+/// How much of the disk cache one kind of file uses
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct CacheKindUsage {
+    pub kind: CacheKind,
+    pub files: usize,
+    pub bytes: u64,
+}
+
+/// How much of the disk cache is used
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CacheUsage {
+    /// Usage of each kind of file
+    pub kinds: Vec<CacheKindUsage>,
+
+    /// When this was measured
+    pub measured_at: Unixtime,
+}
+
+impl CacheUsage {
+    pub fn files(&self) -> usize {
+        self.kinds.iter().map(|k| k.files).sum()
+    }
+
+    pub fn bytes(&self) -> u64 {
+        self.kinds.iter().map(|k| k.bytes).sum()
     }
 }
 
+// A file in the disk cache
+struct CachedFile {
+    path: PathBuf,
+    kind: CacheKind,
+    size: u64,
+    accessed: SystemTime,
+}
+
 /// Information about a URL including it's fetched data or the state the fetch is in
 #[derive(Debug, Default)]
 pub struct UrlData {
@@ -290,6 +545,9 @@ pub struct UrlData {
 
     /// Whether or not the data should be cached
     pub use_cache: bool,
+
+    /// What the data is for, which decides where it is cached
+    pub kind: CacheKind,
 }
 
 /// This is the state of processing of a given URL
@@ -376,6 +634,9 @@ pub struct Fetcher {
     /// HTTP client
     client: RwLock<Option<Client>>,
 
+    /// The proxy settings the client was built with
+    client_proxy: RwLock<(Option<String>, bool)>,
+
     /// Persistent filesystem cache of network objects. This is faster than fetching
     /// over the network, but the data still needs to be loaded into memory
     cache_dir: RwLock<PathBuf>,
@@ -388,27 +649,43 @@ pub struct Fetcher {
 
     // Warned about lack of modification time
     warned_already: AtomicBool,
+
+    /// How much of the disk cache is used, as last measured
+    cache_usage: RwLock<Option<CacheUsage>>,
 }
 
 impl Fetcher {
     /// This initializes the fetcher, which is called internally when it is first used
     fn init(&self) -> Result<(), Error> {
-        // Do not init() if already initialized
+        // The client must be rebuilt if the proxy settings have changed
+        let proxy = (
+            crate::proxy::socks5_proxy(),
+            GLOBALS.db().read_setting_proxy_only_onion(),
+        );
+
         if self.client.read().unwrap().is_some() {
-            return Ok(());
+            if *self.client_proxy.read().unwrap() == proxy {
+                return Ok(());
+            }
+        } else {
+            // Copy profile directory so we don't have to deal with the rare
+            // initialization error every time we use them
+            let cache_dir = Profile::cache_dir(false)?;
+            for kind in CacheKind::ALL {
+                if let Some(subdir) = kind.subdir() {
+                    std::fs::create_dir_all(cache_dir.join(subdir))?;
+                }
+            }
+            *self.cache_dir.write().unwrap() = cache_dir;
         }
 
-        // Copy profile directory so we don't have to deal with the rare
-        // initialization error every time we use them
-        *self.cache_dir.write().unwrap() = Profile::cache_dir(false)?;
-
         // Create client
         let connect_timeout =
             std::time::Duration::new(GLOBALS.db().read_setting_fetcher_connect_timeout_sec(), 0);
         let timeout = std::time::Duration::new(GLOBALS.db().read_setting_fetcher_timeout_sec(), 0);
 
         *self.client.write().unwrap() = Some(
-            Client::builder()
+            crate::proxy::http_client_builder()?
                 .gzip(true)
                 .brotli(true)
                 .deflate(true)
@@ -416,6 +693,7 @@ impl Fetcher {
                 .timeout(timeout)
                 .build()?,
         );
+        *self.client_proxy.write().unwrap() = proxy;
 
         Ok(())
     }
@@ -425,7 +703,11 @@ impl Fetcher {
     // fetch state.  We are the only process changing it, but we drop the lock often
     // so that other processes can check on our progress.
     async fn process(&self, url: Url) {
-        let cache_file = self.cache_file(&url);
+        let kind = match self.url_data.get(&url) {
+            Some(refr) => refr.value().kind,
+            None => CacheKind::default(),
+        };
+        let cache_file = self.cache_file(&url, kind);
         let etag_file = cache_file.with_extension("etag");
 
         // Do not fetch if offline
@@ -435,6 +717,16 @@ impl Fetcher {
             return;
         }
 
+        // Move a file cached before we kept track of kinds into place
+        if kind != CacheKind::Other {
+            let old_file = self.cache_file(&url, CacheKind::Other);
+            if matches!(tokio::fs::try_exists(old_file.as_path()).await, Ok(true)) {
+                let _ = tokio::fs::rename(old_file.as_path(), cache_file.as_path()).await;
+                let _ =
+                    tokio::fs::rename(old_file.with_extension("etag"), etag_file.as_path()).await;
+            }
+        }
+
         // Possibly check the cache
         let use_cache = {
             let mut refmut = self.url_data.get_mut(&url).unwrap();
@@ -478,6 +770,7 @@ impl Fetcher {
                         self.set_state(&url, FetchState::LoadingFromCache);
                         match tokio::fs::read(cache_file.as_path()).await {
                             Ok(bytes) => {
+                                touch(&cache_file);
                                 self.finish(&url, bytes);
                                 return;
                             }
@@ -610,6 +903,7 @@ impl Fetcher {
                     // Read from the file
                     match tokio::fs::read(cache_file.as_path()).await {
                         Ok(bytes) => {
+                            touch(&cache_file);
                             self.finish(&url, bytes);
                             return;
                         }
@@ -706,7 +1000,7 @@ impl Fetcher {
         }
     }
 
-    fn cache_file(&self, url: &Url) -> PathBuf {
+    fn cache_file(&self, url: &Url, kind: CacheKind) -> PathBuf {
         // Hash the url into a SHA256 hex string
         let hash = {
             let mut hasher = sha2::Sha256::new();
@@ -716,10 +1010,30 @@ impl Fetcher {
         };
 
         let mut cache_file = self.cache_dir.read().unwrap().clone();
+        if let Some(subdir) = kind.subdir() {
+            cache_file.push(subdir);
+        }
         cache_file.push(hash);
         cache_file
     }
 
+    fn thumbnail_file(&self, url: &Url, size: u32) -> PathBuf {
+        // Hash the size and url into a SHA256 hex string
+        let hash = {
+            let mut hasher = sha2::Sha256::new();
+            hasher.update(format!("{size}:{url}").as_bytes());
+            let result = hasher.finalize();
+            hex::encode(result)
+        };
+
+        let mut thumbnail_file = self.cache_dir.read().unwrap().clone();
+        if let Some(subdir) = CacheKind::Thumbnail.subdir() {
+            thumbnail_file.push(subdir);
+        }
+        thumbnail_file.push(hash);
+        thumbnail_file
+    }
+
     fn host(&self, url: &Url) -> Option<String> {
         let u = match url::Url::parse(url.as_str()) {
             Ok(u) => u,
@@ -779,3 +1093,9 @@ impl Fetcher {
         }
     }
 }
+
+// Mark a cached file as just used, for least-recently-used eviction.
+// Many filesystems don't keep access times (noatime, relatime), so we set it ourselves.
+fn touch(cache_file: &std::path::Path) {
+    let _ = filetime::set_file_atime(cache_file, filetime::FileTime::now());
+}
@@ -25,6 +25,15 @@ impl FeedRange {
 #[derive(Debug, Clone, PartialEq)]
 pub enum FilterSet {
     Augments(Vec<Id>),
+    ChannelFeedFuture {
+        channel: Id,
+        anchor: Unixtime,
+    },
+    ChannelFeedChunk {
+        channel: Id,
+        anchor: Unixtime,
+    },
+    Channels,
     Config,
     Discover(Vec<PublicKey>),
     DmChannel(DmChannel),
@@ -40,6 +49,15 @@ pub enum FilterSet {
     Giftwraps(FeedRange),
     GlobalFeedFuture(Unixtime),
     GlobalFeedChunk(Unixtime),
+    GroupFeedFuture {
+        group: String,
+        anchor: Unixtime,
+    },
+    GroupFeedChunk {
+        group: String,
+        anchor: Unixtime,
+    },
+    GroupMetadata(String),
     InboxFeedFuture(Unixtime),
     InboxFeedChunk(Unixtime),
     Metadata(Vec<PublicKey>),
@@ -61,6 +79,9 @@ impl FilterSet {
     pub fn temporary(&self) -> bool {
         match self {
             FilterSet::Augments(_) => true,
+            FilterSet::ChannelFeedFuture { .. } => false,
+            FilterSet::ChannelFeedChunk { .. } => true,
+            FilterSet::Channels => true,
             FilterSet::Config => false,
             FilterSet::Discover(_) => true,
             FilterSet::DmChannel(_) => false,
@@ -70,6 +91,9 @@ impl FilterSet {
             FilterSet::Giftwraps(_) => false,
             FilterSet::GlobalFeedFuture(_) => false,
             FilterSet::GlobalFeedChunk(_) => true,
+            FilterSet::GroupFeedFuture { .. } => false,
+            FilterSet::GroupFeedChunk { .. } => true,
+            FilterSet::GroupMetadata(_) => true,
             FilterSet::InboxFeedFuture(_) => false,
             FilterSet::InboxFeedChunk(_) => true,
             FilterSet::Metadata(_) => true,
@@ -84,8 +108,10 @@ impl FilterSet {
 
     pub fn can_have_duplicates(&self) -> bool {
         match self {
+            FilterSet::ChannelFeedChunk { .. } => true,
             FilterSet::GeneralFeedChunk { .. } => true,
             FilterSet::GlobalFeedChunk(_) => true,
+            FilterSet::GroupFeedChunk { .. } => true,
             FilterSet::InboxFeedChunk(_) => true,
             FilterSet::PersonFeedChunk { .. } => true,
             _ => false,
@@ -94,8 +120,10 @@ impl FilterSet {
 
     pub fn is_loading_more(&self) -> bool {
         match self {
+            FilterSet::ChannelFeedChunk { .. } => true,
             FilterSet::GeneralFeedChunk { .. } => true,
             FilterSet::GlobalFeedChunk(_) => true,
+            FilterSet::GroupFeedChunk { .. } => true,
             FilterSet::InboxFeedChunk(_) => true,
             FilterSet::PersonFeedChunk { .. } => true,
             _ => false,
@@ -105,6 +133,9 @@ impl FilterSet {
     pub fn inner_handle(&self) -> &'static str {
         match self {
             FilterSet::Augments(_) => "augments",
+            FilterSet::ChannelFeedFuture { .. } => "channel_feed",
+            FilterSet::ChannelFeedChunk { .. } => "channel_feed_chunk",
+            FilterSet::Channels => "channels",
             FilterSet::Config => "config_feed",
             FilterSet::Discover(_) => "discover_feed",
             FilterSet::DmChannel(_) => "dm_channel",
@@ -114,6 +145,9 @@ impl FilterSet {
             FilterSet::Giftwraps(_) => "giftwraps",
             FilterSet::GlobalFeedFuture(_) => "global_feed",
             FilterSet::GlobalFeedChunk(_) => "global_feed_chunk",
+            FilterSet::GroupFeedFuture { .. } => "group_feed",
+            FilterSet::GroupFeedChunk { .. } => "group_feed_chunk",
+            FilterSet::GroupMetadata(_) => "group_metadata",
             FilterSet::InboxFeedFuture(_) => "inbox_feed",
             FilterSet::InboxFeedChunk(_) => "inbox_feed_chunk",
             FilterSet::Metadata(_) => "subscribe_metadata",
@@ -149,6 +183,47 @@ impl FilterSet {
                 filter.set_tag_values('e', ids.iter().map(|id| id.as_hex_string()).collect());
                 Some(filter)
             }
+            FilterSet::ChannelFeedFuture { channel, anchor } => {
+                // Metadata changes come along with the messages
+                let mut filter = Filter {
+                    kinds: vec![EventKind::ChannelMessage, EventKind::ChannelMetadata],
+                    ..Default::default()
+                };
+                filter.set_tag_values('e', vec![channel.as_hex_string()]);
+
+                let range = FeedRange::After { since: *anchor };
+                let (since, until, limit) = range.since_until_limit();
+                filter.since = since;
+                filter.until = until;
+                filter.limit = limit;
+                Some(filter)
+            }
+            FilterSet::ChannelFeedChunk { channel, anchor } => {
+                let mut filter = Filter {
+                    kinds: vec![EventKind::ChannelMessage],
+                    ..Default::default()
+                };
+                filter.set_tag_values('e', vec![channel.as_hex_string()]);
+
+                let limit = GLOBALS.db().read_setting_load_more_count() as usize;
+                let range = FeedRange::ChunkBefore {
+                    until: *anchor,
+                    limit,
+                };
+                let (since, until, limit) = range.since_until_limit();
+                filter.since = since;
+                filter.until = until;
+                filter.limit = limit;
+                Some(filter)
+            }
+            FilterSet::Channels => {
+                let limit = GLOBALS.db().read_setting_load_more_count() as usize;
+                Some(Filter {
+                    kinds: vec![EventKind::ChannelCreation],
+                    limit: Some(limit),
+                    ..Default::default()
+                })
+            }
             FilterSet::Config => {
                 let pubkey = GLOBALS.identity.public_key()?;
 
@@ -164,6 +239,7 @@ impl FilterSet {
                         EventKind::DmRelayList,
                         EventKind::BookmarkList,
                         EventKind::UserServerList,
+                        crate::drafts::draft_event_kind(),
                     ],
                     // these are all replaceable, no since required
                     ..Default::default()
@@ -303,6 +379,39 @@ impl FilterSet {
                     ..Default::default()
                 })
             }
+            FilterSet::GroupFeedFuture { group, anchor } => {
+                let mut filter = Self::group_base_filter(group);
+
+                let range = FeedRange::After { since: *anchor };
+                let (since, until, limit) = range.since_until_limit();
+                filter.since = since;
+                filter.until = until;
+                filter.limit = limit;
+                Some(filter)
+            }
+            FilterSet::GroupFeedChunk { group, anchor } => {
+                let mut filter = Self::group_base_filter(group);
+
+                let limit = GLOBALS.db().read_setting_load_more_count() as usize;
+                let range = FeedRange::ChunkBefore {
+                    until: *anchor,
+                    limit,
+                };
+                let (since, until, limit) = range.since_until_limit();
+                filter.since = since;
+                filter.until = until;
+                filter.limit = limit;
+                Some(filter)
+            }
+            FilterSet::GroupMetadata(group) => {
+                let mut filter = Filter {
+                    kinds: crate::groups::group_metadata_event_kinds(),
+                    // these are all replaceable, no since required
+                    ..Default::default()
+                };
+                filter.set_tag_values('d', vec![group.to_owned()]);
+                Some(filter)
+            }
             FilterSet::InboxFeedFuture(anchor) => {
                 let pubkey = GLOBALS.identity.public_key()?;
 
@@ -347,13 +456,26 @@ impl FilterSet {
                 })
             }
             FilterSet::Nip46 => {
-                let pubkey = GLOBALS.identity.public_key()?;
+                // Commands to our own signer are tagged with our pubkey, and
+                // responses from our remote signer are tagged with our client key
+                let mut pubkeys: Vec<String> = Vec::new();
+                if let Some(pubkey) = GLOBALS.identity.public_key() {
+                    pubkeys.push(pubkey.as_hex_string());
+                }
+                if let Some(remote) = GLOBALS.identity.remote_signer() {
+                    if let Some(client_pubkey) = remote.client_pubkey() {
+                        pubkeys.push(client_pubkey.as_hex_string());
+                    }
+                }
+                if pubkeys.is_empty() {
+                    return None;
+                }
 
                 let mut filter = Filter {
                     kinds: vec![EventKind::NostrConnect],
                     ..Default::default()
                 };
-                filter.set_tag_values('p', vec![pubkey.as_hex_string()]);
+                filter.set_tag_values('p', pubkeys);
                 Some(filter)
             }
             FilterSet::PersonFeedFuture { pubkey, anchor } => {
@@ -456,6 +578,16 @@ impl FilterSet {
         }
     }
 
+    fn group_base_filter(group: &str) -> Filter {
+        // The group relay decides who may post, so there is no spam screening here
+        let mut filter = Filter {
+            kinds: crate::groups::group_message_event_kinds(),
+            ..Default::default()
+        };
+        filter.set_tag_values('h', vec![group.to_owned()]);
+        filter
+    }
+
     fn inbox_base_filter(pubkey: PublicKey, spamsafe: bool) -> Filter {
         // Allow all feed displayable event kinds (including DMs)
         let mut event_kinds = crate::feed::feed_displayable_event_kinds(true);
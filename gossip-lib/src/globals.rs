@@ -1,25 +1,32 @@
-use crate::blossom::{BlobDescriptor, Blossom};
+use crate::blossom::{BlobDescriptor, BlobReplication, Blossom};
 use crate::bookmarks::BookmarkList;
+use crate::channels::ChannelMetadata;
 use crate::comms::{RelayJob, ToMinionMessage, ToOverlordMessage};
+use crate::counts::CountTarget;
 use crate::delegation::Delegation;
 use crate::error::Error;
 use crate::feed::Feed;
 use crate::fetcher::Fetcher;
 use crate::gossip_identity::GossipIdentity;
+use crate::groups::GroupMetadata;
 use crate::media::Media;
 use crate::minion::MinionExitReason;
 use crate::misc::ZapState;
+use crate::nostr_connect_client::Nip46PendingRequest;
 use crate::pending::Pending;
 use crate::people::{FollowList, People, Person};
 use crate::relay::Relay;
 use crate::relay_picker::RelayPicker;
+use crate::relay_rejection::{RelayRejection, SkippedWrite};
 use crate::relay_test_results::RelayTestResults;
 use crate::seeker::Seeker;
 use crate::status::StatusQueue;
 use crate::storage::{HandlersTable, Storage, Table};
 use crate::RunState;
 use dashmap::{DashMap, DashSet};
-use nostr_types::{Event, EventKind, Id, Profile, PublicKey, RelayUrl, UncheckedUrl};
+use nostr_types::{
+    Event, EventKind, FileMetadata, Id, Profile, PublicKey, RelayUrl, UncheckedUrl, Unixtime,
+};
 use parking_lot::RwLock as PRwLock;
 use regex::Regex;
 use rhai::{Engine, AST};
@@ -185,20 +192,38 @@ pub struct Globals {
     pub current_bookmarks: PRwLock<Vec<Id>>,
     pub recompute_current_bookmarks: Arc<Notify>,
 
+    /// Notified when our remote signer answers a decryption we did not wait for
+    pub remote_decrypted: Arc<Notify>,
+
     /// If we are doing a long database prune, this will indicate the status
     pub prune_status: PRwLock<Option<String>>,
 
     /// Relay tests
     pub relay_tests: DashMap<RelayUrl, Option<RelayTestResults>>,
 
+    /// Why each relay last turned down a subscription or an event
+    pub relay_rejections: DashMap<RelayUrl, RelayRejection>,
+
+    /// Why we last did not post to each relay, going by its NIP-11 limitations
+    pub skipped_writes: DashMap<RelayUrl, SkippedWrite>,
+
+    /// Relays that refused our events (`blocked:` or `restricted:`)
+    pub write_restricted_relays: DashSet<RelayUrl>,
+
     /// Handlers
     pub handlers: DashMap<EventKind, Vec<(String, UncheckedUrl)>>,
 
     /// Blossom (the uploader)
     pub blossom: OnceLock<Blossom>,
 
-    /// Blossom Uploads (Path to Url)
-    pub blossom_uploads: DashMap<PathBuf, Result<BlobDescriptor, Error>>,
+    /// Blossom Uploads (Path to the NIP-94 description of the upload)
+    pub blossom_uploads: DashMap<PathBuf, Result<FileMetadata, Error>>,
+
+    /// Our blobs listed by each blossom server (base url to list or error)
+    pub blossom_blobs: DashMap<String, Result<Vec<BlobDescriptor>, String>>,
+
+    /// Which of our blossom servers have each of our blobs (by sha256), as of the last check
+    pub blossom_replication: DashMap<String, BlobReplication>,
 
     /// Followers (we keep it in memory only, for just one person)
     pub followers: PRwLock<FollowList>,
@@ -209,6 +234,26 @@ pub struct Globals {
     /// Delayed posts
     pub delayed_posts: DashSet<Id>,
 
+    /// Counts reported by relays (NIP-45)
+    pub relay_counts: DashMap<CountTarget, usize>,
+
+    /// When we last asked relays to count each target (NIP-45)
+    pub relay_counts_asked: DashMap<CountTarget, Unixtime>,
+
+    /// Requests waiting on our remote signer (NIP-46), by request id
+    pub nip46_client_requests: DashMap<String, Nip46PendingRequest>,
+
+    /// The pubkey that signs zap receipts for each lnurl (NIP-57), or None if
+    /// the lnurl does not support nostr zaps
+    pub lnurl_nostr_pubkeys: DashMap<String, Option<PublicKey>>,
+
+    /// Metadata of public chat channels (NIP-28), as loaded from storage
+    pub channel_metadata: DashMap<Id, ChannelMetadata>,
+
+    /// Metadata of relay-based groups (NIP-29), by relay and group id, as loaded
+    /// from storage
+    pub group_metadata: DashMap<(RelayUrl, String), GroupMetadata>,
+
     /// Notify the UI to redraw.
     pub notify_ui_redraw: Notify,
 }
@@ -284,14 +329,26 @@ lazy_static! {
             bookmarks: Arc::new(PRwLock::new(BookmarkList::empty())),
             current_bookmarks: PRwLock::new(Vec::new()),
             recompute_current_bookmarks: Arc::new(Notify::new()),
+            remote_decrypted: Arc::new(Notify::new()),
             prune_status: PRwLock::new(None),
             relay_tests: DashMap::new(),
+            relay_rejections: DashMap::new(),
+            skipped_writes: DashMap::new(),
+            write_restricted_relays: DashSet::new(),
             handlers: DashMap::new(),
             blossom: OnceLock::new(),
             blossom_uploads: DashMap::new(),
+            blossom_blobs: DashMap::new(),
+            blossom_replication: DashMap::new(),
             followers: PRwLock::new(FollowList::default()),
             follows: PRwLock::new(FollowList::default()),
             delayed_posts: DashSet::new(),
+            relay_counts: DashMap::new(),
+            relay_counts_asked: DashMap::new(),
+            nip46_client_requests: DashMap::new(),
+            lnurl_nostr_pubkeys: DashMap::new(),
+            channel_metadata: DashMap::new(),
+            group_metadata: DashMap::new(),
             notify_ui_redraw: Notify::new(),
         }
     };
@@ -1,10 +1,10 @@
-use crate::bookmarks::BookmarkList;
-use crate::error::Error;
+use crate::error::{Error, ErrorKind};
 use crate::globals::GLOBALS;
+use crate::nostr_connect_client::Nip46Client;
 use nostr_types::{
     ContentEncryptionAlgorithm, DelegationConditions, EncryptedPrivateKey, Event, EventKind,
-    EventV1, EventV2, Filter, Id, Identity, KeySecurity, Metadata, PreEvent, PrivateKey, PublicKey,
-    Rumor, RumorV1, RumorV2, Signature,
+    EventV1, EventV2, Filter, Id, Identity, KeySecurity, Metadata, ParsedTag, PreEvent, PrivateKey,
+    PublicKey, Rumor, RumorV1, RumorV2, Signature, Tag, Unixtime,
 };
 use parking_lot::RwLock;
 use std::sync::mpsc::Sender;
@@ -13,12 +13,17 @@ use tokio::task;
 
 pub struct GossipIdentity {
     pub inner: Arc<RwLock<Identity>>,
+
+    /// If set, a remote signer (NIP-46) holds our private key and does our signing,
+    /// encrypting and decrypting. `inner` then only has our public key.
+    remote: RwLock<Option<Arc<Nip46Client>>>,
 }
 
 impl Default for GossipIdentity {
     fn default() -> GossipIdentity {
         GossipIdentity {
             inner: Arc::new(RwLock::new(Identity::default())),
+            remote: RwLock::new(None),
         }
     }
 }
@@ -32,6 +37,14 @@ impl GossipIdentity {
             (Some(pk), None) => *self.inner.write_arc() = Identity::Public(pk),
             (None, _) => *self.inner.write_arc() = Identity::None,
         }
+
+        // Reconnect to our remote signer
+        if let Some(bunker) = GLOBALS.db().read_nip46_bunker()? {
+            if Some(bunker.user_pubkey) == pk {
+                *self.remote.write() = Some(Arc::new(Nip46Client::from_bunker(&bunker)?));
+            }
+        }
+
         Ok(())
     }
 
@@ -80,18 +93,10 @@ impl GossipIdentity {
         GLOBALS.ui_invalidate_notes(&dms);
 
         // Recompute bookmarks (including the private part)
-        if let Some(pk) = self.public_key() {
-            if let Some(event) =
-                GLOBALS
-                    .db()
-                    .get_replaceable_event(EventKind::BookmarkList, pk, "")?
-            {
-                *GLOBALS.bookmarks.write_arc() = BookmarkList::from_event(&event)?;
-                GLOBALS.recompute_current_bookmarks.notify_one();
-            }
-        }
+        crate::bookmarks::rebuild_bookmarks()?;
 
-        // Index any waiting GiftWraps
+        // Index any waiting GiftWraps. A remote signer does not hold this up: the
+        // ones it has yet to decrypt are left for when its answers arrive.
         GLOBALS.db().index_unindexed_giftwraps()?;
 
         // Update wait for login condition
@@ -103,13 +108,52 @@ impl GossipIdentity {
         Ok(())
     }
 
+    // Any function that replaces our key should run this first
+    fn drop_remote_signer(&self) -> Result<(), Error> {
+        if self.remote.write().take().is_some() {
+            GLOBALS.db().delete_nip46_bunker(None)?;
+        }
+        Ok(())
+    }
+
+    /// Sign with a remote signer from now on, as the pubkey it signs for
+    pub(crate) fn set_remote_signer(
+        &self,
+        client: Nip46Client,
+        user_pubkey: PublicKey,
+    ) -> Result<(), Error> {
+        GLOBALS
+            .db()
+            .write_nip46_bunker(&client.to_bunker(user_pubkey)?, None)?;
+        *self.remote.write() = Some(Arc::new(client));
+        *self.inner.write_arc() = Identity::Public(user_pubkey);
+        self.on_keychange()?;
+        self.on_unlock()?;
+        Ok(())
+    }
+
+    /// Stop using the remote signer. We keep our public key.
+    pub(crate) fn clear_remote_signer(&self) -> Result<(), Error> {
+        self.drop_remote_signer()
+    }
+
+    pub fn remote_signer(&self) -> Option<Arc<Nip46Client>> {
+        self.remote.read().clone()
+    }
+
+    pub fn is_remote(&self) -> bool {
+        self.remote.read().is_some()
+    }
+
     pub(crate) fn set_public_key(&self, public_key: PublicKey) -> Result<(), Error> {
+        self.drop_remote_signer()?;
         *self.inner.write_arc() = Identity::Public(public_key);
         self.on_keychange()?;
         Ok(())
     }
 
     pub(crate) fn clear_public_key(&self) -> Result<(), Error> {
+        self.drop_remote_signer()?;
         *self.inner.write_arc() = Identity::None;
         self.on_keychange()?;
         Ok(())
@@ -120,6 +164,7 @@ impl GossipIdentity {
         epk: EncryptedPrivateKey,
         pass: &str,
     ) -> Result<(), Error> {
+        self.drop_remote_signer()?;
         *self.inner.write_arc() = Identity::from_encrypted_private_key(epk, pass)?;
         self.on_keychange()?;
         Ok(())
@@ -135,6 +180,7 @@ impl GossipIdentity {
     pub(crate) fn set_private_key(&self, pk: PrivateKey, pass: &str) -> Result<(), Error> {
         let log_n = GLOBALS.db().read_setting_log_n();
         let identity = Identity::from_private_key(pk, pass, log_n)?;
+        self.drop_remote_signer()?;
         *self.inner.write_arc() = identity;
         self.on_keychange()?;
         Ok(())
@@ -159,12 +205,15 @@ impl GossipIdentity {
 
     pub(crate) fn generate_private_key(&self, pass: &str) -> Result<(), Error> {
         let log_n = GLOBALS.db().read_setting_log_n();
-        *self.inner.write_arc() = Identity::generate(pass, log_n)?;
+        let identity = Identity::generate(pass, log_n)?;
+        self.drop_remote_signer()?;
+        *self.inner.write_arc() = identity;
         self.on_keychange()?;
         Ok(())
     }
 
     pub(crate) fn delete_identity(&self) -> Result<(), Error> {
+        self.drop_remote_signer()?;
         *self.inner.write_arc() = Identity::None;
         self.on_keychange()?;
         Ok(())
@@ -175,7 +224,7 @@ impl GossipIdentity {
     }
 
     pub fn is_unlocked(&self) -> bool {
-        self.inner.read_arc().is_unlocked()
+        self.is_remote() || self.inner.read_arc().is_unlocked()
     }
 
     pub fn public_key(&self) -> Option<PublicKey> {
@@ -190,20 +239,30 @@ impl GossipIdentity {
         Ok(self.inner.read_arc().key_security()?)
     }
 
-    pub fn sign_event(&self, input: PreEvent) -> Result<Event, Error> {
+    pub async fn sign_event(&self, input: PreEvent) -> Result<Event, Error> {
+        if let Some(remote) = self.remote_signer() {
+            return remote.sign_event(input).await;
+        }
         Ok(self.inner.read_arc().sign_event(input)?)
     }
 
-    pub fn sign_event_with_pow(
+    /// Sign with proof of work (NIP-13). The work is done on a blocking thread.
+    pub async fn sign_event_with_pow(
         &self,
         input: PreEvent,
         zero_bits: u8,
         work_sender: Option<Sender<u8>>,
     ) -> Result<Event, Error> {
-        Ok(self
-            .inner
-            .read_arc()
-            .sign_event_with_pow(input, zero_bits, work_sender)?)
+        if let Some(remote) = self.remote_signer() {
+            return remote.sign_event_with_pow(input, zero_bits).await;
+        }
+        let inner = self.inner.clone();
+        task::spawn_blocking(move || -> Result<Event, Error> {
+            Ok(inner
+                .read_arc()
+                .sign_event_with_pow(input, zero_bits, work_sender)?)
+        })
+        .await?
     }
 
     pub fn export_private_key_bech32(&self, pass: &str) -> Result<(String, bool), Error> {
@@ -223,6 +282,9 @@ impl GossipIdentity {
     }
 
     pub fn unwrap_giftwrap(&self, event: &Event) -> Result<Rumor, Error> {
+        if let Some(remote) = self.remote_signer() {
+            return remote.unwrap_giftwrap(event);
+        }
         Ok(self.inner.read_arc().unwrap_giftwrap(event)?)
     }
 
@@ -237,38 +299,79 @@ impl GossipIdentity {
     }
 
     pub fn decrypt_event_contents(&self, event: &Event) -> Result<String, Error> {
+        if let Some(remote) = self.remote_signer() {
+            // DMs we sent are encrypted to the recipient, everything else to the author
+            let mut other = event.pubkey;
+            if event.kind == EventKind::EncryptedDirectMessage && Some(other) == self.public_key() {
+                for tag in &event.tags {
+                    if let Ok(ParsedTag::Pubkey { pubkey, .. }) = tag.parse() {
+                        other = pubkey;
+                        break;
+                    }
+                }
+            }
+            return remote.decrypt(&other, &event.content);
+        }
         Ok(self.inner.read_arc().decrypt_event_contents(event)?)
     }
 
     pub fn decrypt(&self, other: &PublicKey, ciphertext: &str) -> Result<String, Error> {
+        if let Some(remote) = self.remote_signer() {
+            return remote.decrypt(other, ciphertext);
+        }
+        Ok(self.inner.read_arc().decrypt(other, ciphertext)?)
+    }
+
+    /// Like `decrypt`, but waits for a remote signer to answer instead of returning
+    /// `Nip46Pending`
+    pub async fn decrypt_and_wait(
+        &self,
+        other: &PublicKey,
+        ciphertext: &str,
+    ) -> Result<String, Error> {
+        if let Some(remote) = self.remote_signer() {
+            return remote.decrypt_and_wait(other, ciphertext).await;
+        }
         Ok(self.inner.read_arc().decrypt(other, ciphertext)?)
     }
 
+    /// Not available with a remote signer, which does not share conversation keys
     pub fn nip44_conversation_key(&self, other: &PublicKey) -> Result<[u8; 32], Error> {
+        if self.is_remote() {
+            return Err(ErrorKind::Nip46Unsupported("share a conversation key".to_owned()).into());
+        }
         Ok(self.inner.read_arc().nip44_conversation_key(other)?)
     }
 
-    pub fn encrypt(
+    pub async fn encrypt(
         &self,
         other: &PublicKey,
         plaintext: &str,
         algo: ContentEncryptionAlgorithm,
     ) -> Result<String, Error> {
+        if let Some(remote) = self.remote_signer() {
+            return remote.encrypt(other, plaintext, algo).await;
+        }
         Ok(self.inner.read_arc().encrypt(other, plaintext, algo)?)
     }
 
-    pub fn create_metadata_event(
+    pub async fn create_metadata_event(
         &self,
         input: PreEvent,
         metadata: Metadata,
     ) -> Result<Event, Error> {
+        if let Some(remote) = self.remote_signer() {
+            let mut input = input;
+            input.content = serde_json::to_string(&metadata)?;
+            return remote.sign_event(input).await;
+        }
         Ok(self
             .inner
             .read_arc()
             .create_metadata_event(input, metadata)?)
     }
 
-    pub fn create_zap_request_event(
+    pub async fn create_zap_request_event(
         &self,
         recipient_pubkey: PublicKey,
         zapped_event: Option<Id>,
@@ -276,6 +379,47 @@ impl GossipIdentity {
         relays: Vec<String>,
         content: String,
     ) -> Result<Event, Error> {
+        if let Some(remote) = self.remote_signer() {
+            let pubkey = match self.public_key() {
+                Some(pk) => pk,
+                None => return Err(ErrorKind::NoPublicKey.into()),
+            };
+
+            // A zap request (NIP-57) is an ordinary event, so the remote signer can sign it
+            let mut relays_tag: Vec<&str> = vec!["relays"];
+            relays_tag.extend(relays.iter().map(|r| r.as_str()));
+            let mut tags = vec![
+                ParsedTag::Pubkey {
+                    pubkey: recipient_pubkey,
+                    recommended_relay_url: None,
+                    petname: None,
+                }
+                .into_tag(),
+                Tag::new(&["amount", &format!("{}", millisatoshis)]),
+                Tag::new(&relays_tag),
+            ];
+            if let Some(id) = zapped_event {
+                tags.push(
+                    ParsedTag::Event {
+                        id,
+                        recommended_relay_url: None,
+                        marker: None,
+                        author_pubkey: None,
+                    }
+                    .into_tag(),
+                );
+            }
+
+            return remote
+                .sign_event(PreEvent {
+                    pubkey,
+                    created_at: Unixtime::now(),
+                    kind: EventKind::ZapRequest,
+                    tags,
+                    content,
+                })
+                .await;
+        }
         Ok(self.inner.read_arc().create_zap_request_event(
             recipient_pubkey,
             zapped_event,
@@ -285,18 +429,25 @@ impl GossipIdentity {
         )?)
     }
 
+    /// Not available with a remote signer, as a delegation is not an event
     pub fn generate_delegation_signature(
         &self,
         delegated_pubkey: PublicKey,
         delegation_conditions: &DelegationConditions,
     ) -> Result<Signature, Error> {
+        if self.is_remote() {
+            return Err(ErrorKind::Nip46Unsupported("sign delegations".to_owned()).into());
+        }
         Ok(self
             .inner
             .read_arc()
             .generate_delegation_signature(delegated_pubkey, delegation_conditions)?)
     }
 
-    pub fn giftwrap(&self, input: PreEvent, pubkey: PublicKey) -> Result<Event, Error> {
+    pub async fn giftwrap(&self, input: PreEvent, pubkey: PublicKey) -> Result<Event, Error> {
+        if let Some(remote) = self.remote_signer() {
+            return remote.giftwrap(input, pubkey).await;
+        }
         Ok(self.inner.read_arc().giftwrap(input, pubkey)?)
     }
 
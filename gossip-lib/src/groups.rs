@@ -0,0 +1,262 @@
+use crate::error::{Error, ErrorKind};
+use crate::globals::GLOBALS;
+use nostr_types::{Event, EventKind, Filter, PublicKey, RelayUrl, Unixtime};
+use speedy::{Readable, Writable};
+
+/// The kinds of events a group relay publishes about a group:
+/// metadata (39000), admins (39001), members (39002) and roles (39003)
+pub fn group_metadata_event_kinds() -> Vec<EventKind> {
+    vec![
+        EventKind::from(39000),
+        EventKind::from(39001),
+        EventKind::from(39002),
+        EventKind::from(39003),
+    ]
+}
+
+/// The kinds of events that are posted into a group: chat messages (9) and threads (11)
+pub fn group_message_event_kinds() -> Vec<EventKind> {
+    vec![EventKind::GroupChatMessage, EventKind::GroupChatThread]
+}
+
+/// The kind of a request to join a group
+pub(crate) fn join_request_event_kind() -> EventKind {
+    EventKind::from(9021)
+}
+
+/// The kind of a request to leave a group
+pub(crate) fn leave_request_event_kind() -> EventKind {
+    EventKind::from(9022)
+}
+
+/// A relay-based group (NIP-29) that we have joined
+#[derive(Debug, Clone, PartialEq, Eq, Readable, Writable)]
+pub struct JoinedGroup {
+    /// The relay that hosts the group
+    pub relay: RelayUrl,
+
+    /// The group id, unique only on that relay
+    pub id: String,
+
+    /// When we asked to join
+    pub joined_at: Unixtime,
+}
+
+impl JoinedGroup {
+    /// The name of the group, from its metadata, else its id
+    pub fn name(&self) -> String {
+        group_name(&self.relay, &self.id)
+    }
+}
+
+/// What the relay of a group says about the group
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct GroupMetadata {
+    pub name: Option<String>,
+    pub picture: Option<String>,
+    pub about: Option<String>,
+
+    /// Only members can read the group
+    pub private: bool,
+
+    /// Join requests are not honored automatically
+    pub closed: bool,
+
+    /// Admins, with their roles
+    pub admins: Vec<(PublicKey, Vec<String>)>,
+
+    /// Members, if the relay publishes them
+    pub members: Vec<PublicKey>,
+
+    /// Roles the relay supports, with their descriptions
+    pub roles: Vec<(String, String)>,
+}
+
+impl GroupMetadata {
+    /// The metadata of a group, loading it from storage only if it is not
+    /// cached. The cache is cleared as group metadata events come in.
+    pub fn get(relay: &RelayUrl, id: &str) -> GroupMetadata {
+        let key = (relay.to_owned(), id.to_owned());
+        if let Some(metadata) = GLOBALS.group_metadata.get(&key) {
+            return metadata.clone();
+        }
+        match Self::load(relay, id) {
+            Ok(metadata) => {
+                GLOBALS.group_metadata.insert(key, metadata.clone());
+                metadata
+            }
+            Err(e) => {
+                tracing::error!("Loading group metadata: {}", e);
+                GroupMetadata::default()
+            }
+        }
+    }
+
+    /// Load what we have of the metadata of a group from storage.
+    ///
+    /// Only events from the group's relay count, and if the relay gives a pubkey
+    /// in its NIP-11, only events signed with it.
+    pub fn load(relay: &RelayUrl, id: &str) -> Result<GroupMetadata, Error> {
+        let mut filter = Filter::new();
+        filter.kinds = group_metadata_event_kinds();
+        filter.add_tag_value('d', id.to_owned());
+
+        let relay_pubkey = relay_pubkey(relay)?;
+        let events = GLOBALS.db().find_events_by_filter(&filter, |e| {
+            (relay_pubkey.is_none() || relay_pubkey == Some(e.pubkey)) && seen_on(e, relay)
+        })?;
+
+        let mut metadata = GroupMetadata::default();
+        for event in &events {
+            metadata.apply(event);
+        }
+        Ok(metadata)
+    }
+
+    fn apply(&mut self, event: &Event) {
+        let kind: u32 = event.kind.into();
+        for tag in &event.tags {
+            match (kind, tag.tagname()) {
+                (39000, "name") => self.name = Some(tag.value().to_owned()),
+                (39000, "picture") => self.picture = Some(tag.value().to_owned()),
+                (39000, "about") => self.about = Some(tag.value().to_owned()),
+                (39000, "private") => self.private = true,
+                (39000, "closed") => self.closed = true,
+                (39001, "p") => {
+                    if let Ok(pubkey) = PublicKey::try_from_hex_string(tag.value(), true) {
+                        let roles = (2..tag.len())
+                            .map(|i| tag.get_index(i).to_owned())
+                            .collect();
+                        self.admins.push((pubkey, roles));
+                    }
+                }
+                (39002, "p") => {
+                    if let Ok(pubkey) = PublicKey::try_from_hex_string(tag.value(), true) {
+                        self.members.push(pubkey);
+                    }
+                }
+                (39003, "role") => {
+                    let description = tag.get_index(2).to_owned();
+                    self.roles.push((tag.value().to_owned(), description));
+                }
+                _ => {}
+            }
+        }
+    }
+
+    /// Whether the relay lists this person as a member or admin of the group
+    pub fn is_member(&self, pubkey: PublicKey) -> bool {
+        self.members.contains(&pubkey) || self.admins.iter().any(|(pk, _)| *pk == pubkey)
+    }
+}
+
+/// The name of a group, from its metadata, else its id
+pub fn group_name(relay: &RelayUrl, id: &str) -> String {
+    name_or_id(id, &GroupMetadata::get(relay, id))
+}
+
+/// The name of a group if its metadata is cached, else its id. This does not
+/// read storage.
+pub fn cached_group_name(relay: &RelayUrl, id: &str) -> String {
+    match GLOBALS
+        .group_metadata
+        .get(&(relay.to_owned(), id.to_owned()))
+    {
+        Some(metadata) => name_or_id(id, &metadata),
+        None => id.to_owned(),
+    }
+}
+
+fn name_or_id(id: &str, metadata: &GroupMetadata) -> String {
+    match &metadata.name {
+        Some(name) if !name.trim().is_empty() => name.clone(),
+        _ => id.to_owned(),
+    }
+}
+
+/// Forget the cached metadata of the group that a group metadata event
+/// (39000-39003) is about, on whichever relay
+pub(crate) fn forget_group_metadata(event: &Event) {
+    if let Some(tag) = event.tags.iter().find(|t| t.tagname() == "d") {
+        let id = tag.value();
+        GLOBALS.group_metadata.retain(|(_, group), _| group != id);
+    }
+}
+
+/// Forget the cached metadata of the groups on a relay, as when its NIP-11
+/// (which has the pubkey it signs group metadata with) changes
+pub(crate) fn forget_relay_group_metadata(relay: &RelayUrl) {
+    GLOBALS.group_metadata.retain(|(url, _), _| url != relay);
+}
+
+// The pubkey a relay signs group metadata with, from its NIP-11, if it gave one
+fn relay_pubkey(relay: &RelayUrl) -> Result<Option<PublicKey>, Error> {
+    let pubkey = GLOBALS
+        .db()
+        .read_relay(relay)?
+        .and_then(|r| r.nip11)
+        .and_then(|nip11| nip11.pubkey);
+    Ok(pubkey.and_then(|pk| PublicKey::try_from_hex_string(pk.as_str(), true).ok()))
+}
+
+/// Parse a group identifier of the form `<host>'<group-id>`. The host may also be
+/// given as a relay URL.
+pub fn parse_group_identifier(identifier: &str) -> Result<(RelayUrl, String), Error> {
+    let (host, id) = match identifier.trim().rsplit_once('\'') {
+        Some((host, id)) if !host.is_empty() && !id.is_empty() => (host, id),
+        _ => {
+            return Err(ErrorKind::General(format!(
+                "Not a group identifier (expected host'group-id): {identifier}"
+            ))
+            .into())
+        }
+    };
+
+    let url = if host.starts_with("wss://") || host.starts_with("ws://") {
+        host.to_owned()
+    } else {
+        format!("wss://{host}")
+    };
+
+    Ok((RelayUrl::try_from_str(&url)?, id.to_owned()))
+}
+
+/// The group an event was posted into (its `h` tag), if any
+pub fn event_group(event: &Event) -> Option<&str> {
+    event
+        .tags
+        .iter()
+        .find(|t| t.tagname() == "h" && !t.value().is_empty())
+        .map(|t| t.value())
+}
+
+// Whether the event came from this relay (or is ours, as we don't record
+// where our own events came from)
+pub(crate) fn seen_on(event: &Event, relay: &RelayUrl) -> bool {
+    if GLOBALS.identity.public_key() == Some(event.pubkey) {
+        return true;
+    }
+    match GLOBALS.db().get_event_seen_on_relay(event.id) {
+        Ok(seen) => seen.iter().any(|(url, _)| url == relay),
+        Err(_) => false,
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_parse_group_identifier() {
+        let (relay, id) = parse_group_identifier("groups.example.com'abc-123").unwrap();
+        assert_eq!(relay.as_str(), "wss://groups.example.com/");
+        assert_eq!(id, "abc-123");
+
+        let (relay, id) = parse_group_identifier("ws://localhost:7777'test").unwrap();
+        assert_eq!(relay.as_str(), "ws://localhost:7777/");
+        assert_eq!(id, "test");
+
+        assert!(parse_group_identifier("groups.example.com").is_err());
+        assert!(parse_group_identifier("'abc").is_err());
+    }
+}
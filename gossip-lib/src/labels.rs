@@ -0,0 +1,66 @@
+use crate::globals::GLOBALS;
+use crate::people::PersonList;
+use nostr_types::{Event, EventKind, Id, PublicKey};
+
+/// The namespace of labels that do not name one (NIP-32 "user generated content")
+pub const UGC_NAMESPACE: &str = "ugc";
+
+/// A label (NIP-32) applied by a label event (kind 1985) to an event or a person
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Label {
+    pub namespace: String,
+
+    pub label: String,
+
+    /// Who applied the label
+    pub by: PublicKey,
+
+    /// The label event that applied it
+    pub label_event: Id,
+}
+
+impl Label {
+    /// Extract the labels from a label event
+    pub fn from_event(event: &Event) -> Vec<Label> {
+        if event.kind != EventKind::Label {
+            return vec![];
+        }
+
+        let mut output: Vec<Label> = Vec::new();
+        for tag in &event.tags {
+            if tag.tagname() != "l" || tag.value().is_empty() {
+                continue;
+            }
+            let namespace = match tag.get_index(2) {
+                "" => UGC_NAMESPACE,
+                ns => ns,
+            };
+            output.push(Label {
+                namespace: namespace.to_owned(),
+                label: tag.value().to_owned(),
+                by: event.pubkey,
+                label_event: event.id,
+            });
+        }
+        output
+    }
+
+    /// Whether the label was applied by us or by somebody we follow
+    pub fn is_trusted(&self) -> bool {
+        Some(self.by) == GLOBALS.identity.public_key()
+            || GLOBALS
+                .people
+                .is_person_in_list(&self.by, PersonList::Followed)
+    }
+}
+
+/// Whether a label event labels people (rather than events). Per NIP-32 "p" tags may also
+/// just name the author of a labelled event, so they are only targets when there are no
+/// event targets.
+pub(crate) fn labels_people(event: &Event) -> bool {
+    event.kind == EventKind::Label
+        && !event
+            .tags
+            .iter()
+            .any(|t| t.tagname() == "e" || t.tagname() == "a")
+}
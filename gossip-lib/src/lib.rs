@@ -70,9 +70,17 @@ pub use blossom::Blossom;
 pub mod bookmarks;
 pub use bookmarks::BookmarkList;
 
+/// NIP-28 public chat channels
+pub mod channels;
+pub use channels::{ChannelMetadata, FollowedChannel};
+
 /// Defines messages sent to the overlord
 pub mod comms;
 
+/// NIP-45 counts reported by relays
+pub mod counts;
+pub use counts::CountTarget;
+
 mod delegation;
 pub use delegation::Delegation;
 
@@ -82,6 +90,10 @@ pub use dm_channel::{DmChannel, DmChannelData};
 // direct quick-temporary communication with relays, without overlord/minion involvement
 pub mod direct;
 
+/// NIP-37 drafts, encrypted to ourself and synced via our relays
+pub mod drafts;
+pub use drafts::Draft;
+
 mod error;
 pub use error::{Error, ErrorKind};
 
@@ -92,7 +104,7 @@ pub use feed::{
 };
 
 mod fetcher;
-pub use fetcher::{FetchResult, Fetcher};
+pub use fetcher::{CacheKind, CacheKindUsage, CacheUsage, FetchResult, Fetcher};
 
 mod filter_set;
 
@@ -102,6 +114,14 @@ pub use globals::{Globals, GLOBALS};
 mod gossip_identity;
 pub use gossip_identity::GossipIdentity;
 
+/// NIP-29 relay-based groups
+pub mod groups;
+pub use groups::{GroupMetadata, JoinedGroup};
+
+/// NIP-32 labels
+pub mod labels;
+pub use labels::Label;
+
 pub mod manager;
 
 mod media;
@@ -110,14 +130,21 @@ pub use media::{media_url_mimetype, Media, MediaLoadingResult};
 mod minion;
 
 mod misc;
-pub use misc::{Freshness, Private, ZapState};
+pub use misc::{event_expiration, event_is_expired, Freshness, Private, ZapState, REPORT_TYPES};
 
 /// Rendering various names of users
 pub mod names;
 
+/// Set reconciliation with relays (NIP-77)
+mod negentropy;
+
 /// nip05 handling
 pub mod nip05;
 
+/// Signing with a remote signer (NIP-46 client)
+pub mod nostr_connect_client;
+pub use nostr_connect_client::{BunkerToken, Nip46Bunker, Nip46Client, Nip46PendingRequest};
+
 #[allow(dead_code)]
 pub mod nostr_connect_server;
 pub use nostr_connect_server::{Nip46Server, Nip46UnconnectedServer};
@@ -125,6 +152,10 @@ pub use nostr_connect_server::{Nip46Server, Nip46UnconnectedServer};
 mod overlord;
 pub use overlord::Overlord;
 
+/// Paying zap invoices
+pub mod payment;
+pub use payment::{CommandPaymentHandler, PaymentHandler};
+
 mod pending;
 pub use pending::Pending;
 pub use pending::PendingItem;
@@ -138,6 +169,7 @@ mod person_relay;
 pub use person_relay::PersonRelay;
 
 mod post;
+pub use post::LongFormArticle;
 
 /// Processing incoming events
 pub mod process;
@@ -145,11 +177,22 @@ pub mod process;
 mod profile;
 pub use profile::Profile;
 
+/// Routing connections through a SOCKS5 proxy
+pub mod proxy;
+
 mod relationship;
 
 pub mod relay;
 pub use relay::{Relay, ScoreFactors};
 
+/// Per-relay connection and bandwidth statistics
+pub mod relay_stats;
+pub use relay_stats::RelayStats;
+
+/// Why relays turned down our subscriptions and events
+pub mod relay_rejection;
+pub use relay_rejection::{RelayRejection, SkippedWrite};
+
 pub mod relay_picker;
 pub use relay_picker::RelayPicker;
 
@@ -170,6 +213,10 @@ pub use storage::{FollowingsTable, HandlersTable, PersonTable, Storage, Table};
 
 mod tasks;
 
+/// NIP-57 zap receipt validation
+pub mod zaps;
+pub use zaps::InvalidZap;
+
 #[macro_use]
 extern crate lazy_static;
 
@@ -177,7 +224,6 @@ extern crate lazy_static;
 /// when connecting to relays
 pub static USER_AGENT: &str = concat!(env!("CARGO_PKG_NAME"), "/", env!("CARGO_PKG_VERSION"));
 
-use nostr_types::EventKind;
 use std::ops::DerefMut;
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
@@ -255,16 +301,7 @@ pub fn init(rapid: bool, command_mode: bool) -> Result<(), Error> {
     }
 
     // Populate global bookmarks
-    if let Some(pubkey) = GLOBALS.identity.public_key() {
-        if let Some(event) =
-            GLOBALS
-                .db()
-                .get_replaceable_event(EventKind::BookmarkList, pubkey, "")?
-        {
-            *GLOBALS.bookmarks.write_arc() = BookmarkList::from_event(&event)?;
-            GLOBALS.recompute_current_bookmarks.notify_one();
-        }
-    }
+    bookmarks::rebuild_bookmarks()?;
 
     Ok(())
 }
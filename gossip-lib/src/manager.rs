@@ -111,7 +111,7 @@ async fn engage_minion_inner(url: RelayUrl, mut jobs: Vec<RelayJob>) -> Result<(
             if !job.reason.persistent() {
                 if let Some(pos) = vec.iter().position(|e| e.reason == job.reason) {
                     vec[pos] = job;
-                    return Ok(());
+                    continue;
                 }
             }
             vec.push(job);
@@ -1,13 +1,16 @@
+use crate::blossom::HashOutput;
 use crate::error::{Error, ErrorKind};
-use crate::fetcher::FetchResult;
+use crate::fetcher::{CacheKind, FetchResult};
 use crate::globals::GLOBALS;
 use dashmap::{DashMap, DashSet};
 use image::imageops;
 use image::imageops::FilterType;
 use image::{DynamicImage, Rgba, RgbaImage};
-use nostr_types::{FileMetadata, UncheckedUrl, Url};
+use nostr_types::{FileMetadata, PublicKey, UncheckedUrl, Url};
 use std::fmt;
-use std::sync::atomic::Ordering;
+use std::io::Cursor;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::time::Instant;
 
 pub enum MediaLoadingResult<T> {
     Disabled,
@@ -33,9 +36,18 @@ pub struct Media {
     // until the UI next asks for them, at which point we remove them
     // and hand them over. This way we can do the work that takes
     // longer and the UI can do as little work as possible.
-    image_temp: DashMap<Url, RgbaImage>,
+    // Images held the longest are dropped to keep these under the memory cap.
+    image_temp: DashMap<Url, (RgbaImage, Instant)>,
+    image_temp_bytes: AtomicUsize,
     media_pending_processing: DashSet<Url>,
     failed_media: DashMap<UncheckedUrl, String>,
+
+    // Urls we have looked for a saved thumbnail of
+    thumbnails_checked: DashSet<Url>,
+
+    // Blossom URLs that failed, which we are trying on the author's other servers
+    // (None while in progress, or the error if that failed too)
+    blossom_fallbacks: DashMap<Url, Option<String>>,
 }
 
 impl Default for Media {
@@ -48,8 +60,11 @@ impl Media {
     pub(crate) fn new() -> Media {
         Media {
             image_temp: DashMap::new(),
+            image_temp_bytes: AtomicUsize::new(0),
             media_pending_processing: DashSet::new(),
             failed_media: DashMap::new(),
+            thumbnails_checked: DashSet::new(),
+            blossom_fallbacks: DashMap::new(),
         }
     }
 
@@ -81,6 +96,9 @@ impl Media {
     /// Retry a failed Url
     pub fn retry_failed(&self, unchecked_url: &UncheckedUrl) {
         self.failed_media.remove(unchecked_url);
+        if let Ok(url) = Url::try_from_unchecked_url(unchecked_url) {
+            self.blossom_fallbacks.remove(&url);
+        }
     }
 
     /// Get an image by Url
@@ -88,16 +106,25 @@ impl Media {
     /// This returns immediately, usually with None if never called on that Url before.
     /// Call it again later to try to pick up the result.
     ///
+    /// Large images are downsized to the `media_max_dimension` setting. These
+    /// thumbnails are saved in the cache, and used instead of the image from then on.
+    ///
+    /// If the image is a blossom blob that fails to load, it is tried on the blossom
+    /// servers of the `author`.
+    ///
     /// FIXME: this API doesn't serve async clients well.
     pub fn get_image(
         &self,
         url: &Url,
         volatile: bool,
         file_metadata: Option<&FileMetadata>,
+        author: Option<PublicKey>,
     ) -> MediaLoadingResult<RgbaImage> {
         // If we have it, hand it over (we won't need a copy anymore)
-        if let Some(th) = self.image_temp.remove(url) {
-            return MediaLoadingResult::Ready(th.1);
+        if let Some((_, (image, _))) = self.image_temp.remove(url) {
+            self.image_temp_bytes
+                .fetch_sub(image.as_raw().len(), Ordering::Relaxed);
+            return MediaLoadingResult::Ready(image);
         }
 
         // If it is pending processing, don't get it again
@@ -105,7 +132,32 @@ impl Media {
             return MediaLoadingResult::Loading;
         }
 
-        match self.get_data(url, volatile, file_metadata) {
+        let size = media_size();
+
+        // Use a thumbnail we made before, if there is one
+        if !volatile && !self.thumbnails_checked.contains(url) {
+            self.media_pending_processing.insert(url.clone());
+            let aurl = url.to_owned();
+            std::mem::drop(tokio::spawn(async move {
+                match GLOBALS.fetcher.read_thumbnail(&aurl, size).await {
+                    Ok(Some(bytes)) => match image::load_from_memory(&bytes) {
+                        Ok(image) => GLOBALS.media.hold_image(aurl.clone(), image.into_rgba8()),
+                        Err(e) => tracing::warn!("Bad thumbnail for {}: {}", aurl, e),
+                    },
+                    Ok(None) => {}
+                    Err(e) => tracing::error!("{}", e),
+                }
+                GLOBALS.media.thumbnails_checked.insert(aurl.clone());
+                GLOBALS.media.media_pending_processing.remove(&aurl);
+
+                // Notify the UI to redraw, it will either pick up the thumbnail
+                // or now fetch the image
+                GLOBALS.notify_ui_redraw.notify_waiters();
+            }));
+            return MediaLoadingResult::Loading;
+        }
+
+        match self.get_data(url, volatile, file_metadata, author) {
             MediaLoadingResult::Disabled => MediaLoadingResult::Disabled,
             MediaLoadingResult::Loading => MediaLoadingResult::Loading,
             MediaLoadingResult::Ready(bytes) => {
@@ -120,20 +172,34 @@ impl Media {
                 let aurl = url.to_owned();
                 tokio::spawn(async move {
                     let start = std::time::Instant::now();
-                    let size = 800 * 3 // 3x feed size, 1x Media page size
-                        * GLOBALS
-                            .pixels_per_point_times_100
-                            .load(Ordering::Relaxed)
-                        / 100;
+
+                    // Downsize images larger than the size we show them at
+                    let too_large = match image_dimensions(&bytes) {
+                        Some((w, h)) => w.max(h) > size,
+                        None => false,
+                    };
 
                     match load_image_bytes(
-                        &bytes, false, // don't crop square
-                        size,  // default size,
-                        false, // don't force that size
-                        false, // don't round
+                        &bytes, false,     // don't crop square
+                        size,      // default size,
+                        too_large, // only force that size if it is smaller
+                        false,     // don't round
                     ) {
                         Ok(color_image) => {
-                            GLOBALS.media.image_temp.insert(aurl, color_image);
+                            // Save the downsized image so we don't have to do this again
+                            if too_large && !volatile {
+                                match encode_thumbnail(&color_image) {
+                                    Ok(png) => {
+                                        if let Err(e) =
+                                            GLOBALS.fetcher.write_thumbnail(&aurl, size, &png).await
+                                        {
+                                            tracing::warn!("Could not save thumbnail: {}", e);
+                                        }
+                                    }
+                                    Err(e) => tracing::warn!("Could not encode thumbnail: {}", e),
+                                }
+                            }
+                            GLOBALS.media.hold_image(aurl.clone(), color_image);
                         }
                         Err(e) => {
                             let error = format!("{e}");
@@ -142,6 +208,7 @@ impl Media {
                                 .set_has_failed(&aurl.to_unchecked_url(), error);
                         }
                     }
+                    GLOBALS.media.media_pending_processing.remove(&aurl);
                     let end = std::time::Instant::now();
                     tracing::debug!(target: "fetcher", "Media processing took {}ms", (end - start).as_millis());
 
@@ -159,6 +226,9 @@ impl Media {
     /// This returns immediately, usually with None if never called on that Url before.
     /// Call it again later to try to pick up the result.
     ///
+    /// If the data is a blossom blob that fails to load, it is tried on the blossom
+    /// servers of the `author`.
+    ///
     /// FIXME: this API doesn't serve async clients well.
     ///
     /// DO NOT CALL FROM LIB, ONLY FROM UI
@@ -167,6 +237,7 @@ impl Media {
         url: &Url,
         volatile: bool,
         file_metadata: Option<&FileMetadata>,
+        author: Option<PublicKey>,
     ) -> MediaLoadingResult<Vec<u8>> {
         // If it failed before, error out now
         if let Some(s) = self.failed_media.get(&url.to_unchecked_url()) {
@@ -179,7 +250,10 @@ impl Media {
         }
 
         let use_cache = !volatile;
-        match GLOBALS.fetcher.try_get(url.clone(), use_cache) {
+        match GLOBALS
+            .fetcher
+            .try_get(url.clone(), CacheKind::Media, use_cache)
+        {
             Ok(FetchResult::Processing(_)) => MediaLoadingResult::Loading,
             Ok(FetchResult::Ready(bytes)) => {
                 // Verify metadata hash
@@ -206,6 +280,9 @@ impl Media {
                 MediaLoadingResult::Failed(error)
             }
             Ok(FetchResult::Failed(error)) => {
+                if let Some(result) = self.blossom_fallback(url, author, &error) {
+                    return result;
+                }
                 tracing::error!("{}", error);
                 self.set_has_failed(&url.to_unchecked_url(), error.clone());
                 MediaLoadingResult::Failed(error)
@@ -219,6 +296,128 @@ impl Media {
             }
         }
     }
+
+    // Hold an image until the UI asks for it
+    fn hold_image(&self, url: Url, image: RgbaImage) {
+        let len = image.as_raw().len();
+        if let Some((_, (old, _))) = self.image_temp.remove(&url) {
+            self.image_temp_bytes
+                .fetch_sub(old.as_raw().len(), Ordering::Relaxed);
+        }
+        self.image_temp.insert(url.clone(), (image, Instant::now()));
+        let mut total = self.image_temp_bytes.fetch_add(len, Ordering::Relaxed) + len;
+
+        // Drop the images held the longest until we are under the memory cap.
+        // If the UI asks for them after all, they are loaded again.
+        let cap = GLOBALS.db().read_setting_media_memory_cap_mb() as usize * 1_048_576;
+        while total > cap {
+            let oldest = self
+                .image_temp
+                .iter()
+                .filter(|r| *r.key() != url)
+                .min_by_key(|r| r.value().1)
+                .map(|r| r.key().clone());
+            let Some(oldest) = oldest else {
+                break;
+            };
+            if let Some((_, (old, _))) = self.image_temp.remove(&oldest) {
+                total = self
+                    .image_temp_bytes
+                    .fetch_sub(old.as_raw().len(), Ordering::Relaxed)
+                    - old.as_raw().len();
+            }
+            self.thumbnails_checked.remove(&oldest);
+        }
+    }
+
+    // If the url is a blossom blob, try the blossom servers of the author.
+    // This returns None if there is nothing more to try.
+    fn blossom_fallback(
+        &self,
+        url: &Url,
+        author: Option<PublicKey>,
+        error: &str,
+    ) -> Option<MediaLoadingResult<Vec<u8>>> {
+        let author = author?;
+        let hash = HashOutput::from_url(url.as_str())?;
+
+        match self.blossom_fallbacks.get(url).map(|r| r.value().clone()) {
+            Some(None) => Some(MediaLoadingResult::Loading),
+            Some(Some(fallback_error)) => {
+                let error = format!("{error}; on the author's blossom servers: {fallback_error}");
+                self.set_has_failed(&url.to_unchecked_url(), error.clone());
+                Some(MediaLoadingResult::Failed(error))
+            }
+            None => {
+                if GLOBALS.db().read_setting_offline() {
+                    return None;
+                }
+
+                tracing::debug!("Trying {} on the blossom servers of {}", url, author);
+                self.blossom_fallbacks.insert(url.clone(), None);
+                let aurl = url.clone();
+                std::mem::drop(tokio::spawn(async move {
+                    let result = match crate::blossom::download_from_servers_of(author, hash).await
+                    {
+                        Ok(bytes) => {
+                            GLOBALS
+                                .fetcher
+                                .put(aurl.clone(), CacheKind::Media, bytes)
+                                .await
+                        }
+                        Err(e) => Err(e),
+                    };
+                    match result {
+                        Ok(()) => {
+                            GLOBALS.media.blossom_fallbacks.remove(&aurl);
+                        }
+                        Err(e) => {
+                            GLOBALS
+                                .media
+                                .blossom_fallbacks
+                                .insert(aurl, Some(format!("{e}")));
+                        }
+                    }
+
+                    // Notify the UI to redraw now that the media loading is complete
+                    GLOBALS.notify_ui_redraw.notify_waiters();
+                }));
+                Some(MediaLoadingResult::Loading)
+            }
+        }
+    }
+}
+
+// The largest side of images from notes, in pixels
+fn media_size() -> u32 {
+    GLOBALS.db().read_setting_media_max_dimension()
+        * GLOBALS.pixels_per_point_times_100.load(Ordering::Relaxed)
+        / 100
+}
+
+// The dimensions of an image, read from its header without decoding it
+fn image_dimensions(image_bytes: &[u8]) -> Option<(u32, u32)> {
+    image::ImageReader::new(Cursor::new(image_bytes))
+        .with_guessed_format()
+        .ok()?
+        .into_dimensions()
+        .ok()
+}
+
+// Thumbnails are saved as PNG, favoring speed over size
+fn encode_thumbnail(image: &RgbaImage) -> Result<Vec<u8>, Error> {
+    use image::codecs::png::{CompressionType, FilterType as PngFilterType, PngEncoder};
+    use image::ImageEncoder;
+
+    let mut png: Vec<u8> = Vec::new();
+    PngEncoder::new_with_quality(&mut png, CompressionType::Fast, PngFilterType::Adaptive)
+        .write_image(
+            image.as_raw(),
+            image.width(),
+            image.height(),
+            image::ExtendedColorType::Rgba8,
+        )?;
+    Ok(png)
 }
 
 // Note: size is required for SVG which has no inherent size, even if we don't resize
@@ -278,6 +477,267 @@ pub(crate) fn load_image_bytes(
     }
 }
 
+// A blurhash keeps only a few components, so we compute it from the image shrunk
+// to fit this size
+const BLURHASH_SOURCE_SIZE: u32 = 64;
+
+/// The dimensions and blurhash of an image, for NIP-92 imeta tags. The image is
+/// decoded on a blocking thread.
+pub(crate) async fn image_dim_and_blurhash(
+    image_bytes: Vec<u8>,
+) -> Option<((usize, usize), String)> {
+    tokio::task::spawn_blocking(move || dim_and_blurhash(&image_bytes))
+        .await
+        .ok()
+        .flatten()
+}
+
+fn dim_and_blurhash(image_bytes: &[u8]) -> Option<((usize, usize), String)> {
+    use image::GenericImageView;
+
+    let dynamic_image = image::load_from_memory(image_bytes).ok()?;
+    let (w, h) = dynamic_image.dimensions();
+    if w == 0 || h == 0 {
+        return None;
+    }
+
+    let small = if w > BLURHASH_SOURCE_SIZE || h > BLURHASH_SOURCE_SIZE {
+        dynamic_image.thumbnail(BLURHASH_SOURCE_SIZE, BLURHASH_SOURCE_SIZE)
+    } else {
+        dynamic_image
+    };
+
+    // Convert to RGBA8
+    let small = small.to_rgba8();
+    let blurhash = blurhash::encode(
+        (4 * w / h).clamp(1, 9),
+        (4 * h / w).clamp(1, 9),
+        small.width(),
+        small.height(),
+        small.as_raw(),
+    )
+    .ok()?;
+
+    Some(((w as usize, h as usize), blurhash))
+}
+
+/// Remove GPS location data from an image we are about to upload.
+///
+/// JPEG images keep the rest of their EXIF (such as orientation). PNG and WebP
+/// images lose their EXIF if it has GPS data. Images of other types (such as
+/// HEIC or TIFF) that have GPS data are refused, as we cannot remove it from them.
+pub(crate) fn strip_image_gps(bytes: Vec<u8>, mime: &mime::Mime) -> Result<Vec<u8>, Error> {
+    let bytes = match mime.essence_str() {
+        "image/jpeg" => strip_jpeg_gps(&bytes),
+        "image/png" => strip_png_gps(&bytes),
+        "image/webp" => strip_webp_gps(&bytes),
+        _ => bytes,
+    };
+
+    // Make sure
+    if has_gps(&bytes) {
+        return Err(ErrorKind::General(format!(
+            "This {} image has location data that gossip cannot remove, so it was not uploaded.",
+            mime.subtype()
+        ))
+        .into());
+    }
+
+    Ok(bytes)
+}
+
+// Whether an image (of any type the exif crate understands) has GPS data
+fn has_gps(bytes: &[u8]) -> bool {
+    match exif::Reader::new().read_from_container(&mut Cursor::new(bytes)) {
+        Ok(exif) => exif.fields().any(is_gps_field),
+        Err(_) => false,
+    }
+}
+
+// Whether EXIF (TIFF) data has GPS data
+fn tiff_has_gps(tiff: &[u8]) -> bool {
+    match exif::Reader::new().read_raw(tiff.to_vec()) {
+        Ok(exif) => exif.fields().any(is_gps_field),
+        Err(_) => false,
+    }
+}
+
+fn is_gps_field(field: &exif::Field) -> bool {
+    field.tag.context() == exif::Context::Gps || field.tag == exif::Tag::GPSInfoIFDPointer
+}
+
+// Remove GPS location data from the EXIF of a JPEG image, leaving the image and
+// the rest of the EXIF as they were.
+//
+// If the EXIF has GPS data but cannot be rewritten, it is dropped entirely.
+// Data that is not a JPEG is returned unchanged.
+fn strip_jpeg_gps(jpeg: &[u8]) -> Vec<u8> {
+    if !jpeg.starts_with(&[0xFF, 0xD8]) {
+        return jpeg.to_vec();
+    }
+
+    let mut output: Vec<u8> = Vec::with_capacity(jpeg.len());
+    output.extend_from_slice(&jpeg[0..2]);
+    let mut pos: usize = 2;
+    loop {
+        if pos + 4 > jpeg.len() || jpeg[pos] != 0xFF {
+            break;
+        }
+        let marker = jpeg[pos + 1];
+
+        // Fill bytes and markers without a length
+        if marker == 0xFF {
+            output.push(0xFF);
+            pos += 1;
+            continue;
+        }
+        if marker == 0x01 || (0xD0..=0xD7).contains(&marker) {
+            output.extend_from_slice(&jpeg[pos..pos + 2]);
+            pos += 2;
+            continue;
+        }
+
+        // Start of scan (image data follows) or end of image
+        if marker == 0xDA || marker == 0xD9 {
+            break;
+        }
+
+        let len = u16::from_be_bytes([jpeg[pos + 2], jpeg[pos + 3]]) as usize;
+        let end = pos + 2 + len;
+        if len < 2 || end > jpeg.len() {
+            break;
+        }
+
+        let payload = &jpeg[pos + 4..end];
+        if marker == 0xE1 && payload.starts_with(b"Exif\0\0") {
+            // A None means drop the segment
+            if let Some(tiff) = strip_tiff_gps(&payload[6..]) {
+                let new_len = 2 + 6 + tiff.len();
+                if new_len <= u16::MAX as usize {
+                    output.extend_from_slice(&[0xFF, 0xE1]);
+                    output.extend_from_slice(&(new_len as u16).to_be_bytes());
+                    output.extend_from_slice(b"Exif\0\0");
+                    output.extend_from_slice(&tiff);
+                }
+            }
+        } else {
+            output.extend_from_slice(&jpeg[pos..end]);
+        }
+        pos = end;
+    }
+
+    // The rest is copied as is
+    output.extend_from_slice(&jpeg[pos..]);
+    output
+}
+
+// Rewrite EXIF (TIFF) data without the GPS fields, or None if it must be dropped
+fn strip_tiff_gps(tiff: &[u8]) -> Option<Vec<u8>> {
+    let exif = exif::Reader::new().read_raw(tiff.to_vec()).ok()?;
+    if !exif.fields().any(is_gps_field) {
+        return Some(tiff.to_vec());
+    }
+
+    let mut writer = exif::experimental::Writer::new();
+    for field in exif.fields() {
+        // We don't carry the thumbnail over, so skip its IFD
+        if field.ifd_num != exif::In::PRIMARY {
+            continue;
+        }
+        if field.tag.context() == exif::Context::Gps {
+            continue;
+        }
+        // The writer creates the pointers itself
+        if matches!(
+            field.tag,
+            exif::Tag::ExifIFDPointer | exif::Tag::GPSInfoIFDPointer | exif::Tag::InteropIFDPointer
+        ) {
+            continue;
+        }
+        writer.push_field(field);
+    }
+
+    let mut cursor = std::io::Cursor::new(Vec::new());
+    writer.write(&mut cursor, exif.little_endian()).ok()?;
+    Some(cursor.into_inner())
+}
+
+// Drop the eXIf chunk of a PNG image if it has GPS data. (Rewriting it would
+// mean computing its CRC again, and PNG viewers rarely use the rest.)
+// Data that is not a PNG is returned unchanged.
+fn strip_png_gps(png: &[u8]) -> Vec<u8> {
+    const SIGNATURE: &[u8] = b"\x89PNG\r\n\x1a\n";
+    if !png.starts_with(SIGNATURE) {
+        return png.to_vec();
+    }
+
+    let mut output: Vec<u8> = Vec::with_capacity(png.len());
+    output.extend_from_slice(SIGNATURE);
+    let mut pos: usize = SIGNATURE.len();
+    while pos + 12 <= png.len() {
+        // length, type, data, crc
+        let len = u32::from_be_bytes(png[pos..pos + 4].try_into().unwrap()) as usize;
+        let end = pos + 12 + len;
+        if end > png.len() {
+            break;
+        }
+        let chunk_type = &png[pos + 4..pos + 8];
+        let data = &png[pos + 8..pos + 8 + len];
+        if !(chunk_type == b"eXIf" && tiff_has_gps(data)) {
+            output.extend_from_slice(&png[pos..end]);
+        }
+        pos = end;
+    }
+
+    // The rest is copied as is
+    output.extend_from_slice(&png[pos..]);
+    output
+}
+
+// Drop the EXIF chunk of a WebP image if it has GPS data.
+// Data that is not a WebP is returned unchanged.
+fn strip_webp_gps(webp: &[u8]) -> Vec<u8> {
+    if webp.len() < 12 || &webp[0..4] != b"RIFF" || &webp[8..12] != b"WEBP" {
+        return webp.to_vec();
+    }
+
+    let mut chunks: Vec<u8> = Vec::with_capacity(webp.len());
+    let mut dropped = false;
+    let mut pos: usize = 12;
+    while pos + 8 <= webp.len() {
+        // fourcc, length (little endian), data, padding to an even length
+        let len = u32::from_le_bytes(webp[pos + 4..pos + 8].try_into().unwrap()) as usize;
+        if pos + 8 + len > webp.len() {
+            break;
+        }
+        let end = (pos + 8 + len + (len & 1)).min(webp.len());
+        let fourcc = &webp[pos..pos + 4];
+        let data = &webp[pos + 8..pos + 8 + len];
+        if fourcc == b"EXIF" && tiff_has_gps(data.strip_prefix(b"Exif\0\0").unwrap_or(data)) {
+            dropped = true;
+        } else {
+            chunks.extend_from_slice(&webp[pos..end]);
+        }
+        pos = end;
+    }
+
+    if !dropped {
+        return webp.to_vec();
+    }
+
+    // The VP8X chunk comes first, and has a flag for having EXIF
+    if chunks.starts_with(b"VP8X") && chunks.len() > 8 {
+        chunks[8] &= !0x08;
+    }
+
+    let mut output: Vec<u8> = Vec::with_capacity(12 + chunks.len());
+    output.extend_from_slice(b"RIFF");
+    output.extend_from_slice(&((4 + chunks.len()) as u32).to_le_bytes());
+    output.extend_from_slice(b"WEBP");
+    output.extend_from_slice(&chunks);
+    output
+}
+
 fn adjust_orientation(image_bytes: &[u8], image: DynamicImage) -> DynamicImage {
     match get_orientation(image_bytes) {
         1 => image,
@@ -399,3 +859,109 @@ pub fn media_url_mimetype(s: &str) -> Option<&'static str> {
         None
     }
 }
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    // These images have EXIF with a camera make, an orientation of 6 and a GPS
+    // position. The TIFF is only EXIF, standing in for the types we cannot rewrite.
+    const GPS_JPEG: &[u8] = include_bytes!("../testdata/gps.jpg");
+    const GPS_PNG: &[u8] = include_bytes!("../testdata/gps.png");
+    const GPS_WEBP: &[u8] = include_bytes!("../testdata/gps.webp");
+    const GPS_TIFF: &[u8] = include_bytes!("../testdata/gps.tiff");
+
+    #[test]
+    fn test_strip_jpeg_gps() {
+        assert!(has_gps(GPS_JPEG));
+
+        let stripped = strip_jpeg_gps(GPS_JPEG);
+        assert!(!has_gps(&stripped));
+
+        // The rest of the EXIF is kept
+        assert_eq!(get_orientation(&stripped), 6);
+        let exif = exif::Reader::new()
+            .read_from_container(&mut Cursor::new(&stripped))
+            .unwrap();
+        assert!(exif.get_field(exif::Tag::Make, exif::In::PRIMARY).is_some());
+
+        // And so is the image
+        assert!(stripped.ends_with(&GPS_JPEG[GPS_JPEG.len() - 20..]));
+
+        // Stripping again changes nothing
+        assert_eq!(strip_jpeg_gps(&stripped), stripped);
+
+        // Other data is left alone
+        assert_eq!(strip_jpeg_gps(GPS_PNG), GPS_PNG);
+    }
+
+    #[test]
+    fn test_strip_tiff_gps() {
+        assert!(tiff_has_gps(GPS_TIFF));
+
+        let stripped = strip_tiff_gps(GPS_TIFF).unwrap();
+        assert!(!tiff_has_gps(&stripped));
+        let exif = exif::Reader::new().read_raw(stripped).unwrap();
+        assert!(exif.get_field(exif::Tag::Make, exif::In::PRIMARY).is_some());
+        assert!(exif
+            .get_field(exif::Tag::Orientation, exif::In::PRIMARY)
+            .is_some());
+    }
+
+    #[test]
+    fn test_strip_png_and_webp_gps() {
+        assert!(has_gps(GPS_PNG));
+        let stripped = strip_png_gps(GPS_PNG);
+        assert!(!has_gps(&stripped));
+        assert!(stripped.len() < GPS_PNG.len());
+        assert!(stripped.ends_with(b"IEND\xae\x42\x60\x82"));
+
+        assert!(has_gps(GPS_WEBP));
+        let stripped = strip_webp_gps(GPS_WEBP);
+        assert!(!has_gps(&stripped));
+        assert_eq!(
+            u32::from_le_bytes(stripped[4..8].try_into().unwrap()) as usize,
+            stripped.len() - 8
+        );
+        // No longer flagged as having EXIF
+        assert_eq!(stripped[20] & 0x08, 0);
+    }
+
+    #[test]
+    fn test_dim_and_blurhash() {
+        let image = RgbaImage::from_fn(300, 200, |x, y| Rgba([x as u8, y as u8, 128, 255]));
+        let mut png: Vec<u8> = Vec::new();
+        DynamicImage::ImageRgba8(image)
+            .write_to(&mut Cursor::new(&mut png), image::ImageFormat::Png)
+            .unwrap();
+
+        let (dim, blurhash) = dim_and_blurhash(&png).unwrap();
+
+        // The dimensions are of the full image, though the blurhash is made from less
+        assert_eq!(dim, (300, 200));
+        // 6x2 components: a size flag of (6-1)+(2-1)*9, the maximum, the average in
+        // four characters and the other 11 in two each
+        assert!(blurhash.starts_with('E'));
+        assert_eq!(blurhash.len(), 1 + 1 + 4 + 2 * 11);
+    }
+
+    #[test]
+    fn test_strip_image_gps() {
+        for (bytes, mime) in [
+            (GPS_JPEG, mime::IMAGE_JPEG),
+            (GPS_PNG, mime::IMAGE_PNG),
+            (GPS_WEBP, "image/webp".parse().unwrap()),
+        ] {
+            let stripped = strip_image_gps(bytes.to_vec(), &mime).unwrap();
+            assert!(!has_gps(&stripped));
+        }
+
+        // We cannot rewrite a TIFF, so it is refused
+        let tiff: mime::Mime = "image/tiff".parse().unwrap();
+        assert!(strip_image_gps(GPS_TIFF.to_vec(), &tiff).is_err());
+
+        // Without GPS data, it is fine
+        let stripped = strip_tiff_gps(GPS_TIFF).unwrap();
+        assert_eq!(strip_image_gps(stripped.clone(), &tiff).unwrap(), stripped);
+    }
+}
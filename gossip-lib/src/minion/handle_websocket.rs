@@ -1,7 +1,9 @@
+use super::retry::PostRetry;
 use super::{AuthState, Minion};
 use crate::comms::ToOverlordMessage;
 use crate::error::Error;
 use crate::globals::GLOBALS;
+use crate::relay_rejection;
 use nostr_types::{RelayMessage, Unixtime};
 
 impl Minion {
@@ -9,6 +11,14 @@ impl Minion {
         // TODO: pull out the raw event without any deserialization to be sure we don't mangle
         //       it.
 
+        // Negentropy (NIP-77) messages are not relay messages nostr-types knows about
+        if ws_message
+            .trim_start_matches(|c: char| c == '[' || c.is_whitespace())
+            .starts_with("\"NEG-")
+        {
+            return self.handle_negentropy_message(&ws_message).await;
+        }
+
         let relay_message: RelayMessage = match serde_json::from_str(&ws_message) {
             Ok(rm) => rm,
             Err(e) => {
@@ -29,6 +39,8 @@ impl Minion {
                     .get_handle_by_id(&subid.0)
                     .unwrap_or_else(|| "_".to_owned());
 
+                self.stats.events_received += 1;
+
                 if let Some(sub) = self.subscription_map.get_mut_by_id(&subid.0) {
                     // Check if the event matches one of our filters
                     //
@@ -83,15 +95,19 @@ impl Minion {
                 }
 
                 // Process the event
-                crate::process::process_new_event(
+                let duplicate = crate::process::process_new_event(
                     &event,
                     Some(self.url.clone()),
                     Some(handle),
                     true,
                     false,
                 )?;
+                if duplicate {
+                    self.stats.duplicate_events += 1;
+                }
             }
             RelayMessage::Notice(msg) => {
+                self.stats.notices += 1;
                 tracing::warn!("{}: NOTICE: {}", &self.url, msg);
             }
             RelayMessage::Notify(msg) => {
@@ -111,6 +127,13 @@ impl Minion {
                 // Update the matching subscription
                 match self.subscription_map.get_mut_by_id(&subid.0) {
                     Some(sub) => {
+                        self.rate_limit_strikes = 0;
+                        if !sub.eose() {
+                            if let Some(sent_at) = sub.sent_at() {
+                                self.stats.eose_count += 1;
+                                self.stats.eose_latency_ms += sent_at.elapsed().as_millis() as u64;
+                            }
+                        }
                         tracing::debug!("{}: {}: EOSE: {:?}", &self.url, handle, subid);
                         if close {
                             self.unsubscribe(&handle).await?;
@@ -190,7 +213,7 @@ impl Minion {
                     }
                 }
 
-                if let Some(job_id) = self.posting_ids.get(&id).copied() {
+                if self.posting_ids.contains_key(&id) {
                     if ok {
                         // Save seen_on data
                         // (it was already processed by the overlord before the minion got it,
@@ -201,32 +224,20 @@ impl Minion {
                             Unixtime::now(),
                             None,
                         )?;
+                        self.rate_limit_strikes = 0;
                     } else {
-                        // demerit the relay
-                        self.bump_failure_count().await;
-                    }
-
-                    let mut job_is_done: bool = false;
-                    {
-                        // Take it out of the posting_jobs
-                        if let Some(job_ids) = self.posting_jobs.get_mut(&job_id) {
-                            job_ids.retain(|id_in_vec| *id_in_vec != id);
-                            job_is_done = job_ids.is_empty();
-                        } // else is not expected, but if it happens we ignore it.
-                    }
-
-                    if job_is_done {
-                        self.posting_jobs.remove(&job_id);
-
-                        // Tell overlord
-                        self.to_overlord.send(ToOverlordMessage::MinionJobComplete(
-                            self.url.clone(),
-                            job_id,
-                        ))?;
+                        match self.retry_post(id, &ok_message).await? {
+                            // It is not done yet
+                            PostRetry::Later => return Ok(()),
+                            PostRetry::Remined => {}
+                            PostRetry::Failed => {
+                                // demerit the relay
+                                self.bump_failure_count().await;
+                            }
+                        }
                     }
 
-                    // Take it out of the posting_ids whether or not job is done
-                    self.posting_ids.remove(&id);
+                    self.post_finished(id)?;
                 }
             }
             RelayMessage::Auth(challenge) => {
@@ -239,6 +250,21 @@ impl Minion {
                 self.maybe_authenticate().await?;
             }
             RelayMessage::Closed(subid, message) => {
+                self.stats.closed += 1;
+
+                // Neither are our negentropy syncs
+                if self.negentropy_sync_closed(&subid.0).await? {
+                    tracing::info!("{}: Closed: {}: {}", &self.url, &subid.0, message);
+                    return Ok(());
+                }
+
+                // Our COUNT requests are not in the subscription map
+                if let Some(cr) = self.count_requests.remove(&subid.0) {
+                    tracing::info!("{}: Closed: {}: {}", &self.url, &subid.0, message);
+                    self.count_finished(cr)?;
+                    return Ok(());
+                }
+
                 let handle = self
                     .subscription_map
                     .get_handle_by_id(&subid.0)
@@ -246,6 +272,7 @@ impl Minion {
 
                 if handle != "_" {
                     tracing::info!("{}: Closed: {}: {}", &self.url, handle, message);
+                    relay_rejection::record_relay_rejection(&self.url, &message, false);
                 }
 
                 // Check the machine-readable prefix
@@ -267,11 +294,16 @@ impl Minion {
                             );
                         }
                         "rate-limited" => {
-                            // Wait to retry later
-                            self.subscriptions_rate_limited.push(handle);
+                            if let Some(delay) = self.rate_limit_delay() {
+                                // Wait to retry later
+                                self.subscriptions_rate_limited
+                                    .insert(handle, Unixtime::now() + delay);
 
-                            // return now, don't remove sub from map
-                            return Ok(());
+                                // return now, don't remove sub from map
+                                return Ok(());
+                            }
+                            tracing::warn!("{} keeps rate limiting our {} sub", &self.url, &handle);
+                            self.failed_subs.insert(handle.clone());
                         }
                         "invalid" => {
                             tracing::warn!(
@@ -307,7 +339,9 @@ impl Minion {
                                         // return now, don't remove sub from map
                                         return Ok(());
                                     }
-                                    AuthState::Waiting(_) | AuthState::FakeWaiting(_) => {
+                                    AuthState::Signing
+                                    | AuthState::Waiting(_)
+                                    | AuthState::FakeWaiting(_) => {
                                         // cork and retry once auth completes
                                         self.subscriptions_waiting_for_auth
                                             .insert(handle, Unixtime::now());
@@ -335,6 +369,14 @@ impl Minion {
                             );
                             self.failed_subs.insert(handle.clone());
                         }
+                        "blocked" => {
+                            tracing::warn!(
+                                "{} won't serve our {} sub (says blocked)",
+                                &self.url,
+                                &handle
+                            );
+                            self.failed_subs.insert(handle.clone());
+                        }
                         _ => {
                             tracing::debug!("{} closed with unknown prefix {}", &self.url, prefix);
                             // Presume any other kind of Closed is an failed subscription
@@ -347,11 +389,23 @@ impl Minion {
                 tracing::debug!("{}: removed subscription {}", &self.url, handle);
                 let _ = self.subscription_map.remove(&handle);
             }
-            RelayMessage::Count(_subid, _count_result) => {
-                tracing::info!(
-                    "{} Relay returned count results that we do not yet support",
-                    &self.url
-                );
+            RelayMessage::Count(subid, count_result) => {
+                if let Some(cr) = self.count_requests.remove(&subid.0) {
+                    tracing::debug!(
+                        "{}: counted {} for {:?}",
+                        &self.url,
+                        count_result.count,
+                        cr.target
+                    );
+                    crate::counts::record_relay_count(cr.target, count_result.count);
+                    self.count_finished(cr)?;
+                } else {
+                    tracing::debug!(
+                        "{}: count result for unknown request {}",
+                        &self.url,
+                        &subid.0
+                    );
+                }
             }
         }
 
@@ -0,0 +1,242 @@
+use super::Minion;
+use crate::error::Error;
+use futures_util::sink::SinkExt;
+use nostr_types::{Filter, RelayLimitation, RelayUrl};
+use tungstenite::protocol::Message as WsMessage;
+
+// A hex id or pubkey in a filter list: the quoted hex and a comma
+const HEX_ENTRY_LENGTH: usize = 67;
+
+// What a REQ adds around its filter: ["REQ","<subid>",<filter>]
+const REQ_OVERHEAD: usize = 32;
+
+// Subscriptions that a filter was split into beyond the first are named
+// after the first
+fn part_handle(handle: &str, n: usize) -> String {
+    format!("{}:part{}", handle, n)
+}
+
+pub(super) fn is_part_handle(handle: &str) -> bool {
+    handle.contains(":part")
+}
+
+impl Minion {
+    // Fit a filter to the relay's NIP-11 limitations. There is always at least
+    // one filter returned.
+    pub(super) fn filters_within_limits(&self, filter: Filter) -> Vec<Filter> {
+        match self.dbrelay.limitation() {
+            Some(limitation) => fit_filter(filter, limitation, &self.url),
+            None => vec![filter],
+        }
+    }
+
+    // Subscribe to the extra filters a filter was split into
+    pub(super) async fn subscribe_parts(
+        &mut self,
+        filters: Vec<Filter>,
+        handle: &str,
+        job_id: u64,
+    ) -> Result<(), Error> {
+        tracing::debug!(
+            "{}: split {} into {} subscriptions to fit the relay's limits",
+            &self.url,
+            handle,
+            filters.len() + 1
+        );
+        for (n, filter) in filters.into_iter().enumerate() {
+            self.subscribe_one(filter, &part_handle(handle, n + 1), job_id)
+                .await?;
+        }
+        Ok(())
+    }
+
+    // Close the extra subscriptions a filter was split into. These are not
+    // jobs of their own, so the overlord is not told.
+    pub(super) async fn close_subscription_parts(&mut self, handle: &str) -> Result<(), Error> {
+        let prefix = format!("{}:part", handle);
+        let parts: Vec<String> = self
+            .subscription_map
+            .get_all_handles_matching(&prefix)
+            .into_iter()
+            .filter(|h| h.starts_with(&prefix))
+            .collect();
+        for part in parts {
+            if let Some(subscription) = self.subscription_map.get(&part) {
+                let wire = serde_json::to_string(&subscription.close_message())?;
+                let websocket_stream = self.stream.as_mut().unwrap();
+                tracing::trace!("{}: Sending {}", &self.url, &wire);
+                self.last_message_sent = wire.clone();
+                self.stats.bytes_sent += wire.len() as u64;
+                websocket_stream.send(WsMessage::Text(wire)).await?;
+            }
+            let _ = self.subscription_map.remove(&part);
+        }
+        Ok(())
+    }
+
+    // Whether the relay's max_subscriptions would be exceeded by sending this
+    // subscription
+    pub(super) fn subscription_slots_full(&self, handle: &str) -> bool {
+        let max = match self.dbrelay.limitation().and_then(|l| l.max_subscriptions) {
+            Some(max) if max > 0 => max,
+            _ => return false,
+        };
+        let open = self.subscription_map.count_sent_except(handle)
+            + self.count_requests.len()
+            + self.negentropy_syncs.len();
+        open >= max
+    }
+}
+
+// Lower a filter's limit to the relay's max_limit, and split it into several
+// filters (by ids or by authors) if it would be longer than the relay's
+// max_message_length.
+fn fit_filter(mut filter: Filter, limitation: &RelayLimitation, url: &RelayUrl) -> Vec<Filter> {
+    if let (Some(max), Some(limit)) = (limitation.max_limit, filter.limit) {
+        if limit > max {
+            filter.limit = Some(max);
+        }
+    }
+
+    let max_length = match limitation.max_message_length {
+        Some(max) => max,
+        None => return vec![filter],
+    };
+    let length = match serde_json::to_string(&filter) {
+        Ok(s) => s.len() + REQ_OVERHEAD,
+        Err(_) => return vec![filter],
+    };
+    if length <= max_length {
+        return vec![filter];
+    }
+
+    // Split whichever of ids and authors is longer
+    let split_ids = filter.ids.len() >= filter.authors.len();
+    let count = if split_ids {
+        filter.ids.len()
+    } else {
+        filter.authors.len()
+    };
+    let rest = length.saturating_sub(count * HEX_ENTRY_LENGTH);
+    let per_part = max_length.saturating_sub(rest) / HEX_ENTRY_LENGTH;
+    if count < 2 || per_part == 0 {
+        tracing::warn!(
+            "{}: filter is too long for the relay and cannot be split",
+            url
+        );
+        return vec![filter];
+    }
+
+    if split_ids {
+        filter
+            .ids
+            .chunks(per_part)
+            .map(|ids| {
+                let mut part = filter.clone();
+                part.ids = ids.to_vec();
+                part
+            })
+            .collect()
+    } else {
+        filter
+            .authors
+            .chunks(per_part)
+            .map(|authors| {
+                let mut part = filter.clone();
+                part.authors = authors.to_vec();
+                part
+            })
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use nostr_types::PrivateKey;
+
+    fn url() -> RelayUrl {
+        RelayUrl::try_from_str("wss://relay.example.com").unwrap()
+    }
+
+    fn limitation(json: &str) -> RelayLimitation {
+        serde_json::from_str(json).unwrap()
+    }
+
+    fn req_length(filter: &Filter) -> usize {
+        serde_json::to_string(filter).unwrap().len() + REQ_OVERHEAD
+    }
+
+    #[test]
+    fn test_fit_filter_limit() {
+        let filter: Filter = serde_json::from_str(r#"{"kinds":[1],"limit":500}"#).unwrap();
+
+        let filters = fit_filter(filter.clone(), &limitation(r#"{"max_limit":100}"#), &url());
+        assert_eq!(filters.len(), 1);
+        assert_eq!(filters[0].limit, Some(100));
+
+        let filters = fit_filter(filter, &limitation(r#"{"max_limit":1000}"#), &url());
+        assert_eq!(filters.len(), 1);
+        assert_eq!(filters[0].limit, Some(500));
+    }
+
+    #[test]
+    fn test_fit_filter_split() {
+        let ids: Vec<String> = (0..50).map(|n| format!("\"{:064x}\"", n)).collect();
+        let filter: Filter =
+            serde_json::from_str(&format!(r#"{{"ids":[{}],"kinds":[1]}}"#, ids.join(","))).unwrap();
+        let max = 1000;
+        let limits = limitation(&format!(r#"{{"max_message_length":{}}}"#, max));
+
+        // 50 ids at 67 bytes each need at least 4 REQs of 1000 bytes
+        let filters = fit_filter(filter.clone(), &limits, &url());
+        assert!(filters.len() >= 4);
+        assert!(filters.iter().all(|f| req_length(f) <= max));
+        let parts: Vec<_> = filters.iter().flat_map(|f| f.ids.clone()).collect();
+        assert_eq!(parts, filter.ids);
+
+        // It fits if the relay takes longer messages
+        let filters = fit_filter(
+            filter,
+            &limitation(r#"{"max_message_length":100000}"#),
+            &url(),
+        );
+        assert_eq!(filters.len(), 1);
+        assert_eq!(filters[0].ids.len(), 50);
+    }
+
+    #[test]
+    fn test_fit_filter_split_authors() {
+        let authors: Vec<String> = (0..30)
+            .map(|_| {
+                format!(
+                    "\"{}\"",
+                    PrivateKey::generate().public_key().as_hex_string()
+                )
+            })
+            .collect();
+        let filter: Filter = serde_json::from_str(&format!(
+            r#"{{"ids":["{:064x}"],"authors":[{}]}}"#,
+            1,
+            authors.join(",")
+        ))
+        .unwrap();
+        let max = 800;
+        let limits = limitation(&format!(r#"{{"max_message_length":{}}}"#, max));
+
+        let filters = fit_filter(filter.clone(), &limits, &url());
+        assert!(filters.len() >= 3);
+        assert!(filters.iter().all(|f| req_length(f) <= max));
+        assert!(filters.iter().all(|f| f.ids == filter.ids));
+        let parts: Vec<_> = filters.iter().flat_map(|f| f.authors.clone()).collect();
+        assert_eq!(parts, filter.authors);
+    }
+
+    #[test]
+    fn test_fit_filter_cannot_split() {
+        let filter: Filter = serde_json::from_str(&format!(r#"{{"ids":["{:064x}"]}}"#, 1)).unwrap();
+        let filters = fit_filter(filter, &limitation(r#"{"max_message_length":50}"#), &url());
+        assert_eq!(filters.len(), 1);
+        assert_eq!(filters[0].ids.len(), 1);
+    }
+}
@@ -1,12 +1,18 @@
 mod handle_websocket;
+mod limits;
+mod negentropy_sync;
+mod retry;
 mod subscription;
 mod subscription_map;
 
 use crate::comms::{ToMinionMessage, ToMinionPayload, ToMinionPayloadDetail, ToOverlordMessage};
+use crate::counts::{CountTarget, NIP_COUNT};
 use crate::error::{Error, ErrorKind};
 use crate::filter_set::FilterSet;
 use crate::globals::GLOBALS;
 use crate::relay::Relay;
+use crate::relay_rejection;
+use crate::relay_stats::RelayStats;
 use crate::{RunState, USER_AGENT};
 use base64::Engine;
 use encoding_rs::{Encoding, UTF_8};
@@ -15,9 +21,10 @@ use futures_util::stream::{FusedStream, StreamExt};
 use http::uri::{Parts, Scheme};
 use http::Uri;
 use mime::Mime;
+use negentropy_sync::NegentropySync;
 use nostr_types::{
-    ClientMessage, EventKind, Filter, Id, KeySigner, NAddr, PreEvent, PublicKey,
-    RelayInformationDocument, RelayUrl, Signer, Tag, Unixtime,
+    ClientMessage, Event, EventKind, Filter, Id, KeySigner, NAddr, PreEvent, PublicKey,
+    RelayInformationDocument, RelayUrl, Signer, SubscriptionId, Tag, Unixtime,
 };
 use reqwest::Response;
 use std::borrow::Cow;
@@ -35,6 +42,7 @@ use watcher::Receiver as WatchReceiver;
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum AuthState {
     None,
+    Signing,     // our remote signer is signing our AUTH
     Waiting(Id), // we sent AUTH, have not got response back yet
     Authenticated,
     FakeWaiting(Id), // we sent fake AUTH, have not got response back yet
@@ -44,7 +52,9 @@ pub enum AuthState {
 
 impl AuthState {
     pub fn is_waiting(&self) -> bool {
-        matches!(self, AuthState::Waiting(_)) || matches!(self, AuthState::FakeWaiting(_))
+        matches!(self, AuthState::Signing)
+            || matches!(self, AuthState::Waiting(_))
+            || matches!(self, AuthState::FakeWaiting(_))
     }
 
     pub fn is_authenticated(&self) -> bool {
@@ -60,6 +70,12 @@ pub struct EventSeekState {
     pub asked: bool,
 }
 
+pub struct CountRequest {
+    pub job_id: u64,
+    pub target: CountTarget,
+    pub asked_at: Unixtime,
+}
+
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum MinionExitReason {
     GotDisconnected,
@@ -92,13 +108,20 @@ pub struct Minion {
     next_events_subscription_id: u32,
     posting_jobs: HashMap<u64, Vec<Id>>,
     posting_ids: HashMap<Id, u64>,
+    posting_events: HashMap<Id, Event>,
+    posts_waiting_for_auth: Vec<Id>,
+    posts_rate_limited: HashMap<Id, Unixtime>,
+    rate_limit_strikes: u32,
     sought_events: HashMap<Id, EventSeekState>,
     sought_naddrs: HashMap<NAddr, EventSeekState>,
+    count_requests: HashMap<String, CountRequest>,
+    negentropy_syncs: HashMap<String, NegentropySync>,
     last_message_sent: String,
     auth_challenge: String,
     subscriptions_waiting_for_auth: HashMap<String, Unixtime>,
     subscriptions_waiting_for_metadata: Vec<(u64, Vec<PublicKey>)>,
-    subscriptions_rate_limited: Vec<String>,
+    subscriptions_rate_limited: HashMap<String, Unixtime>,
+    subscriptions_waiting_for_slot: Vec<String>,
     read_runstate: WatchReceiver<RunState>,
     exiting: Option<MinionExitReason>,
     auth_state: AuthState,
@@ -107,6 +130,8 @@ pub struct Minion {
     loading_more: usize,
     subscriptions_empty_asof: Option<Unixtime>,
     fake_auth_signer: KeySigner,
+    stats: RelayStats,
+    stats_saved_at: Unixtime,
 }
 
 impl Drop for Minion {
@@ -114,6 +139,10 @@ impl Drop for Minion {
         let _ = GLOBALS
             .loading_more
             .fetch_sub(self.loading_more, Ordering::SeqCst);
+
+        if let Err(e) = self.save_stats() {
+            tracing::warn!("{}: could not save relay statistics: {}", &self.url, e);
+        }
     }
 }
 
@@ -139,13 +168,20 @@ impl Minion {
             next_events_subscription_id: 0,
             posting_jobs: HashMap::new(),
             posting_ids: HashMap::new(),
+            posting_events: HashMap::new(),
+            posts_waiting_for_auth: Vec::new(),
+            posts_rate_limited: HashMap::new(),
+            rate_limit_strikes: 0,
             sought_events: HashMap::new(),
             sought_naddrs: HashMap::new(),
+            count_requests: HashMap::new(),
+            negentropy_syncs: HashMap::new(),
             last_message_sent: String::new(),
             auth_challenge: "".to_string(),
             subscriptions_waiting_for_auth: HashMap::new(),
             subscriptions_waiting_for_metadata: Vec::new(),
-            subscriptions_rate_limited: Vec::new(),
+            subscriptions_rate_limited: HashMap::new(),
+            subscriptions_waiting_for_slot: Vec::new(),
             read_runstate,
             exiting: None,
             auth_state: AuthState::None,
@@ -154,6 +190,8 @@ impl Minion {
             loading_more: 0,
             subscriptions_empty_asof: None,
             fake_auth_signer: KeySigner::generate("", 1)?,
+            stats: RelayStats::new(RelayStats::today()),
+            stats_saved_at: Unixtime::now(),
         })
     }
 }
@@ -267,7 +305,7 @@ impl Minion {
 
             let connect_future = tokio::time::timeout(
                 std::time::Duration::new(connect_timeout_secs, 0),
-                tokio_tungstenite::connect_async_with_config(req, Some(config), false),
+                crate::proxy::connect_websocket(req, Some(config)),
             );
 
             let websocket_stream;
@@ -377,7 +415,7 @@ impl Minion {
         };
         let uri = http::Uri::from_parts(parts)?;
 
-        let request_nip11_future = reqwest::Client::builder()
+        let request_nip11_future = crate::proxy::http_client_builder()?
             .timeout(fetcher_timeout)
             .redirect(reqwest::redirect::Policy::none())
             .gzip(true)
@@ -425,6 +463,9 @@ impl Minion {
         // Save updated NIP-11 data (even if it failed)
         GLOBALS.db().write_relay(&self.dbrelay, None)?;
 
+        // Group metadata is checked against the pubkey in it
+        crate::groups::forget_relay_group_metadata(&self.url);
+
         Ok(())
     }
 
@@ -453,6 +494,17 @@ impl Minion {
 
                 // Try to subscribe to subscriptions waiting for something
                 self.try_subscribe_waiting().await?;
+
+                // Give up on counts the relay never answered
+                self.expire_count_requests()?;
+
+                // Give up on negentropy syncs the relay never answered
+                self.expire_negentropy_syncs().await?;
+
+                // Save statistics every minute or so
+                if Unixtime::now() - self.stats_saved_at > Duration::from_secs(60) {
+                    self.save_stats()?;
+                }
             },
             to_minion_message = self.from_overlord.recv() => {
                 let to_minion_message = match to_minion_message {
@@ -481,6 +533,7 @@ impl Minion {
                 }?;
 
                 GLOBALS.bytes_read.fetch_add(ws_message.len(), Ordering::Relaxed);
+                self.stats.bytes_received += ws_message.len() as u64;
 
                 tracing::trace!("{}: Handling message", &self.url);
                 match ws_message {
@@ -507,6 +560,8 @@ impl Minion {
             && self.subscriptions_waiting_for_auth.is_empty()
             && self.subscriptions_waiting_for_metadata.is_empty()
             && self.posting_jobs.is_empty()
+            && self.count_requests.is_empty()
+            && self.negentropy_syncs.is_empty()
         {
             let now = Unixtime::now();
             if let Some(when) = self.subscriptions_empty_asof {
@@ -537,18 +592,22 @@ impl Minion {
 
                 let id = event.id;
                 self.posting_ids.insert(id, message.job_id);
+                self.posting_events.insert(id, (*event).clone());
                 let msg = ClientMessage::Event(event);
                 let wire = serde_json::to_string(&msg)?;
                 let ws_stream = self.stream.as_mut().unwrap();
                 self.last_message_sent = wire.clone();
+                self.stats.bytes_sent += wire.len() as u64;
                 ws_stream.send(WsMessage::Text(wire)).await?;
 
                 let id = dmevent.id;
                 self.posting_ids.insert(id, message.job_id);
+                self.posting_events.insert(id, (*dmevent).clone());
                 let msg = ClientMessage::Event(dmevent);
                 let wire = serde_json::to_string(&msg)?;
                 let ws_stream = self.stream.as_mut().unwrap();
                 self.last_message_sent = wire.clone();
+                self.stats.bytes_sent += wire.len() as u64;
                 ws_stream.send(WsMessage::Text(wire)).await?;
 
                 tracing::info!("Advertised relay lists to {}", &self.url)
@@ -565,6 +624,14 @@ impl Minion {
                     );
                 }
             }
+            ToMinionPayloadDetail::AuthSigned(signed) => {
+                if self.auth_state == AuthState::Signing {
+                    match signed {
+                        Some(event) => self.send_auth(*event).await?,
+                        None => self.auth_state = AuthState::Failed,
+                    }
+                }
+            }
             ToMinionPayloadDetail::AuthDeclined => {
                 self.dbrelay.allow_auth = Some(false); // save in our memory copy of the relay
                 self.maybe_authenticate().await?;
@@ -577,6 +644,38 @@ impl Minion {
                     );
                 }
             }
+            ToMinionPayloadDetail::Count(targets) => {
+                if self.dbrelay.supports_nip(NIP_COUNT) && !targets.is_empty() {
+                    // Each COUNT yields a single number, so each target needs its own.
+                    // The job completes when the last of them is answered.
+                    for target in targets {
+                        let handle = format!("count_{}", self.next_events_subscription_id);
+                        self.next_events_subscription_id += 1;
+                        let msg =
+                            ClientMessage::Count(SubscriptionId(handle.clone()), target.filter());
+                        let wire = serde_json::to_string(&msg)?;
+                        let ws_stream = self.stream.as_mut().unwrap();
+                        tracing::trace!("{}: Sending {}", &self.url, &wire);
+                        self.last_message_sent = wire.clone();
+                        self.stats.bytes_sent += wire.len() as u64;
+                        ws_stream.send(WsMessage::Text(wire)).await?;
+                        self.count_requests.insert(
+                            handle,
+                            CountRequest {
+                                job_id: message.job_id,
+                                target,
+                                asked_at: Unixtime::now(),
+                            },
+                        );
+                    }
+                } else {
+                    // The relay cannot count, so callers fall back on what we have locally
+                    self.to_overlord.send(ToOverlordMessage::MinionJobComplete(
+                        self.url.clone(),
+                        message.job_id,
+                    ))?;
+                }
+            }
             ToMinionPayloadDetail::FetchEvent(id) => {
                 // We don't ask the relay immediately. See task_timer.
                 self.sought_events
@@ -598,18 +697,60 @@ impl Minion {
                     });
             }
             ToMinionPayloadDetail::PostEvents(mut events) => {
+                // Skip events the relay's limitations say it would reject
+                let mut posts: Vec<(Id, String)> = Vec::new();
+                for event in events.drain(..) {
+                    let id = event.id;
+
+                    // Mine more proof of work for our events if the relay wants it
+                    if let Some(min) = self.dbrelay.limitation().and_then(|l| l.min_pow_difficulty)
+                    {
+                        if self.ask_for_pow(&event, min as u32)? {
+                            continue;
+                        }
+                    }
+
+                    let wire =
+                        serde_json::to_string(&ClientMessage::Event(Box::new(event.clone())))?;
+                    match self.dbrelay.write_rejection(&event, wire.len()) {
+                        Some(reason) => {
+                            tracing::warn!(
+                                "{}: Not posting {}: {}",
+                                &self.url,
+                                id.as_hex_string(),
+                                reason
+                            );
+                            relay_rejection::record_skipped_write(&self.url, &reason);
+                            GLOBALS
+                                .status_queue
+                                .write()
+                                .write(format!("Not posting to {}: {}", &self.url, reason));
+                        }
+                        None => {
+                            self.posting_events.insert(id, event);
+                            posts.push((id, wire));
+                        }
+                    }
+                }
+
+                if posts.is_empty() {
+                    self.to_overlord.send(ToOverlordMessage::MinionJobComplete(
+                        self.url.clone(),
+                        message.job_id,
+                    ))?;
+                    return Ok(());
+                }
+
                 self.posting_jobs.insert(
                     message.job_id,
-                    events.iter().map(|e| e.id).collect::<Vec<Id>>(),
+                    posts.iter().map(|(id, _)| *id).collect::<Vec<Id>>(),
                 );
 
-                for event in events.drain(..) {
-                    let id = event.id;
+                for (id, wire) in posts.drain(..) {
                     self.posting_ids.insert(id, message.job_id);
-                    let msg = ClientMessage::Event(Box::new(event));
-                    let wire = serde_json::to_string(&msg)?;
                     let ws_stream = self.stream.as_mut().unwrap();
                     self.last_message_sent = wire.clone();
+                    self.stats.bytes_sent += wire.len() as u64;
                     ws_stream.send(WsMessage::Text(wire)).await?;
                     tracing::info!("Posted event to {}", &self.url);
                 }
@@ -631,7 +772,12 @@ impl Minion {
                 if !self.subscription_map.has(&handle) || filter_set.can_have_duplicates() {
                     let spamsafe = self.dbrelay.has_usage_bits(Relay::SPAMSAFE);
                     if let Some(filter) = filter_set.filter(spamsafe) {
-                        self.subscribe(filter, &handle, message.job_id).await?;
+                        if !self
+                            .start_negentropy_sync(&filter_set, &filter, &handle, message.job_id)
+                            .await?
+                        {
+                            self.subscribe(filter, &handle, message.job_id).await?;
+                        }
                     }
                 } else {
                     // It does not allow duplicates and we are already running it,
@@ -661,6 +807,35 @@ impl Minion {
         Ok(())
     }
 
+    fn expire_count_requests(&mut self) -> Result<(), Error> {
+        let now = Unixtime::now();
+        let expired: Vec<String> = self
+            .count_requests
+            .iter()
+            .filter(|(_, cr)| now - cr.asked_at > Duration::from_secs(30))
+            .map(|(handle, _)| handle.clone())
+            .collect();
+        for handle in expired {
+            if let Some(cr) = self.count_requests.remove(&handle) {
+                tracing::debug!("{}: gave up waiting for {}", &self.url, handle);
+                self.count_finished(cr)?;
+            }
+        }
+        Ok(())
+    }
+
+    // A COUNT request is over. Its job completes once no other COUNT of the job remains.
+    fn count_finished(&mut self, cr: CountRequest) -> Result<(), Error> {
+        if self.count_requests.values().any(|c| c.job_id == cr.job_id) {
+            return Ok(());
+        }
+        self.to_overlord.send(ToOverlordMessage::MinionJobComplete(
+            self.url.clone(),
+            cr.job_id,
+        ))?;
+        Ok(())
+    }
+
     async fn get_events(&mut self) -> Result<(), Error> {
         // Collect all the sought events we have not yet asked for, and
         // presumptively mark them as having been asked for.
@@ -783,9 +958,26 @@ impl Minion {
             }
         }
 
-        // Retry rate-limited subscriptions
+        // Send subscriptions that were waiting for the relay's subscription limit
+        if !self.subscriptions_waiting_for_slot.is_empty() {
+            let mut handles = std::mem::take(&mut self.subscriptions_waiting_for_slot);
+            for handle in handles.drain(..) {
+                self.send_subscription(&handle).await?;
+            }
+        }
+
+        // Retry rate-limited subscriptions once they have waited long enough
         if !self.subscriptions_rate_limited.is_empty() {
-            let mut handles = std::mem::take(&mut self.subscriptions_rate_limited);
+            let now = Unixtime::now();
+            let mut handles: Vec<String> = Vec::new();
+            self.subscriptions_rate_limited.retain(|handle, when| {
+                if *when <= now {
+                    handles.push(handle.to_owned());
+                    false
+                } else {
+                    true
+                }
+            });
             for handle in handles.drain(..) {
                 tracing::info!(
                     "Sending previously rate-limited subscription {} to {}",
@@ -796,10 +988,31 @@ impl Minion {
             }
         }
 
+        // Post events that were waiting too
+        self.repost_waiting().await?;
+
         Ok(())
     }
 
     async fn subscribe(&mut self, filter: Filter, handle: &str, job_id: u64) -> Result<(), Error> {
+        // Any extra subscriptions from splitting a previous filter are replaced
+        self.close_subscription_parts(handle).await?;
+
+        let mut filters = self.filters_within_limits(filter);
+        let filter = filters.remove(0);
+        if !filters.is_empty() {
+            self.subscribe_parts(filters, handle, job_id).await?;
+        }
+
+        self.subscribe_one(filter, handle, job_id).await
+    }
+
+    async fn subscribe_one(
+        &mut self,
+        filter: Filter,
+        handle: &str,
+        job_id: u64,
+    ) -> Result<(), Error> {
         // Reset timing of empty subscription period
         self.subscriptions_empty_asof = None;
 
@@ -861,14 +1074,36 @@ impl Minion {
     }
 
     async fn send_subscription(&mut self, handle: &str) -> Result<(), Error> {
-        let req_message = match self.subscription_map.get(handle) {
-            Some(sub) => sub.req_message(),
+        // Wait if the relay would not take another subscription
+        if self.subscription_map.has(handle) && self.subscription_slots_full(handle) {
+            if !self
+                .subscriptions_waiting_for_slot
+                .iter()
+                .any(|h| h == handle)
+            {
+                tracing::debug!(
+                    "{}: {} waits for the relay's subscription limit",
+                    &self.url,
+                    handle
+                );
+                self.subscriptions_waiting_for_slot.push(handle.to_owned());
+            }
+            return Ok(());
+        }
+
+        let req_message = match self.subscription_map.get_mut(handle) {
+            Some(sub) => {
+                sub.set_sent();
+                sub.req_message()
+            }
             None => return Ok(()), // Not much we can do. It is not there.
         };
+        self.stats.subscriptions += 1;
         let wire = serde_json::to_string(&req_message)?;
         let websocket_stream = self.stream.as_mut().unwrap();
         tracing::trace!("{}: Sending {}", &self.url, &wire);
         self.last_message_sent = wire.clone();
+        self.stats.bytes_sent += wire.len() as u64;
         websocket_stream.send(WsMessage::Text(wire.clone())).await?;
         Ok(())
     }
@@ -877,16 +1112,25 @@ impl Minion {
         if !self.subscription_map.has(handle) {
             return Ok(());
         }
+        self.cancel_negentropy_sync(handle).await?;
+        // Extra subscriptions from splitting a filter are not jobs of their own
+        let part = limits::is_part_handle(handle);
         // If it was a chunk, update loading_more
-        if handle.contains("_feed_chunk") {
+        if handle.contains("_feed_chunk") && !part {
             self.loading_more -= 1;
             let _ = GLOBALS.loading_more.fetch_sub(1, Ordering::SeqCst);
         }
+        // Persistent subscriptions take their extra parts with them. Temporary
+        // ones leave theirs to close on their own EOSE.
+        if !part && !handle.starts_with("temp_") {
+            self.close_subscription_parts(handle).await?;
+        }
         let subscription = self.subscription_map.get(handle).unwrap();
         let wire = serde_json::to_string(&subscription.close_message())?;
         let websocket_stream = self.stream.as_mut().unwrap();
         tracing::trace!("{}: Sending {}", &self.url, &wire);
         self.last_message_sent = wire.clone();
+        self.stats.bytes_sent += wire.len() as u64;
         websocket_stream.send(WsMessage::Text(wire.clone())).await?;
         let id = self.subscription_map.remove(handle);
         if let Some(id) = id {
@@ -903,10 +1147,12 @@ impl Minion {
                 handle
             );
         }
-        self.to_overlord.send(ToOverlordMessage::MinionJobComplete(
-            self.url.clone(),
-            subscription.get_job_id(),
-        ))?;
+        if !part {
+            self.to_overlord.send(ToOverlordMessage::MinionJobComplete(
+                self.url.clone(),
+                subscription.get_job_id(),
+            ))?;
+        }
         Ok(())
     }
 
@@ -960,11 +1206,42 @@ impl Minion {
             ],
             content: "".to_string(),
         };
-        let event = GLOBALS.identity.sign_event(pre_event)?;
+
+        // A remote signer answers through a minion, maybe this one, so we must not
+        // wait for it here. The signed AUTH comes back to us as a job.
+        if GLOBALS.identity.is_remote() {
+            self.auth_state = AuthState::Signing;
+            let url = self.url.clone();
+            std::mem::drop(tokio::spawn(async move {
+                let signed = match GLOBALS.identity.sign_event(pre_event).await {
+                    Ok(event) => Some(Box::new(event)),
+                    Err(e) => {
+                        tracing::warn!("{}: remote signer did not sign AUTH: {}", &url, e);
+                        None
+                    }
+                };
+                let _ = GLOBALS.to_minions.send(ToMinionMessage {
+                    target: url.as_str().to_owned(),
+                    payload: ToMinionPayload {
+                        job_id: 0,
+                        detail: ToMinionPayloadDetail::AuthSigned(signed),
+                    },
+                });
+            }));
+            return Ok(());
+        }
+
+        let event = GLOBALS.identity.sign_event(pre_event).await?;
+        self.send_auth(event).await
+    }
+
+    // Send our signed AUTH event
+    async fn send_auth(&mut self, event: Event) -> Result<(), Error> {
         let id = event.id;
         let msg = ClientMessage::Auth(Box::new(event));
         let wire = serde_json::to_string(&msg)?;
         self.last_message_sent = wire.clone();
+        self.stats.bytes_sent += wire.len() as u64;
         let ws_stream = self.stream.as_mut().unwrap();
         ws_stream.send(WsMessage::Text(wire)).await?;
 
@@ -997,6 +1274,7 @@ impl Minion {
         let msg = ClientMessage::Auth(Box::new(event));
         let wire = serde_json::to_string(&msg)?;
         self.last_message_sent = wire.clone();
+        self.stats.bytes_sent += wire.len() as u64;
         let ws_stream = self.stream.as_mut().unwrap();
         ws_stream.send(WsMessage::Text(wire)).await?;
 
@@ -1058,4 +1336,16 @@ impl Minion {
             tracing::error!("{}: ERROR bumping relay success count: {}", &self.url, e);
         }
     }
+
+    // Add the statistics gathered since last time to the daily rollup
+    fn save_stats(&mut self) -> Result<(), Error> {
+        self.stats_saved_at = Unixtime::now();
+        if self.stats.is_empty() {
+            return Ok(());
+        }
+        self.stats.day = RelayStats::today();
+        GLOBALS.db().add_relay_stats(&self.url, &self.stats, None)?;
+        self.stats = RelayStats::new(self.stats.day);
+        Ok(())
+    }
 }
@@ -0,0 +1,286 @@
+use super::Minion;
+use crate::comms::ToOverlordMessage;
+use crate::error::{Error, ErrorKind};
+use crate::filter_set::FilterSet;
+use crate::globals::GLOBALS;
+use crate::negentropy::{Negentropy, NIP_NEGENTROPY};
+use futures_util::sink::SinkExt;
+use nostr_types::{Filter, Id, Unixtime};
+use std::sync::atomic::Ordering;
+use std::time::Duration;
+use tungstenite::protocol::Message as WsMessage;
+
+// How many missing events we ask for in one subscription
+const IDS_PER_FETCH: usize = 500;
+
+/// A negentropy (NIP-77) sync in progress, standing in for a subscription
+pub struct NegentropySync {
+    // The subscription we run instead if the sync does not work out
+    handle: String,
+    job_id: u64,
+    filter: Filter,
+
+    negentropy: Negentropy,
+    need: Vec<Id>,
+    waiting_since: Unixtime,
+}
+
+impl Minion {
+    // Catch up on the general feed by negentropy instead of by a REQ, if the
+    // relay supports it and we have synced with it before. Then only the
+    // events we are missing since we were last in sync are fetched.
+    //
+    // Returns false if a REQ should be sent instead.
+    pub(super) async fn start_negentropy_sync(
+        &mut self,
+        filter_set: &FilterSet,
+        filter: &Filter,
+        handle: &str,
+        job_id: u64,
+    ) -> Result<bool, Error> {
+        let anchor = match filter_set {
+            FilterSet::GeneralFeedChunk { anchor, .. } => *anchor,
+            _ => return Ok(false),
+        };
+
+        if !self.dbrelay.supports_nip(NIP_NEGENTROPY) || self.auth_state.is_waiting() {
+            return Ok(false);
+        }
+
+        // The handle stays registered while a sync is in flight. Do not start a
+        // second one, nor sync a subscription that is already running.
+        if self.subscription_map.has(handle) {
+            return Ok(self
+                .negentropy_syncs
+                .values()
+                .any(|sync| sync.handle == handle));
+        }
+
+        let since = match self.dbrelay.last_general_eose_at {
+            Some(at) if (at as i64) < anchor.0 => Unixtime(at as i64),
+            _ => return Ok(false),
+        };
+
+        let mut neg_filter = filter.clone();
+        neg_filter.since = Some(since);
+        neg_filter.until = Some(anchor);
+        neg_filter.limit = None;
+
+        let items =
+            GLOBALS
+                .db()
+                .negentropy_items(&neg_filter.authors, &neg_filter.kinds, since, anchor)?;
+        let mut negentropy = Negentropy::new(items);
+        let initial = negentropy.initiate();
+
+        let subid = format!("neg_{}", self.next_events_subscription_id);
+        self.next_events_subscription_id += 1;
+
+        let wire = serde_json::to_string(&serde_json::json!([
+            "NEG-OPEN",
+            subid,
+            neg_filter,
+            hex::encode(initial)
+        ]))?;
+        self.send_negentropy_wire(wire).await?;
+
+        tracing::debug!(
+            "{}: negentropy sync {} for {} since {}",
+            &self.url,
+            subid,
+            handle,
+            since.0
+        );
+
+        // Stand in for the subscription until the sync is done. This is never sent.
+        let _ = self.subscription_map.add(handle, job_id, filter.clone());
+
+        self.negentropy_syncs.insert(
+            subid,
+            NegentropySync {
+                handle: handle.to_owned(),
+                job_id,
+                filter: filter.clone(),
+                negentropy,
+                need: Vec::new(),
+                waiting_since: Unixtime::now(),
+            },
+        );
+
+        Ok(true)
+    }
+
+    // NEG-MSG and NEG-ERR from the relay
+    pub(super) async fn handle_negentropy_message(
+        &mut self,
+        ws_message: &str,
+    ) -> Result<(), Error> {
+        let parts: Vec<String> = serde_json::from_str(ws_message)?;
+        if parts.len() < 3 {
+            tracing::warn!("{}: malformed negentropy message", &self.url);
+            return Ok(());
+        }
+
+        let mut sync = match self.negentropy_syncs.remove(&parts[1]) {
+            Some(sync) => sync,
+            None => {
+                tracing::debug!("{}: {} for unknown sync {}", &self.url, parts[0], parts[1]);
+                return Ok(());
+            }
+        };
+
+        match &*parts[0] {
+            "NEG-MSG" => {
+                let query = hex::decode(&parts[2]).map_err(|e| {
+                    Error::from(ErrorKind::General(format!("negentropy message: {}", e)))
+                });
+                let mut have: Vec<Id> = Vec::new();
+                match query.and_then(|q| sync.negentropy.reconcile(&q, &mut have, &mut sync.need)) {
+                    Ok(Some(next)) => {
+                        let wire = serde_json::to_string(&serde_json::json!([
+                            "NEG-MSG",
+                            parts[1],
+                            hex::encode(next)
+                        ]))?;
+                        self.send_negentropy_wire(wire).await?;
+                        sync.waiting_since = Unixtime::now();
+                        self.negentropy_syncs.insert(parts[1].clone(), sync);
+                    }
+                    Ok(None) => {
+                        self.close_negentropy_sync(&parts[1]).await?;
+                        self.finish_negentropy_sync(sync).await?;
+                    }
+                    Err(e) => {
+                        tracing::warn!("{}: negentropy sync failed: {}", &self.url, e);
+                        self.close_negentropy_sync(&parts[1]).await?;
+                        self.fall_back_to_req(sync).await?;
+                    }
+                }
+            }
+            "NEG-ERR" => {
+                tracing::info!("{}: negentropy sync refused: {}", &self.url, parts[2]);
+                self.fall_back_to_req(sync).await?;
+            }
+            _ => {
+                self.negentropy_syncs.insert(parts[1].clone(), sync);
+            }
+        }
+
+        Ok(())
+    }
+
+    // The relay closed a sync (some relays answer NEG-OPEN with CLOSED).
+    // Returns false if this was not a sync.
+    pub(super) async fn negentropy_sync_closed(&mut self, subid: &str) -> Result<bool, Error> {
+        match self.negentropy_syncs.remove(subid) {
+            Some(sync) => {
+                self.fall_back_to_req(sync).await?;
+                Ok(true)
+            }
+            None => Ok(false),
+        }
+    }
+
+    // Fall back to a REQ for syncs the relay never answered
+    pub(super) async fn expire_negentropy_syncs(&mut self) -> Result<(), Error> {
+        let now = Unixtime::now();
+        let expired: Vec<String> = self
+            .negentropy_syncs
+            .iter()
+            .filter(|(_, sync)| now - sync.waiting_since > Duration::from_secs(30))
+            .map(|(subid, _)| subid.clone())
+            .collect();
+        for subid in expired {
+            if let Some(sync) = self.negentropy_syncs.remove(&subid) {
+                tracing::debug!("{}: gave up waiting for negentropy {}", &self.url, subid);
+                self.close_negentropy_sync(&subid).await?;
+                self.fall_back_to_req(sync).await?;
+            }
+        }
+        Ok(())
+    }
+
+    // Stop any sync standing in for this subscription, as it is being
+    // unsubscribed
+    pub(super) async fn cancel_negentropy_sync(&mut self, handle: &str) -> Result<(), Error> {
+        let subids: Vec<String> = self
+            .negentropy_syncs
+            .iter()
+            .filter(|(_, sync)| sync.handle == handle)
+            .map(|(subid, _)| subid.clone())
+            .collect();
+        for subid in subids {
+            let _ = self.negentropy_syncs.remove(&subid);
+            self.close_negentropy_sync(&subid).await?;
+        }
+        Ok(())
+    }
+
+    // Send the subscription the sync stood in for as a REQ after all
+    async fn fall_back_to_req(&mut self, sync: NegentropySync) -> Result<(), Error> {
+        let _ = self.subscription_map.remove(&sync.handle);
+        self.subscribe(sync.filter, &sync.handle, sync.job_id).await
+    }
+
+    // Fetch what we are missing, and count the sync as the finished subscription
+    async fn finish_negentropy_sync(&mut self, sync: NegentropySync) -> Result<(), Error> {
+        // The sync covers everything since we were last in sync, without the chunk's
+        // limit. If more than that is missing, the REQ fetches just the newest of it.
+        if let Some(limit) = sync.filter.limit {
+            if sync.need.len() > limit {
+                tracing::info!(
+                    "{}: negentropy sync found {} events we are missing, over the limit of {}",
+                    &self.url,
+                    sync.need.len(),
+                    limit
+                );
+                return self.fall_back_to_req(sync).await;
+            }
+        }
+
+        let _ = self.subscription_map.remove(&sync.handle);
+
+        tracing::info!(
+            "{}: negentropy sync found {} events we are missing",
+            &self.url,
+            sync.need.len()
+        );
+
+        for ids in sync.need.chunks(IDS_PER_FETCH) {
+            let mut filter = Filter::new();
+            filter.ids = ids.to_vec();
+
+            // The subscription job_id wont be used.
+            let handle = format!("temp_events_{}", self.next_events_subscription_id);
+            self.next_events_subscription_id += 1;
+            self.subscribe(filter, &handle, u64::MAX).await?;
+        }
+
+        // This stood in for a feed chunk
+        if self.loading_more > 0 {
+            self.loading_more -= 1;
+            let _ = GLOBALS.loading_more.fetch_sub(1, Ordering::SeqCst);
+        }
+
+        self.to_overlord.send(ToOverlordMessage::MinionJobComplete(
+            self.url.clone(),
+            sync.job_id,
+        ))?;
+
+        Ok(())
+    }
+
+    async fn close_negentropy_sync(&mut self, subid: &str) -> Result<(), Error> {
+        let wire = serde_json::to_string(&serde_json::json!(["NEG-CLOSE", subid]))?;
+        self.send_negentropy_wire(wire).await
+    }
+
+    async fn send_negentropy_wire(&mut self, wire: String) -> Result<(), Error> {
+        let ws_stream = self.stream.as_mut().unwrap();
+        tracing::trace!("{}: Sending {}", &self.url, &wire);
+        self.last_message_sent = wire.clone();
+        self.stats.bytes_sent += wire.len() as u64;
+        ws_stream.send(WsMessage::Text(wire)).await?;
+        Ok(())
+    }
+}
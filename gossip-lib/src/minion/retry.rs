@@ -0,0 +1,270 @@
+use super::{AuthState, Minion};
+use crate::comms::ToOverlordMessage;
+use crate::error::Error;
+use crate::globals::GLOBALS;
+use crate::misc::pow_difficulty;
+use crate::relay_rejection::{self, RelayRejection};
+use futures_util::sink::SinkExt;
+use nostr_types::{ClientMessage, Event, EventKind, Id, Unixtime};
+use std::time::Duration;
+use tungstenite::protocol::Message as WsMessage;
+
+// How long to wait the first time a relay rate limits us. This doubles each
+// time it does so again, until we give up.
+const RATE_LIMIT_BASE_SECS: u64 = 5;
+const MAX_RATE_LIMIT_STRIKES: u32 = 6;
+
+// What becomes of an event the relay refused
+pub(super) enum PostRetry {
+    // It will be sent again later
+    Later,
+
+    // The overlord will mine it again with more proof of work and post that
+    Remined,
+
+    // Nothing more can be done
+    Failed,
+}
+
+impl Minion {
+    // How long to wait after the relay rate limited us, with some jitter so that
+    // everything waiting does not come back at once. None once the relay has
+    // rate limited us too many times in a row.
+    pub(super) fn rate_limit_delay(&mut self) -> Option<Duration> {
+        if self.rate_limit_strikes >= MAX_RATE_LIMIT_STRIKES {
+            return None;
+        }
+        let millis = (RATE_LIMIT_BASE_SECS << self.rate_limit_strikes) * 1000;
+        self.rate_limit_strikes += 1;
+        let jitter = rand::random::<u64>() % (millis / 2);
+        Some(Duration::from_millis(millis + jitter))
+    }
+
+    // React to the relay refusing an event we posted, going by the
+    // machine-readable prefix of its OK message
+    pub(super) async fn retry_post(&mut self, id: Id, message: &str) -> Result<PostRetry, Error> {
+        relay_rejection::record_relay_rejection(&self.url, message, true);
+
+        match RelayRejection::prefix_of(message) {
+            "auth-required" => {
+                if self.dbrelay.allow_auth == Some(false) {
+                    return Ok(PostRetry::Failed);
+                }
+                match self.auth_state {
+                    AuthState::None => self.maybe_authenticate().await?,
+                    AuthState::Signing | AuthState::Waiting(_) => {}
+                    // Fake AUTH does not let us post, and real AUTH did not help
+                    _ => return Ok(PostRetry::Failed),
+                }
+                // Post again once AUTH completes
+                self.posts_waiting_for_auth.push(id);
+                Ok(PostRetry::Later)
+            }
+            "rate-limited" => match self.rate_limit_delay() {
+                Some(delay) => {
+                    tracing::info!(
+                        "{}: rate limited, posting {} again in {}s",
+                        &self.url,
+                        id.as_hex_string(),
+                        delay.as_secs()
+                    );
+                    self.posts_rate_limited.insert(id, Unixtime::now() + delay);
+                    Ok(PostRetry::Later)
+                }
+                None => Ok(PostRetry::Failed),
+            },
+            "pow" => {
+                let event = match self.posting_events.get(&id) {
+                    Some(event) => event.clone(),
+                    None => return Ok(PostRetry::Failed),
+                };
+
+                // Relays usually say how much they want (e.g. "pow: difficulty 8 is
+                // less than 20"), otherwise go by their NIP-11
+                let said = pow_wanted(message);
+                let advertised = self
+                    .dbrelay
+                    .limitation()
+                    .and_then(|l| l.min_pow_difficulty)
+                    .map(|d| d as u32);
+
+                match said.max(advertised) {
+                    Some(difficulty) if self.ask_for_pow(&event, difficulty)? => {
+                        Ok(PostRetry::Remined)
+                    }
+                    _ => Ok(PostRetry::Failed),
+                }
+            }
+            "blocked" | "restricted" => {
+                relay_rejection::set_write_restricted(&self.url);
+                GLOBALS.status_queue.write().write(format!(
+                    "{} refused our event ({}). Not posting there again until restarted.",
+                    &self.url, message
+                ));
+                Ok(PostRetry::Failed)
+            }
+            _ => Ok(PostRetry::Failed),
+        }
+    }
+
+    // Have the overlord mine a copy of one of our events with `difficulty` bits
+    // of proof of work and post it here. Returns false if that would not help.
+    pub(super) fn ask_for_pow(&self, event: &Event, difficulty: u32) -> Result<bool, Error> {
+        if GLOBALS.identity.public_key() != Some(event.pubkey) {
+            return Ok(false);
+        }
+
+        // If it already has that much, more will not satisfy the relay
+        if pow_difficulty(event.id) >= difficulty {
+            return Ok(false);
+        }
+
+        // The copy gets a new id, so anything referring to the event by id would
+        // not find it there, and a replaceable event would be replaced on this
+        // relay only
+        if event.kind.is_replaceable()
+            || event.kind.is_parameterized_replaceable()
+            || event.kind == EventKind::Reaction
+        {
+            return Ok(false);
+        }
+
+        // Do no more work than the user is willing to do for their own posts
+        let setting = GLOBALS.db().read_setting_pow() as u32;
+        if difficulty > setting {
+            GLOBALS.status_queue.write().write(format!(
+                "{} wants proof of work of {}, more than your setting of {}. Not posting there.",
+                &self.url, difficulty, setting
+            ));
+            return Ok(false);
+        }
+
+        GLOBALS.status_queue.write().write(format!(
+            "{} wants proof of work of {}. Computing it to post a copy there, which will have \
+             a different id and will not be on your other relays.",
+            &self.url, difficulty
+        ));
+        self.to_overlord.send(ToOverlordMessage::PostWithPow(
+            self.url.clone(),
+            Box::new(event.clone()),
+            difficulty as u8,
+        ))?;
+        Ok(true)
+    }
+
+    // Send an event we posted earlier again
+    async fn repost(&mut self, id: Id) -> Result<(), Error> {
+        let event = match self.posting_events.get(&id) {
+            Some(event) => event.clone(),
+            None => return Ok(()),
+        };
+        let wire = serde_json::to_string(&ClientMessage::Event(Box::new(event)))?;
+        let ws_stream = self.stream.as_mut().unwrap();
+        self.last_message_sent = wire.clone();
+        self.stats.bytes_sent += wire.len() as u64;
+        ws_stream.send(WsMessage::Text(wire)).await?;
+        tracing::info!("Posted event to {} again", &self.url);
+        Ok(())
+    }
+
+    // Post events that were waiting on AUTH or on rate limiting.
+    // This is run every tick.
+    pub(super) async fn repost_waiting(&mut self) -> Result<(), Error> {
+        if !self.posts_waiting_for_auth.is_empty() {
+            match self.auth_state {
+                AuthState::Authenticated => {
+                    for id in std::mem::take(&mut self.posts_waiting_for_auth) {
+                        self.repost(id).await?;
+                    }
+                }
+                AuthState::FakeAuthenticated | AuthState::Failed => {
+                    for id in std::mem::take(&mut self.posts_waiting_for_auth) {
+                        self.post_finished(id)?;
+                    }
+                }
+                _ => {}
+            }
+        }
+
+        let now = Unixtime::now();
+        let due: Vec<Id> = self
+            .posts_rate_limited
+            .iter()
+            .filter(|(_, when)| **when <= now)
+            .map(|(id, _)| *id)
+            .collect();
+        for id in due {
+            self.posts_rate_limited.remove(&id);
+            self.repost(id).await?;
+        }
+
+        Ok(())
+    }
+
+    // We are done with an event we posted, whether or not the relay took it
+    pub(super) fn post_finished(&mut self, id: Id) -> Result<(), Error> {
+        self.posting_events.remove(&id);
+
+        let job_id = match self.posting_ids.remove(&id) {
+            Some(job_id) => job_id,
+            None => return Ok(()),
+        };
+
+        let mut job_is_done: bool = false;
+        {
+            // Take it out of the posting_jobs
+            if let Some(job_ids) = self.posting_jobs.get_mut(&job_id) {
+                job_ids.retain(|id_in_vec| *id_in_vec != id);
+                job_is_done = job_ids.is_empty();
+            } // else is not expected, but if it happens we ignore it.
+        }
+
+        if job_is_done {
+            self.posting_jobs.remove(&job_id);
+
+            // Tell overlord
+            self.to_overlord.send(ToOverlordMessage::MinionJobComplete(
+                self.url.clone(),
+                job_id,
+            ))?;
+        }
+
+        Ok(())
+    }
+}
+
+// How much proof of work a relay said it wants in a "pow:" message. Relays
+// word these differently ("difficulty 8 is less than 20", "difficulty 8<20"),
+// so take the largest number after the prefix.
+fn pow_wanted(message: &str) -> Option<u32> {
+    let (prefix, rest) = message.split_once(':')?;
+    if prefix.trim() != "pow" {
+        return None;
+    }
+    rest.split(|c: char| c.is_whitespace() || "<>=,;:()".contains(c))
+        .map(|word| word.trim_end_matches('.'))
+        .filter_map(|word| word.parse::<u32>().ok())
+        .filter(|difficulty| *difficulty <= 256)
+        .max()
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_pow_wanted() {
+        assert_eq!(pow_wanted("pow: difficulty 8 is less than 20"), Some(20));
+        assert_eq!(pow_wanted("pow: difficulty 8<20"), Some(20));
+        assert_eq!(pow_wanted("pow: minimum difficulty is 24."), Some(24));
+        assert_eq!(pow_wanted("pow: not enough work"), None);
+
+        // Numbers before the prefix, or inside ids, do not count
+        assert_eq!(pow_wanted("rate-limited: 100 per minute"), None);
+        assert_eq!(
+            pow_wanted("pow: 00ab12 has difficulty 8, wanted 16"),
+            Some(16)
+        );
+        assert_eq!(pow_wanted("pow: difficulty 4294967295"), None);
+    }
+}
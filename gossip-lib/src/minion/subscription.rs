@@ -1,6 +1,7 @@
 use crate::globals::GLOBALS;
 use nostr_types::{ClientMessage, Filter, SubscriptionId};
 use std::sync::atomic::Ordering;
+use std::time::Instant;
 
 #[derive(Debug)]
 pub struct Subscription {
@@ -9,6 +10,7 @@ pub struct Subscription {
     filter: Filter,
     eose: bool,
     clone: bool,
+    sent_at: Option<Instant>,
 }
 
 impl Subscription {
@@ -20,6 +22,7 @@ impl Subscription {
             filter,
             eose: false,
             clone: false,
+            sent_at: None,
         }
     }
 
@@ -56,6 +59,14 @@ impl Subscription {
         self.eose
     }
 
+    pub fn set_sent(&mut self) {
+        self.sent_at = Some(Instant::now());
+    }
+
+    pub fn sent_at(&self) -> Option<Instant> {
+        self.sent_at
+    }
+
     pub fn req_message(&self) -> ClientMessage {
         ClientMessage::Req(SubscriptionId(self.get_id()), self.filter.clone())
     }
@@ -73,6 +84,7 @@ impl Clone for Subscription {
             filter: self.filter.clone(),
             eose: self.eose,
             clone: true,
+            sent_at: self.sent_at,
         }
     }
 }
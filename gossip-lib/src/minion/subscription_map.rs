@@ -93,6 +93,15 @@ impl SubscriptionMap {
         self.by_id.is_empty()
     }
 
+    // How many subscriptions have been sent to the relay, other than this one
+    pub fn count_sent_except(&self, handle: &str) -> usize {
+        let except = self.handle_to_id.get(handle);
+        self.by_id
+            .iter()
+            .filter(|(id, sub)| Some(*id) != except && sub.sent_at().is_some())
+            .count()
+    }
+
     /*
         pub fn remove_by_id(&mut self, id: &str) {
             self.by_id.remove(id);
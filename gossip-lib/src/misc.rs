@@ -1,9 +1,25 @@
 use crate::error::Error;
 use crate::globals::GLOBALS;
-use nostr_types::{Event, EventReference, Id, PayRequestData, PublicKey, UncheckedUrl};
+use nostr_types::{
+    Event, EventReference, Id, MilliSatoshi, PayRequestData, PublicKey, UncheckedUrl, Unixtime,
+};
 use std::ops::Deref;
 
+/// The report types of NIP-56
+pub const REPORT_TYPES: [&str; 7] = [
+    "nudity",
+    "malware",
+    "profanity",
+    "illegal",
+    "spam",
+    "impersonation",
+    "other",
+];
+
 /// The state that a Zap is in (it moves through 5 states before it is complete)
+///
+/// If there is a [PaymentHandler](crate::payment::PaymentHandler), it goes on from
+/// `ReadyToPay` to `Paying`, and then to `Paid` and `Receipted` or to `Failed`.
 #[derive(Debug, Clone)]
 pub enum ZapState {
     None,
@@ -11,6 +27,27 @@ pub enum ZapState {
     SeekingAmount(Id, PublicKey, PayRequestData, UncheckedUrl),
     LoadingInvoice(Id, PublicKey),
     ReadyToPay(Id, String), // String is the Zap Invoice as a string, to be shown as a QR code
+    Paying(Id, String, MilliSatoshi), // The payment handler is paying the invoice
+    Paid(Id, MilliSatoshi), // Waiting for the zap receipt
+    Receipted(Id, Id, MilliSatoshi), // Second Id is the zap receipt event
+    Failed(Id, String, String), // The invoice, and why the payment handler failed
+}
+
+impl ZapState {
+    /// The note being zapped, if any
+    pub fn zapped_id(&self) -> Option<Id> {
+        match *self {
+            ZapState::None => None,
+            ZapState::CheckingLnurl(id, _, _) => Some(id),
+            ZapState::SeekingAmount(id, _, _, _) => Some(id),
+            ZapState::LoadingInvoice(id, _) => Some(id),
+            ZapState::ReadyToPay(id, _) => Some(id),
+            ZapState::Paying(id, _, _) => Some(id),
+            ZapState::Paid(id, _) => Some(id),
+            ZapState::Receipted(id, _, _) => Some(id),
+            ZapState::Failed(id, _, _) => Some(id),
+        }
+    }
 }
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
@@ -106,3 +143,102 @@ pub fn exponential_decay(base: f32, halflife_seconds: u64, elapsed_seconds: u64)
     let decay_constant = 2.0_f32.ln() / halflife_seconds as f32;
     base * E.powf(-decay_constant * elapsed_seconds as f32)
 }
+
+/// The NIP-40 expiration time of an event, if it has one
+pub fn event_expiration(event: &Event) -> Option<Unixtime> {
+    event
+        .tags
+        .iter()
+        .find(|t| t.tagname() == "expiration")
+        .and_then(|t| t.value().trim().parse::<i64>().ok())
+        .map(Unixtime)
+}
+
+/// Whether an event has expired as of `now` (NIP-40)
+pub fn event_is_expired(event: &Event, now: Unixtime) -> bool {
+    event_expiration(event).is_some_and(|expiration| expiration <= now)
+}
+
+/// The proof of work of an event id: its number of leading zero bits (NIP-13)
+pub(crate) fn pow_difficulty(id: Id) -> u32 {
+    let mut bits = 0;
+    for byte in id.as_slice() {
+        if *byte == 0 {
+            bits += 8;
+        } else {
+            bits += byte.leading_zeros();
+            break;
+        }
+    }
+    bits
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use nostr_types::{EventKind, Identity, PreEvent, Tag};
+
+    fn event_with_tags(tags: Vec<Tag>) -> Event {
+        let identity = Identity::generate("", 1).unwrap();
+        let pre_event = PreEvent {
+            pubkey: identity.public_key().unwrap(),
+            created_at: Unixtime::now(),
+            kind: EventKind::TextNote,
+            tags,
+            content: "".to_owned(),
+        };
+        identity.sign_event(pre_event).unwrap()
+    }
+
+    #[test]
+    fn test_event_expiration() {
+        let now = Unixtime::now();
+
+        let missing = event_with_tags(vec![]);
+        assert_eq!(event_expiration(&missing), None);
+        assert!(!event_is_expired(&missing, now));
+
+        let malformed = event_with_tags(vec![Tag::new(&["expiration", "tomorrow"])]);
+        assert_eq!(event_expiration(&malformed), None);
+        assert!(!event_is_expired(&malformed, now));
+
+        let empty = event_with_tags(vec![Tag::new(&["expiration"])]);
+        assert_eq!(event_expiration(&empty), None);
+
+        let padded = event_with_tags(vec![Tag::new(&["expiration", " 1700000000 "])]);
+        assert_eq!(event_expiration(&padded), Some(Unixtime(1700000000)));
+
+        let past = (now.0 - 60).to_string();
+        let past = event_with_tags(vec![Tag::new(&["expiration", &past])]);
+        assert_eq!(event_expiration(&past), Some(Unixtime(now.0 - 60)));
+        assert!(event_is_expired(&past, now));
+
+        let future = (now.0 + 60).to_string();
+        let future = event_with_tags(vec![Tag::new(&["expiration", &future])]);
+        assert!(!event_is_expired(&future, now));
+        assert!(event_is_expired(&future, Unixtime(now.0 + 60)));
+    }
+
+    #[test]
+    fn test_pow_difficulty() {
+        assert_eq!(pow_difficulty(Id([0xff; 32])), 0);
+        assert_eq!(pow_difficulty(Id([0; 32])), 256);
+
+        let mut bytes = [0xff; 32];
+        bytes[0] = 0x00;
+        bytes[1] = 0x0f;
+        assert_eq!(pow_difficulty(Id(bytes)), 12);
+
+        // Only leading zeroes count
+        bytes[0] = 0x01;
+        bytes[1] = 0x00;
+        assert_eq!(pow_difficulty(Id(bytes)), 7);
+
+        // The example from NIP-13
+        let id = Id::try_from_hex_string(
+            "000000000e9d97a1ab09fc381030b346cdd7a142ad57e6df0b46dc9bef6c7e2d",
+        )
+        .unwrap();
+        assert_eq!(pow_difficulty(id), 36);
+    }
+}
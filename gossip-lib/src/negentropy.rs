@@ -0,0 +1,493 @@
+use crate::error::{Error, ErrorKind};
+use nostr_types::{Id, Unixtime};
+use sha2::{Digest, Sha256};
+use std::collections::HashSet;
+
+/// The NIP that defines the NEG-OPEN, NEG-MSG and NEG-CLOSE verbs
+pub const NIP_NEGENTROPY: u32 = 77;
+
+// Negentropy protocol version 1
+const PROTOCOL_VERSION: u8 = 0x61;
+
+const ID_SIZE: usize = 32;
+const FINGERPRINT_SIZE: usize = 16;
+
+// How many ranges a range is split into when fingerprints differ
+const BUCKETS: usize = 16;
+
+const MODE_SKIP: u64 = 0;
+const MODE_FINGERPRINT: u64 = 1;
+const MODE_ID_LIST: u64 = 2;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+struct Item {
+    timestamp: u64,
+    id: [u8; ID_SIZE],
+}
+
+// Ranges are delimited by bounds. The id of a bound is a prefix, zero-padded
+// for comparison with items.
+#[derive(Debug, Clone, Copy)]
+struct Bound {
+    timestamp: u64,
+    id: [u8; ID_SIZE],
+    id_len: usize,
+}
+
+impl Bound {
+    fn new(timestamp: u64) -> Bound {
+        Bound {
+            timestamp,
+            id: [0; ID_SIZE],
+            id_len: 0,
+        }
+    }
+
+    fn is_above(&self, item: &Item) -> bool {
+        (item.timestamp, item.id) < (self.timestamp, self.id)
+    }
+}
+
+/// One side of a negentropy (NIP-77) set reconciliation over events we have,
+/// as (created_at, id). We only ever start the reconciliation; the relay
+/// answers.
+#[derive(Debug)]
+pub struct Negentropy {
+    items: Vec<Item>,
+    is_initiator: bool,
+    last_timestamp_in: u64,
+    last_timestamp_out: u64,
+}
+
+impl Negentropy {
+    pub fn new(items: Vec<(Unixtime, Id)>) -> Negentropy {
+        let mut items: Vec<Item> = items
+            .into_iter()
+            .map(|(created_at, id)| Item {
+                timestamp: created_at.0.max(0) as u64,
+                id: id.0,
+            })
+            .collect();
+        items.sort();
+        items.dedup();
+
+        Negentropy {
+            items,
+            is_initiator: false,
+            last_timestamp_in: 0,
+            last_timestamp_out: 0,
+        }
+    }
+
+    /// The first message, to send with NEG-OPEN
+    pub fn initiate(&mut self) -> Vec<u8> {
+        self.is_initiator = true;
+        self.last_timestamp_out = 0;
+
+        let mut output = vec![PROTOCOL_VERSION];
+        self.split_range(&mut output, 0, self.items.len(), Bound::new(u64::MAX));
+        output
+    }
+
+    /// Handle a message from the other side. Ids that only we have are added
+    /// to `have`, and ids that only they have are added to `need`. Returns the
+    /// next message to send, or None if the reconciliation is complete.
+    pub fn reconcile(
+        &mut self,
+        query: &[u8],
+        have: &mut Vec<Id>,
+        need: &mut Vec<Id>,
+    ) -> Result<Option<Vec<u8>>, Error> {
+        self.last_timestamp_in = 0;
+        self.last_timestamp_out = 0;
+
+        let mut reader = Reader { buf: query };
+
+        let version = reader.byte()?;
+        if version != PROTOCOL_VERSION {
+            return Err(ErrorKind::General(format!(
+                "Unsupported negentropy protocol version {:#x}",
+                version
+            ))
+            .into());
+        }
+
+        let mut output = vec![PROTOCOL_VERSION];
+        let mut prev_bound = Bound::new(0);
+        let mut prev_index: usize = 0;
+        let mut skip = false;
+
+        while !reader.is_empty() {
+            let curr_bound = self.decode_bound(&mut reader)?;
+            let mode = reader.varint()?;
+
+            let lower = prev_index;
+            let upper = prev_index
+                + self.items[prev_index..].partition_point(|item| curr_bound.is_above(item));
+
+            match mode {
+                MODE_SKIP => skip = true,
+                MODE_FINGERPRINT => {
+                    let theirs = reader.bytes(FINGERPRINT_SIZE)?;
+                    if theirs != fingerprint(&self.items[lower..upper]) {
+                        if skip {
+                            skip = false;
+                            self.encode_bound(&mut output, &prev_bound);
+                            encode_varint(&mut output, MODE_SKIP);
+                        }
+                        self.split_range(&mut output, lower, upper, curr_bound);
+                    } else {
+                        skip = true;
+                    }
+                }
+                MODE_ID_LIST => {
+                    let count = reader.varint()?;
+                    let mut theirs: HashSet<[u8; ID_SIZE]> = HashSet::new();
+                    for _ in 0..count {
+                        theirs.insert(reader.bytes(ID_SIZE)?.try_into().unwrap());
+                    }
+
+                    if self.is_initiator {
+                        for item in &self.items[lower..upper] {
+                            if !theirs.remove(&item.id) {
+                                have.push(Id(item.id));
+                            }
+                        }
+                        need.extend(theirs.into_iter().map(Id));
+                        skip = true;
+                    } else {
+                        // Answer with all of ours in the range
+                        if skip {
+                            skip = false;
+                            self.encode_bound(&mut output, &prev_bound);
+                            encode_varint(&mut output, MODE_SKIP);
+                        }
+                        self.encode_bound(&mut output, &curr_bound);
+                        encode_varint(&mut output, MODE_ID_LIST);
+                        encode_varint(&mut output, (upper - lower) as u64);
+                        for item in &self.items[lower..upper] {
+                            output.extend(item.id);
+                        }
+                    }
+                }
+                _ => {
+                    return Err(
+                        ErrorKind::General(format!("Unexpected negentropy mode {}", mode)).into(),
+                    );
+                }
+            }
+
+            prev_index = upper;
+            prev_bound = curr_bound;
+        }
+
+        if self.is_initiator && output.len() == 1 {
+            Ok(None)
+        } else {
+            Ok(Some(output))
+        }
+    }
+
+    // Describe the items in [lower, upper) as a list of ids if there are few,
+    // else as fingerprints of BUCKETS sub-ranges
+    fn split_range(&mut self, output: &mut Vec<u8>, lower: usize, upper: usize, bound: Bound) {
+        let count = upper - lower;
+
+        if count < BUCKETS * 2 {
+            self.encode_bound(output, &bound);
+            encode_varint(output, MODE_ID_LIST);
+            encode_varint(output, count as u64);
+            for item in &self.items[lower..upper] {
+                output.extend(item.id);
+            }
+            return;
+        }
+
+        let per_bucket = count / BUCKETS;
+        let with_extra = count % BUCKETS;
+        let mut curr = lower;
+        for i in 0..BUCKETS {
+            let size = per_bucket + usize::from(i < with_extra);
+            let fp = fingerprint(&self.items[curr..curr + size]);
+            curr += size;
+
+            let next_bound = if curr == upper {
+                bound
+            } else {
+                minimal_bound(&self.items[curr - 1], &self.items[curr])
+            };
+
+            self.encode_bound(output, &next_bound);
+            encode_varint(output, MODE_FINGERPRINT);
+            output.extend(fp);
+        }
+    }
+
+    // Timestamps are sent as the difference from the previous one in the
+    // message, plus one. Zero means infinity.
+    fn encode_bound(&mut self, output: &mut Vec<u8>, bound: &Bound) {
+        if bound.timestamp == u64::MAX {
+            self.last_timestamp_out = u64::MAX;
+            encode_varint(output, 0);
+        } else {
+            let delta = bound.timestamp - self.last_timestamp_out;
+            self.last_timestamp_out = bound.timestamp;
+            encode_varint(output, delta + 1);
+        }
+        encode_varint(output, bound.id_len as u64);
+        output.extend(&bound.id[..bound.id_len]);
+    }
+
+    fn decode_bound(&mut self, reader: &mut Reader<'_>) -> Result<Bound, Error> {
+        let encoded = reader.varint()?;
+        let timestamp = if encoded == 0 || self.last_timestamp_in == u64::MAX {
+            u64::MAX
+        } else {
+            self.last_timestamp_in.saturating_add(encoded - 1)
+        };
+        self.last_timestamp_in = timestamp;
+
+        let id_len = reader.varint()? as usize;
+        if id_len > ID_SIZE {
+            return Err(ErrorKind::General("Negentropy bound id is too long".to_owned()).into());
+        }
+        let mut bound = Bound::new(timestamp);
+        bound.id[..id_len].copy_from_slice(reader.bytes(id_len)?);
+        bound.id_len = id_len;
+        Ok(bound)
+    }
+}
+
+// The smallest bound that separates two adjacent items
+fn minimal_bound(prev: &Item, curr: &Item) -> Bound {
+    if curr.timestamp != prev.timestamp {
+        Bound::new(curr.timestamp)
+    } else {
+        let shared = prev
+            .id
+            .iter()
+            .zip(curr.id.iter())
+            .take_while(|(a, b)| a == b)
+            .count();
+        let mut bound = Bound::new(curr.timestamp);
+        bound.id_len = (shared + 1).min(ID_SIZE);
+        bound.id[..bound.id_len].copy_from_slice(&curr.id[..bound.id_len]);
+        bound
+    }
+}
+
+// The ids summed as little-endian 256-bit numbers, then hashed with the count
+fn fingerprint(items: &[Item]) -> [u8; FINGERPRINT_SIZE] {
+    let mut sum = [0_u8; ID_SIZE];
+    for item in items {
+        let mut carry: u16 = 0;
+        for (s, b) in sum.iter_mut().zip(item.id.iter()) {
+            let total = *s as u16 + *b as u16 + carry;
+            *s = total as u8;
+            carry = total >> 8;
+        }
+    }
+
+    let mut count = Vec::new();
+    encode_varint(&mut count, items.len() as u64);
+
+    let mut hasher = Sha256::new();
+    hasher.update(sum);
+    hasher.update(count);
+    let hash = hasher.finalize();
+
+    let mut output = [0_u8; FINGERPRINT_SIZE];
+    output.copy_from_slice(&hash[..FINGERPRINT_SIZE]);
+    output
+}
+
+// Base-128, most significant group first, with the high bit set on all but
+// the last byte
+fn encode_varint(output: &mut Vec<u8>, mut n: u64) {
+    let mut groups: Vec<u8> = vec![(n & 0x7f) as u8];
+    n >>= 7;
+    while n > 0 {
+        groups.push((n & 0x7f) as u8 | 0x80);
+        n >>= 7;
+    }
+    output.extend(groups.iter().rev());
+}
+
+struct Reader<'a> {
+    buf: &'a [u8],
+}
+
+impl<'a> Reader<'a> {
+    fn is_empty(&self) -> bool {
+        self.buf.is_empty()
+    }
+
+    fn byte(&mut self) -> Result<u8, Error> {
+        Ok(self.bytes(1)?[0])
+    }
+
+    fn bytes(&mut self, n: usize) -> Result<&'a [u8], Error> {
+        if self.buf.len() < n {
+            return Err(ErrorKind::General("Negentropy message ended early".to_owned()).into());
+        }
+        let (bytes, rest) = self.buf.split_at(n);
+        self.buf = rest;
+        Ok(bytes)
+    }
+
+    fn varint(&mut self) -> Result<u64, Error> {
+        let mut n: u64 = 0;
+        for _ in 0..10 {
+            let byte = self.byte()?;
+            n = (n << 7) | (byte & 0x7f) as u64;
+            if byte & 0x80 == 0 {
+                return Ok(n);
+            }
+        }
+        Err(ErrorKind::General("Negentropy varint is too long".to_owned()).into())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn item(n: u32) -> (Unixtime, Id) {
+        let hash = Sha256::digest(n.to_be_bytes());
+        // Several items share each timestamp
+        (Unixtime(1_700_000_000 + (n / 3) as i64), Id(hash.into()))
+    }
+
+    // Run a full reconciliation, returning (have, need) sorted
+    fn sync(ours: Vec<(Unixtime, Id)>, theirs: Vec<(Unixtime, Id)>) -> (Vec<Id>, Vec<Id>) {
+        let mut client = Negentropy::new(ours);
+        let mut relay = Negentropy::new(theirs);
+
+        let mut have: Vec<Id> = Vec::new();
+        let mut need: Vec<Id> = Vec::new();
+        let mut msg = client.initiate();
+        for _ in 0..20 {
+            let answer = relay
+                .reconcile(&msg, &mut Vec::new(), &mut Vec::new())
+                .unwrap()
+                .unwrap();
+            match client.reconcile(&answer, &mut have, &mut need).unwrap() {
+                Some(next) => msg = next,
+                None => {
+                    have.sort_by_key(|id| id.0);
+                    need.sort_by_key(|id| id.0);
+                    return (have, need);
+                }
+            }
+        }
+        panic!("Negentropy did not finish");
+    }
+
+    #[test]
+    fn test_varint() {
+        for n in [0, 1, 127, 128, 255, 16384, u32::MAX as u64, u64::MAX] {
+            let mut buf = Vec::new();
+            encode_varint(&mut buf, n);
+            let mut reader = Reader { buf: &buf };
+            assert_eq!(reader.varint().unwrap(), n);
+            assert!(reader.is_empty());
+        }
+        let mut buf = Vec::new();
+        encode_varint(&mut buf, 300);
+        assert_eq!(buf, vec![0x82, 0x2c]);
+    }
+
+    #[test]
+    fn test_negentropy_sync() {
+        // Small sets are exchanged as id lists
+        let ours: Vec<_> = (0..10).map(item).collect();
+        let theirs: Vec<_> = (5..20).map(item).collect();
+        let (have, need) = sync(ours, theirs);
+        let mut expected_have: Vec<Id> = (0..5).map(|n| item(n).1).collect();
+        let mut expected_need: Vec<Id> = (10..20).map(|n| item(n).1).collect();
+        expected_have.sort_by_key(|id| id.0);
+        expected_need.sort_by_key(|id| id.0);
+        assert_eq!(have, expected_have);
+        assert_eq!(need, expected_need);
+
+        // Large sets are narrowed down by fingerprints
+        let ours: Vec<_> = (0..5000).filter(|n| n % 97 != 0).map(item).collect();
+        let theirs: Vec<_> = (0..5000).filter(|n| n % 89 != 0).map(item).collect();
+        let (have, need) = sync(ours, theirs);
+        let mut expected_have: Vec<Id> = (0..5000)
+            .filter(|n| n % 89 == 0 && n % 97 != 0)
+            .map(|n| item(n).1)
+            .collect();
+        let mut expected_need: Vec<Id> = (0..5000)
+            .filter(|n| n % 97 == 0 && n % 89 != 0)
+            .map(|n| item(n).1)
+            .collect();
+        expected_have.sort_by_key(|id| id.0);
+        expected_need.sort_by_key(|id| id.0);
+        assert_eq!(have, expected_have);
+        assert_eq!(need, expected_need);
+
+        // Nothing to do when the sets match
+        let all: Vec<_> = (0..1000).map(item).collect();
+        let (have, need) = sync(all.clone(), all);
+        assert!(have.is_empty());
+        assert!(need.is_empty());
+    }
+
+    // Vectors worked out by hand from the NIP-77 message format
+    #[test]
+    fn test_negentropy_vectors() {
+        // With nothing, we ask for everything they have
+        let mut client = Negentropy::new(Vec::new());
+        assert_eq!(hex::encode(client.initiate()), "6100000200");
+
+        // A few items go as a list of ids, up to an infinite bound
+        let items: Vec<(Unixtime, Id)> = (0..2)
+            .map(|n| (Unixtime(1000 + n), Id([n as u8 + 1; 32])))
+            .collect();
+        let mut client = Negentropy::new(items);
+        assert_eq!(
+            hex::encode(client.initiate()),
+            format!("6100000202{}{}", "01".repeat(32), "02".repeat(32))
+        );
+
+        // The relay answers with its ids in that range: we have 02.., they have 03..
+        let answer =
+            hex::decode(format!("6100000202{}{}", "01".repeat(32), "03".repeat(32))).unwrap();
+        let mut have: Vec<Id> = Vec::new();
+        let mut need: Vec<Id> = Vec::new();
+        assert_eq!(
+            client.reconcile(&answer, &mut have, &mut need).unwrap(),
+            None
+        );
+        assert_eq!(have, vec![Id([2; 32])]);
+        assert_eq!(need, vec![Id([3; 32])]);
+
+        // 32 items are split into 16 ranges of 2, each sent as a fingerprint.
+        // Timestamps differ, so the bounds are timestamps only: 1002 (sent as
+        // 1003), then +2 each (sent as 3), then infinity.
+        let items: Vec<(Unixtime, Id)> = (0..32)
+            .map(|n| (Unixtime(1000 + n), Id([n as u8; 32])))
+            .collect();
+        let mut client = Negentropy::new(items.clone());
+        let msg = client.initiate();
+        assert_eq!(
+            hex::encode(&msg),
+            "61876b00012badbd7659924f488790ab577a29bcbe0300011ba91635a8ddc0e32a0a796ee0389def\
+             03000107aa15a5c153b036e64b5d1a0b68a741030001c3b220cd2682a0d35c0d55f0690eb14a\
+             030001de7cc218a459caaf756d498e46da3749030001d1cf570526e4c1870196a8d9e539e22d\
+             030001f8dbdbec6eee46577c119184fa3622dd030001228ff9be580af0340ea2a3f9955863f7\
+             030001eb56fc10dc92e87eb8dd8693fa52503f030001aaaa6ba6f4c7fa312492e18f360799f9\
+             030001c989e48aaeb895ae19ea03e4c384b27d030001a2952b0bc61a90aea947bdd3d3b2511d\
+             030001ea0b199ccb75b2bf4ad341fb9fc51123030001a1f81d851b2aeb0e73172e413a6ab574\
+             030001b3e3858268b6e16cf19cad87464bb7950000011fcfbdb995ca766a5be2bbd528e9ee3d"
+        );
+
+        // A relay with the same items finds every fingerprint matches
+        let mut relay = Negentropy::new(items);
+        let answer = relay
+            .reconcile(&msg, &mut Vec::new(), &mut Vec::new())
+            .unwrap();
+        assert_eq!(answer, Some(vec![PROTOCOL_VERSION]));
+    }
+}
@@ -0,0 +1,690 @@
+use crate::comms::{RelayConnectionReason, RelayJob, ToMinionPayload, ToMinionPayloadDetail};
+use crate::error::{Error, ErrorKind};
+use crate::filter_set::FilterSet;
+use crate::globals::GLOBALS;
+use crate::manager;
+use dashmap::{DashMap, DashSet};
+use nostr_types::{
+    ContentEncryptionAlgorithm, EncryptedPrivateKey, Event, EventKind, Id, Identity, ParsedTag,
+    PreEvent, PrivateKey, PublicKey, RelayUrl, Rumor, Tag, Unixtime,
+};
+use serde::Deserialize;
+use sha2::Digest;
+use speedy::{Readable, Writable};
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::sync::oneshot;
+
+/// How long we wait for the remote signer to answer a request
+const REQUEST_TIMEOUT: Duration = Duration::from_secs(60);
+
+/// How many remote decryptions we remember
+const MAX_DECRYPTED: usize = 1000;
+
+/// A `bunker://` connection token, as handed out by a remote signer
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct BunkerToken {
+    pub remote_pubkey: PublicKey,
+    pub relays: Vec<RelayUrl>,
+    pub secret: Option<String>,
+}
+
+impl BunkerToken {
+    pub fn parse(token: &str) -> Result<BunkerToken, Error> {
+        let url = url::Url::parse(token.trim())?;
+        if url.scheme() != "bunker" {
+            return Err(ErrorKind::BadNostrConnectString.into());
+        }
+
+        let remote_pubkey = match url.host_str() {
+            Some(host) => PublicKey::try_from_hex_string(host, true)?,
+            None => return Err(ErrorKind::BadNostrConnectString.into()),
+        };
+
+        let mut relays: Vec<RelayUrl> = Vec::new();
+        let mut secret: Option<String> = None;
+        for (key, value) in url.query_pairs() {
+            match &*key {
+                "relay" => relays.push(RelayUrl::try_from_str(&value)?),
+                "secret" => secret = Some(value.into_owned()),
+                _ => (),
+            }
+        }
+
+        if relays.is_empty() {
+            return Err(ErrorKind::BadNostrConnectString.into());
+        }
+
+        Ok(BunkerToken {
+            remote_pubkey,
+            relays,
+            secret,
+        })
+    }
+}
+
+/// A remote signer we are logged in with, saved so we can reconnect at startup
+#[derive(Debug, Clone, Readable, Writable)]
+pub struct Nip46Bunker {
+    /// The remote signer's pubkey, which we talk to
+    pub remote_pubkey: PublicKey,
+
+    /// The pubkey the remote signer signs as (our identity)
+    pub user_pubkey: PublicKey,
+
+    /// The relays the remote signer listens on
+    pub relays: Vec<RelayUrl>,
+
+    /// Our client key, under an empty passphrase. This is a credential: the remote
+    /// signer honours whatever requests are signed with it, so anyone who reads it can
+    /// have the user's events signed until the connection is revoked at the signer.
+    pub client_key: String,
+}
+
+/// A request sent to the remote signer that has not been answered yet
+#[derive(Debug)]
+pub struct Nip46PendingRequest {
+    pub method: String,
+    pub sent_at: Unixtime,
+    answer: oneshot::Sender<Result<String, String>>,
+}
+
+/// An answer from the remote signer
+#[derive(Debug, Clone, Deserialize)]
+struct Nip46Response {
+    id: String,
+    #[serde(default)]
+    result: Option<String>,
+    #[serde(default)]
+    error: Option<String>,
+}
+
+/// A client of a remote signer (NIP-46). Requests travel as kind 24133 events over
+/// the signer's relays, and answers come back through our NIP-46 subscription.
+pub struct Nip46Client {
+    pub remote_pubkey: PublicKey,
+    pub relays: Vec<RelayUrl>,
+    client: Identity,
+
+    // Remote decryptions already answered, by ciphertext (with when they were
+    // answered, to drop the oldest), and those in flight
+    decrypted: DashMap<String, (String, Unixtime)>,
+    decrypting: DashSet<String>,
+}
+
+impl Nip46Client {
+    pub fn new(remote_pubkey: PublicKey, relays: Vec<RelayUrl>) -> Result<Nip46Client, Error> {
+        Ok(Nip46Client {
+            remote_pubkey,
+            relays,
+            client: Identity::generate("", 1)?,
+            decrypted: DashMap::new(),
+            decrypting: DashSet::new(),
+        })
+    }
+
+    pub fn from_bunker(bunker: &Nip46Bunker) -> Result<Nip46Client, Error> {
+        let epk = EncryptedPrivateKey(bunker.client_key.clone());
+        Ok(Nip46Client {
+            remote_pubkey: bunker.remote_pubkey,
+            relays: bunker.relays.clone(),
+            client: Identity::from_encrypted_private_key(epk, "")?,
+            decrypted: DashMap::new(),
+            decrypting: DashSet::new(),
+        })
+    }
+
+    pub fn to_bunker(&self, user_pubkey: PublicKey) -> Result<Nip46Bunker, Error> {
+        let client_key = match self.client.encrypted_private_key() {
+            Some(epk) => epk.0.clone(),
+            None => return Err(ErrorKind::NoPrivateKey.into()),
+        };
+        Ok(Nip46Bunker {
+            remote_pubkey: self.remote_pubkey,
+            user_pubkey,
+            relays: self.relays.clone(),
+            client_key,
+        })
+    }
+
+    pub fn client_pubkey(&self) -> Option<PublicKey> {
+        self.client.public_key()
+    }
+
+    /// Connect to the remote signer, and learn which pubkey it signs as
+    pub async fn connect(&self, secret: Option<String>) -> Result<PublicKey, Error> {
+        let params = vec![
+            self.remote_pubkey.as_hex_string(),
+            secret.clone().unwrap_or_default(),
+        ];
+        let answer = self.request("connect", params).await?;
+        if answer != "ack" && Some(&answer) != secret.as_ref() {
+            return Err(ErrorKind::Nip46RemoteError(format!(
+                "unexpected answer to connect: {}",
+                answer
+            ))
+            .into());
+        }
+
+        let answer = self.request("get_public_key", vec![]).await?;
+        Ok(PublicKey::try_from_hex_string(&answer, true)?)
+    }
+
+    pub async fn sign_event(&self, input: PreEvent) -> Result<Event, Error> {
+        let unsigned = serde_json::json!({
+            "kind": input.kind,
+            "content": input.content,
+            "tags": input.tags,
+            "created_at": input.created_at,
+        });
+        let answer = self
+            .request("sign_event", vec![unsigned.to_string()])
+            .await?;
+        let event: Event = serde_json::from_str(&answer)?;
+        event.verify(None)?;
+        if event.pubkey != input.pubkey {
+            return Err(ErrorKind::Nip46RemoteError(
+                "remote signer signed with a different key".to_owned(),
+            )
+            .into());
+        }
+        Ok(event)
+    }
+
+    /// Sign with proof of work. The nonce is mined here, then the remote signer
+    /// signs the event with the nonce tag included.
+    pub async fn sign_event_with_pow(
+        &self,
+        input: PreEvent,
+        zero_bits: u8,
+    ) -> Result<Event, Error> {
+        let input = tokio::task::spawn_blocking(move || mine_nonce(input, zero_bits)).await??;
+        self.sign_event(input).await
+    }
+
+    pub async fn encrypt(
+        &self,
+        other: &PublicKey,
+        plaintext: &str,
+        algo: ContentEncryptionAlgorithm,
+    ) -> Result<String, Error> {
+        let method = match algo {
+            ContentEncryptionAlgorithm::Nip04 => "nip04_encrypt",
+            _ => "nip44_encrypt",
+        };
+        self.request(method, vec![other.as_hex_string(), plaintext.to_owned()])
+            .await
+    }
+
+    /// Giftwrap (NIP-59) an event to `pubkey`. The remote signer signs the seal and
+    /// encrypts it. The outer wrap uses a throwaway key of our own.
+    pub async fn giftwrap(&self, input: PreEvent, pubkey: PublicKey) -> Result<Event, Error> {
+        let rumor = serde_json::json!({
+            "id": event_hash(&input)?,
+            "pubkey": input.pubkey,
+            "created_at": input.created_at,
+            "kind": input.kind,
+            "tags": input.tags,
+            "content": input.content,
+        });
+
+        let seal_content = self
+            .encrypt(
+                &pubkey,
+                &rumor.to_string(),
+                ContentEncryptionAlgorithm::Nip44v2,
+            )
+            .await?;
+        let seal = self
+            .sign_event(PreEvent {
+                pubkey: input.pubkey,
+                created_at: tweaked_now(),
+                kind: EventKind::Seal,
+                tags: vec![],
+                content: seal_content,
+            })
+            .await?;
+
+        let wrapper = Identity::generate("", 1)?;
+        let wrapper_pubkey = match wrapper.public_key() {
+            Some(pk) => pk,
+            None => return Err(ErrorKind::NoPublicKey.into()),
+        };
+        let content = wrapper.encrypt(
+            &pubkey,
+            &serde_json::to_string(&seal)?,
+            ContentEncryptionAlgorithm::Nip44v2,
+        )?;
+        Ok(wrapper.sign_event(PreEvent {
+            pubkey: wrapper_pubkey,
+            created_at: tweaked_now(),
+            kind: EventKind::GiftWrap,
+            tags: vec![ParsedTag::Pubkey {
+                pubkey,
+                recommended_relay_url: None,
+                petname: None,
+            }
+            .into_tag()],
+            content,
+        })?)
+    }
+
+    /// Decrypt via the remote signer.
+    ///
+    /// This never waits. Unless the answer is already remembered, it asks the
+    /// remote signer and returns `Nip46Pending`; the answer is remembered, and
+    /// `GLOBALS.remote_decrypted` notified and the UI invalidated, when it arrives.
+    pub fn decrypt(self: &Arc<Self>, other: &PublicKey, ciphertext: &str) -> Result<String, Error> {
+        if let Some(entry) = self.decrypted.get(ciphertext) {
+            return Ok(entry.0.clone());
+        }
+
+        let method = decrypt_method(ciphertext);
+        let params = vec![other.as_hex_string(), ciphertext.to_owned()];
+
+        if self.decrypting.insert(ciphertext.to_owned()) {
+            let client = self.clone();
+            let ciphertext = ciphertext.to_owned();
+            std::mem::drop(tokio::spawn(async move {
+                match client.request(method, params).await {
+                    Ok(plaintext) => {
+                        client.remember_decrypted(ciphertext.clone(), plaintext);
+                        GLOBALS.remote_decrypted.notify_one();
+                        GLOBALS.ui_invalidate_all();
+                    }
+                    Err(e) => tracing::warn!("Remote signer could not decrypt: {}", e),
+                }
+                client.decrypting.remove(&ciphertext);
+            }));
+        }
+
+        Err(ErrorKind::Nip46Pending.into())
+    }
+
+    /// Decrypt via the remote signer, waiting for its answer unless it is already
+    /// remembered
+    pub async fn decrypt_and_wait(
+        &self,
+        other: &PublicKey,
+        ciphertext: &str,
+    ) -> Result<String, Error> {
+        if let Some(entry) = self.decrypted.get(ciphertext) {
+            return Ok(entry.0.clone());
+        }
+
+        let params = vec![other.as_hex_string(), ciphertext.to_owned()];
+        let plaintext = self.request(decrypt_method(ciphertext), params).await?;
+        self.remember_decrypted(ciphertext.to_owned(), plaintext.clone());
+        Ok(plaintext)
+    }
+
+    // Remember a decryption, forgetting the oldest one if we have too many
+    fn remember_decrypted(&self, ciphertext: String, plaintext: String) {
+        if self.decrypted.len() >= MAX_DECRYPTED {
+            let oldest = self
+                .decrypted
+                .iter()
+                .min_by_key(|entry| entry.value().1)
+                .map(|entry| entry.key().clone());
+            if let Some(oldest) = oldest {
+                self.decrypted.remove(&oldest);
+            }
+        }
+        self.decrypted
+            .insert(ciphertext, (plaintext, Unixtime::now()));
+    }
+
+    pub fn unwrap_giftwrap(self: &Arc<Self>, event: &Event) -> Result<Rumor, Error> {
+        if event.kind != EventKind::GiftWrap {
+            return Err(ErrorKind::WrongEventKind.into());
+        }
+
+        let seal: Event = serde_json::from_str(&self.decrypt(&event.pubkey, &event.content)?)?;
+        seal.verify(None)?;
+        if seal.kind != EventKind::Seal {
+            return Err(ErrorKind::WrongEventKind.into());
+        }
+
+        let rumor: Rumor = serde_json::from_str(&self.decrypt(&seal.pubkey, &seal.content)?)?;
+        if rumor.pubkey != seal.pubkey {
+            return Err(ErrorKind::Nip46RemoteError(
+                "giftwrap rumor author does not match its seal".to_owned(),
+            )
+            .into());
+        }
+
+        Ok(rumor)
+    }
+
+    /// Send a request to the remote signer and wait for its answer.
+    ///
+    /// The answer arrives through a minion, so this must not be awaited from a
+    /// minion's own event processing.
+    pub async fn request(&self, method: &str, params: Vec<String>) -> Result<String, Error> {
+        let id = format!("{:016x}", rand::random::<u64>());
+        let event = request_event(&self.client, self.remote_pubkey, &id, method, &params)?;
+
+        let (answer, receiver) = oneshot::channel();
+        GLOBALS.nip46_client_requests.insert(
+            id.clone(),
+            Nip46PendingRequest {
+                method: method.to_owned(),
+                sent_at: Unixtime::now(),
+                answer,
+            },
+        );
+        GLOBALS.notify_ui_redraw.notify_waiters();
+
+        // Subscribe along with posting (the subscription is not repeated if it is
+        // already running) so that we are listening before the answer comes back
+        manager::run_jobs_on_all_relays(
+            self.relays.clone(),
+            vec![
+                RelayJob {
+                    reason: RelayConnectionReason::NostrConnect,
+                    payload: ToMinionPayload {
+                        job_id: rand::random::<u64>(),
+                        detail: ToMinionPayloadDetail::Subscribe(FilterSet::Nip46),
+                    },
+                },
+                RelayJob {
+                    reason: RelayConnectionReason::PostNostrConnect,
+                    payload: ToMinionPayload {
+                        job_id: rand::random::<u64>(),
+                        detail: ToMinionPayloadDetail::PostEvents(vec![event]),
+                    },
+                },
+            ],
+        );
+
+        let outcome = tokio::time::timeout(REQUEST_TIMEOUT, receiver).await;
+        GLOBALS.nip46_client_requests.remove(&id);
+        GLOBALS.notify_ui_redraw.notify_waiters();
+
+        match outcome {
+            Ok(Ok(Ok(result))) => Ok(result),
+            Ok(Ok(Err(error))) => Err(ErrorKind::Nip46RemoteError(error).into()),
+            _ => Err(ErrorKind::TimedOut.into()),
+        }
+    }
+}
+
+// The remote signer method that decrypts this ciphertext
+fn decrypt_method(ciphertext: &str) -> &'static str {
+    match PrivateKey::detect_encryption_algorithm(ciphertext) {
+        ContentEncryptionAlgorithm::Nip04 => "nip04_decrypt",
+        _ => "nip44_decrypt",
+    }
+}
+
+// The id (NIP-01) the event will have once signed
+fn event_hash(input: &PreEvent) -> Result<Id, Error> {
+    let serialized = serde_json::to_string(&serde_json::json!([
+        0,
+        input.pubkey,
+        input.created_at,
+        input.kind,
+        input.tags,
+        input.content,
+    ]))?;
+    let hash = sha2::Sha256::digest(serialized.as_bytes());
+    Ok(Id(hash.into()))
+}
+
+// Add a nonce tag (NIP-13) that gives the event's id `zero_bits` leading zero bits
+fn mine_nonce(mut input: PreEvent, zero_bits: u8) -> Result<PreEvent, Error> {
+    input.tags.retain(|t| t.tagname() != "nonce");
+    if zero_bits == 0 {
+        return Ok(input);
+    }
+    let target = format!("{}", zero_bits);
+    input.tags.push(Tag::new(&["nonce", "0", &target]));
+    let index = input.tags.len() - 1;
+    let mut nonce: u64 = 0;
+    loop {
+        input.tags[index] = Tag::new(&["nonce", &format!("{}", nonce), &target]);
+        if crate::misc::pow_difficulty(event_hash(&input)?) >= zero_bits as u32 {
+            return Ok(input);
+        }
+        nonce += 1;
+    }
+}
+
+// Now, tweaked up to two days into the past (NIP-59) so the time does not give the
+// event away
+fn tweaked_now() -> Unixtime {
+    Unixtime(Unixtime::now().0 - (rand::random::<u32>() % (2 * 24 * 60 * 60)) as i64)
+}
+
+// Build a request event from our client key to the remote signer
+fn request_event(
+    client: &Identity,
+    remote_pubkey: PublicKey,
+    id: &str,
+    method: &str,
+    params: &[String],
+) -> Result<Event, Error> {
+    let client_pubkey = match client.public_key() {
+        Some(pk) => pk,
+        None => return Err(ErrorKind::NoPublicKey.into()),
+    };
+
+    let request = serde_json::json!({
+        "id": id,
+        "method": method,
+        "params": params,
+    });
+    let content = client.encrypt(
+        &remote_pubkey,
+        &request.to_string(),
+        ContentEncryptionAlgorithm::Nip44v2,
+    )?;
+
+    let pre_event = PreEvent {
+        pubkey: client_pubkey,
+        created_at: Unixtime::now(),
+        kind: EventKind::NostrConnect,
+        tags: vec![ParsedTag::Pubkey {
+            pubkey: remote_pubkey,
+            recommended_relay_url: None,
+            petname: None,
+        }
+        .into_tag()],
+        content,
+    };
+
+    Ok(client.sign_event(pre_event)?)
+}
+
+// Read a response event from the remote signer to our client key
+fn parse_response(client: &Identity, event: &Event) -> Result<Nip46Response, Error> {
+    let json = client.decrypt(&event.pubkey, &event.content)?;
+    Ok(serde_json::from_str(&json)?)
+}
+
+/// Handle a NIP-46 event if it is an answer from our remote signer.
+/// Returns false if it is not, so it can be handled as a command to us instead.
+pub(crate) fn handle_response(event: &Event) -> Result<bool, Error> {
+    let client = match GLOBALS.identity.remote_signer() {
+        Some(client) => client,
+        None => return Ok(false),
+    };
+
+    let client_pubkey = match client.client_pubkey() {
+        Some(pk) => pk,
+        None => return Ok(false),
+    };
+
+    let to_us = event
+        .tags
+        .iter()
+        .any(|t| t.tagname() == "p" && t.value() == client_pubkey.as_hex_string());
+    if event.pubkey != client.remote_pubkey || !to_us {
+        return Ok(false);
+    }
+
+    let response = parse_response(&client.client, event)?;
+    let result = response.result.unwrap_or_default();
+    let error = response.error.unwrap_or_default();
+
+    // The remote signer wants the user to authorize us on a web page first.
+    // Keep waiting; the real answer follows.
+    if result == "auth_url" {
+        GLOBALS.status_queue.write().write(format!(
+            "Your remote signer asks you to authorize gossip at {}",
+            error
+        ));
+        return Ok(true);
+    }
+
+    if let Some((_, pending)) = GLOBALS.nip46_client_requests.remove(&response.id) {
+        let answer = if error.is_empty() {
+            Ok(result)
+        } else {
+            Err(error)
+        };
+        let _ = pending.answer.send(answer);
+    }
+
+    Ok(true)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    // Plays the part of a remote signer, in-process
+    struct LocalSigner {
+        identity: Identity,
+    }
+
+    impl LocalSigner {
+        fn answer(&self, request: &Event) -> Event {
+            let json = self
+                .identity
+                .decrypt(&request.pubkey, &request.content)
+                .unwrap();
+            let command: serde_json::Value = serde_json::from_str(&json).unwrap();
+            let params = command["params"].as_array().unwrap();
+
+            let result = match command["method"].as_str().unwrap() {
+                "get_public_key" => self.identity.public_key().unwrap().as_hex_string(),
+                "sign_event" => {
+                    let unsigned: serde_json::Value =
+                        serde_json::from_str(params[0].as_str().unwrap()).unwrap();
+                    let pre_event = PreEvent {
+                        pubkey: self.identity.public_key().unwrap(),
+                        created_at: serde_json::from_value(unsigned["created_at"].clone()).unwrap(),
+                        kind: serde_json::from_value(unsigned["kind"].clone()).unwrap(),
+                        tags: serde_json::from_value(unsigned["tags"].clone()).unwrap(),
+                        content: unsigned["content"].as_str().unwrap().to_owned(),
+                    };
+                    serde_json::to_string(&self.identity.sign_event(pre_event).unwrap()).unwrap()
+                }
+                _ => "ack".to_owned(),
+            };
+
+            let response = serde_json::json!({
+                "id": command["id"],
+                "result": result,
+            });
+            let pre_event = PreEvent {
+                pubkey: self.identity.public_key().unwrap(),
+                created_at: Unixtime::now(),
+                kind: EventKind::NostrConnect,
+                tags: vec![ParsedTag::Pubkey {
+                    pubkey: request.pubkey,
+                    recommended_relay_url: None,
+                    petname: None,
+                }
+                .into_tag()],
+                content: self
+                    .identity
+                    .encrypt(
+                        &request.pubkey,
+                        &response.to_string(),
+                        ContentEncryptionAlgorithm::Nip44v2,
+                    )
+                    .unwrap(),
+            };
+            self.identity.sign_event(pre_event).unwrap()
+        }
+    }
+
+    #[test]
+    fn test_remote_sign_event() {
+        let signer = LocalSigner {
+            identity: Identity::generate("", 1).unwrap(),
+        };
+        let user_pubkey = signer.identity.public_key().unwrap();
+        let client = Identity::generate("", 1).unwrap();
+
+        // Learn the user's pubkey
+        let request = request_event(&client, user_pubkey, "1", "get_public_key", &[]).unwrap();
+        let response = parse_response(&client, &signer.answer(&request)).unwrap();
+        assert_eq!(response.id, "1");
+        assert_eq!(response.result.unwrap(), user_pubkey.as_hex_string());
+
+        // Have an event signed
+        let unsigned = serde_json::json!({
+            "kind": EventKind::TextNote,
+            "content": "Hello from a bunker",
+            "tags": Vec::<nostr_types::Tag>::new(),
+            "created_at": Unixtime::now(),
+        });
+        let request = request_event(
+            &client,
+            user_pubkey,
+            "2",
+            "sign_event",
+            &[unsigned.to_string()],
+        )
+        .unwrap();
+        let response = parse_response(&client, &signer.answer(&request)).unwrap();
+        assert_eq!(response.id, "2");
+        let event: Event = serde_json::from_str(&response.result.unwrap()).unwrap();
+        assert!(event.verify(None).is_ok());
+        assert_eq!(event.pubkey, user_pubkey);
+        assert_eq!(event.content, "Hello from a bunker");
+    }
+
+    #[test]
+    fn test_event_hash_and_nonce() {
+        let identity = Identity::generate("", 1).unwrap();
+        let pre_event = PreEvent {
+            pubkey: identity.public_key().unwrap(),
+            created_at: Unixtime::now(),
+            kind: EventKind::TextNote,
+            tags: vec![Tag::new(&["t", "nostr"])],
+            content: "Hello \"world\"\n".to_owned(),
+        };
+
+        // The id we compute is the one the event gets when signed
+        let event = identity.sign_event(pre_event.clone()).unwrap();
+        assert_eq!(event_hash(&pre_event).unwrap(), event.id);
+
+        // Mining keeps the other tags and reaches the difficulty
+        let mined = mine_nonce(pre_event, 8).unwrap();
+        assert!(mined.tags.iter().any(|t| t.tagname() == "t"));
+        assert!(mined
+            .tags
+            .iter()
+            .any(|t| t.tagname() == "nonce" && t.get_index(2) == "8"));
+        let event = identity.sign_event(mined).unwrap();
+        assert!(crate::misc::pow_difficulty(event.id) >= 8);
+    }
+
+    #[test]
+    fn test_parse_bunker_token() {
+        let pubkey = Identity::generate("", 1).unwrap().public_key().unwrap();
+        let token = format!(
+            "bunker://{}?relay=wss%3A%2F%2Frelay.example.com%2F&secret=abc123",
+            pubkey.as_hex_string()
+        );
+        let parsed = BunkerToken::parse(&token).unwrap();
+        assert_eq!(parsed.remote_pubkey, pubkey);
+        assert_eq!(parsed.relays.len(), 1);
+        assert_eq!(parsed.secret.as_deref(), Some("abc123"));
+        assert!(BunkerToken::parse("nostrconnect://nothing").is_err());
+    }
+}
@@ -7,6 +7,8 @@ use nostr_types::{
 };
 use serde::Deserialize;
 use speedy::{Readable, Writable};
+use std::fmt;
+use std::time::Duration;
 
 /// This is a server not yet connected, ready to be connected
 #[derive(Debug, Clone, Readable, Writable)]
@@ -89,17 +91,159 @@ impl Approval {
     }
 }
 
+// Check an approval, telling apart "ask the user" from "denied"
+fn check_approval(approval: &mut Approval) -> Result<(), Error> {
+    if approval.is_approved() {
+        Ok(())
+    } else if *approval == Approval::Ask {
+        Err(ErrorKind::Nip46NeedApproval.into())
+    } else {
+        Err(ErrorKind::Nip46Denied.into())
+    }
+}
+
+/// Limits on what a connected client may do, beyond the per-method approvals
+#[derive(Debug, Default, Clone, Readable, Writable, PartialEq, Eq)]
+pub struct Nip46Policy {
+    /// Event kinds the client may have signed. Empty allows every kind.
+    pub allowed_kinds: Vec<u32>,
+
+    /// Most requests served per minute. 0 is unlimited.
+    pub max_requests_per_minute: u32,
+
+    /// Hours of the day (UTC) during which requests are served, from the first
+    /// (inclusive) to the second (exclusive), wrapping past midnight. Equal hours
+    /// means any time.
+    pub active_hours: (u8, u8),
+
+    /// When the connection stops being served
+    pub expires_at: Option<Unixtime>,
+}
+
+impl Nip46Policy {
+    pub fn allows_kind(&self, kind: EventKind) -> bool {
+        self.allowed_kinds.is_empty() || self.allowed_kinds.contains(&u32::from(kind))
+    }
+
+    pub fn is_active_at(&self, when: Unixtime) -> bool {
+        let (from, until) = self.active_hours;
+        if from == until {
+            return true;
+        }
+        let hour = (when.0.rem_euclid(86400) / 3600) as u8;
+        if from < until {
+            hour >= from && hour < until
+        } else {
+            hour >= from || hour < until
+        }
+    }
+
+    pub fn is_expired_at(&self, when: Unixtime) -> bool {
+        matches!(self.expires_at, Some(expires_at) if when >= expires_at)
+    }
+
+    // Check the parts of the policy that apply to every request
+    fn check(&self, peer_pubkey: PublicKey, method: &str) -> Result<(), Error> {
+        let now = Unixtime::now();
+        if self.is_expired_at(now) {
+            return Err(ErrorKind::Nip46PolicyDenied("connection expired".to_owned()).into());
+        }
+        if method == "ping" {
+            return Ok(());
+        }
+        if !self.is_active_at(now) {
+            return Err(ErrorKind::Nip46PolicyDenied("outside of allowed hours".to_owned()).into());
+        }
+        if self.max_requests_per_minute > 0 {
+            let served = GLOBALS
+                .db()
+                .count_nip46_requests_served_since(peer_pubkey, now - Duration::from_secs(60))?;
+            if served >= self.max_requests_per_minute as usize {
+                return Err(ErrorKind::Nip46PolicyDenied("rate limited".to_owned()).into());
+            }
+        }
+        Ok(())
+    }
+}
+
+/// What became of a request from a connected client
+#[derive(Debug, Clone, Readable, Writable, PartialEq, Eq)]
+pub enum Nip46AuditOutcome {
+    Served,
+    AwaitingApproval,
+    Denied(String),
+    Failed(String),
+}
+
+impl fmt::Display for Nip46AuditOutcome {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Nip46AuditOutcome::Served => write!(f, "served"),
+            Nip46AuditOutcome::AwaitingApproval => write!(f, "awaiting approval"),
+            Nip46AuditOutcome::Denied(why) => write!(f, "denied: {why}"),
+            Nip46AuditOutcome::Failed(why) => write!(f, "failed: {why}"),
+        }
+    }
+}
+
+/// A record of a request from a connected client
+#[derive(Debug, Clone, Readable, Writable)]
+pub struct Nip46AuditEntry {
+    pub peer_pubkey: PublicKey,
+    pub request_id: String,
+    pub when: Unixtime,
+    pub method: String,
+
+    /// The event kind for `sign_event`, the other party for encryption, otherwise empty
+    pub detail: String,
+
+    pub outcome: Nip46AuditOutcome,
+}
+
 #[derive(Debug, Clone, Readable, Writable)]
 pub struct Nip46Server {
     pub name: String,
     pub peer_pubkey: PublicKey,
     pub relays: Vec<RelayUrl>,
     pub sign_approval: Approval,
-    pub encrypt_approval: Approval,
-    pub decrypt_approval: Approval,
+    pub nip04_encrypt_approval: Approval,
+    pub nip04_decrypt_approval: Approval,
+    pub nip44_encrypt_approval: Approval,
+    pub nip44_decrypt_approval: Approval,
+    pub policy: Nip46Policy,
 }
 
 impl Nip46Server {
+    pub fn new(name: String, peer_pubkey: PublicKey, relays: Vec<RelayUrl>) -> Nip46Server {
+        Nip46Server {
+            name,
+            peer_pubkey,
+            relays,
+            sign_approval: Approval::Ask,
+            nip04_encrypt_approval: Approval::Ask,
+            nip04_decrypt_approval: Approval::Ask,
+            nip44_encrypt_approval: Approval::Ask,
+            nip44_decrypt_approval: Approval::Ask,
+            policy: Nip46Policy::default(),
+        }
+    }
+
+    /// Set the approval that governs a method
+    pub fn set_approval(&mut self, method: &str, approval: Approval) {
+        match method {
+            "sign_event" => self.sign_approval = approval,
+            "nip04_encrypt" => self.nip04_encrypt_approval = approval,
+            "nip04_decrypt" => self.nip04_decrypt_approval = approval,
+            "nip44_encrypt" => self.nip44_encrypt_approval = approval,
+            "nip44_decrypt" => self.nip44_decrypt_approval = approval,
+            "nip44_get_key" => {
+                self.nip44_encrypt_approval = approval;
+                self.nip44_decrypt_approval = approval;
+            }
+            _ => {}
+        }
+    }
+
     pub fn handle(&mut self, cmd: &ParsedCommand) -> Result<(), Error> {
         let ParsedCommand {
             ref id,
@@ -108,68 +252,41 @@ impl Nip46Server {
             ref algo,
         } = cmd;
 
-        let result: Result<String, Error> = match method.as_str() {
-            "connect" => Ok("ack".into()),
-            "get_public_key" => self.get_public_key(),
-            "sign_event" => {
-                if self.sign_approval.is_approved() {
-                    self.sign_event(params)
-                } else if self.sign_approval == Approval::Ask {
-                    Err(ErrorKind::Nip46NeedApproval.into())
-                } else {
-                    Err(ErrorKind::Nip46Denied.into())
-                }
-            }
-            "get_relays" => self.get_relays(),
-            "nip04_encrypt" => {
-                if self.encrypt_approval.is_approved() {
-                    self.nip04_encrypt(params)
-                } else if self.encrypt_approval == Approval::Ask {
-                    Err(ErrorKind::Nip46NeedApproval.into())
-                } else {
-                    Err(ErrorKind::Nip46Denied.into())
+        let result: Result<String, Error> = match self.policy.check(self.peer_pubkey, method) {
+            Err(e) => Err(e),
+            Ok(()) => match method.as_str() {
+                "connect" => Ok("ack".into()),
+                "get_public_key" => self.get_public_key(),
+                "sign_event" => match self.check_sign_event_kind(params) {
+                    Ok(()) => check_approval(&mut self.sign_approval)
+                        .and_then(|_| self.sign_event(params)),
+                    Err(e) => Err(e),
+                },
+                "get_relays" => self.get_relays(),
+                "nip04_encrypt" => check_approval(&mut self.nip04_encrypt_approval)
+                    .and_then(|_| self.nip04_encrypt(params)),
+                "nip04_decrypt" => check_approval(&mut self.nip04_decrypt_approval)
+                    .and_then(|_| self.nip04_decrypt(params)),
+                "nip44_get_key" => {
+                    // The conversation key can both encrypt and decrypt
+                    check_approval(&mut self.nip44_encrypt_approval)
+                        .and_then(|_| check_approval(&mut self.nip44_decrypt_approval))
+                        .and_then(|_| self.nip44_get_key(params))
                 }
-            }
-            "nip04_decrypt" => {
-                if self.decrypt_approval.is_approved() {
-                    self.nip04_decrypt(params)
-                } else if self.decrypt_approval == Approval::Ask {
-                    Err(ErrorKind::Nip46NeedApproval.into())
-                } else {
-                    Err(ErrorKind::Nip46Denied.into())
-                }
-            }
-            "nip44_get_key" => {
-                if self.encrypt_approval.is_approved() || self.decrypt_approval.is_approved() {
-                    self.nip44_get_key(params)
-                } else if self.encrypt_approval == Approval::Ask {
-                    Err(ErrorKind::Nip46NeedApproval.into())
-                } else {
-                    Err(ErrorKind::Nip46Denied.into())
-                }
-            }
-            "nip44_encrypt" => {
-                if self.encrypt_approval.is_approved() {
-                    self.nip44_encrypt(params)
-                } else if self.encrypt_approval == Approval::Ask {
-                    Err(ErrorKind::Nip46NeedApproval.into())
-                } else {
-                    Err(ErrorKind::Nip46Denied.into())
-                }
-            }
-            "nip44_decrypt" => {
-                if self.decrypt_approval.is_approved() {
-                    self.nip44_decrypt(params)
-                } else if self.decrypt_approval == Approval::Ask {
-                    Err(ErrorKind::Nip46NeedApproval.into())
-                } else {
-                    Err(ErrorKind::Nip46Denied.into())
-                }
-            }
-            "ping" => self.ping(),
-            _ => Err("unrecognized command".into()),
+                "nip44_encrypt" => check_approval(&mut self.nip44_encrypt_approval)
+                    .and_then(|_| self.nip44_encrypt(params)),
+                "nip44_decrypt" => check_approval(&mut self.nip44_decrypt_approval)
+                    .and_then(|_| self.nip44_decrypt(params)),
+                "ping" => self.ping(),
+                _ => Err("unrecognized command".into()),
+            },
         };
 
+        // A failure to record the request must not keep the client waiting
+        if let Err(e) = self.audit(cmd, &result) {
+            tracing::error!("NIP-46 audit log: {}", e);
+        }
+
         match result {
             Ok(answer) => send_response(
                 id.to_owned(),
@@ -198,6 +315,63 @@ impl Nip46Server {
         Ok(())
     }
 
+    // Record the request in the audit log
+    fn audit(&self, cmd: &ParsedCommand, result: &Result<String, Error>) -> Result<(), Error> {
+        let detail = match cmd.method.as_str() {
+            "sign_event" => match cmd.params.first() {
+                Some(param) => match serde_json::from_str::<Nip46PreEvent>(param) {
+                    Ok(pre_event) => format!("kind {}", u32::from(pre_event.kind)),
+                    Err(_) => "".to_owned(),
+                },
+                None => "".to_owned(),
+            },
+            "nip04_encrypt" | "nip04_decrypt" | "nip44_encrypt" | "nip44_decrypt"
+            | "nip44_get_key" => match cmd.params.first() {
+                Some(pubkey) => pubkey.clone(),
+                None => "".to_owned(),
+            },
+            _ => "".to_owned(),
+        };
+
+        let outcome = match result {
+            Ok(_) => Nip46AuditOutcome::Served,
+            Err(e) => match e.kind {
+                ErrorKind::Nip46NeedApproval => Nip46AuditOutcome::AwaitingApproval,
+                ErrorKind::Nip46Denied => Nip46AuditOutcome::Denied("not approved".to_owned()),
+                ErrorKind::Nip46PolicyDenied(ref why) => Nip46AuditOutcome::Denied(why.clone()),
+                _ => Nip46AuditOutcome::Failed(format!("{}", e)),
+            },
+        };
+
+        GLOBALS.db().write_nip46_audit_entry(
+            &Nip46AuditEntry {
+                peer_pubkey: self.peer_pubkey,
+                request_id: cmd.id.clone(),
+                when: Unixtime::now(),
+                method: cmd.method.clone(),
+                detail,
+                outcome,
+            },
+            None,
+        )
+    }
+
+    // Refuse event kinds the policy does not allow, before asking for approval
+    fn check_sign_event_kind(&self, params: &[String]) -> Result<(), Error> {
+        if params.is_empty() {
+            return Err("sign_event: requires a parameter".into());
+        }
+        let pre_event: Nip46PreEvent = serde_json::from_str(&params[0])?;
+        if !self.policy.allows_kind(pre_event.kind) {
+            return Err(ErrorKind::Nip46PolicyDenied(format!(
+                "event kind {} not allowed",
+                u32::from(pre_event.kind)
+            ))
+            .into());
+        }
+        Ok(())
+    }
+
     fn get_public_key(&self) -> Result<String, Error> {
         if let Some(pk) = GLOBALS.identity.public_key() {
             Ok(pk.as_hex_string())
@@ -231,7 +405,7 @@ impl Nip46Server {
             content,
         };
 
-        let event = GLOBALS.identity.sign_event(pre_event)?;
+        let event = GLOBALS.identity.inner.read_arc().sign_event(pre_event)?;
 
         let event_str = serde_json::to_string(&event)?;
 
@@ -248,7 +422,7 @@ impl Nip46Server {
             return Err("nip04_encrypt: requires two parameters".into());
         }
         let other_pubkey = PublicKey::try_from_hex_string(&params[0], true)?;
-        let ciphertext = GLOBALS.identity.encrypt(
+        let ciphertext = GLOBALS.identity.inner.read_arc().encrypt(
             &other_pubkey,
             &params[1],
             ContentEncryptionAlgorithm::Nip04,
@@ -279,7 +453,7 @@ impl Nip46Server {
             return Err("nip44_encrypt: requires two parameters".into());
         }
         let other_pubkey = PublicKey::try_from_hex_string(&params[0], true)?;
-        let ciphertext = GLOBALS.identity.encrypt(
+        let ciphertext = GLOBALS.identity.inner.read_arc().encrypt(
             &other_pubkey,
             &params[1],
             ContentEncryptionAlgorithm::Nip44v2,
@@ -412,7 +586,11 @@ fn send_response(
     });
     let s = output.to_string();
 
-    let e = GLOBALS.identity.encrypt(&peer_pubkey, &s, algo)?;
+    let e = GLOBALS
+        .identity
+        .inner
+        .read_arc()
+        .encrypt(&peer_pubkey, &s, algo)?;
 
     let pre_event = PreEvent {
         pubkey: public_key,
@@ -427,7 +605,7 @@ fn send_response(
         content: e,
     };
 
-    let event = GLOBALS.identity.sign_event(pre_event)?;
+    let event = GLOBALS.identity.inner.read_arc().sign_event(pre_event)?;
 
     GLOBALS
         .to_overlord
@@ -437,6 +615,12 @@ fn send_response(
 }
 
 pub fn handle_command(event: &Event, seen_on: Option<RelayUrl>) -> Result<(), Error> {
+    // We sign for others only with a key we hold. With a remote signer of our own,
+    // every answer would wait on it from within event processing.
+    if GLOBALS.identity.is_remote() {
+        return Err(ErrorKind::Nip46Unsupported("act as a remote signer".to_owned()).into());
+    }
+
     // If we have a server for that pubkey
     if let Some(mut server) = GLOBALS.db().read_nip46server(event.pubkey)? {
         // Parse the command
@@ -602,14 +786,7 @@ pub fn handle_command(event: &Event, seen_on: Option<RelayUrl>) -> Result<(), Er
     }
 
     // Turn it into a full server
-    let server = Nip46Server {
-        name: userver.name,
-        peer_pubkey: event.pubkey,
-        relays: reply_relays.clone(),
-        sign_approval: Approval::Ask,
-        encrypt_approval: Approval::Ask,
-        decrypt_approval: Approval::Ask,
-    };
+    let server = Nip46Server::new(userver.name, event.pubkey, reply_relays.clone());
 
     // Save the server, and delete the unconnected server
     let mut txn = GLOBALS.db().get_write_txn()?;
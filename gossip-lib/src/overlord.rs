@@ -1,22 +1,29 @@
-use crate::blossom::{Blossom, HashOutput};
+use crate::blossom::HashOutput;
+use crate::channels::FollowedChannel;
 use crate::comms::{
     RelayConnectionReason, RelayJob, ToMinionMessage, ToMinionPayload, ToMinionPayloadDetail,
     ToOverlordMessage,
 };
+use crate::counts::{CountTarget, NIP_COUNT};
 use crate::dm_channel::DmChannel;
+use crate::drafts::Draft;
 use crate::error::{Error, ErrorKind};
 use crate::feed::FeedKind;
 use crate::filter_set::{FeedRange, FilterSet};
 use crate::globals::GLOBALS;
+use crate::groups::JoinedGroup;
 use crate::manager;
 use crate::minion::MinionExitReason;
 use crate::misc::{Private, ZapState};
+use crate::nostr_connect_client::{BunkerToken, Nip46Client};
 use crate::nostr_connect_server::{Approval, ParsedCommand};
 use crate::pending::PendingItem;
 use crate::people::{Person, PersonList};
+use crate::post::LongFormArticle;
 use crate::relay;
 use crate::relay::Relay;
 use crate::relay_picker::RelayAssignment;
+use crate::relay_stats::RelayStats;
 use crate::relay_test_results::{RelayTestResult, RelayTestResults};
 use crate::storage::types::{HandlerKey, ScoreFactors};
 use crate::storage::{PersonTable, Table};
@@ -24,9 +31,9 @@ use crate::RunState;
 use heed::RwTxn;
 use http::StatusCode;
 use nostr_types::{
-    EncryptedPrivateKey, Event, EventKind, EventReference, Filter, Id, Metadata, MilliSatoshi,
-    NAddr, NostrBech32, ParsedTag, PayRequestData, PreEvent, PrivateKey, Profile, PublicKey,
-    RelayUrl, Tag, UncheckedUrl, Unixtime,
+    EncryptedPrivateKey, Event, EventKind, EventReference, FileMetadata, Filter, Id, Metadata,
+    MilliSatoshi, NAddr, NostrBech32, ParsedTag, PayRequestData, PreEvent, PrivateKey, Profile,
+    PublicKey, RelayUrl, Tag, UncheckedUrl, Unixtime,
 };
 use std::collections::HashMap;
 use std::path::PathBuf;
@@ -45,6 +52,9 @@ use zeroize::Zeroize;
 
 type MinionResult = Result<MinionExitReason, Error>;
 
+// How many relays count the replies and reactions of the notes in view (NIP-45)
+const AUGMENT_COUNT_RELAYS: usize = 3;
+
 /// The overlord handles any operation that involves talking to relays, and a few more.
 ///
 /// There are two ways to engage the Overlord to do something:
@@ -106,6 +116,45 @@ impl Overlord {
         }
     }
 
+    // An overlord for handling messages off the main loop. It has no inbox of its own.
+    fn worker() -> Overlord {
+        let (_, inbox) = tokio::sync::mpsc::unbounded_channel();
+        Overlord::new(inbox)
+    }
+
+    // Whether handling this message signs or encrypts, which with a remote signer
+    // means waiting on its answers
+    fn signs(message: &ToOverlordMessage) -> bool {
+        matches!(
+            message,
+            ToOverlordMessage::AdvertiseRelayList
+                | ToOverlordMessage::BookmarkAdd(..)
+                | ToOverlordMessage::BookmarkRm(..)
+                | ToOverlordMessage::DeleteDraft(..)
+                | ToOverlordMessage::DeletePersonList(..)
+                | ToOverlordMessage::DeletePost(..)
+                | ToOverlordMessage::HideChannelMessage(..)
+                | ToOverlordMessage::JoinGroup(..)
+                | ToOverlordMessage::Label(..)
+                | ToOverlordMessage::LeaveGroup(..)
+                | ToOverlordMessage::MuteChannelUser(..)
+                | ToOverlordMessage::Post { .. }
+                | ToOverlordMessage::PostChannel { .. }
+                | ToOverlordMessage::PostGroup { .. }
+                | ToOverlordMessage::PostLongForm(..)
+                | ToOverlordMessage::PushBlossomServers
+                | ToOverlordMessage::PushMetadata(..)
+                | ToOverlordMessage::PushPersonList(..)
+                | ToOverlordMessage::React(..)
+                | ToOverlordMessage::Report(..)
+                | ToOverlordMessage::Repost(..)
+                | ToOverlordMessage::SaveDraft(..)
+                | ToOverlordMessage::ShareHandlerRecommendations(..)
+                | ToOverlordMessage::UpdatePersonList { .. }
+                | ToOverlordMessage::Zap(..)
+        )
+    }
+
     /// This runs the overlord. This blocks for the entire duration and only exits
     /// when the overlord receives a signal to shutdown.
     pub async fn run(&mut self) {
@@ -173,6 +222,12 @@ impl Overlord {
                 GLOBALS.db().rebuild_event_tags_index(None)?;
             }
 
+            // If we need to rebuild the search index, do so now
+            if GLOBALS.db().get_flag_rebuild_search_index_needed() {
+                tracing::info!("Rebuilding search index...");
+                GLOBALS.db().rebuild_search_index(None)?;
+            }
+
             // If we need to reapply relay lists, do so now
             if GLOBALS.db().get_flag_reprocess_relay_lists_needed() {
                 tracing::info!("Reprocessing relay lists...");
@@ -221,6 +276,21 @@ impl Overlord {
         let minion_task_interval = tokio::time::interval(Duration::from_millis(500));
         tokio::pin!(minion_task_interval);
 
+        // With a remote signer, messages that sign wait up to a minute on each answer.
+        // A worker handles those, in the order they arrive, so the main loop keeps
+        // going meanwhile.
+        let (remote_signed_sender, mut remote_signed_receiver) =
+            tokio::sync::mpsc::unbounded_channel::<ToOverlordMessage>();
+        let remote_signed_worker = async move {
+            let mut worker = Overlord::worker();
+            while let Some(message) = remote_signed_receiver.recv().await {
+                if let Err(e) = worker.handle_message(message).await {
+                    tracing::error!("{}", e);
+                }
+            }
+        };
+        tokio::pin!(remote_signed_worker);
+
         'mainloop: loop {
             tracing::trace!("overlord looping");
 
@@ -255,10 +325,14 @@ impl Overlord {
                             return Ok(());
                         }
                     };
-                    if let Err(e) = self.handle_message(message).await {
+                    if GLOBALS.identity.is_remote() && Self::signs(&message) {
+                        let _ = remote_signed_sender.send(message);
+                    } else if let Err(e) = self.handle_message(message).await {
                         tracing::error!("{}", e);
                     }
                 },
+                // This never finishes, as we hold the sender. Polling it runs the worker.
+                _ = &mut remote_signed_worker => { },
                 _ = self.read_runstate.changed() => {
                     match *self.read_runstate.borrow_and_update() {
                         RunState::ShuttingDown => break 'mainloop,
@@ -603,14 +677,23 @@ impl Overlord {
             ToOverlordMessage::AuthDeclined(relay_url, permanent) => {
                 self.auth_declined(relay_url, permanent)?;
             }
+            ToOverlordMessage::BlossomDelete(base_url, sha256) => {
+                self.blossom_delete(base_url, sha256)?;
+            }
+            ToOverlordMessage::BlossomList => {
+                self.blossom_list()?;
+            }
+            ToOverlordMessage::BlossomRepair => {
+                self.blossom_repair()?;
+            }
             ToOverlordMessage::BlossomUpload(pathbuf) => {
                 self.blossom_upload(pathbuf).await?;
             }
             ToOverlordMessage::BookmarkAdd(er, private) => {
-                self.bookmark_add(er, private)?;
+                self.bookmark_add(er, private).await?;
             }
             ToOverlordMessage::BookmarkRm(er) => {
-                self.bookmark_rm(er)?;
+                self.bookmark_rm(er).await?;
             }
             ToOverlordMessage::ChangePassphrase { old, new } => {
                 Self::change_passphrase(old, new).await?;
@@ -624,14 +707,20 @@ impl Overlord {
             ToOverlordMessage::ConnectDeclined(relay_url, permanent) => {
                 self.connect_declined(relay_url, permanent)?;
             }
+            ToOverlordMessage::Count(targets) => {
+                self.count(targets)?;
+            }
             ToOverlordMessage::DelegationReset => {
                 Self::delegation_reset().await?;
             }
+            ToOverlordMessage::DeleteDraft(draft) => {
+                self.delete_draft(draft).await?;
+            }
             ToOverlordMessage::DeletePersonList(list) => {
-                self.delete_person_list(list)?;
+                self.delete_person_list(list).await?;
             }
             ToOverlordMessage::DeletePost(id) => {
-                self.delete_post(id)?;
+                self.delete_post(id).await?;
             }
             ToOverlordMessage::DeletePriv => {
                 Self::delete_priv().await?;
@@ -642,12 +731,18 @@ impl Overlord {
             ToOverlordMessage::DropRelay(relay_url) => {
                 self.drop_relay(relay_url)?;
             }
+            ToOverlordMessage::FetchChannels => {
+                self.fetch_channels()?;
+            }
             ToOverlordMessage::FetchEvent(id, relay_urls) => {
                 self.fetch_event(id, relay_urls)?;
             }
             ToOverlordMessage::FetchNAddr(ea) => {
                 self.fetch_naddr(ea)?;
             }
+            ToOverlordMessage::FollowChannel(id, relay) => {
+                self.follow_channel(id, relay)?;
+            }
             ToOverlordMessage::FollowPubkey(pubkey, list, private) => {
                 self.follow_pubkey(pubkey, list, private)?;
             }
@@ -666,6 +761,9 @@ impl Overlord {
                     GLOBALS.status_queue.write().write(format!("{}", e));
                 }
             }
+            ToOverlordMessage::HideChannelMessage(id, reason) => {
+                self.hide_channel_message(id, reason).await?;
+            }
             ToOverlordMessage::HideOrShowRelay(relay_url, hidden) => {
                 Self::hide_or_show_relay(relay_url, hidden)?;
             }
@@ -675,9 +773,21 @@ impl Overlord {
             ToOverlordMessage::ImportPub(pubstr) => {
                 Self::import_pub(pubstr)?;
             }
+            ToOverlordMessage::JoinGroup(relay_url, group) => {
+                self.join_group(relay_url, group).await?;
+            }
+            ToOverlordMessage::Label(id, namespace, label) => {
+                self.label(id, namespace, label).await?;
+            }
+            ToOverlordMessage::LeaveGroup(relay_url, group) => {
+                self.leave_group(relay_url, group).await?;
+            }
             ToOverlordMessage::LoadMoreCurrentFeed => {
                 self.load_more()?;
             }
+            ToOverlordMessage::MeasureCache => {
+                Self::measure_cache().await?;
+            }
             ToOverlordMessage::MinionJobComplete(url, job_id) => {
                 self.finish_job(url, Some(job_id), None)?;
             }
@@ -698,6 +808,15 @@ impl Overlord {
                     }
                 }
             }
+            ToOverlordMessage::MuteChannelUser(pubkey, reason) => {
+                self.mute_channel_user(pubkey, reason).await?;
+            }
+            ToOverlordMessage::Nip46Login(token) => {
+                self.nip46_login(token)?;
+            }
+            ToOverlordMessage::Nip46Logout => {
+                self.nip46_logout()?;
+            }
             ToOverlordMessage::Nip46ServerOpApprovalResponse(pubkey, parsed_command, approval) => {
                 self.nip46_server_op_approval_response(pubkey, parsed_command, approval)?;
             }
@@ -710,19 +829,53 @@ impl Overlord {
                 in_reply_to,
                 annotation,
                 dm_channel,
+                expiration,
             } => {
-                self.post(content, tags, in_reply_to, annotation, dm_channel)
-                    .await?;
+                self.post(
+                    content,
+                    tags,
+                    in_reply_to,
+                    annotation,
+                    dm_channel,
+                    expiration,
+                )
+                .await?;
             }
             ToOverlordMessage::PostAgain(event) => {
                 self.post_again(event)?;
             }
+            ToOverlordMessage::PostChannel {
+                channel,
+                content,
+                tags,
+                in_reply_to,
+            } => {
+                self.post_channel(channel, content, tags, in_reply_to)
+                    .await?;
+            }
+            ToOverlordMessage::PostGroup {
+                relay_url,
+                group,
+                content,
+                tags,
+                in_reply_to,
+                title,
+            } => {
+                self.post_group(relay_url, group, content, tags, in_reply_to, title)
+                    .await?;
+            }
+            ToOverlordMessage::PostLongForm(article) => {
+                self.post_long_form(article).await?;
+            }
             ToOverlordMessage::PostCancel => {
                 self.post_cancel();
             }
             ToOverlordMessage::PostNip46Event(event, relays) => {
                 self.post_nip46_event(event, relays)?;
             }
+            ToOverlordMessage::PostWithPow(relay_url, event, difficulty) => {
+                Self::post_with_pow(relay_url, *event, difficulty);
+            }
             ToOverlordMessage::PruneCache => {
                 Self::prune_cache().await?;
             }
@@ -739,13 +892,13 @@ impl Overlord {
                 self.push_person_list(person_list).await?;
             }
             ToOverlordMessage::PushMetadata(metadata) => {
-                self.push_metadata(metadata)?;
+                self.push_metadata(metadata).await?;
             }
             ToOverlordMessage::RankRelay(relay_url, rank) => {
                 Self::rank_relay(relay_url, rank)?;
             }
             ToOverlordMessage::React(id, pubkey, emoji) => {
-                self.react(id, pubkey, emoji)?;
+                self.react(id, pubkey, emoji).await?;
             }
             ToOverlordMessage::ReengageMinion(url, jobs) => {
                 manager::engage_minion(url, jobs);
@@ -753,8 +906,14 @@ impl Overlord {
             ToOverlordMessage::RefreshSubscribedMetadata => {
                 self.refresh_subscribed_metadata()?;
             }
+            ToOverlordMessage::Report(id, pubkey, report_type) => {
+                self.report(id, pubkey, report_type).await?;
+            }
             ToOverlordMessage::Repost(id) => {
-                self.repost(id)?;
+                self.repost(id).await?;
+            }
+            ToOverlordMessage::SaveDraft(draft) => {
+                self.save_draft(draft).await?;
             }
             ToOverlordMessage::SearchLocally(text) => {
                 Self::search_locally(text)?;
@@ -765,12 +924,18 @@ impl Overlord {
             ToOverlordMessage::SetActivePerson(pubkey) => {
                 Self::set_active_person(pubkey).await?;
             }
+            ToOverlordMessage::SetChannelFeed(id, anchor) => {
+                self.set_channel_feed(id, anchor)?;
+            }
             ToOverlordMessage::SetDmChannel(dmchannel) => {
                 self.set_dm_channel(dmchannel)?;
             }
             ToOverlordMessage::SetGlobalFeed(anchor) => {
                 self.set_global_feed(anchor)?;
             }
+            ToOverlordMessage::SetGroupFeed(relay_url, group, anchor) => {
+                self.set_group_feed(relay_url, group, anchor)?;
+            }
             ToOverlordMessage::SetPersonFeed(pubkey, anchor) => {
                 self.set_person_feed(pubkey, anchor)?;
             }
@@ -811,6 +976,12 @@ impl Overlord {
             ToOverlordMessage::TrackFollows(pubkey) => {
                 self.track_follows(pubkey).await?;
             }
+            ToOverlordMessage::TrimCache => {
+                Self::trim_cache().await?;
+            }
+            ToOverlordMessage::UnfollowChannel(id) => {
+                self.unfollow_channel(id)?;
+            }
             ToOverlordMessage::UnlockKey(password) => {
                 Self::unlock_key(password)?;
             }
@@ -896,7 +1067,7 @@ impl Overlord {
                 content: "".to_string(),
             };
 
-            GLOBALS.identity.sign_event(pre_event)?
+            GLOBALS.identity.sign_event(pre_event).await?
         };
 
         let dmevent = {
@@ -916,7 +1087,7 @@ impl Overlord {
                 content: "".to_string(),
             };
 
-            GLOBALS.identity.sign_event(pre_event)?
+            GLOBALS.identity.sign_event(pre_event).await?
         };
 
         let mut relays = Relay::choose_relays(0, |r| r.is_good_for_advertise())?;
@@ -1042,6 +1213,86 @@ impl Overlord {
         Ok(())
     }
 
+    /// Delete our blob from a blossom server (BUD-02)
+    pub fn blossom_delete(&mut self, base_url: String, sha256: String) -> Result<(), Error> {
+        let hash = HashOutput::from_hex(&sha256)?;
+        let blossom = crate::blossom::client()?;
+
+        std::mem::drop(tokio::spawn(async move {
+            match blossom.delete(base_url.clone(), hash).await {
+                Ok(()) => {
+                    if let Some(mut entry) = GLOBALS.blossom_blobs.get_mut(&base_url) {
+                        if let Ok(blobs) = entry.value_mut() {
+                            blobs.retain(|bd| bd.sha256 != sha256);
+                        }
+                    }
+
+                    // Stop mirroring it, or we would put it right back
+                    if let Err(e) = GLOBALS.db().delete_blossom_blob(&sha256, None) {
+                        tracing::error!("{}", e);
+                    }
+                    GLOBALS.blossom_replication.remove(&sha256);
+
+                    GLOBALS
+                        .status_queue
+                        .write()
+                        .write(format!("Deleted {} from {}", sha256, base_url));
+                }
+                Err(e) => {
+                    GLOBALS
+                        .status_queue
+                        .write()
+                        .write(format!("Could not delete from {}: {}", base_url, e));
+                }
+            }
+        }));
+
+        Ok(())
+    }
+
+    /// List our blobs on each configured blossom server (BUD-02)
+    pub fn blossom_list(&mut self) -> Result<(), Error> {
+        let pubkey = match GLOBALS.identity.public_key() {
+            Some(pk) => pk,
+            None => return Err(ErrorKind::NoPublicKey.into()),
+        };
+        let blossom = crate::blossom::client()?;
+
+        GLOBALS.blossom_blobs.clear();
+        let blossom_servers = GLOBALS.db().read_setting_blossom_servers();
+        for server in blossom_servers.split_whitespace() {
+            let base_url = match crate::blossom::base_url(server) {
+                Ok(url) => url,
+                Err(e) => {
+                    GLOBALS
+                        .blossom_blobs
+                        .insert(server.to_owned(), Err(format!("{}", e)));
+                    continue;
+                }
+            };
+            std::mem::drop(tokio::spawn(async move {
+                let result = blossom
+                    .list(base_url.clone(), pubkey)
+                    .await
+                    .map_err(|e| format!("{}", e));
+                GLOBALS.blossom_blobs.insert(base_url, result);
+            }));
+        }
+
+        Ok(())
+    }
+
+    /// Mirror our blobs to any of our blossom servers that are missing them (BUD-04)
+    pub fn blossom_repair(&mut self) -> Result<(), Error> {
+        std::mem::drop(tokio::spawn(async move {
+            if let Err(e) = crate::blossom::repair_blobs().await {
+                tracing::error!("{}", e);
+            }
+        }));
+
+        Ok(())
+    }
+
     pub async fn blossom_upload(&mut self, pathbuf: PathBuf) -> Result<(), Error> {
         std::mem::drop(tokio::spawn(async move {
             if let Err(e) = Overlord::inner_blossom_upload(pathbuf.clone()).await {
@@ -1053,57 +1304,95 @@ impl Overlord {
     }
 
     async fn inner_blossom_upload(pathbuf: PathBuf) -> Result<(), Error> {
-        let blossom = match GLOBALS.blossom.get() {
-            Some(b) => b,
-            None => {
-                let blossom = Blossom::new()?;
-                let _ = GLOBALS.blossom.set(blossom);
-                GLOBALS.blossom.get().unwrap()
-            }
-        };
+        let blossom = crate::blossom::client()?;
 
         let base_url = {
             let blossom_servers = GLOBALS.db().read_setting_blossom_servers();
             let first = blossom_servers.split_whitespace().next();
             match first {
-                Some(bs) => {
-                    use http::uri::{Parts, PathAndQuery, Scheme};
-                    use http::Uri;
-
-                    let uri = bs.parse::<Uri>()?;
-                    let mut parts: Parts = uri.into_parts();
-                    parts.path_and_query = Some(PathAndQuery::from_static("/")); // Force no path
-                    if parts.scheme.is_none() {
-                        // Default to https
-                        parts.scheme = Some(Scheme::HTTPS);
-                    }
-                    let uri = Uri::from_parts(parts)?;
-                    format!("{}", uri)
-                }
+                Some(bs) => crate::blossom::base_url(bs)?,
                 None => return Err(ErrorKind::General("Blossom not configured".to_owned()).into()),
             }
         };
 
-        // metadata
-        let metadata = tokio::fs::metadata(&pathbuf).await?;
-
-        // hash
-        let hash = HashOutput::from_file(&pathbuf)?;
-
         // mime type
         let mime = crate::blossom::get_content_type(&pathbuf)?;
 
-        // open
-        let file = tokio::fs::File::open(&pathbuf).await?;
-
         // upload
-        let result = blossom
-            .upload(file, base_url, hash, mime, metadata.len())
-            .await;
-        if let Ok(ref bd) = result {
-            println!("UPLOADED:  {} -> {}", pathbuf.display(), &bd.url);
+        // (images are loaded so that we can describe them and remove location data)
+        let mut image_description: Option<((usize, usize), String)> = None;
+        let result = if mime.type_() == mime::IMAGE {
+            let bytes = tokio::fs::read(&pathbuf).await?;
+            let bytes = crate::media::strip_image_gps(bytes, &mime)?;
+            image_description = crate::media::image_dim_and_blurhash(bytes.clone()).await;
+            let hash = HashOutput::from_bytes(&bytes);
+            let len = bytes.len() as u64;
+            blossom
+                .upload(bytes, base_url, hash, mime.clone(), len)
+                .await
+        } else {
+            let metadata = tokio::fs::metadata(&pathbuf).await?;
+            let hash = HashOutput::from_file(&pathbuf)?;
+            let file = tokio::fs::File::open(&pathbuf).await?;
+            blossom
+                .upload(file, base_url, hash, mime.clone(), metadata.len())
+                .await
+        };
+
+        // describe it for a NIP-92 imeta tag
+        let uploaded = match result {
+            Ok(bd) => {
+                println!("UPLOADED:  {} -> {}", pathbuf.display(), &bd.url);
+
+                // Clients decide how to render by the file extension, so make sure it has one
+                let mut url = bd.url.clone();
+                let has_extension = url
+                    .rsplit('/')
+                    .next()
+                    .is_some_and(|name| name.contains('.'));
+                if !has_extension {
+                    if let Some(ext) = pathbuf.extension() {
+                        url.push('.');
+                        url.push_str(&ext.to_string_lossy());
+                    }
+                }
+
+                let mut file_metadata = FileMetadata::new(UncheckedUrl(url));
+                file_metadata.m = Some(mime.essence_str().to_owned());
+                file_metadata.x = Some(bd.sha256.clone());
+                file_metadata.size = Some(bd.size);
+                if let Some((dim, blurhash)) = image_description {
+                    file_metadata.dim = Some(dim);
+                    file_metadata.blurhash = Some(blurhash);
+                }
+                GLOBALS.blossom_uploads.insert(pathbuf, Ok(file_metadata));
+
+                Some(bd)
+            }
+            Err(e) => {
+                GLOBALS.blossom_uploads.insert(pathbuf, Err(e));
+                None
+            }
+        };
+
+        // mirror to our other servers (BUD-04)
+        // (the upload itself succeeded, so these errors are not returned)
+        if let Some(bd) = uploaded {
+            if let Err(e) = GLOBALS.db().write_blossom_blob(&bd, None) {
+                tracing::error!("{}", e);
+            }
+            match crate::blossom::replicate(blossom, &bd, &crate::blossom::our_servers()).await {
+                Ok(replication) => {
+                    if replication.is_under_replicated() {
+                        GLOBALS.status_queue.write().write(format!(
+                            "Upload could not be mirrored to {} of your blossom servers",
+                            replication.missing.len()
+                        ));
+                    }
+                }
+                Err(e) => tracing::error!("{}", e),
+            }
         }
-        GLOBALS.blossom_uploads.insert(pathbuf, result);
 
         Ok(())
     }
@@ -1128,13 +1417,42 @@ impl Overlord {
         Ok(())
     }
 
+    fn post_draft_wrap(&mut self, event: Event) -> Result<(), Error> {
+        // Process this event locally (ignore any error)
+        let _ = crate::process::process_new_event(&event, None, None, false, false);
+
+        let config_relays: Vec<RelayUrl> = Relay::choose_relay_urls(Relay::WRITE, |_| true)?;
+
+        manager::run_jobs_on_all_relays(
+            config_relays,
+            vec![RelayJob {
+                reason: RelayConnectionReason::PostEvent,
+                payload: ToMinionPayload {
+                    job_id: rand::random::<u64>(),
+                    detail: ToMinionPayloadDetail::PostEvents(vec![event.clone()]),
+                },
+            }],
+        );
+
+        Ok(())
+    }
+
     /// Adds or removes a bookmark, and publishes new bookmarks list
-    pub fn bookmark_add(&mut self, er: EventReference, private: bool) -> Result<(), Error> {
+    pub async fn bookmark_add(&mut self, er: EventReference, private: bool) -> Result<(), Error> {
+        if GLOBALS.bookmarks.read_arc().is_private_pending() {
+            GLOBALS.status_queue.write().write(
+                "Your remote signer is still decrypting your private bookmarks. Try again shortly."
+                    .to_owned(),
+            );
+            return Ok(());
+        }
+
         let added = GLOBALS.bookmarks.write_arc().add(er.clone(), private)?;
 
         if added {
             GLOBALS.recompute_current_bookmarks.notify_one();
-            let event = GLOBALS.bookmarks.read_arc().into_event()?;
+            let bookmarks = GLOBALS.bookmarks.read_arc().clone();
+            let event = bookmarks.into_event().await?;
             self.post_bookmarks(event)?;
 
             if let Some(event) = GLOBALS.db().read_event_reference(&er)? {
@@ -1152,12 +1470,21 @@ impl Overlord {
     }
 
     /// Adds or removes a bookmark, and publishes new bookmarks list
-    pub fn bookmark_rm(&mut self, er: EventReference) -> Result<(), Error> {
+    pub async fn bookmark_rm(&mut self, er: EventReference) -> Result<(), Error> {
+        if GLOBALS.bookmarks.read_arc().is_private_pending() {
+            GLOBALS.status_queue.write().write(
+                "Your remote signer is still decrypting your private bookmarks. Try again shortly."
+                    .to_owned(),
+            );
+            return Ok(());
+        }
+
         let removed = GLOBALS.bookmarks.write_arc().remove(er.clone())?;
 
         if removed {
             GLOBALS.recompute_current_bookmarks.notify_one();
-            let event = GLOBALS.bookmarks.read_arc().into_event()?;
+            let bookmarks = GLOBALS.bookmarks.read_arc().clone();
+            let event = bookmarks.into_event().await?;
             self.post_bookmarks(event)?;
 
             if let Some(event) = GLOBALS.db().read_event_reference(&er)? {
@@ -1231,6 +1558,50 @@ impl Overlord {
         Ok(())
     }
 
+    /// Ask relays that support NIP-45 to count events for us. The results show up in
+    /// `GLOBALS.relay_counts` (see [relay_count](crate::counts::relay_count)).
+    pub fn count(&mut self, targets: Vec<CountTarget>) -> Result<(), Error> {
+        let num_relays = GLOBALS.db().read_setting_num_relays_for_counting() as usize;
+        self.count_on_relays(targets, num_relays)
+    }
+
+    // Ask up to num_relays of the best relays that support NIP-45 to count the targets
+    // that have not been counted recently, as one job per relay
+    fn count_on_relays(
+        &mut self,
+        targets: Vec<CountTarget>,
+        num_relays: usize,
+    ) -> Result<(), Error> {
+        let targets = crate::counts::targets_needing_count(targets);
+        if targets.is_empty() {
+            return Ok(());
+        }
+
+        let mut relays = Relay::choose_relays(0, |r| {
+            r.is_good_for_advertise() && r.supports_nip(NIP_COUNT)
+        })?;
+        relays.sort_by(|a, b| {
+            b.adjusted_score(ScoreFactors::FULLY_ADJUSTED)
+                .partial_cmp(&a.adjusted_score(ScoreFactors::FULLY_ADJUSTED))
+                .unwrap()
+        });
+        relays.truncate(num_relays);
+        let relays: Vec<RelayUrl> = relays.iter().map(|r| r.url.clone()).collect();
+
+        manager::run_jobs_on_all_relays(
+            relays,
+            vec![RelayJob {
+                reason: RelayConnectionReason::Counting,
+                payload: ToMinionPayload {
+                    job_id: rand::random::<u64>(),
+                    detail: ToMinionPayloadDetail::Count(targets),
+                },
+            }],
+        );
+
+        Ok(())
+    }
+
     /// Remove any key delegation setup
     pub async fn delegation_reset() -> Result<(), Error> {
         if GLOBALS.delegation.reset() {
@@ -1245,7 +1616,7 @@ impl Overlord {
     }
 
     /// Delete a person list
-    pub fn delete_person_list(&mut self, list: PersonList) -> Result<(), Error> {
+    pub async fn delete_person_list(&mut self, list: PersonList) -> Result<(), Error> {
         // Get the metadata first, we need it to delete events
         let metadata = match GLOBALS.db().get_person_list_metadata(list)? {
             Some(m) => m,
@@ -1347,7 +1718,7 @@ impl Overlord {
             };
 
             // Should we add a pow? Maybe the relay needs it.
-            GLOBALS.identity.sign_event(pre_event)?
+            GLOBALS.identity.sign_event(pre_event).await?
         };
 
         // Process this event locally
@@ -1396,8 +1767,14 @@ impl Overlord {
         Ok(())
     }
 
+    /// Delete a saved draft, by replacing its draft wrap with a blank one
+    pub async fn delete_draft(&mut self, draft: Draft) -> Result<(), Error> {
+        let event = Draft::deletion_event(&draft.identifier, draft.kind).await?;
+        self.post_draft_wrap(event)
+    }
+
     /// Delete a post
-    pub fn delete_post(&mut self, id: Id) -> Result<(), Error> {
+    pub async fn delete_post(&mut self, id: Id) -> Result<(), Error> {
         let public_key = match GLOBALS.identity.public_key() {
             Some(pk) => pk,
             None => {
@@ -1428,7 +1805,7 @@ impl Overlord {
             };
 
             // Should we add a pow? Maybe the relay needs it.
-            GLOBALS.identity.sign_event(pre_event)?
+            GLOBALS.identity.sign_event(pre_event).await?
         };
 
         // Process this event locally
@@ -1500,6 +1877,24 @@ impl Overlord {
         Ok(())
     }
 
+    /// Fetch public chat channels (NIP-28) from our read relays, for the channel directory
+    pub fn fetch_channels(&mut self) -> Result<(), Error> {
+        let relays = Relay::choose_relay_urls(Relay::READ, |_| true)?;
+
+        manager::run_jobs_on_all_relays(
+            relays,
+            vec![RelayJob {
+                reason: RelayConnectionReason::SubscribeChannel,
+                payload: ToMinionPayload {
+                    job_id: rand::random::<u64>(),
+                    detail: ToMinionPayloadDetail::Subscribe(FilterSet::Channels),
+                },
+            }],
+        );
+
+        Ok(())
+    }
+
     /// Fetch an event from specific relays by event `Id`
     pub fn fetch_event(&mut self, id: Id, mut relay_urls: Vec<RelayUrl>) -> Result<(), Error> {
         // Use READ relays if relays are unknown
@@ -1551,6 +1946,23 @@ impl Overlord {
         Ok(())
     }
 
+    /// Follow a public chat channel (NIP-28) by the id of its creation event
+    pub fn follow_channel(&mut self, id: Id, relay: Option<RelayUrl>) -> Result<(), Error> {
+        GLOBALS.db().write_followed_channel(
+            &FollowedChannel {
+                id,
+                relay: relay.clone(),
+                followed_at: Unixtime::now(),
+            },
+            None,
+        )?;
+
+        // Get the channel creation event, so we can show its name
+        self.fetch_event(id, relay.into_iter().collect())?;
+
+        Ok(())
+    }
+
     /// Follow a person by `PublicKey`
     pub fn follow_pubkey(
         &mut self,
@@ -1618,6 +2030,31 @@ impl Overlord {
         Ok(())
     }
 
+    /// Hide a message in public chat channels (NIP-28)
+    pub async fn hide_channel_message(&mut self, id: Id, reason: String) -> Result<(), Error> {
+        let mut relays = Relay::choose_relay_urls(Relay::WRITE, |_| true)?;
+        if let Some(event) = GLOBALS.db().read_event(id)? {
+            if let Some(channel) = crate::channels::message_channel(&event) {
+                relays.extend(crate::channels::channel_relays(channel)?);
+            }
+        }
+
+        let tags = vec![ParsedTag::Event {
+            id,
+            recommended_relay_url: None,
+            marker: None,
+            author_pubkey: None,
+        }
+        .into_tag()];
+
+        Self::send_channel_moderation(EventKind::ChannelHideMessage, tags, reason, relays).await?;
+
+        GLOBALS.ui_invalidate_note(id);
+        GLOBALS.feed.sync_recompute();
+
+        Ok(())
+    }
+
     /// Hide or Show a relay. This adjusts the `hidden` a flag on the `Relay` record
     /// (You could easily do this yourself by talking to GLOBALS.db() directly too)
     pub fn hide_or_show_relay(relay_url: RelayUrl, hidden: bool) -> Result<(), Error> {
@@ -1683,6 +2120,165 @@ impl Overlord {
         Ok(())
     }
 
+    /// Ask the relay of a group (NIP-29) to let us in, and remember the group
+    pub async fn join_group(&mut self, relay_url: RelayUrl, group: String) -> Result<(), Error> {
+        Self::send_group_request(
+            relay_url.clone(),
+            &group,
+            crate::groups::join_request_event_kind(),
+        )
+        .await?;
+
+        GLOBALS.db().write_joined_group(
+            &JoinedGroup {
+                relay: relay_url.clone(),
+                id: group.clone(),
+                joined_at: Unixtime::now(),
+            },
+            None,
+        )?;
+
+        // Get the group metadata, so we can show its name
+        manager::run_jobs_on_all_relays(
+            vec![relay_url],
+            vec![RelayJob {
+                reason: RelayConnectionReason::SubscribeGroup,
+                payload: ToMinionPayload {
+                    job_id: rand::random::<u64>(),
+                    detail: ToMinionPayloadDetail::Subscribe(FilterSet::GroupMetadata(group)),
+                },
+            }],
+        );
+
+        Ok(())
+    }
+
+    /// Ask the relay of a group (NIP-29) to take us out, and forget the group
+    pub async fn leave_group(&mut self, relay_url: RelayUrl, group: String) -> Result<(), Error> {
+        Self::send_group_request(
+            relay_url.clone(),
+            &group,
+            crate::groups::leave_request_event_kind(),
+        )
+        .await?;
+
+        GLOBALS.db().delete_joined_group(&relay_url, &group, None)?;
+
+        Ok(())
+    }
+
+    // Join and leave requests carry nothing but the group id
+    async fn send_group_request(
+        relay_url: RelayUrl,
+        group: &str,
+        kind: EventKind,
+    ) -> Result<(), Error> {
+        let public_key = match GLOBALS.identity.public_key() {
+            Some(pk) => pk,
+            None => {
+                tracing::warn!("No public key! Not posting");
+                return Ok(());
+            }
+        };
+
+        let pre_event = PreEvent {
+            pubkey: public_key,
+            created_at: Unixtime::now(),
+            kind,
+            tags: vec![Tag::new(&["h", group])],
+            content: "".to_owned(),
+        };
+
+        let event = GLOBALS.identity.sign_event(pre_event).await?;
+
+        manager::run_jobs_on_all_relays(
+            vec![relay_url],
+            vec![RelayJob {
+                reason: RelayConnectionReason::PostEvent,
+                payload: ToMinionPayload {
+                    job_id: rand::random::<u64>(),
+                    detail: ToMinionPayloadDetail::PostEvents(vec![event]),
+                },
+            }],
+        );
+
+        Ok(())
+    }
+
+    /// Label (NIP-32) an event. An empty namespace uses the "ugc" namespace.
+    pub async fn label(&mut self, id: Id, namespace: String, label: String) -> Result<(), Error> {
+        let label = label.trim().to_owned();
+        if label.is_empty() {
+            return Ok(());
+        }
+        let namespace = match namespace.trim() {
+            "" => crate::labels::UGC_NAMESPACE.to_owned(),
+            ns => ns.to_owned(),
+        };
+
+        let event = {
+            let public_key = match GLOBALS.identity.public_key() {
+                Some(pk) => pk,
+                None => {
+                    tracing::warn!("No public key! Not posting");
+                    return Ok(());
+                }
+            };
+
+            let mut tags: Vec<Tag> = vec![
+                Tag::new(&["L", &namespace]),
+                Tag::new(&["l", &label, &namespace]),
+                Tag::new(&["e", &id.as_hex_string()]),
+            ];
+
+            // Label the address of parameterized replaceable events too, so the
+            // label follows new versions of the event
+            if let Some(labelled) = GLOBALS.db().read_event(id)? {
+                if let Some(d) = labelled.parameter() {
+                    let address = format!(
+                        "{}:{}:{}",
+                        u32::from(labelled.kind),
+                        labelled.pubkey.as_hex_string(),
+                        d
+                    );
+                    tags.push(Tag::new(&["a", &address]));
+                }
+            }
+
+            if GLOBALS.db().read_setting_set_client_tag() {
+                tags.push(Tag::new(&["client", "gossip"]));
+            }
+
+            let pre_event = PreEvent {
+                pubkey: public_key,
+                created_at: Unixtime::now(),
+                kind: EventKind::Label,
+                tags,
+                content: "".to_owned(),
+            };
+
+            GLOBALS.identity.sign_event(pre_event).await?
+        };
+
+        let relay_urls: Vec<RelayUrl> = relay::relays_to_post_to(&event)?;
+
+        manager::run_jobs_on_all_relays(
+            relay_urls,
+            vec![RelayJob {
+                reason: RelayConnectionReason::PostEvent,
+                payload: ToMinionPayload {
+                    job_id: rand::random::<u64>(),
+                    detail: ToMinionPayloadDetail::PostEvents(vec![event.clone()]),
+                },
+            }],
+        );
+
+        // Process the message for ourself
+        crate::process::process_new_event(&event, None, None, false, false)?;
+
+        Ok(())
+    }
+
     pub fn load_more(&mut self) -> Result<(), Error> {
         // Change the feed range:
         let anchor = GLOBALS.feed.load_more()?;
@@ -1755,12 +2351,158 @@ impl Overlord {
                     }],
                 );
             }
+            FeedKind::Channel(id) => {
+                let mut relays = crate::channels::channel_relays(id)?;
+                if relays.is_empty() {
+                    relays = Relay::choose_relay_urls(Relay::READ, |_| true)?;
+                }
+                manager::run_jobs_on_all_relays(
+                    relays,
+                    vec![RelayJob {
+                        reason: RelayConnectionReason::SubscribeChannel,
+                        payload: ToMinionPayload {
+                            job_id: rand::random::<u64>(),
+                            detail: ToMinionPayloadDetail::Subscribe(FilterSet::ChannelFeedChunk {
+                                channel: id,
+                                anchor,
+                            }),
+                        },
+                    }],
+                );
+            }
+            FeedKind::Group(relay_url, group) => {
+                manager::run_jobs_on_all_relays(
+                    vec![relay_url],
+                    vec![RelayJob {
+                        reason: RelayConnectionReason::SubscribeGroup,
+                        payload: ToMinionPayload {
+                            job_id: rand::random::<u64>(),
+                            detail: ToMinionPayloadDetail::Subscribe(FilterSet::GroupFeedChunk {
+                                group,
+                                anchor,
+                            }),
+                        },
+                    }],
+                );
+            }
             _ => (), // other feeds can't load more
         }
 
         Ok(())
     }
 
+    /// Mute a person in public chat channels (NIP-28). They are also put on the
+    /// mute list, so they are muted everywhere.
+    pub async fn mute_channel_user(
+        &mut self,
+        pubkey: PublicKey,
+        reason: String,
+    ) -> Result<(), Error> {
+        let relays = Relay::choose_relay_urls(Relay::WRITE, |_| true)?;
+
+        let tags = vec![ParsedTag::Pubkey {
+            pubkey,
+            recommended_relay_url: None,
+            petname: None,
+        }
+        .into_tag()];
+
+        Self::send_channel_moderation(EventKind::ChannelMuteUser, tags, reason, relays).await?;
+
+        GLOBALS.people.mute(&pubkey, true, Private(false))?;
+        GLOBALS.feed.sync_recompute();
+
+        Ok(())
+    }
+
+    // Hide message and mute user events carry the reason as JSON content
+    async fn send_channel_moderation(
+        kind: EventKind,
+        tags: Vec<Tag>,
+        reason: String,
+        relays: Vec<RelayUrl>,
+    ) -> Result<(), Error> {
+        let public_key = match GLOBALS.identity.public_key() {
+            Some(pk) => pk,
+            None => {
+                tracing::warn!("No public key! Not posting");
+                return Ok(());
+            }
+        };
+
+        let content = if reason.trim().is_empty() {
+            "".to_owned()
+        } else {
+            serde_json::json!({ "reason": reason.trim() }).to_string()
+        };
+
+        let pre_event = PreEvent {
+            pubkey: public_key,
+            created_at: Unixtime::now(),
+            kind,
+            tags,
+            content,
+        };
+
+        let event = GLOBALS.identity.sign_event(pre_event).await?;
+
+        // Process the event locally (ignore any errors)
+        let _ = crate::process::process_new_event(&event, None, None, false, false);
+
+        manager::run_jobs_on_all_relays(
+            relays,
+            vec![RelayJob {
+                reason: RelayConnectionReason::PostEvent,
+                payload: ToMinionPayload {
+                    job_id: rand::random::<u64>(),
+                    detail: ToMinionPayloadDetail::PostEvents(vec![event]),
+                },
+            }],
+        );
+
+        Ok(())
+    }
+
+    /// Log in with a remote signer (NIP-46) from a `bunker://` token
+    pub fn nip46_login(&mut self, token: String) -> Result<(), Error> {
+        if GLOBALS.identity.has_private_key() {
+            GLOBALS.status_queue.write().write(
+                "Delete your private key before logging in with a remote signer.".to_owned(),
+            );
+            return Ok(());
+        }
+
+        let token = BunkerToken::parse(&token)?;
+        let client = Nip46Client::new(token.remote_pubkey, token.relays)?;
+
+        GLOBALS
+            .status_queue
+            .write()
+            .write("Connecting to the remote signer...".to_owned());
+
+        // Connecting waits on the remote signer, so we do it in a separate task
+        std::mem::drop(tokio::task::spawn(async move {
+            let result = match client.connect(token.secret).await {
+                Ok(user_pubkey) => GLOBALS.identity.set_remote_signer(client, user_pubkey),
+                Err(e) => Err(e),
+            };
+            let message = match result {
+                Ok(()) => "Logged in with the remote signer.".to_owned(),
+                Err(e) => format!("Remote signer login failed: {e}"),
+            };
+            GLOBALS.status_queue.write().write(message);
+        }));
+
+        Ok(())
+    }
+
+    /// Stop signing with the remote signer (NIP-46). We keep the public key.
+    pub fn nip46_logout(&mut self) -> Result<(), Error> {
+        GLOBALS.identity.clear_remote_signer()?;
+        GLOBALS.ui_invalidate_all();
+        Ok(())
+    }
+
     /// Process approved nip46 server operation
     pub fn nip46_server_op_approval_response(
         &mut self,
@@ -1773,16 +2515,7 @@ impl Overlord {
 
         // Handle the request
         if let Some(mut server) = GLOBALS.db().read_nip46server(pubkey)? {
-            match parsed_command.method.as_str() {
-                "sign_event" => server.sign_approval = approval,
-                "nip04_encrypt" | "nip44_encrypt" => server.encrypt_approval = approval,
-                "nip04_decrypt" | "nip44_decrypt" => server.decrypt_approval = approval,
-                "nip44_get_key" => {
-                    server.encrypt_approval = approval;
-                    server.decrypt_approval = approval;
-                }
-                _ => {}
-            }
+            server.set_approval(parsed_command.method.as_str(), approval);
 
             // Save back
             GLOBALS.db().write_nip46server(&server, None)?;
@@ -1835,7 +2568,7 @@ impl Overlord {
 
     /// React to a post. The backend doesn't read the event, so you have to supply the
     /// pubkey author too.
-    pub fn react(&mut self, id: Id, pubkey: PublicKey, reaction: char) -> Result<(), Error> {
+    pub async fn react(&mut self, id: Id, pubkey: PublicKey, reaction: char) -> Result<(), Error> {
         let event = {
             let public_key = match GLOBALS.identity.public_key() {
                 Some(pk) => pk,
@@ -1882,9 +2615,10 @@ impl Overlord {
                 });
                 GLOBALS
                     .identity
-                    .sign_event_with_pow(pre_event, powint, Some(work_sender))?
+                    .sign_event_with_pow(pre_event, powint, Some(work_sender))
+                    .await?
             } else {
-                GLOBALS.identity.sign_event(pre_event)?
+                GLOBALS.identity.sign_event(pre_event).await?
             }
         };
 
@@ -1918,6 +2652,7 @@ impl Overlord {
         in_reply_to: Option<Id>,
         annotation: bool,
         dm_channel: Option<DmChannel>,
+        expiration: Option<Unixtime>,
     ) -> Result<(), Error> {
         let author = match GLOBALS.identity.public_key() {
             Some(pk) => pk,
@@ -1934,7 +2669,7 @@ impl Overlord {
                     crate::post::prepare_post_nip17(author, content, tags, channel, annotation)
                         .await?
                 } else {
-                    crate::post::prepare_post_nip04(author, content, channel, annotation)?
+                    crate::post::prepare_post_nip04(author, content, channel, annotation).await?
                 }
             }
             None => {
@@ -1951,15 +2686,20 @@ impl Overlord {
                             tags,
                             Some(parent),
                             annotation,
+                            expiration,
                         )
                         .await?
                     } else {
-                        crate::post::prepare_post_comment(author, content, tags, parent, annotation)
-                            .await?
+                        crate::post::prepare_post_comment(
+                            author, content, tags, parent, annotation, expiration,
+                        )
+                        .await?
                     }
                 } else {
-                    crate::post::prepare_post_normal(author, content, tags, None, annotation)
-                        .await?
+                    crate::post::prepare_post_normal(
+                        author, content, tags, None, annotation, expiration,
+                    )
+                    .await?
                 }
             }
         };
@@ -2004,28 +2744,169 @@ impl Overlord {
                     );
                 }
             }
-        }));
+        }));
+
+        Ok(())
+    }
+
+    pub fn post_again(&mut self, event: Event) -> Result<(), Error> {
+        let relay_urls = relay::relays_to_post_to(&event)?;
+
+        for url in &relay_urls {
+            tracing::debug!("Asking {} to post", url);
+        }
+
+        manager::run_jobs_on_all_relays(
+            relay_urls,
+            vec![RelayJob {
+                reason: RelayConnectionReason::PostEvent,
+                payload: ToMinionPayload {
+                    job_id: rand::random::<u64>(),
+                    detail: ToMinionPayloadDetail::PostEvents(vec![event.clone()]),
+                },
+            }],
+        );
+
+        Ok(())
+    }
+
+    /// Post a message into a public chat channel (NIP-28)
+    pub async fn post_channel(
+        &mut self,
+        channel: Id,
+        content: String,
+        tags: Vec<Tag>,
+        in_reply_to: Option<Id>,
+    ) -> Result<(), Error> {
+        let author = match GLOBALS.identity.public_key() {
+            Some(pk) => pk,
+            None => {
+                tracing::warn!("No public key! Not posting");
+                return Ok(());
+            }
+        };
+
+        let prepared_events =
+            crate::post::prepare_post_channel(author, channel, content, tags, in_reply_to).await?;
+
+        for (event, relay_urls) in prepared_events {
+            // Process the event locally (ignore any errors)
+            let _ = crate::process::process_new_event(&event, None, None, false, false);
+
+            for url in &relay_urls {
+                tracing::debug!("Asking {} to post", url);
+            }
+
+            manager::run_jobs_on_all_relays(
+                relay_urls,
+                vec![RelayJob {
+                    reason: RelayConnectionReason::PostEvent,
+                    payload: ToMinionPayload {
+                        job_id: rand::random::<u64>(),
+                        detail: ToMinionPayloadDetail::PostEvents(vec![event.clone()]),
+                    },
+                }],
+            );
+        }
+
+        // Sync recompute their feeds right now (so they can see what they posted)
+        GLOBALS.feed.sync_recompute();
+
+        Ok(())
+    }
+
+    /// Post into a relay-based group (NIP-29). Posts with a title start a thread.
+    pub async fn post_group(
+        &mut self,
+        relay_url: RelayUrl,
+        group: String,
+        content: String,
+        tags: Vec<Tag>,
+        in_reply_to: Option<Id>,
+        title: Option<String>,
+    ) -> Result<(), Error> {
+        let author = match GLOBALS.identity.public_key() {
+            Some(pk) => pk,
+            None => {
+                tracing::warn!("No public key! Not posting");
+                return Ok(());
+            }
+        };
+
+        let prepared_events = crate::post::prepare_post_group(
+            author,
+            relay_url,
+            group,
+            content,
+            tags,
+            in_reply_to,
+            title,
+        )
+        .await?;
+
+        for (event, relay_urls) in prepared_events {
+            // Process the event locally (ignore any errors)
+            let _ = crate::process::process_new_event(&event, None, None, false, false);
+
+            for url in &relay_urls {
+                tracing::debug!("Asking {} to post", url);
+            }
+
+            manager::run_jobs_on_all_relays(
+                relay_urls,
+                vec![RelayJob {
+                    reason: RelayConnectionReason::PostEvent,
+                    payload: ToMinionPayload {
+                        job_id: rand::random::<u64>(),
+                        detail: ToMinionPayloadDetail::PostEvents(vec![event.clone()]),
+                    },
+                }],
+            );
+        }
+
+        // Sync recompute their feeds right now (so they can see what they posted)
+        GLOBALS.feed.sync_recompute();
 
         Ok(())
     }
 
-    pub fn post_again(&mut self, event: Event) -> Result<(), Error> {
-        let relay_urls = relay::relays_to_post_to(&event)?;
+    /// Publish a long-form article (NIP-23), replacing any earlier version
+    /// with the same identifier
+    pub async fn post_long_form(&mut self, article: LongFormArticle) -> Result<(), Error> {
+        let author = match GLOBALS.identity.public_key() {
+            Some(pk) => pk,
+            None => {
+                tracing::warn!("No public key! Not posting");
+                return Ok(());
+            }
+        };
 
-        for url in &relay_urls {
-            tracing::debug!("Asking {} to post", url);
+        let prepared_events = crate::post::prepare_post_long_form(author, article).await?;
+
+        for (event, relay_urls) in prepared_events {
+            // Process the event locally (this replaces the prior version)
+            crate::process::process_new_event(&event, None, None, false, false)?;
+
+            for url in &relay_urls {
+                tracing::debug!("Asking {} to post", url);
+            }
+
+            manager::run_jobs_on_all_relays(
+                relay_urls,
+                vec![RelayJob {
+                    reason: RelayConnectionReason::PostEvent,
+                    payload: ToMinionPayload {
+                        job_id: rand::random::<u64>(),
+                        detail: ToMinionPayloadDetail::PostEvents(vec![event.clone()]),
+                    },
+                }],
+            );
         }
 
-        manager::run_jobs_on_all_relays(
-            relay_urls,
-            vec![RelayJob {
-                reason: RelayConnectionReason::PostEvent,
-                payload: ToMinionPayload {
-                    job_id: rand::random::<u64>(),
-                    detail: ToMinionPayloadDetail::PostEvents(vec![event.clone()]),
-                },
-            }],
-        );
+        GLOBALS
+            .status_queue
+            .write()
+            .write("Article published.".to_owned());
 
         Ok(())
     }
@@ -2059,6 +2940,56 @@ impl Overlord {
         Ok(())
     }
 
+    /// Post one of our events to a relay that wants more proof of work than it
+    /// has. The event is mined again with `difficulty` bits of work, which makes
+    /// it a new event that only goes to that relay. The mining happens in the
+    /// background.
+    pub fn post_with_pow(relay_url: RelayUrl, event: Event, difficulty: u8) {
+        let pre_event = PreEvent {
+            pubkey: event.pubkey,
+            created_at: event.created_at,
+            kind: event.kind,
+            tags: event
+                .tags
+                .iter()
+                .filter(|t| t.tagname() != "nonce")
+                .cloned()
+                .collect(),
+            content: event.content,
+        };
+
+        std::mem::drop(tokio::spawn(async move {
+            let (work_sender, work_receiver) = mpsc::channel();
+            std::thread::spawn(move || {
+                work_logger(work_receiver, difficulty);
+            });
+            let event = match GLOBALS
+                .identity
+                .sign_event_with_pow(pre_event, difficulty, Some(work_sender))
+                .await
+            {
+                Ok(event) => event,
+                Err(e) => {
+                    tracing::error!("Proof of work for {}: {}", &relay_url, e);
+                    return;
+                }
+            };
+
+            tracing::debug!("Asking {} to post with proof of work", &relay_url);
+
+            manager::run_jobs_on_all_relays(
+                vec![relay_url],
+                vec![RelayJob {
+                    reason: RelayConnectionReason::PostEvent,
+                    payload: ToMinionPayload {
+                        job_id: rand::random::<u64>(),
+                        detail: ToMinionPayloadDetail::PostEvents(vec![event]),
+                    },
+                }],
+            );
+        }));
+    }
+
     /// Prune the cache (downloaded files)
     pub async fn prune_cache() -> Result<(), Error> {
         GLOBALS
@@ -2081,6 +3012,35 @@ impl Overlord {
         Ok(())
     }
 
+    /// Measure how much disk space the cache (downloaded files) uses
+    pub async fn measure_cache() -> Result<(), Error> {
+        let usage = GLOBALS.fetcher.measure_cache().await?;
+
+        GLOBALS.status_queue.write().write(format!(
+            "Cache uses {} MB in {} files.",
+            usage.bytes() / 1_048_576,
+            usage.files()
+        ));
+
+        Ok(())
+    }
+
+    /// Trim the cache (downloaded files) down to the maximum size setting,
+    /// removing the least recently used files first
+    pub async fn trim_cache() -> Result<(), Error> {
+        let max_bytes = GLOBALS.db().read_setting_cache_max_size_mb() * 1_048_576;
+
+        let (count, freed) = GLOBALS.fetcher.trim_cache(max_bytes).await?;
+
+        GLOBALS.status_queue.write().write(format!(
+            "Cache has been trimmed. {} files removed, {} MB freed.",
+            count,
+            freed / 1_048_576
+        ));
+
+        Ok(())
+    }
+
     /// Prune old events from the database
     pub fn prune_old_events() -> Result<(), Error> {
         GLOBALS
@@ -2098,6 +3058,8 @@ impl Overlord {
                 0,
             );
         let count = GLOBALS.db().prune_old_events(then)?;
+        GLOBALS.db().prune_nip46_audit_log(then)?;
+        GLOBALS.db().prune_relay_stats(RelayStats::day_of(then))?;
 
         GLOBALS.status_queue.write().write(format!(
             "Database has been pruned. {} events removed.",
@@ -2165,7 +3127,7 @@ impl Overlord {
             content: "".to_string(),
         };
 
-        let event = GLOBALS.identity.sign_event(pre_event)?;
+        let event = GLOBALS.identity.sign_event(pre_event).await?;
 
         let config_relays: Vec<RelayUrl> = Relay::choose_relay_urls(Relay::WRITE, |_| true)?;
 
@@ -2216,7 +3178,7 @@ impl Overlord {
     }
 
     /// Publish the user's metadata
-    pub fn push_metadata(&mut self, metadata: Metadata) -> Result<(), Error> {
+    pub async fn push_metadata(&mut self, metadata: Metadata) -> Result<(), Error> {
         let public_key = match GLOBALS.identity.public_key() {
             Some(pk) => pk,
             None => return Err((ErrorKind::NoPrivateKey, file!(), line!()).into()), // not even a public key
@@ -2230,7 +3192,7 @@ impl Overlord {
             content: serde_json::to_string(&metadata)?,
         };
 
-        let event = GLOBALS.identity.sign_event(pre_event)?;
+        let event = GLOBALS.identity.sign_event(pre_event).await?;
 
         // Push to all of the relays we post to
         // Send it the event to pull our followers
@@ -2300,8 +3262,69 @@ impl Overlord {
         Ok(())
     }
 
+    /// Report (NIP-56) an event by `Id` with one of the [REPORT_TYPES](crate::REPORT_TYPES)
+    pub async fn report(
+        &mut self,
+        id: Id,
+        pubkey: PublicKey,
+        report_type: String,
+    ) -> Result<(), Error> {
+        let event = {
+            let public_key = match GLOBALS.identity.public_key() {
+                Some(pk) => pk,
+                None => {
+                    tracing::warn!("No public key! Not posting");
+                    return Ok(());
+                }
+            };
+
+            // The report type goes in the 3rd place of the tag being reported
+            let mut tags: Vec<Tag> = vec![
+                Tag::new(&["e", &id.as_hex_string(), &report_type]),
+                Tag::new(&["p", &pubkey.as_hex_string()]),
+            ];
+
+            if GLOBALS.db().read_setting_set_client_tag() {
+                tags.push(Tag::new(&["client", "gossip"]));
+            }
+
+            let pre_event = PreEvent {
+                pubkey: public_key,
+                created_at: Unixtime::now(),
+                kind: EventKind::Reporting,
+                tags,
+                content: "".to_owned(),
+            };
+
+            GLOBALS.identity.sign_event(pre_event).await?
+        };
+
+        let relay_urls: Vec<RelayUrl> = relay::relays_to_post_to(&event)?;
+
+        manager::run_jobs_on_all_relays(
+            relay_urls,
+            vec![RelayJob {
+                reason: RelayConnectionReason::PostEvent,
+                payload: ToMinionPayload {
+                    job_id: rand::random::<u64>(),
+                    detail: ToMinionPayloadDetail::PostEvents(vec![event.clone()]),
+                },
+            }],
+        );
+
+        // Process the message for ourself
+        crate::process::process_new_event(&event, None, None, false, false)?;
+
+        GLOBALS
+            .status_queue
+            .write()
+            .write("Report sent.".to_owned());
+
+        Ok(())
+    }
+
     /// Repost a post by `Id`
-    pub fn repost(&mut self, id: Id) -> Result<(), Error> {
+    pub async fn repost(&mut self, id: Id) -> Result<(), Error> {
         let reposted_event = match GLOBALS.db().read_event(id)? {
             Some(event) => event,
             None => {
@@ -2411,9 +3434,10 @@ impl Overlord {
                 });
                 GLOBALS
                     .identity
-                    .sign_event_with_pow(pre_event, powint, Some(work_sender))?
+                    .sign_event_with_pow(pre_event, powint, Some(work_sender))
+                    .await?
             } else {
-                GLOBALS.identity.sign_event(pre_event)?
+                GLOBALS.identity.sign_event(pre_event).await?
             }
         };
 
@@ -2448,6 +3472,19 @@ impl Overlord {
         Ok(())
     }
 
+    /// Save a draft, encrypted to ourself (NIP-37), locally and to our write relays
+    pub async fn save_draft(&mut self, draft: Draft) -> Result<(), Error> {
+        let event = draft.to_event().await?;
+        self.post_draft_wrap(event)?;
+
+        GLOBALS
+            .status_queue
+            .write()
+            .write("Draft saved.".to_owned());
+
+        Ok(())
+    }
+
     /// Search people and notes in the local database.
     /// Search results eventually arrive in `GLOBALS.people_search_results` and `GLOBALS.note_search_results`
     pub fn search_locally(mut text: String) -> Result<(), Error> {
@@ -2636,6 +3673,44 @@ impl Overlord {
         Ok(())
     }
 
+    fn set_channel_feed(&mut self, id: Id, anchor: Unixtime) -> Result<(), Error> {
+        let mut relays = crate::channels::channel_relays(id)?;
+        if relays.is_empty() {
+            relays = Relay::choose_relay_urls(Relay::READ, |_| true)?;
+        }
+
+        // Get the channel creation event if we don't have it yet
+        self.fetch_event(id, relays.clone())?;
+
+        manager::run_jobs_on_all_relays(
+            relays,
+            vec![
+                RelayJob {
+                    reason: RelayConnectionReason::SubscribeChannel,
+                    payload: ToMinionPayload {
+                        job_id: rand::random::<u64>(),
+                        detail: ToMinionPayloadDetail::Subscribe(FilterSet::ChannelFeedFuture {
+                            channel: id,
+                            anchor,
+                        }),
+                    },
+                },
+                RelayJob {
+                    reason: RelayConnectionReason::SubscribeChannel,
+                    payload: ToMinionPayload {
+                        job_id: rand::random::<u64>(),
+                        detail: ToMinionPayloadDetail::Subscribe(FilterSet::ChannelFeedChunk {
+                            channel: id,
+                            anchor,
+                        }),
+                    },
+                },
+            ],
+        );
+
+        Ok(())
+    }
+
     fn set_dm_channel(&mut self, dmchannel: DmChannel) -> Result<(), Error> {
         // subscribe to channel on outbox and inbox relays
         //   outbox: you may have written them there. Other clients may have too.
@@ -2689,6 +3764,48 @@ impl Overlord {
         Ok(())
     }
 
+    fn set_group_feed(
+        &mut self,
+        relay_url: RelayUrl,
+        group: String,
+        anchor: Unixtime,
+    ) -> Result<(), Error> {
+        manager::run_jobs_on_all_relays(
+            vec![relay_url],
+            vec![
+                RelayJob {
+                    reason: RelayConnectionReason::SubscribeGroup,
+                    payload: ToMinionPayload {
+                        job_id: rand::random::<u64>(),
+                        detail: ToMinionPayloadDetail::Subscribe(FilterSet::GroupFeedFuture {
+                            group: group.clone(),
+                            anchor,
+                        }),
+                    },
+                },
+                RelayJob {
+                    reason: RelayConnectionReason::SubscribeGroup,
+                    payload: ToMinionPayload {
+                        job_id: rand::random::<u64>(),
+                        detail: ToMinionPayloadDetail::Subscribe(FilterSet::GroupFeedChunk {
+                            group: group.clone(),
+                            anchor,
+                        }),
+                    },
+                },
+                RelayJob {
+                    reason: RelayConnectionReason::SubscribeGroup,
+                    payload: ToMinionPayload {
+                        job_id: rand::random::<u64>(),
+                        detail: ToMinionPayloadDetail::Subscribe(FilterSet::GroupMetadata(group)),
+                    },
+                },
+            ],
+        );
+
+        Ok(())
+    }
+
     fn set_person_feed(&mut self, pubkey: PublicKey, anchor: Unixtime) -> Result<(), Error> {
         let relays: Vec<RelayUrl> = relay::get_some_pubkey_outboxes(pubkey)?;
         manager::run_jobs_on_all_relays(
@@ -3043,7 +4160,7 @@ impl Overlord {
                 content: "".to_string(),
             };
 
-            GLOBALS.identity.sign_event(pre_event)?
+            GLOBALS.identity.sign_event(pre_event).await?
         };
 
         // Process this event locally
@@ -3102,6 +4219,10 @@ impl Overlord {
         if let Some(nip46unconnected) = GLOBALS.db().read_nip46_unconnected_server()? {
             relays.extend(nip46unconnected.relays);
         }
+        // And to the relays of our remote signer, if we sign with one
+        if let Some(remote) = GLOBALS.identity.remote_signer() {
+            relays.extend(remote.relays.clone());
+        }
         relays.sort();
         relays.dedup();
         self.subscribe_nip46(relays)?;
@@ -3362,7 +4483,7 @@ impl Overlord {
                 tags: vec![],
                 content: format!("This is an automated test of the suitability of {} for inbox/outbox/dm usage. Please disregard.", relay_url),
             };
-            GLOBALS.identity.sign_event(pre_event)?
+            GLOBALS.identity.sign_event(pre_event).await?
         };
 
         let mut conn1 = crate::direct::Connection::new(relay_url.as_str().to_owned()).await?;
@@ -3492,6 +4613,12 @@ impl Overlord {
         })
     }
 
+    /// Stop following a public chat channel (NIP-28)
+    pub fn unfollow_channel(&mut self, id: Id) -> Result<(), Error> {
+        GLOBALS.db().delete_followed_channel(id, None)?;
+        Ok(())
+    }
+
     /// Unlock the private key with the given passphrase so that gossip can use it.
     /// This is akin to logging in.
     pub fn unlock_key(mut password: String) -> Result<(), Error> {
@@ -3604,6 +4731,24 @@ impl Overlord {
             }
         };
 
+        // Decrypt the private entries before opening a write transaction, as a
+        // remote signer may take a while to answer
+        let mut private_tags: Vec<Tag> = Vec::new();
+        if list != PersonList::Followed && !event.content.is_empty() {
+            if GLOBALS.identity.is_unlocked() {
+                let decrypted_content = GLOBALS
+                    .identity
+                    .decrypt_and_wait(&my_pubkey, &event.content)
+                    .await?;
+                private_tags = serde_json::from_str(&decrypted_content)?;
+            } else {
+                // If we need to decrypt contents but can't, let them know we couldn't read that part
+                GLOBALS.status_queue.write().write(
+                    format!("Since you are not logged in, the encrypted contents of the list {} will not be processed.", metadata.title),
+                );
+            }
+        }
+
         let now = Unixtime::now();
 
         let mut txn = GLOBALS.db().get_write_txn()?;
@@ -3637,27 +4782,13 @@ impl Overlord {
             }
         }
 
-        if list != PersonList::Followed && !event.content.is_empty() {
-            if GLOBALS.identity.is_unlocked() {
-                // Private entries
-                let decrypted_content = GLOBALS.identity.decrypt(&my_pubkey, &event.content)?;
-
-                let tags: Vec<Tag> = serde_json::from_str(&decrypted_content)?;
-
-                for tag in &tags {
-                    if let Ok(ParsedTag::Pubkey { pubkey, .. }) = tag.parse() {
-                        // Save the pubkey
-                        entries.push((pubkey.to_owned(), Private(true)));
-                    }
-                    if let Ok(ParsedTag::Title(title)) = tag.parse() {
-                        metadata.title = title.to_owned();
-                    }
-                }
-            } else {
-                // If we need to decrypt contents but can't, let them know we couldn't read that part
-                GLOBALS.status_queue.write().write(
-                    format!("Since you are not logged in, the encrypted contents of the list {} will not be processed.", metadata.title),
-                );
+        for tag in &private_tags {
+            if let Ok(ParsedTag::Pubkey { pubkey, .. }) = tag.parse() {
+                // Save the pubkey
+                entries.push((pubkey.to_owned(), Private(true)));
+            }
+            if let Ok(ParsedTag::Title(title)) = tag.parse() {
+                metadata.title = title.to_owned();
             }
         }
 
@@ -3867,6 +4998,14 @@ impl Overlord {
     ///
     /// WARNING: DO NOT CALL TOO OFTEN or relays will hate you.
     pub fn visible_notes_changed(&mut self, mut visible: Vec<Id>) -> Result<(), Error> {
+        // Relays that support NIP-45 can also count replies and reactions beyond the
+        // ones we have. A few of them are enough.
+        let count_targets: Vec<CountTarget> = visible
+            .iter()
+            .flat_map(|id| [CountTarget::Replies(*id), CountTarget::Reactions(*id)])
+            .collect();
+        self.count_on_relays(count_targets, AUGMENT_COUNT_RELAYS)?;
+
         // Work out which relays to use to find augments for which ids
         let mut augment_subs: HashMap<RelayUrl, Vec<Id>> = HashMap::new();
         for id in visible.drain(..) {
@@ -3887,16 +5026,15 @@ impl Overlord {
 
         // Create jobs for minions
         for (relay_url, ids) in augment_subs.drain() {
-            manager::engage_minion(
-                relay_url,
-                vec![RelayJob {
-                    reason: RelayConnectionReason::FetchAugments,
-                    payload: ToMinionPayload {
-                        job_id: rand::random::<u64>(),
-                        detail: ToMinionPayloadDetail::Subscribe(FilterSet::Augments(ids)),
-                    },
-                }],
-            );
+            let jobs = vec![RelayJob {
+                reason: RelayConnectionReason::FetchAugments,
+                payload: ToMinionPayload {
+                    job_id: rand::random::<u64>(),
+                    detail: ToMinionPayloadDetail::Subscribe(FilterSet::Augments(ids)),
+                },
+            }];
+
+            manager::engage_minion(relay_url, jobs);
         }
 
         Ok(())
@@ -3923,7 +5061,7 @@ impl Overlord {
 
         *GLOBALS.current_zap.write() = ZapState::CheckingLnurl(id, target_pubkey, lnurl.clone());
 
-        let client = reqwest::Client::builder()
+        let client = crate::proxy::http_client_builder()?
             .timeout(std::time::Duration::new(15, 0))
             .gzip(true)
             .brotli(true)
@@ -3964,6 +5102,8 @@ impl Overlord {
     }
 
     /// Complete a zap on the note with Id and author PublicKey by setting a value and a comment.
+    ///
+    /// If there is a [PaymentHandler](crate::payment::PaymentHandler), it pays the invoice.
     pub async fn zap(
         &mut self,
         id: Id,
@@ -4102,11 +5242,11 @@ impl Overlord {
             content: comment,
         };
 
-        let event = GLOBALS.identity.sign_event(pre_event)?;
+        let event = GLOBALS.identity.sign_event(pre_event).await?;
 
         let serialized_event = serde_json::to_string(&event)?;
 
-        let client = reqwest::Client::builder()
+        let client = crate::proxy::http_client_builder()?
             .timeout(std::time::Duration::new(15, 0))
             .gzip(true)
             .brotli(true)
@@ -4134,7 +5274,32 @@ impl Overlord {
         if let Value::Object(map) = value {
             if let Some(Value::String(s)) = map.get("pr") {
                 tracing::debug!("Zap Invoice = {}", s);
+
+                // Only pay for what we asked for
+                let payment_hash = match crate::zaps::check_zap_invoice(s, msats, &serialized_event)
+                {
+                    Ok(payment_hash) => payment_hash,
+                    Err(invalid) => {
+                        tracing::warn!("Zap invoice {} refused: {}", s, invalid);
+                        *GLOBALS.current_zap.write() = ZapState::None;
+                        GLOBALS.status_queue.write().write(format!(
+                                "The zap invoice is not what we asked for ({}), so it will not be paid.",
+                                invalid
+                            ));
+                        return Ok(());
+                    }
+                };
+
                 *GLOBALS.current_zap.write() = ZapState::ReadyToPay(id, s.to_owned());
+
+                // Pay it ourselves if we can
+                if let Some(handler) = crate::payment::payment_handler() {
+                    let invoice = s.to_owned();
+                    std::mem::drop(tokio::spawn(async move {
+                        crate::payment::pay_zap(handler, id, invoice, msats, payment_hash).await;
+                    }));
+                }
+
                 return Ok(());
             }
         }
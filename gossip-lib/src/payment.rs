@@ -0,0 +1,283 @@
+use crate::error::{Error, ErrorKind};
+use crate::globals::GLOBALS;
+use crate::misc::ZapState;
+use futures::future::BoxFuture;
+use nostr_types::{Id, MilliSatoshi};
+use serde_json::Value;
+use sha2::{Digest, Sha256};
+use std::process::Stdio;
+use std::sync::{Arc, RwLock};
+use std::time::Duration;
+
+/// How long we wait for a payment handler to pay an invoice
+const PAYMENT_TIMEOUT: Duration = Duration::from_secs(120);
+
+/// Something that pays lightning invoices for us, so that zaps complete without
+/// the user paying the invoice by hand.
+pub trait PaymentHandler: Send + Sync {
+    /// A name for this handler to show the user
+    fn name(&self) -> String;
+
+    /// Pay the bolt11 invoice, returning the payment preimage as a hex string
+    fn pay_invoice(&self, invoice: String) -> BoxFuture<'_, Result<String, Error>>;
+}
+
+/// Pays invoices by running a local command, such as a CLI wallet.
+///
+/// The invoice replaces the argument `{invoice}`, or is appended as the last argument
+/// if there is no such argument. The command must print the payment preimage, either
+/// alone or as a `preimage` (or `payment_preimage`) field of a JSON object.
+#[derive(Debug, Clone)]
+pub struct CommandPaymentHandler {
+    command: String,
+}
+
+impl CommandPaymentHandler {
+    pub fn new(command: String) -> CommandPaymentHandler {
+        CommandPaymentHandler { command }
+    }
+}
+
+impl PaymentHandler for CommandPaymentHandler {
+    fn name(&self) -> String {
+        match self.command.split_whitespace().next() {
+            Some(program) => program.to_owned(),
+            None => "payment command".to_owned(),
+        }
+    }
+
+    fn pay_invoice(&self, invoice: String) -> BoxFuture<'_, Result<String, Error>> {
+        Box::pin(run_payment_command(&self.command, invoice))
+    }
+}
+
+async fn run_payment_command(command: &str, invoice: String) -> Result<String, Error> {
+    let payment_hash = crate::zaps::invoice_payment_hash(&invoice)
+        .ok_or::<Error>(ErrorKind::General("The invoice has no payment hash".to_owned()).into())?;
+
+    let mut parts = command.split_whitespace();
+    let program = parts
+        .next()
+        .ok_or::<Error>(ErrorKind::General("No payment command".to_owned()).into())?;
+
+    let mut args: Vec<String> = Vec::new();
+    let mut substituted = false;
+    for part in parts {
+        if part.contains("{invoice}") {
+            args.push(part.replace("{invoice}", &invoice));
+            substituted = true;
+        } else {
+            args.push(part.to_owned());
+        }
+    }
+    if !substituted {
+        args.push(invoice);
+    }
+
+    let output = tokio::process::Command::new(program)
+        .args(&args)
+        .stdin(Stdio::null())
+        .kill_on_drop(true)
+        .output()
+        .await?;
+
+    if !output.status.success() {
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        return Err(ErrorKind::General(format!(
+            "{} failed ({}): {}",
+            program,
+            output.status,
+            stderr.trim()
+        ))
+        .into());
+    }
+
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    match find_preimage(&stdout, &payment_hash) {
+        Some(preimage) => Ok(preimage),
+        None => {
+            Err(ErrorKind::General(format!("{program} did not print a payment preimage")).into())
+        }
+    }
+}
+
+// Find the payment preimage in the output of a wallet. Only a preimage of the
+// payment hash counts, as wallets may print other hashes too.
+fn find_preimage(output: &str, payment_hash: &[u8; 32]) -> Option<String> {
+    fn is_preimage(s: &str, payment_hash: &[u8; 32]) -> bool {
+        s.len() == 64
+            && s.chars().all(|c| c.is_ascii_hexdigit())
+            && preimage_matches(s, payment_hash)
+    }
+
+    if let Ok(Value::Object(map)) = serde_json::from_str::<Value>(output.trim()) {
+        for key in ["preimage", "payment_preimage", "paymentPreimage"] {
+            if let Some(Value::String(s)) = map.get(key) {
+                if is_preimage(s, payment_hash) {
+                    return Some(s.to_lowercase());
+                }
+            }
+        }
+        return None;
+    }
+
+    output
+        .split(|c: char| !c.is_ascii_hexdigit())
+        .find(|s| is_preimage(s, payment_hash))
+        .map(|s| s.to_lowercase())
+}
+
+// Whether a preimage (in hex) hashes to the payment hash
+fn preimage_matches(preimage: &str, payment_hash: &[u8; 32]) -> bool {
+    match hex::decode(preimage) {
+        Ok(bytes) => Sha256::digest(bytes).as_slice() == payment_hash,
+        Err(_) => false,
+    }
+}
+
+static PAYMENT_HANDLER: RwLock<Option<Arc<dyn PaymentHandler>>> = RwLock::new(None);
+
+/// Set the handler that pays zap invoices. This takes precedence over the
+/// `zap_payment_command` setting. Set None to go back to that setting.
+pub fn set_payment_handler(handler: Option<Arc<dyn PaymentHandler>>) {
+    *PAYMENT_HANDLER.write().unwrap() = handler;
+}
+
+/// The handler that pays zap invoices, if there is one
+pub fn payment_handler() -> Option<Arc<dyn PaymentHandler>> {
+    if let Some(handler) = &*PAYMENT_HANDLER.read().unwrap() {
+        return Some(handler.clone());
+    }
+
+    let command = GLOBALS.db().read_setting_zap_payment_command();
+    if command.trim().is_empty() {
+        None
+    } else {
+        Some(Arc::new(CommandPaymentHandler::new(command)))
+    }
+}
+
+/// Pay the invoice of the zap on the note with Id, moving the zap to
+/// `ZapState::Paid` or `ZapState::Failed`. The invoice should have been checked
+/// with [check_zap_invoice](crate::zaps::check_zap_invoice), which gives the
+/// payment hash.
+pub(crate) async fn pay_zap(
+    handler: Arc<dyn PaymentHandler>,
+    id: Id,
+    invoice: String,
+    msats: MilliSatoshi,
+    payment_hash: [u8; 32],
+) {
+    *GLOBALS.current_zap.write() = ZapState::Paying(id, invoice.clone(), msats);
+
+    let result =
+        match tokio::time::timeout(PAYMENT_TIMEOUT, handler.pay_invoice(invoice.clone())).await {
+            Ok(result) => result,
+            Err(_) => Err(ErrorKind::TimedOut.into()),
+        };
+
+    let mut current_zap = GLOBALS.current_zap.write();
+
+    // Leave it be if the user closed the zap, or the zap receipt already came in
+    if !matches!(*current_zap, ZapState::Paying(zap_id, _, _) if zap_id == id) {
+        return;
+    }
+
+    match result {
+        Ok(preimage) if !preimage_matches(&preimage, &payment_hash) => {
+            tracing::warn!(
+                "Zap payment with {} gave preimage {} which does not match the invoice",
+                handler.name(),
+                preimage
+            );
+            *current_zap = ZapState::Failed(
+                id,
+                invoice,
+                "The wallet did not show that it paid the invoice".to_owned(),
+            );
+        }
+        Ok(preimage) => {
+            tracing::info!("Zap paid with {}, preimage {}", handler.name(), preimage);
+            *current_zap = ZapState::Paid(id, msats);
+        }
+        Err(e) => {
+            tracing::warn!("Zap payment with {} failed: {}", handler.name(), e);
+            *current_zap = ZapState::Failed(id, invoice, format!("{e}"));
+        }
+    }
+}
+
+/// Note a zap receipt (kind 9735) for the zap in progress, if it is ours
+pub(crate) fn zap_receipt_arrived(zapped: Id, payer: nostr_types::PublicKey, receipt: Id) {
+    if GLOBALS.identity.public_key() != Some(payer) {
+        return;
+    }
+
+    let mut current_zap = GLOBALS.current_zap.write();
+    let msats = match *current_zap {
+        ZapState::Paying(id, _, msats) if id == zapped => msats,
+        ZapState::Paid(id, msats) if id == zapped => msats,
+        _ => return,
+    };
+    *current_zap = ZapState::Receipted(zapped, receipt, msats);
+    drop(current_zap);
+
+    GLOBALS.notify_ui_redraw.notify_waiters();
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_find_preimage() {
+        let preimage = "0123456789abcdef0123456789abcdef0123456789abcdef0123456789abcdef";
+        let payment_hash: [u8; 32] = Sha256::digest(hex::decode(preimage).unwrap()).into();
+        let other = "fedcba9876543210fedcba9876543210fedcba9876543210fedcba9876543210";
+
+        assert_eq!(
+            find_preimage(preimage, &payment_hash),
+            Some(preimage.to_owned())
+        );
+        assert_eq!(
+            find_preimage(
+                &format!("Payment sent.\npreimage: {}\n", preimage.to_uppercase()),
+                &payment_hash
+            ),
+            Some(preimage.to_owned())
+        );
+
+        // The payment hash, or anything else, printed first is not the preimage
+        assert_eq!(
+            find_preimage(
+                &format!(
+                    "hash: {}\nid: {other}\npreimage: {preimage}\n",
+                    hex::encode(payment_hash)
+                ),
+                &payment_hash
+            ),
+            Some(preimage.to_owned())
+        );
+        assert_eq!(
+            find_preimage(
+                &format!("{{\"payment_hash\": \"ab\", \"payment_preimage\": \"{preimage}\"}}"),
+                &payment_hash
+            ),
+            Some(preimage.to_owned())
+        );
+        assert_eq!(
+            find_preimage(&format!("{{\"preimage\": \"{other}\"}}"), &payment_hash),
+            None
+        );
+        assert_eq!(find_preimage(other, &payment_hash), None);
+        assert_eq!(
+            find_preimage("{\"status\": \"pending\"}", &payment_hash),
+            None
+        );
+        assert_eq!(find_preimage("Payment failed", &payment_hash), None);
+
+        assert!(preimage_matches(preimage, &payment_hash));
+        assert!(!preimage_matches(other, &payment_hash));
+        assert!(!preimage_matches("not hex", &payment_hash));
+    }
+}
@@ -1,5 +1,5 @@
 use crate::comms::RelayJob;
-use crate::error::Error;
+use crate::error::{Error, ErrorKind};
 use crate::globals::GLOBALS;
 use crate::nostr_connect_server::ParsedCommand;
 use crate::people::PersonList;
@@ -339,6 +339,10 @@ impl Pending {
             
             let last_event_hash = match crate::people::hash_person_list_event(*list) {
                 Ok(hash) => hash,
+                Err(e) if matches!(e.kind, ErrorKind::Nip46Pending) => {
+                    // The remote signer is still decrypting it, check it next time
+                    continue;
+                }
                 Err(e) => {
                     error!("Error computing event hash for person list {:?}: {:?}", list, e);
                     return Err(e);
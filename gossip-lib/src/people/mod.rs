@@ -3,7 +3,7 @@ pub use follow_list::FollowList;
 
 use crate::comms::ToOverlordMessage;
 use crate::error::{Error, ErrorKind};
-use crate::fetcher::FetchResult;
+use crate::fetcher::{CacheKind, FetchResult};
 use crate::globals::GLOBALS;
 use crate::misc::{Freshness, Private};
 use crate::relay;
@@ -417,7 +417,7 @@ impl People {
         // cache expires in 3 days
 
         let use_cache = true;
-        match GLOBALS.fetcher.try_get(url, use_cache) {
+        match GLOBALS.fetcher.try_get(url, CacheKind::Avatar, use_cache) {
             Ok(FetchResult::Ready(bytes)) => {
                 // Finish this later (spawn)
                 let apubkey = *pubkey;
@@ -589,7 +589,10 @@ impl People {
         let old_tags = {
             if let Some(ref event) = existing_event {
                 if !event.content.is_empty() && kind != EventKind::ContactList {
-                    let decrypted_content = GLOBALS.identity.decrypt(&my_pubkey, &event.content)?;
+                    let decrypted_content = GLOBALS
+                        .identity
+                        .decrypt_and_wait(&my_pubkey, &event.content)
+                        .await?;
                     let mut tags: Vec<Tag> = serde_json::from_str(&decrypted_content)?;
                     tags.extend(event.tags.clone());
                     tags
@@ -705,11 +708,14 @@ impl People {
                 }
             } else {
                 let private_tags_string = serde_json::to_string(&private_tags)?;
-                GLOBALS.identity.encrypt(
-                    &my_pubkey,
-                    &private_tags_string,
-                    ContentEncryptionAlgorithm::Nip44v2,
-                )?
+                GLOBALS
+                    .identity
+                    .encrypt(
+                        &my_pubkey,
+                        &private_tags_string,
+                        ContentEncryptionAlgorithm::Nip44v2,
+                    )
+                    .await?
             }
         };
 
@@ -721,7 +727,7 @@ impl People {
             content,
         };
 
-        GLOBALS.identity.sign_event(pre_event)
+        GLOBALS.identity.sign_event(pre_event).await
     }
 
     /// Follow (or unfollow) the public key
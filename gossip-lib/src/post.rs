@@ -1,6 +1,7 @@
 use crate::dm_channel::DmChannel;
+use crate::drafts::Draft;
 use crate::error::{Error, ErrorKind};
-use crate::fetcher::FetchResult;
+use crate::fetcher::{CacheKind, FetchResult};
 use crate::globals::GLOBALS;
 use crate::relay;
 use crate::relay::Relay;
@@ -17,6 +18,7 @@ pub async fn prepare_post_normal(
     mut tags: Vec<Tag>,
     in_reply_to: Option<Event>,
     annotation: bool,
+    expiration: Option<Unixtime>,
 ) -> Result<Vec<(Event, Vec<RelayUrl>)>, Error> {
     add_gossip_tag(&mut tags);
 
@@ -24,6 +26,10 @@ pub async fn prepare_post_normal(
         tags.push(Tag::new(&["annotation"]))
     }
 
+    if let Some(expiration) = expiration {
+        add_expiration_tag(&mut tags, expiration);
+    }
+
     if let Some(ref parent) = in_reply_to {
         add_thread_based_tags(author, &mut tags, parent)?;
     }
@@ -47,23 +53,310 @@ pub async fn prepare_post_normal(
             });
             GLOBALS
                 .identity
-                .sign_event_with_pow(pre_event, powint, Some(work_sender))?
+                .sign_event_with_pow(pre_event, powint, Some(work_sender))
+                .await?
+        } else {
+            GLOBALS.identity.sign_event(pre_event).await?
+        }
+    };
+
+    let relays = relay::relays_to_post_to(&event)?;
+
+    Ok(vec![(event, relays)])
+}
+
+/// A NIP-23 long-form article as edited in the client
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct LongFormArticle {
+    /// The 'd' tag. Together with the author and kind this addresses the article,
+    /// so republishing with the same identifier replaces the old version.
+    /// If empty, one is generated from the title.
+    pub identifier: String,
+    pub title: String,
+    pub summary: String,
+    pub image: String,
+    pub hashtags: Vec<String>,
+    pub content: String,
+
+    /// When the article was first published. Kept when republishing an edit.
+    pub published_at: Option<Unixtime>,
+}
+
+impl LongFormArticle {
+    /// Load an article from a long-form content event (for editing)
+    pub fn from_event(event: &Event) -> Option<LongFormArticle> {
+        if event.kind != EventKind::LongFormContent && event.kind != EventKind::DraftLongFormContent
+        {
+            return None;
+        }
+
+        Some(Self::from_parts(&event.content, &event.tags))
+    }
+
+    /// Load an article from a saved (NIP-37) draft of one
+    pub fn from_draft(draft: &Draft) -> Option<LongFormArticle> {
+        if draft.kind != EventKind::LongFormContent {
+            return None;
+        }
+
+        Some(Self::from_parts(&draft.content, &draft.tags))
+    }
+
+    /// A draft of this article, to save with NIP-37. The identifier of the draft
+    /// itself is new; replace it to overwrite an earlier draft.
+    pub fn to_draft(&self) -> Draft {
+        let mut tags: Vec<Tag> = Vec::new();
+        if !self.identifier.is_empty() {
+            tags.push(Tag::new(&["d", &self.identifier]));
+        }
+        tags.push(Tag::new(&["title", &self.title]));
+        if !self.summary.is_empty() {
+            tags.push(Tag::new(&["summary", &self.summary]));
+        }
+        if !self.image.is_empty() {
+            tags.push(Tag::new(&["image", &self.image]));
+        }
+        for hashtag in &self.hashtags {
+            tags.push(Tag::new(&["t", hashtag]));
+        }
+        if let Some(published_at) = self.published_at {
+            tags.push(Tag::new(&["published_at", &format!("{}", published_at.0)]));
+        }
+
+        Draft {
+            identifier: format!("{:016x}", rand::random::<u64>()),
+            kind: EventKind::LongFormContent,
+            content: self.content.clone(),
+            tags,
+            saved_at: Unixtime::now(),
+        }
+    }
+
+    fn from_parts(content: &str, tags: &[Tag]) -> LongFormArticle {
+        let mut article = LongFormArticle {
+            content: content.to_owned(),
+            ..Default::default()
+        };
+
+        for tag in tags {
+            match tag.tagname() {
+                "d" => article.identifier = tag.value().to_owned(),
+                "title" => article.title = tag.value().to_owned(),
+                "summary" => article.summary = tag.value().to_owned(),
+                "image" => article.image = tag.value().to_owned(),
+                "t" => article.hashtags.push(tag.value().to_owned()),
+                "published_at" => {
+                    article.published_at = tag.value().parse::<i64>().ok().map(Unixtime)
+                }
+                _ => {}
+            }
+        }
+
+        article
+    }
+
+    // Lowercase alphanumerics separated by dashes, plus a random suffix so that
+    // two articles with the same title do not replace each other
+    fn generate_identifier(&self) -> String {
+        let mut slug = String::new();
+        for c in self.title.chars() {
+            if c.is_alphanumeric() {
+                slug.extend(c.to_lowercase());
+            } else if !slug.is_empty() && !slug.ends_with('-') {
+                slug.push('-');
+            }
+        }
+        let slug = slug.trim_end_matches('-');
+        let suffix = format!("{:08x}", rand::random::<u32>());
+        if slug.is_empty() {
+            suffix
         } else {
-            GLOBALS.identity.sign_event(pre_event)?
+            format!("{}-{}", slug, suffix)
+        }
+    }
+}
+
+pub async fn prepare_post_long_form(
+    author: PublicKey,
+    article: LongFormArticle,
+) -> Result<Vec<(Event, Vec<RelayUrl>)>, Error> {
+    let now = Unixtime::now();
+
+    let identifier = if article.identifier.trim().is_empty() {
+        article.generate_identifier()
+    } else {
+        article.identifier.trim().to_owned()
+    };
+
+    let mut tags: Vec<Tag> = vec![
+        Tag::new(&["d", &identifier]),
+        Tag::new(&["title", article.title.trim()]),
+    ];
+    if !article.summary.trim().is_empty() {
+        tags.push(Tag::new(&["summary", article.summary.trim()]));
+    }
+    if !article.image.trim().is_empty() {
+        tags.push(Tag::new(&["image", article.image.trim()]));
+    }
+    let published_at = article.published_at.unwrap_or(now);
+    tags.push(Tag::new(&["published_at", &format!("{}", published_at.0)]));
+
+    add_gossip_tag(&mut tags);
+
+    add_tags_mirroring_content(&article.content, &mut tags, false).await;
+
+    // Hashtags from the editor (the content may have already tagged some)
+    for hashtag in &article.hashtags {
+        let hashtag = hashtag.trim().trim_start_matches('#').to_lowercase();
+        if hashtag.is_empty() {
+            continue;
+        }
+        if !tags
+            .iter()
+            .any(|t| t.tagname() == "t" && t.value() == hashtag)
+        {
+            tags.push(ParsedTag::Hashtag(hashtag).into_tag());
         }
+    }
+
+    let pre_event = PreEvent {
+        pubkey: author,
+        created_at: now,
+        kind: EventKind::LongFormContent,
+        tags,
+        content: article.content,
     };
 
+    let event = GLOBALS.identity.sign_event(pre_event).await?;
+
     let relays = relay::relays_to_post_to(&event)?;
 
     Ok(vec![(event, relays)])
 }
 
+/// Prepare a post into a relay-based group (NIP-29): a chat message, or a thread
+/// if it has a title. It is only posted to the group relay.
+pub async fn prepare_post_group(
+    author: PublicKey,
+    relay_url: RelayUrl,
+    group: String,
+    content: String,
+    mut tags: Vec<Tag>,
+    in_reply_to: Option<Id>,
+    title: Option<String>,
+) -> Result<Vec<(Event, Vec<RelayUrl>)>, Error> {
+    tags.push(Tag::new(&["h", &group]));
+
+    let kind = match title.as_deref().map(|t| t.trim()) {
+        Some(title) if !title.is_empty() => {
+            tags.push(Tag::new(&["title", title]));
+            EventKind::GroupChatThread
+        }
+        _ => EventKind::GroupChatMessage,
+    };
+
+    // Chat messages reply by quoting the message they reply to
+    if let Some(parent_id) = in_reply_to {
+        let id = parent_id.as_hex_string();
+        match GLOBALS.db().read_event(parent_id)? {
+            Some(parent) => tags.push(Tag::new(&[
+                "q",
+                &id,
+                relay_url.as_str(),
+                &parent.pubkey.as_hex_string(),
+            ])),
+            None => tags.push(Tag::new(&["q", &id, relay_url.as_str()])),
+        }
+    }
+
+    add_gossip_tag(&mut tags);
+
+    add_tags_mirroring_content(&content, &mut tags, false).await;
+
+    let pre_event = PreEvent {
+        pubkey: author,
+        created_at: Unixtime::now(),
+        kind,
+        tags,
+        content,
+    };
+
+    let event = GLOBALS.identity.sign_event(pre_event).await?;
+
+    Ok(vec![(event, vec![relay_url])])
+}
+
+/// Prepare a message for a public chat channel (NIP-28). It is posted to the
+/// relays of the channel, else to our write relays.
+pub async fn prepare_post_channel(
+    author: PublicKey,
+    channel: Id,
+    content: String,
+    mut tags: Vec<Tag>,
+    in_reply_to: Option<Id>,
+) -> Result<Vec<(Event, Vec<RelayUrl>)>, Error> {
+    let mut relays = crate::channels::channel_relays(channel)?;
+    if relays.is_empty() {
+        relays = Relay::choose_relay_urls(Relay::WRITE, |_| true)?;
+    }
+    let relay_hint: Option<UncheckedUrl> = relays.first().map(|r| r.to_unchecked_url());
+
+    tags.push(
+        ParsedTag::Event {
+            id: channel,
+            recommended_relay_url: relay_hint.clone(),
+            marker: Some("root".to_owned()),
+            author_pubkey: None,
+        }
+        .into_tag(),
+    );
+
+    if let Some(parent_id) = in_reply_to {
+        tags.push(
+            ParsedTag::Event {
+                id: parent_id,
+                recommended_relay_url: relay_hint.clone(),
+                marker: Some("reply".to_owned()),
+                author_pubkey: None,
+            }
+            .into_tag(),
+        );
+        if let Some(parent) = GLOBALS.db().read_event(parent_id)? {
+            tags.push(
+                ParsedTag::Pubkey {
+                    pubkey: parent.pubkey,
+                    recommended_relay_url: relay_hint,
+                    petname: None,
+                }
+                .into_tag(),
+            );
+        }
+    }
+
+    add_gossip_tag(&mut tags);
+
+    add_tags_mirroring_content(&content, &mut tags, false).await;
+
+    let pre_event = PreEvent {
+        pubkey: author,
+        created_at: Unixtime::now(),
+        kind: EventKind::ChannelMessage,
+        tags,
+        content,
+    };
+
+    let event = GLOBALS.identity.sign_event(pre_event).await?;
+
+    Ok(vec![(event, relays)])
+}
+
 pub async fn prepare_post_comment(
     author: PublicKey,
     content: String,
     mut tags: Vec<Tag>,
     parent: Event,
     annotation: bool,
+    expiration: Option<Unixtime>,
 ) -> Result<Vec<(Event, Vec<RelayUrl>)>, Error> {
     add_gossip_tag(&mut tags);
 
@@ -71,6 +364,10 @@ pub async fn prepare_post_comment(
         tags.push(Tag::new(&["annotation"]))
     }
 
+    if let Some(expiration) = expiration {
+        add_expiration_tag(&mut tags, expiration);
+    }
+
     if copy_root_tags(&mut tags, &parent) < 1 {
         set_parent_as_root_tags(&mut tags, &parent);
     }
@@ -96,9 +393,10 @@ pub async fn prepare_post_comment(
             });
             GLOBALS
                 .identity
-                .sign_event_with_pow(pre_event, powint, Some(work_sender))?
+                .sign_event_with_pow(pre_event, powint, Some(work_sender))
+                .await?
         } else {
-            GLOBALS.identity.sign_event(pre_event)?
+            GLOBALS.identity.sign_event(pre_event).await?
         }
     };
 
@@ -107,7 +405,7 @@ pub async fn prepare_post_comment(
     Ok(vec![(event, relays)])
 }
 
-pub fn prepare_post_nip04(
+pub async fn prepare_post_nip04(
     author: PublicKey,
     content: String,
     dm_channel: DmChannel,
@@ -123,10 +421,10 @@ pub fn prepare_post_nip04(
         dm_channel.keys()[0]
     };
 
-    let content =
-        GLOBALS
-            .identity
-            .encrypt(&recipient, &content, ContentEncryptionAlgorithm::Nip04)?;
+    let content = GLOBALS
+        .identity
+        .encrypt(&recipient, &content, ContentEncryptionAlgorithm::Nip04)
+        .await?;
 
     let mut tags = vec![ParsedTag::Pubkey {
         pubkey: recipient,
@@ -146,7 +444,7 @@ pub fn prepare_post_nip04(
         content,
     };
 
-    let event = GLOBALS.identity.sign_event(pre_event)?;
+    let event = GLOBALS.identity.sign_event(pre_event).await?;
 
     let relay_urls = relay::relays_to_post_to(&event)?;
 
@@ -198,14 +496,14 @@ pub async fn prepare_post_nip17(
 
     // To all recipients
     for pk in dm_channel.keys() {
-        let event = GLOBALS.identity.giftwrap(pre_event.clone(), *pk)?;
+        let event = GLOBALS.identity.giftwrap(pre_event.clone(), *pk).await?;
         let relays = relay::get_dm_relays(*pk)?;
         output.push((event, relays));
     }
 
     // And a copy to us
     {
-        let event = GLOBALS.identity.giftwrap(pre_event.clone(), our_pk)?;
+        let event = GLOBALS.identity.giftwrap(pre_event.clone(), our_pk).await?;
         let relays = Relay::choose_relay_urls(Relay::DM, |_| true)?;
         output.push((event, relays));
     }
@@ -219,6 +517,11 @@ fn add_gossip_tag(tags: &mut Vec<Tag>) {
     }
 }
 
+// NIP-40
+fn add_expiration_tag(tags: &mut Vec<Tag>, expiration: Unixtime) {
+    tags.push(Tag::new(&["expiration", &format!("{}", expiration.0)]));
+}
+
 async fn add_tags_mirroring_content(content: &str, tags: &mut Vec<Tag>, direct_message: bool) {
     let shattered_content = ShatteredContent::new(content.to_owned(), false);
     for segment in shattered_content.segments.iter() {
@@ -304,18 +607,28 @@ async fn add_imeta_tag(urlstr: &str, mimetype: &str, tags: &mut Vec<Tag>) {
         _ => return,
     };
 
+    // Skip if we already have an imeta tag for this url (e.g. from our upload)
+    if tags.iter().any(|tag| {
+        tag.tagname() == "imeta"
+            && (1..)
+                .map(|i| tag.get_index(i))
+                .take_while(|s| !s.is_empty())
+                .any(|s| s.strip_prefix("url ") == Some(urlstr))
+    }) {
+        return;
+    }
+
     // Fetch the link and wait for it
     let use_cache = true;
-    let bytes = match GLOBALS.fetcher.get(url.clone(), use_cache).await {
+    let bytes = match GLOBALS
+        .fetcher
+        .get(url.clone(), CacheKind::Media, use_cache)
+        .await
+    {
         Ok(FetchResult::Ready(b)) => b,
         _ => return,
     };
 
-    // FIXME - in case we already have an imeta tag matching this url, we should
-    //         find it, convert it into a FileMetadata, and delete it from tags to
-    //         be replaced at the bottom of this function. However, I don't think
-    //         it will ever happen so I'm just writing this note instead.
-
     let imeta = {
         let unchecked_url = url.to_unchecked_url();
         let mut imeta = FileMetadata::new(unchecked_url);
@@ -333,21 +646,9 @@ async fn add_imeta_tag(urlstr: &str, mimetype: &str, tags: &mut Vec<Tag>) {
         imeta.x = Some(hash);
 
         if mimetype.starts_with("image") {
-            use image::{DynamicImage, GenericImageView};
-            if let Ok(dynamic_image) = image::load_from_memory(&bytes) {
-                let (w, h) = dynamic_image.dimensions();
-                // Convert to RGBA8
-                let dynamic_image = DynamicImage::ImageRgba8(dynamic_image.to_rgba8());
-                if let Ok(blurhash) = blurhash::encode(
-                    (4 * w / h).min(9),
-                    (4 * h / w).min(9),
-                    w,
-                    h,
-                    dynamic_image.as_bytes(),
-                ) {
-                    imeta.blurhash = Some(blurhash);
-                    imeta.dim = Some((w as usize, h as usize));
-                }
+            if let Some((dim, blurhash)) = crate::media::image_dim_and_blurhash(bytes).await {
+                imeta.blurhash = Some(blurhash);
+                imeta.dim = Some(dim);
             }
         }
 
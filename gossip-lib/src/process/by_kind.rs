@@ -242,6 +242,11 @@ pub fn process_repost(event: &Event, verify: bool) -> Result<(), Error> {
 
 // EventKind::NostrConnect
 pub fn process_nostr_connect(event: &Event, seen_on: Option<RelayUrl>) -> Result<(), Error> {
+    // Responses from our remote signer
+    if crate::nostr_connect_client::handle_response(event)? {
+        return Ok(());
+    }
+
     crate::nostr_connect_server::handle_command(event, seen_on)?;
 
     Ok(())
@@ -268,6 +273,41 @@ pub fn process_user_server_list(event: &Event, ours: bool) -> Result<(), Error>
     Ok(())
 }
 
+// EventKind::Zap
+// Check who signed zap receipts against the recipient's lnurl (NIP-57 appendix F).
+// The rest of the receipt was checked with its relationships.
+pub fn process_zap_receipt(event: &Event) -> Result<(), Error> {
+    if GLOBALS.db().read_setting_offline() || !GLOBALS.db().read_setting_enable_zap_receipts() {
+        return Ok(());
+    }
+
+    // This costs an HTTP request to the recipient's lightning service per receipt
+    // (cached per lnurl), so only when asked for. `verify_zaps` checks on demand.
+    if !GLOBALS.db().read_setting_check_zap_receipt_signers() {
+        return Ok(());
+    }
+
+    // Already flagged
+    if GLOBALS.db().read_invalid_zap(event.id, None)?.is_some() {
+        return Ok(());
+    }
+
+    let event = event.clone();
+    std::mem::drop(GLOBALS.runtime.spawn(async move {
+        match crate::zaps::check_zap_receipt_lnurl(&event).await {
+            Ok(None) => {}
+            Ok(Some(invalid)) => {
+                if let Err(e) = crate::zaps::flag_invalid_zap(&event, invalid) {
+                    tracing::error!("{}", e);
+                }
+            }
+            Err(e) => tracing::debug!("Could not check zap receipt {}: {}", event.id, e),
+        }
+    }));
+
+    Ok(())
+}
+
 pub fn process_somebody_elses_contact_list(event: &Event, force: bool) -> Result<(), Error> {
     use crate::people::PersonList;
     use crate::storage::Storage;
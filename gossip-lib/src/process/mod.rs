@@ -17,13 +17,15 @@ mod by_kind;
 /// This is mainly used internally to gossip-lib, but you can use it to stuff events
 /// into gossip from other sources. This processes a new event, saving the results into
 /// the database and also populating the GLOBALS maps.
+///
+/// Returns whether we already had the event.
 pub fn process_new_event(
     event: &Event,
     seen_on: Option<RelayUrl>,
     subscription: Option<String>,
     verify: bool,
     process_even_if_duplicate: bool,
-) -> Result<(), Error> {
+) -> Result<bool, Error> {
     // Now
     let now = Unixtime::now();
 
@@ -55,7 +57,7 @@ pub fn process_new_event(
         if let Err(e) = event.verify(Some(maxtime)) {
             // Don't print these, they clutter the console
             tracing::debug!("{}: VERIFY ERROR: {}", e, serde_json::to_string(&event)?);
-            return Ok(());
+            return Ok(duplicate);
         }
     }
 
@@ -100,10 +102,10 @@ pub fn process_new_event(
             crate::spam_filter::filter_event(event.clone(), EventFilterCaller::Process, spamsafe);
         match filter_result {
             EventFilterAction::Allow => {}
-            EventFilterAction::Deny => return Ok(()),
+            EventFilterAction::Deny => return Ok(duplicate),
             EventFilterAction::MuteAuthor => {
                 GLOBALS.people.mute(&event.pubkey, true, Private(false))?;
-                return Ok(());
+                return Ok(duplicate);
             }
         }
     }
@@ -120,7 +122,7 @@ pub fn process_new_event(
             event.kind,
             event.created_at
         );
-        return Ok(()); // No more processing needed for existing event.
+        return Ok(duplicate); // No more processing needed for existing event.
     }
 
     // Bail out if the event was deleted (by id)
@@ -134,7 +136,7 @@ pub fn process_new_event(
                     event.kind,
                     event.created_at
                 );
-                return Ok(());
+                return Ok(duplicate);
             }
         }
     }
@@ -157,12 +159,24 @@ pub fn process_new_event(
                         event.kind,
                         event.created_at
                     );
-                    return Ok(());
+                    return Ok(duplicate);
                 }
             }
         }
     }
 
+    // Bail out if the event has expired (NIP-40)
+    if crate::misc::event_is_expired(event, now) {
+        tracing::trace!(
+            "{}: Expired Event: {} {:?} @{}",
+            seen_on.as_ref().map(|r| r.as_str()).unwrap_or("_"),
+            subscription.as_ref().unwrap_or(&"_".to_string()),
+            event.kind,
+            event.created_at
+        );
+        return Ok(duplicate);
+    }
+
     // Save event
     if global_feed {
         GLOBALS.db().write_event_volatile(event.to_owned());
@@ -176,13 +190,22 @@ pub fn process_new_event(
                 event.kind,
                 event.created_at
             );
-            return Ok(()); // This did not replace anything.
+            return Ok(duplicate); // This did not replace anything.
         }
     } else {
         // This will ignore if it is already there
         GLOBALS.db().write_event(event, None)?;
     }
 
+    // Remember when it expires, so it can be pruned (NIP-40)
+    if !global_feed {
+        if let Some(expiration) = crate::misc::event_expiration(event) {
+            GLOBALS
+                .db()
+                .add_event_expiration(event.id, expiration, None)?;
+        }
+    }
+
     // Log
     tracing::debug!(
         "{}: New Event: {} {:?} @{}",
@@ -215,7 +238,7 @@ pub fn process_new_event(
             event = &rumor_event;
         } else {
             // Not for us.
-            return Ok(());
+            return Ok(duplicate);
         }
     }
 
@@ -288,10 +311,17 @@ pub fn process_new_event(
         EventKind::Repost => by_kind::process_repost(event, verify)?,
         EventKind::NostrConnect => by_kind::process_nostr_connect(event, seen_on.clone())?,
         EventKind::UserServerList => by_kind::process_user_server_list(event, ours)?,
+        EventKind::Zap => by_kind::process_zap_receipt(event)?,
+        EventKind::ChannelCreation | EventKind::ChannelMetadata => {
+            crate::channels::forget_channel_metadata(event)
+        }
+        kind if crate::groups::group_metadata_event_kinds().contains(&kind) => {
+            crate::groups::forget_group_metadata(event)
+        }
         _ => {}
     }
 
-    Ok(())
+    Ok(duplicate)
 }
 
 // Process the content for references to things we might want
@@ -533,6 +563,7 @@ pub(crate) fn process_relationships_of_event(
                     },
                     Some(txn),
                 )?;
+                invalidate.push(id);
             } else if let Ok(ParsedTag::Address { address, .. }) = tag.parse() {
                 GLOBALS.db().write_relationship_by_addr(
                     address,
@@ -637,6 +668,8 @@ pub(crate) fn process_relationships_of_event(
     }
 
     if event.kind == EventKind::Reporting {
+        GLOBALS.db().write_person_reports(event, Some(txn))?;
+
         for tag in &event.tags {
             if let Ok(ParsedTag::Event {
                 id,
@@ -651,12 +684,22 @@ pub(crate) fn process_relationships_of_event(
                     RelationshipById::Reports(report.to_owned()),
                     Some(txn),
                 )?;
+                invalidate.push(id);
             }
         }
     }
 
     // zaps
     if let Ok(Some(zapdata)) = event.zaps() {
+        // Flag receipts that don't hold up, so they are left out of totals.
+        // Who signed the receipt is checked later (see by_kind::process_zap_receipt)
+        let invalid = crate::zaps::check_zap_receipt(event);
+        if let Some(invalid) = invalid {
+            GLOBALS
+                .db()
+                .write_invalid_zap(event.id, &invalid.to_string(), Some(txn))?;
+        }
+
         match zapdata.zapped_event {
             EventReference::Id { id, .. } => {
                 GLOBALS.db().write_relationship_by_id(
@@ -669,6 +712,9 @@ pub(crate) fn process_relationships_of_event(
                     Some(txn),
                 )?;
                 invalidate.push(id);
+                if invalid.is_none() {
+                    crate::payment::zap_receipt_arrived(id, zapdata.payer, event.id);
+                }
             }
             EventReference::Addr(naddr) => {
                 GLOBALS.db().write_relationship_by_addr(
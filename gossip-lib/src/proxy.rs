@@ -0,0 +1,206 @@
+use crate::error::{Error, ErrorKind};
+use crate::globals::GLOBALS;
+use http::Request;
+use std::sync::Arc;
+use tokio::net::TcpStream;
+use tokio_socks::tcp::Socks5Stream;
+use tokio_tungstenite::{MaybeTlsStream, WebSocketStream};
+use tungstenite::handshake::client::Response;
+use tungstenite::protocol::WebSocketConfig;
+
+/// The configured SOCKS5 proxy as host:port, if any. The setting may also be
+/// written as a socks5:// or socks5h:// URL.
+pub fn socks5_proxy() -> Option<String> {
+    parse_socks5_proxy(&GLOBALS.db().read_setting_socks5_proxy())
+}
+
+fn parse_socks5_proxy(setting: &str) -> Option<String> {
+    let proxy = setting.trim();
+    let proxy = proxy
+        .strip_prefix("socks5h://")
+        .or_else(|| proxy.strip_prefix("socks5://"))
+        .unwrap_or(proxy)
+        .trim_end_matches('/');
+    if proxy.is_empty() {
+        None
+    } else {
+        Some(proxy.to_owned())
+    }
+}
+
+/// The SOCKS5 proxy to connect to this host through, if any.
+///
+/// Hosts under .onion can only be reached through a proxy, so this errors for
+/// them if no proxy is configured. If the proxy is set to be used only for
+/// .onion hosts, other hosts are connected to directly.
+pub fn proxy_for_host(host: &str) -> Result<Option<String>, Error> {
+    choose_proxy(
+        host,
+        socks5_proxy(),
+        GLOBALS.db().read_setting_proxy_only_onion(),
+    )
+}
+
+fn choose_proxy(
+    host: &str,
+    proxy: Option<String>,
+    proxy_only_onion: bool,
+) -> Result<Option<String>, Error> {
+    let onion = is_onion(host);
+    match proxy {
+        None if onion => Err(ErrorKind::ProxyRequired(host.to_owned()).into()),
+        None => Ok(None),
+        Some(_) if !onion && proxy_only_onion => Ok(None),
+        Some(proxy) => Ok(Some(proxy)),
+    }
+}
+
+fn is_onion(host: &str) -> bool {
+    host.trim_end_matches('.')
+        .to_ascii_lowercase()
+        .ends_with(".onion")
+}
+
+/// A reqwest client builder that goes through the SOCKS5 proxy, if one is
+/// configured. Hostnames are resolved by the proxy, so DNS does not leak.
+///
+/// As with websockets, requests to .onion hosts fail with
+/// [ProxyRequired](ErrorKind::ProxyRequired) if no proxy is configured, rather
+/// than their names being looked up in DNS.
+pub fn http_client_builder() -> Result<reqwest::ClientBuilder, Error> {
+    // Proxied hosts are resolved by the proxy, so this only sees the others
+    let builder = reqwest::Client::builder().dns_resolver(Arc::new(OnionRefusingResolver));
+
+    let proxy = match socks5_proxy() {
+        Some(proxy) => format!("socks5h://{}", proxy),
+        None => return Ok(builder),
+    };
+
+    if GLOBALS.db().read_setting_proxy_only_onion() {
+        Ok(
+            builder.proxy(reqwest::Proxy::custom(move |url| match url.host_str() {
+                Some(host) if is_onion(host) => Some(proxy.clone()),
+                _ => None,
+            })),
+        )
+    } else {
+        Ok(builder.proxy(reqwest::Proxy::all(proxy)?))
+    }
+}
+
+// Resolves hostnames as usual, except that .onion hosts are refused, as they
+// can only be reached through a proxy
+struct OnionRefusingResolver;
+
+impl reqwest::dns::Resolve for OnionRefusingResolver {
+    fn resolve(&self, name: reqwest::dns::Name) -> reqwest::dns::Resolving {
+        let host = name.as_str().to_owned();
+        Box::pin(async move {
+            if is_onion(&host) {
+                let e = Error::from(ErrorKind::ProxyRequired(host));
+                return Err(Box::new(std::io::Error::other(e.to_string()))
+                    as Box<dyn std::error::Error + Send + Sync>);
+            }
+            let addrs: reqwest::dns::Addrs =
+                Box::new(tokio::net::lookup_host((host.as_str(), 0)).await?);
+            Ok(addrs)
+        })
+    }
+}
+
+/// Connect a websocket, through the SOCKS5 proxy if one applies to the host.
+/// The proxy resolves the hostname, so DNS does not leak.
+pub async fn connect_websocket(
+    request: Request<()>,
+    config: Option<WebSocketConfig>,
+) -> Result<(WebSocketStream<MaybeTlsStream<TcpStream>>, Response), Error> {
+    let host = request
+        .uri()
+        .host()
+        .ok_or_else(|| Error::from(ErrorKind::UrlHasNoHostname))?
+        .to_owned();
+
+    match proxy_for_host(&host)? {
+        None => Ok(tokio_tungstenite::connect_async_with_config(request, config, false).await?),
+        Some(proxy) => {
+            let port = request
+                .uri()
+                .port_u16()
+                .unwrap_or(match request.uri().scheme_str() {
+                    Some("ws") => 80,
+                    _ => 443,
+                });
+
+            tracing::debug!("Connecting to {} through proxy {}", host, proxy);
+            let stream = Socks5Stream::connect(proxy.as_str(), (host, port))
+                .await?
+                .into_inner();
+
+            Ok(
+                tokio_tungstenite::client_async_tls_with_config(request, stream, config, None)
+                    .await?,
+            )
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_parse_socks5_proxy() {
+        assert_eq!(
+            parse_socks5_proxy("127.0.0.1:9050"),
+            Some("127.0.0.1:9050".to_owned())
+        );
+        assert_eq!(
+            parse_socks5_proxy(" socks5h://127.0.0.1:9050/ "),
+            Some("127.0.0.1:9050".to_owned())
+        );
+        assert_eq!(
+            parse_socks5_proxy("socks5://localhost:9150"),
+            Some("localhost:9150".to_owned())
+        );
+        assert_eq!(parse_socks5_proxy(""), None);
+        assert_eq!(parse_socks5_proxy("  "), None);
+        assert_eq!(parse_socks5_proxy("socks5h://"), None);
+    }
+
+    #[test]
+    fn test_choose_proxy() {
+        let proxy = Some("127.0.0.1:9050".to_owned());
+        let onion = "abcdefghijklmnopqrstuvwxyz234567abcdefghijklmnopqrstuvwx.onion";
+
+        // Without a proxy, .onion hosts cannot be reached
+        assert_eq!(
+            choose_proxy("relay.example.com", None, false).unwrap(),
+            None
+        );
+        assert!(matches!(
+            choose_proxy(onion, None, false).map_err(|e| e.kind),
+            Err(ErrorKind::ProxyRequired(_))
+        ));
+        assert!(choose_proxy("RELAY.ONION.", None, true).is_err());
+
+        // With a proxy, everything goes through it
+        assert_eq!(
+            choose_proxy("relay.example.com", proxy.clone(), false).unwrap(),
+            proxy
+        );
+        assert_eq!(choose_proxy(onion, proxy.clone(), false).unwrap(), proxy);
+
+        // Unless it is only for .onion hosts
+        assert_eq!(
+            choose_proxy("relay.example.com", proxy.clone(), true).unwrap(),
+            None
+        );
+        assert_eq!(choose_proxy(onion, proxy.clone(), true).unwrap(), proxy);
+
+        // Only the top level domain counts
+        assert_eq!(
+            choose_proxy("onion.example.com", proxy.clone(), true).unwrap(),
+            None
+        );
+    }
+}
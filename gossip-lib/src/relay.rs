@@ -213,6 +213,9 @@ pub fn relays_to_post_to(event: &Event) -> Result<Vec<RelayUrl>, Error> {
         .collect();
     relays.retain(|r| !seen_on.contains(r));
 
+    // Remove relays that refused our events
+    relays.retain(|r| !crate::relay_rejection::is_write_restricted(r));
+
     relays.sort();
     relays.dedup();
 
@@ -0,0 +1,112 @@
+use crate::globals::GLOBALS;
+use nostr_types::{RelayUrl, Unixtime};
+
+/// Why a relay last turned down one of our subscriptions (CLOSED) or events (OK)
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RelayRejection {
+    /// The machine-readable prefix, e.g. "rate-limited" (empty if there was none)
+    pub prefix: String,
+
+    /// The whole message from the relay
+    pub message: String,
+
+    /// If it was an event we posted, rather than a subscription
+    pub posting: bool,
+
+    /// When it happened
+    pub when: Unixtime,
+}
+
+impl RelayRejection {
+    /// The machine-readable prefix of a CLOSED or OK message (NIP-01)
+    pub fn prefix_of(message: &str) -> &str {
+        match message.split_once(':') {
+            Some((prefix, _)) => prefix.trim(),
+            None => "",
+        }
+    }
+}
+
+/// Why we last did not post one of our events to a relay, going by the
+/// limitations in its NIP-11 document
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SkippedWrite {
+    /// Why the relay would have refused the event
+    pub reason: String,
+
+    /// When it happened
+    pub when: Unixtime,
+}
+
+/// Why the relay last turned us down, if it has since we started
+pub fn relay_rejection(url: &RelayUrl) -> Option<RelayRejection> {
+    GLOBALS.relay_rejections.get(url).map(|r| r.value().clone())
+}
+
+/// Whether the relay has refused our events (`blocked:` or `restricted:`) since
+/// we started. We do not post to such relays again until restarted.
+pub fn is_write_restricted(url: &RelayUrl) -> bool {
+    GLOBALS.write_restricted_relays.contains(url)
+}
+
+pub(crate) fn record_relay_rejection(url: &RelayUrl, message: &str, posting: bool) {
+    GLOBALS.relay_rejections.insert(
+        url.to_owned(),
+        RelayRejection {
+            prefix: RelayRejection::prefix_of(message).to_owned(),
+            message: message.to_owned(),
+            posting,
+            when: Unixtime::now(),
+        },
+    );
+}
+
+/// Why we last did not post to the relay, if we have skipped it since we started
+pub fn skipped_write(url: &RelayUrl) -> Option<SkippedWrite> {
+    GLOBALS.skipped_writes.get(url).map(|s| s.value().clone())
+}
+
+pub(crate) fn record_skipped_write(url: &RelayUrl, reason: &str) {
+    GLOBALS.skipped_writes.insert(
+        url.to_owned(),
+        SkippedWrite {
+            reason: reason.to_owned(),
+            when: Unixtime::now(),
+        },
+    );
+}
+
+pub(crate) fn set_write_restricted(url: &RelayUrl) {
+    GLOBALS.write_restricted_relays.insert(url.to_owned());
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_prefix_of() {
+        assert_eq!(
+            RelayRejection::prefix_of("rate-limited: slow down"),
+            "rate-limited"
+        );
+        assert_eq!(
+            RelayRejection::prefix_of("pow: difficulty 8 is less than 20"),
+            "pow"
+        );
+        assert_eq!(
+            RelayRejection::prefix_of(" blocked : you are banned"),
+            "blocked"
+        );
+        assert_eq!(
+            RelayRejection::prefix_of("auth-required: we only serve members"),
+            "auth-required"
+        );
+        assert_eq!(
+            RelayRejection::prefix_of("error: see https://example.com"),
+            "error"
+        );
+        assert_eq!(RelayRejection::prefix_of("no prefix here"), "");
+        assert_eq!(RelayRejection::prefix_of(""), "");
+    }
+}
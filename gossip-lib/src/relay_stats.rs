@@ -0,0 +1,200 @@
+use crate::error::Error;
+use crate::globals::GLOBALS;
+use nostr_types::{RelayUrl, Unixtime};
+use speedy::{Readable, Writable};
+
+const SECONDS_PER_DAY: i64 = 86400;
+
+/// Connection and bandwidth statistics for a relay over one (UTC) day
+#[derive(Debug, Clone, Default, PartialEq, Eq, Readable, Writable)]
+pub struct RelayStats {
+    /// The day, counted in days since the unix epoch
+    pub day: u32,
+
+    /// Bytes of websocket messages we sent
+    pub bytes_sent: u64,
+
+    /// Bytes of websocket messages we received
+    pub bytes_received: u64,
+
+    /// Events received, including duplicates
+    pub events_received: u64,
+
+    /// Events received that we already had
+    pub duplicate_events: u64,
+
+    /// Subscriptions (REQs) sent
+    pub subscriptions: u64,
+
+    /// Subscriptions that reached EOSE
+    pub eose_count: u64,
+
+    /// Total milliseconds from REQ to EOSE, over those subscriptions
+    pub eose_latency_ms: u64,
+
+    /// NOTICE messages received
+    pub notices: u64,
+
+    /// CLOSED messages received
+    pub closed: u64,
+}
+
+impl RelayStats {
+    pub fn new(day: u32) -> RelayStats {
+        RelayStats {
+            day,
+            ..Default::default()
+        }
+    }
+
+    /// The current day, in days since the unix epoch
+    pub fn today() -> u32 {
+        Self::day_of(Unixtime::now())
+    }
+
+    /// The day a time falls on, in days since the unix epoch
+    pub fn day_of(when: Unixtime) -> u32 {
+        (when.0.max(0) / SECONDS_PER_DAY) as u32
+    }
+
+    /// When the day of these statistics started
+    pub fn day_start(&self) -> Unixtime {
+        Unixtime(self.day as i64 * SECONDS_PER_DAY)
+    }
+
+    /// Whether nothing has been counted
+    pub fn is_empty(&self) -> bool {
+        *self == RelayStats::new(self.day)
+    }
+
+    /// Add in the counts from other statistics (the day is left alone)
+    pub fn add(&mut self, other: &RelayStats) {
+        self.bytes_sent += other.bytes_sent;
+        self.bytes_received += other.bytes_received;
+        self.events_received += other.events_received;
+        self.duplicate_events += other.duplicate_events;
+        self.subscriptions += other.subscriptions;
+        self.eose_count += other.eose_count;
+        self.eose_latency_ms += other.eose_latency_ms;
+        self.notices += other.notices;
+        self.closed += other.closed;
+    }
+
+    /// Bytes sent and received
+    pub fn bytes(&self) -> u64 {
+        self.bytes_sent + self.bytes_received
+    }
+
+    /// The average time from REQ to EOSE
+    pub fn mean_eose_latency_ms(&self) -> Option<u64> {
+        if self.eose_count == 0 {
+            None
+        } else {
+            Some(self.eose_latency_ms / self.eose_count)
+        }
+    }
+}
+
+fn first_day(days: u32) -> u32 {
+    RelayStats::today().saturating_sub(days.saturating_sub(1))
+}
+
+/// The daily statistics for a relay over the last `days` days (including today),
+/// oldest first. Days without any activity are left out.
+pub fn relay_stats_history(url: &RelayUrl, days: u32) -> Result<Vec<RelayStats>, Error> {
+    GLOBALS.db().read_relay_stats(url, first_day(days))
+}
+
+/// The statistics for a relay summed over the last `days` days (including today)
+pub fn relay_stats_total(url: &RelayUrl, days: u32) -> Result<RelayStats, Error> {
+    let mut total = RelayStats::new(first_day(days));
+    for stats in relay_stats_history(url, days)? {
+        total.add(&stats);
+    }
+    Ok(total)
+}
+
+/// The statistics for every relay summed over the last `days` days (including today),
+/// most traffic first
+pub fn all_relay_stats_totals(days: u32) -> Result<Vec<(RelayUrl, RelayStats)>, Error> {
+    let first_day = first_day(days);
+    let mut totals: Vec<(RelayUrl, RelayStats)> = Vec::new();
+    for (url, stats) in GLOBALS.db().read_all_relay_stats(first_day)? {
+        match totals.last_mut() {
+            Some((last_url, total)) if *last_url == url => total.add(&stats),
+            _ => {
+                let mut total = RelayStats::new(first_day);
+                total.add(&stats);
+                totals.push((url, total));
+            }
+        }
+    }
+    totals.sort_by(|a, b| b.1.bytes().cmp(&a.1.bytes()));
+    Ok(totals)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_relay_stats_add() {
+        let mut total = RelayStats::new(10);
+        assert!(total.is_empty());
+
+        let other = RelayStats {
+            day: 12,
+            bytes_sent: 1,
+            bytes_received: 2,
+            events_received: 3,
+            duplicate_events: 4,
+            subscriptions: 5,
+            eose_count: 6,
+            eose_latency_ms: 7,
+            notices: 8,
+            closed: 9,
+        };
+        total.add(&other);
+        total.add(&other);
+
+        assert_eq!(
+            total,
+            RelayStats {
+                day: 10,
+                bytes_sent: 2,
+                bytes_received: 4,
+                events_received: 6,
+                duplicate_events: 8,
+                subscriptions: 10,
+                eose_count: 12,
+                eose_latency_ms: 14,
+                notices: 16,
+                closed: 18,
+            }
+        );
+        assert!(!total.is_empty());
+        assert_eq!(total.bytes(), 6);
+        assert_eq!(total.mean_eose_latency_ms(), Some(1));
+        assert_eq!(RelayStats::new(10).mean_eose_latency_ms(), None);
+    }
+
+    #[test]
+    fn test_day_of() {
+        assert_eq!(RelayStats::day_of(Unixtime(0)), 0);
+        assert_eq!(RelayStats::day_of(Unixtime(SECONDS_PER_DAY - 1)), 0);
+        assert_eq!(RelayStats::day_of(Unixtime(SECONDS_PER_DAY)), 1);
+        assert_eq!(
+            RelayStats::new(3).day_start(),
+            Unixtime(3 * SECONDS_PER_DAY)
+        );
+    }
+
+    #[test]
+    fn test_first_day() {
+        let today = RelayStats::today();
+        assert_eq!(first_day(1), today);
+        assert_eq!(first_day(0), today);
+        assert_eq!(first_day(7), today - 6);
+        assert_eq!(first_day(u32::MAX), 0);
+    }
+}
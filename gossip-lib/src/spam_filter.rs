@@ -3,7 +3,7 @@ use crate::people::PersonList;
 use crate::profile::Profile;
 use crate::storage::{PersonTable, Table};
 use nostr_types::{Event, EventKind, Id, PublicKey, Tag, Unixtime};
-use rhai::{CallFnOptions, Engine, Scope, AST};
+use rhai::{Array, CallFnOptions, Dynamic, Engine, Scope, AST};
 use std::fs;
 
 #[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
@@ -171,6 +171,8 @@ fn inner_filter(event_params: EventParams) -> EventFilterAction {
         .push_constant("pow", pow as i64)
         .push_constant("spamsafe", spamsafe)
         .push_constant("muted", muted)
+        .push_constant("reported_by_follows", reported_by_follows(id, pubkey))
+        .push_constant("labels", trusted_labels(id, pubkey))
         .push_constant("DENY", 0_i64)
         .push_constant("ALLOW", 1_i64)
         .push_constant("MUTE", 2_i64);
@@ -178,6 +180,43 @@ fn inner_filter(event_params: EventParams) -> EventFilterAction {
     filter_with_script(scope)
 }
 
+// The number of people we follow who reported this event or its author (NIP-56)
+fn reported_by_follows(id: Id, pubkey: PublicKey) -> i64 {
+    let mut reporters: Vec<PublicKey> = GLOBALS
+        .db()
+        .get_reports_by_follows(id, pubkey)
+        .unwrap_or_default()
+        .drain(..)
+        .map(|(reporter, _)| reporter)
+        .collect();
+    reporters.sort();
+    reporters.dedup();
+    reporters.len() as i64
+}
+
+// The labels (NIP-32) that we or people we follow applied to this event or its author,
+// as [namespace, label] pairs
+fn trusted_labels(id: Id, pubkey: PublicKey) -> Array {
+    let mut labels = GLOBALS.db().get_labels_by_id(id).unwrap_or_default();
+    labels.extend(GLOBALS.db().get_person_labels(pubkey).unwrap_or_default());
+    let mut pairs: Vec<(String, String)> = labels
+        .drain(..)
+        .filter(|l| l.is_trusted())
+        .map(|l| (l.namespace, l.label))
+        .collect();
+    pairs.sort();
+    pairs.dedup();
+
+    // Build a rhai array (a Vec would be passed as an opaque custom type)
+    pairs
+        .drain(..)
+        .map(|(namespace, label)| {
+            let pair: Array = vec![namespace.into(), label.into()];
+            Dynamic::from_array(pair)
+        })
+        .collect()
+}
+
 fn filter_with_script(mut scope: Scope) -> EventFilterAction {
     // Get the pre-computed AST
     let ast = match &GLOBALS.spam_filter {
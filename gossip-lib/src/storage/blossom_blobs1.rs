@@ -0,0 +1,93 @@
+use crate::blossom::BlobDescriptor;
+use crate::error::Error;
+use crate::storage::{RawDatabase, Storage};
+use heed::types::Bytes;
+use heed::RwTxn;
+use speedy::{Readable, Writable};
+use std::sync::Mutex;
+
+// Sha256 -> BlobDescriptor
+//   key: sha256 (hex).as_bytes()
+//   val: blobdescriptor.write_to_vec() | BlobDescriptor::read_from_buffer(val)
+//
+// The blobs we uploaded, which we keep mirrored across all of our blossom servers.
+
+static BLOSSOM_BLOBS1_DB_CREATE_LOCK: Mutex<()> = Mutex::new(());
+static mut BLOSSOM_BLOBS1_DB: Option<RawDatabase> = None;
+
+impl Storage {
+    pub(super) fn db_blossom_blobs1(&self) -> Result<RawDatabase, Error> {
+        unsafe {
+            if let Some(db) = BLOSSOM_BLOBS1_DB {
+                Ok(db)
+            } else {
+                // Lock.  This drops when anything returns.
+                let _lock = BLOSSOM_BLOBS1_DB_CREATE_LOCK.lock();
+
+                // In case of a race, check again
+                if let Some(db) = BLOSSOM_BLOBS1_DB {
+                    return Ok(db);
+                }
+
+                // Create it. We know that nobody else is doing this and that
+                // it cannot happen twice.
+                let mut txn = self.env.write_txn()?;
+                let db = self
+                    .env
+                    .database_options()
+                    .types::<Bytes, Bytes>()
+                    // no .flags needed
+                    .name("blossom_blobs1")
+                    .create(&mut txn)?;
+                txn.commit()?;
+                BLOSSOM_BLOBS1_DB = Some(db);
+                Ok(db)
+            }
+        }
+    }
+
+    pub(crate) fn write_blossom_blob1<'a>(
+        &'a self,
+        blob: &BlobDescriptor,
+        rw_txn: Option<&mut RwTxn<'a>>,
+    ) -> Result<(), Error> {
+        let key = blob.sha256.as_bytes();
+        let bytes = blob.write_to_vec()?;
+
+        let mut local_txn = None;
+        let txn = maybe_local_txn!(self, rw_txn, local_txn);
+
+        self.db_blossom_blobs1()?.put(txn, key, &bytes)?;
+
+        maybe_local_txn_commit!(local_txn);
+
+        Ok(())
+    }
+
+    pub(crate) fn read_blossom_blobs1(&self) -> Result<Vec<BlobDescriptor>, Error> {
+        let txn = self.env.read_txn()?;
+        let mut output: Vec<BlobDescriptor> = Vec::new();
+        for result in self.db_blossom_blobs1()?.iter(&txn)? {
+            let (_key, val) = result?;
+            output.push(BlobDescriptor::read_from_buffer(val)?);
+        }
+        Ok(output)
+    }
+
+    pub(crate) fn delete_blossom_blob1<'a>(
+        &'a self,
+        sha256: &str,
+        rw_txn: Option<&mut RwTxn<'a>>,
+    ) -> Result<(), Error> {
+        let key = sha256.as_bytes();
+
+        let mut local_txn = None;
+        let txn = maybe_local_txn!(self, rw_txn, local_txn);
+
+        let _ = self.db_blossom_blobs1()?.delete(txn, key);
+
+        maybe_local_txn_commit!(local_txn);
+
+        Ok(())
+    }
+}
@@ -0,0 +1,202 @@
+use crate::error::{Error, ErrorKind};
+use crate::storage::{RawDatabase, Storage};
+use heed::types::Bytes;
+use heed::RwTxn;
+use nostr_types::{Id, Unixtime};
+use std::ops::Bound;
+use std::sync::Mutex;
+
+// Expiration:Id -> ()
+//   key: ExpirationKey
+//   val: vec![]
+//
+// Keys sort by expiration time (earliest first), so finding everything that
+// has expired is a range scan from the start of the table.
+
+static EVENT_EXPIRATION1_DB_CREATE_LOCK: Mutex<()> = Mutex::new(());
+static mut EVENT_EXPIRATION1_DB: Option<RawDatabase> = None;
+
+impl Storage {
+    pub(super) fn db_event_expiration1(&self) -> Result<RawDatabase, Error> {
+        unsafe {
+            if let Some(db) = EVENT_EXPIRATION1_DB {
+                Ok(db)
+            } else {
+                // Lock.  This drops when anything returns.
+                let _lock = EVENT_EXPIRATION1_DB_CREATE_LOCK.lock();
+
+                // In case of a race, check again
+                if let Some(db) = EVENT_EXPIRATION1_DB {
+                    return Ok(db);
+                }
+
+                // Create it. We know that nobody else is doing this and that
+                // it cannot happen twice.
+                let mut txn = self.env.write_txn()?;
+                let db = self
+                    .env
+                    .database_options()
+                    .types::<Bytes, Bytes>()
+                    // no .flags needed
+                    .name("event_expiration1")
+                    .create(&mut txn)?;
+                txn.commit()?;
+                EVENT_EXPIRATION1_DB = Some(db);
+                Ok(db)
+            }
+        }
+    }
+
+    pub(crate) fn add_event_expiration1<'a>(
+        &'a self,
+        id: Id,
+        expiration: Unixtime,
+        rw_txn: Option<&mut RwTxn<'a>>,
+    ) -> Result<(), Error> {
+        let key = ExpirationKey::from_parts(expiration, id);
+        let bytes = vec![];
+
+        let mut local_txn = None;
+        let txn = maybe_local_txn!(self, rw_txn, local_txn);
+
+        self.db_event_expiration1()?
+            .put(txn, key.as_slice(), &bytes)?;
+
+        maybe_local_txn_commit!(local_txn);
+
+        Ok(())
+    }
+
+    pub(crate) fn delete_event_expiration1<'a>(
+        &'a self,
+        id: Id,
+        expiration: Unixtime,
+        rw_txn: Option<&mut RwTxn<'a>>,
+    ) -> Result<(), Error> {
+        let key = ExpirationKey::from_parts(expiration, id);
+
+        let mut local_txn = None;
+        let txn = maybe_local_txn!(self, rw_txn, local_txn);
+
+        let _ = self.db_event_expiration1()?.delete(txn, key.as_slice());
+
+        maybe_local_txn_commit!(local_txn);
+
+        Ok(())
+    }
+
+    /// Find all events that expire at or before `now`
+    pub(crate) fn find_expired_events1(&self, now: Unixtime) -> Result<Vec<(Id, Unixtime)>, Error> {
+        let txn = self.env.read_txn()?;
+        let end_key = ExpirationKey::from_parts(now, Id([255; 32]));
+        let range = (Bound::Unbounded, Bound::Included(end_key.as_slice()));
+        let mut output: Vec<(Id, Unixtime)> = Vec::new();
+        for result in self.db_event_expiration1()?.range(&txn, &range)? {
+            let (keybytes, _) = result?;
+            let (expiration, id) = ExpirationKey::from_bytes(keybytes)?.into_parts()?;
+            output.push((id, expiration));
+        }
+        Ok(output)
+    }
+}
+
+pub struct ExpirationKey([u8; 40]);
+
+impl ExpirationKey {
+    pub fn from_parts(expiration: Unixtime, id: Id) -> Self {
+        let mut key: [u8; 40] = [0; 40];
+        // Negative times sort (and expire) first
+        key[0..8].copy_from_slice((expiration.0.max(0) as u64).to_be_bytes().as_slice());
+        key[8..40].copy_from_slice(id.0.as_slice());
+        ExpirationKey(key)
+    }
+
+    pub fn into_parts(self) -> Result<(Unixtime, Id), Error> {
+        let expiration = Unixtime(u64::from_be_bytes(self.0[0..8].try_into().unwrap()) as i64);
+        let id = Id(self.0[8..40].try_into().unwrap());
+        Ok((expiration, id))
+    }
+
+    pub fn from_bytes(bytes: &[u8]) -> Result<ExpirationKey, Error> {
+        if bytes.len() != 40 {
+            return Err(ErrorKind::KeySizeWrong.into());
+        }
+        Ok(ExpirationKey(bytes.try_into().unwrap()))
+    }
+
+    pub fn as_slice(&self) -> &[u8] {
+        self.0.as_slice()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_expiration_key() {
+        let expiration = Unixtime::now();
+        let id = Id::try_from_hex_string(
+            "77f7653c67147a125cc624f695029d0557e3ab402e714680eb23dd2499f439a0",
+        )
+        .unwrap();
+
+        let key = ExpirationKey::from_parts(expiration, id);
+        let (expiration2, id2) = key.into_parts().unwrap();
+
+        assert_eq!(expiration, expiration2);
+        assert_eq!(id, id2);
+    }
+
+    #[test]
+    fn test_prune_expired_events() {
+        use nostr_types::{EventKind, Identity, PreEvent, Tag};
+
+        let storage = Storage::for_tests();
+        let identity = Identity::generate("", 1).unwrap();
+        let now = Unixtime::now();
+
+        let mut events = Vec::new();
+        for expiration in [now.0 - 3600, now.0 - 1, now.0 + 3600] {
+            let pre_event = PreEvent {
+                pubkey: identity.public_key().unwrap(),
+                created_at: Unixtime(now.0 - 7200),
+                kind: EventKind::TextNote,
+                tags: vec![Tag::new(&["expiration", &expiration.to_string()])],
+                content: format!("expires at {}", expiration),
+            };
+            let event = identity.sign_event(pre_event).unwrap();
+            storage.write_event(&event, None).unwrap();
+            storage
+                .add_event_expiration(event.id, Unixtime(expiration), None)
+                .unwrap();
+            events.push(event);
+        }
+
+        // An expiration recorded for an event we no longer have is cleared too
+        let gone = Id([7; 32]);
+        storage
+            .add_event_expiration(gone, Unixtime(now.0 - 10), None)
+            .unwrap();
+
+        assert_eq!(storage.prune_expired_events(now).unwrap(), 2);
+
+        assert!(storage.read_event(events[0].id).unwrap().is_none());
+        assert!(storage.read_event(events[1].id).unwrap().is_none());
+        assert!(storage.read_event(events[2].id).unwrap().is_some());
+
+        let remaining: Vec<Id> = storage
+            .find_expired_events(Unixtime(now.0 + 3600))
+            .unwrap()
+            .iter()
+            .map(|(id, _)| *id)
+            .collect();
+        assert!(remaining.contains(&events[2].id));
+        assert!(!remaining.contains(&events[0].id));
+        assert!(!remaining.contains(&events[1].id));
+        assert!(!remaining.contains(&gone));
+
+        // Nothing more has expired
+        assert_eq!(storage.prune_expired_events(now).unwrap(), 0);
+    }
+}
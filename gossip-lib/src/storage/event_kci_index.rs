@@ -9,13 +9,15 @@ use std::sync::Mutex;
 static EVENT_KCI_INDEX_DB_CREATE_LOCK: Mutex<()> = Mutex::new(());
 static mut EVENT_KCI_INDEX_DB: Option<EmptyDatabase> = None;
 
-pub(super) const INDEXED_KINDS: [EventKind; 6] = [
+pub(super) const INDEXED_KINDS: [EventKind; 8] = [
     EventKind::Metadata,
     EventKind::ContactList,
     EventKind::RelayList,
     EventKind::DmRelayList,
     EventKind::EncryptedDirectMessage,
     EventKind::GiftWrap,
+    EventKind::ChannelCreation,
+    EventKind::ChannelMessage,
 ];
 
 impl Storage {
@@ -8,11 +8,12 @@ use std::sync::Mutex;
 // This replaces event_tci_index which didn't have the
 // reverse created_at suffix.
 
-pub(super) const INDEXED_TAGS: [&str; 4] = ["a", "d", "p", "delegation"];
+pub(super) const INDEXED_TAGS: [&str; 5] = ["a", "d", "h", "p", "delegation"];
 
 // This indexes these tags, except for "p" tags we only index it if
 //   1) the "p" tag is our user, or
-//   2) the event is a ContactList
+//   2) the event is a ContactList, or
+//   3) the event is a Label (so we can find labels of people)
 
 // TagKey:QUOTE:TagValue:QUOTE:Created(reversed):Id -> ()
 
@@ -89,9 +90,9 @@ impl Storage {
                 continue;
             }
             // For 'p' tags, only index them if 'p' is our user, or if the event is
-            // a ContactList
+            // a ContactList or a Label
             if tagname == "p" {
-                if event.kind != EventKind::ContactList {
+                if event.kind != EventKind::ContactList && event.kind != EventKind::Label {
                     match &pk {
                         None => continue,
                         Some(pk) => {
@@ -88,6 +88,8 @@ impl Storage {
                 } // upstream bug
                 self.add_hashtag(&hashtag, event.id, Some(txn))?;
             }
+
+            self.write_search_index(event, Some(txn))?;
         }
 
         maybe_local_txn_commit!(local_txn);
@@ -0,0 +1,100 @@
+use crate::channels::FollowedChannel;
+use crate::error::Error;
+use crate::storage::{RawDatabase, Storage};
+use heed::types::Bytes;
+use heed::RwTxn;
+use nostr_types::Id;
+use speedy::{Readable, Writable};
+use std::sync::Mutex;
+
+// Id -> FollowedChannel
+//   key: id.as_slice()
+//   val: followedchannel.write_to_vec() | FollowedChannel::read_from_buffer(val)
+//
+// The public chat channels (NIP-28) we follow, by the id of their creation event.
+
+static FOLLOWED_CHANNELS1_DB_CREATE_LOCK: Mutex<()> = Mutex::new(());
+static mut FOLLOWED_CHANNELS1_DB: Option<RawDatabase> = None;
+
+impl Storage {
+    pub(super) fn db_followed_channels1(&self) -> Result<RawDatabase, Error> {
+        unsafe {
+            if let Some(db) = FOLLOWED_CHANNELS1_DB {
+                Ok(db)
+            } else {
+                // Lock.  This drops when anything returns.
+                let _lock = FOLLOWED_CHANNELS1_DB_CREATE_LOCK.lock();
+
+                // In case of a race, check again
+                if let Some(db) = FOLLOWED_CHANNELS1_DB {
+                    return Ok(db);
+                }
+
+                // Create it. We know that nobody else is doing this and that
+                // it cannot happen twice.
+                let mut txn = self.env.write_txn()?;
+                let db = self
+                    .env
+                    .database_options()
+                    .types::<Bytes, Bytes>()
+                    // no .flags needed
+                    .name("followed_channels1")
+                    .create(&mut txn)?;
+                txn.commit()?;
+                FOLLOWED_CHANNELS1_DB = Some(db);
+                Ok(db)
+            }
+        }
+    }
+
+    pub(crate) fn write_followed_channel1<'a>(
+        &'a self,
+        channel: &FollowedChannel,
+        rw_txn: Option<&mut RwTxn<'a>>,
+    ) -> Result<(), Error> {
+        let bytes = channel.write_to_vec()?;
+
+        let mut local_txn = None;
+        let txn = maybe_local_txn!(self, rw_txn, local_txn);
+
+        self.db_followed_channels1()?
+            .put(txn, channel.id.as_slice(), &bytes)?;
+
+        maybe_local_txn_commit!(local_txn);
+
+        Ok(())
+    }
+
+    pub(crate) fn read_followed_channels1(&self) -> Result<Vec<FollowedChannel>, Error> {
+        let txn = self.env.read_txn()?;
+        let mut output: Vec<FollowedChannel> = Vec::new();
+        for result in self.db_followed_channels1()?.iter(&txn)? {
+            let (_key, val) = result?;
+            output.push(FollowedChannel::read_from_buffer(val)?);
+        }
+        Ok(output)
+    }
+
+    pub(crate) fn is_followed_channel1(&self, id: Id) -> Result<bool, Error> {
+        let txn = self.env.read_txn()?;
+        Ok(self
+            .db_followed_channels1()?
+            .get(&txn, id.as_slice())?
+            .is_some())
+    }
+
+    pub(crate) fn delete_followed_channel1<'a>(
+        &'a self,
+        id: Id,
+        rw_txn: Option<&mut RwTxn<'a>>,
+    ) -> Result<(), Error> {
+        let mut local_txn = None;
+        let txn = maybe_local_txn!(self, rw_txn, local_txn);
+
+        let _ = self.db_followed_channels1()?.delete(txn, id.as_slice());
+
+        maybe_local_txn_commit!(local_txn);
+
+        Ok(())
+    }
+}
@@ -0,0 +1,99 @@
+use crate::error::Error;
+use crate::storage::{RawDatabase, Storage};
+use heed::types::Bytes;
+use heed::{RoTxn, RwTxn};
+use nostr_types::Id;
+use std::sync::Mutex;
+
+// Id -> String
+//   key: id.as_slice()
+//   val: reason.as_bytes() | String::from_utf8(val)
+//
+// Zap receipts (kind 9735) that failed NIP-57 validation, and why.
+// These are left out of zap totals.
+
+static INVALID_ZAPS1_DB_CREATE_LOCK: Mutex<()> = Mutex::new(());
+static mut INVALID_ZAPS1_DB: Option<RawDatabase> = None;
+
+impl Storage {
+    pub(super) fn db_invalid_zaps1(&self) -> Result<RawDatabase, Error> {
+        unsafe {
+            if let Some(db) = INVALID_ZAPS1_DB {
+                Ok(db)
+            } else {
+                // Lock.  This drops when anything returns.
+                let _lock = INVALID_ZAPS1_DB_CREATE_LOCK.lock();
+
+                // In case of a race, check again
+                if let Some(db) = INVALID_ZAPS1_DB {
+                    return Ok(db);
+                }
+
+                // Create it. We know that nobody else is doing this and that
+                // it cannot happen twice.
+                let mut txn = self.env.write_txn()?;
+                let db = self
+                    .env
+                    .database_options()
+                    .types::<Bytes, Bytes>()
+                    // no .flags needed
+                    .name("invalid_zaps1")
+                    .create(&mut txn)?;
+                txn.commit()?;
+                INVALID_ZAPS1_DB = Some(db);
+                Ok(db)
+            }
+        }
+    }
+
+    pub(crate) fn write_invalid_zap1<'a>(
+        &'a self,
+        receipt: Id,
+        reason: &str,
+        rw_txn: Option<&mut RwTxn<'a>>,
+    ) -> Result<(), Error> {
+        let mut local_txn = None;
+        let txn = maybe_local_txn!(self, rw_txn, local_txn);
+
+        self.db_invalid_zaps1()?
+            .put(txn, receipt.as_slice(), reason.as_bytes())?;
+
+        maybe_local_txn_commit!(local_txn);
+
+        Ok(())
+    }
+
+    pub(crate) fn read_invalid_zap1(
+        &self,
+        receipt: Id,
+        txn: Option<&RoTxn<'_>>,
+    ) -> Result<Option<String>, Error> {
+        let local_txn;
+        let txn = match txn {
+            Some(txn) => txn,
+            None => {
+                local_txn = self.env.read_txn()?;
+                &local_txn
+            }
+        };
+        match self.db_invalid_zaps1()?.get(txn, receipt.as_slice())? {
+            Some(bytes) => Ok(Some(String::from_utf8_lossy(bytes).into_owned())),
+            None => Ok(None),
+        }
+    }
+
+    pub(crate) fn delete_invalid_zap1<'a>(
+        &'a self,
+        receipt: Id,
+        rw_txn: Option<&mut RwTxn<'a>>,
+    ) -> Result<(), Error> {
+        let mut local_txn = None;
+        let txn = maybe_local_txn!(self, rw_txn, local_txn);
+
+        let _ = self.db_invalid_zaps1()?.delete(txn, receipt.as_slice());
+
+        maybe_local_txn_commit!(local_txn);
+
+        Ok(())
+    }
+}
@@ -0,0 +1,105 @@
+use crate::error::Error;
+use crate::groups::JoinedGroup;
+use crate::storage::{RawDatabase, Storage};
+use heed::types::Bytes;
+use heed::RwTxn;
+use nostr_types::RelayUrl;
+use speedy::{Readable, Writable};
+use std::sync::Mutex;
+
+// Relay:GroupId -> JoinedGroup
+//   key: relay url + "'" + group id (utf-8)
+//   val: joinedgroup.write_to_vec() | JoinedGroup::read_from_buffer(val)
+//
+// The relay-based groups (NIP-29) we have joined.
+
+static JOINED_GROUPS1_DB_CREATE_LOCK: Mutex<()> = Mutex::new(());
+static mut JOINED_GROUPS1_DB: Option<RawDatabase> = None;
+
+impl Storage {
+    pub(super) fn db_joined_groups1(&self) -> Result<RawDatabase, Error> {
+        unsafe {
+            if let Some(db) = JOINED_GROUPS1_DB {
+                Ok(db)
+            } else {
+                // Lock.  This drops when anything returns.
+                let _lock = JOINED_GROUPS1_DB_CREATE_LOCK.lock();
+
+                // In case of a race, check again
+                if let Some(db) = JOINED_GROUPS1_DB {
+                    return Ok(db);
+                }
+
+                // Create it. We know that nobody else is doing this and that
+                // it cannot happen twice.
+                let mut txn = self.env.write_txn()?;
+                let db = self
+                    .env
+                    .database_options()
+                    .types::<Bytes, Bytes>()
+                    // no .flags needed
+                    .name("joined_groups1")
+                    .create(&mut txn)?;
+                txn.commit()?;
+                JOINED_GROUPS1_DB = Some(db);
+                Ok(db)
+            }
+        }
+    }
+
+    pub(crate) fn write_joined_group1<'a>(
+        &'a self,
+        group: &JoinedGroup,
+        rw_txn: Option<&mut RwTxn<'a>>,
+    ) -> Result<(), Error> {
+        let key = key(&group.relay, &group.id);
+        let bytes = group.write_to_vec()?;
+
+        let mut local_txn = None;
+        let txn = maybe_local_txn!(self, rw_txn, local_txn);
+
+        self.db_joined_groups1()?.put(txn, &key, &bytes)?;
+
+        maybe_local_txn_commit!(local_txn);
+
+        Ok(())
+    }
+
+    pub(crate) fn read_joined_groups1(&self) -> Result<Vec<JoinedGroup>, Error> {
+        let txn = self.env.read_txn()?;
+        let mut output: Vec<JoinedGroup> = Vec::new();
+        for result in self.db_joined_groups1()?.iter(&txn)? {
+            let (_key, val) = result?;
+            output.push(JoinedGroup::read_from_buffer(val)?);
+        }
+        Ok(output)
+    }
+
+    pub(crate) fn is_joined_group1(&self, relay: &RelayUrl, id: &str) -> Result<bool, Error> {
+        let txn = self.env.read_txn()?;
+        Ok(self
+            .db_joined_groups1()?
+            .get(&txn, &key(relay, id))?
+            .is_some())
+    }
+
+    pub(crate) fn delete_joined_group1<'a>(
+        &'a self,
+        relay: &RelayUrl,
+        id: &str,
+        rw_txn: Option<&mut RwTxn<'a>>,
+    ) -> Result<(), Error> {
+        let mut local_txn = None;
+        let txn = maybe_local_txn!(self, rw_txn, local_txn);
+
+        let _ = self.db_joined_groups1()?.delete(txn, &key(relay, id));
+
+        maybe_local_txn_commit!(local_txn);
+
+        Ok(())
+    }
+}
+
+fn key(relay: &RelayUrl, id: &str) -> Vec<u8> {
+    format!("{}'{}", relay.as_str(), id).into_bytes()
+}
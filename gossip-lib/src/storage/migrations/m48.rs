@@ -0,0 +1,24 @@
+use crate::error::Error;
+use crate::storage::Storage;
+use heed::RwTxn;
+
+impl Storage {
+    pub(super) fn m48_trigger(&self) -> Result<(), Error> {
+        let _ = self.db_search_index1()?;
+        Ok(())
+    }
+
+    pub(super) fn m48_migrate<'a>(
+        &'a self,
+        prefix: &str,
+        txn: &mut RwTxn<'a>,
+    ) -> Result<(), Error> {
+        // Info message
+        tracing::info!("{prefix}: Flagging that search index needs to be built...");
+
+        // Build search index
+        self.set_flag_rebuild_search_index_needed(true, Some(txn))?;
+
+        Ok(())
+    }
+}
@@ -0,0 +1,34 @@
+use crate::error::Error;
+use crate::storage::Storage;
+use heed::RwTxn;
+use nostr_types::Event;
+use speedy::Readable;
+
+impl Storage {
+    pub(super) fn m49_trigger(&self) -> Result<(), Error> {
+        let _ = self.db_events()?;
+        let _ = self.db_event_expiration1()?;
+        Ok(())
+    }
+
+    pub(super) fn m49_migrate<'a>(
+        &'a self,
+        prefix: &str,
+        txn: &mut RwTxn<'a>,
+    ) -> Result<(), Error> {
+        // Info message
+        tracing::info!("{prefix}: Indexing event expirations...");
+
+        // Index expiration tags of events we already have
+        let loop_txn = self.env.read_txn()?;
+        for result in self.db_events()?.iter(&loop_txn)? {
+            let (_key, val) = result?;
+            let event = Event::read_from_buffer(val)?;
+            if let Some(expiration) = crate::misc::event_expiration(&event) {
+                self.add_event_expiration1(event.id, expiration, Some(txn))?;
+            }
+        }
+
+        Ok(())
+    }
+}
@@ -0,0 +1,35 @@
+use crate::error::Error;
+use crate::storage::Storage;
+use heed::RwTxn;
+use nostr_types::{Event, EventKind};
+use speedy::Readable;
+
+impl Storage {
+    pub(super) fn m50_trigger(&self) -> Result<(), Error> {
+        let _ = self.db_events()?;
+        let _ = self.db_person_reports1()?;
+        Ok(())
+    }
+
+    pub(super) fn m50_migrate<'a>(
+        &'a self,
+        prefix: &str,
+        txn: &mut RwTxn<'a>,
+    ) -> Result<(), Error> {
+        // Info message
+        tracing::info!("{prefix}: Indexing reports of people...");
+
+        // Index the reports we already have
+        let loop_txn = self.env.read_txn()?;
+        for result in self.db_events()?.iter(&loop_txn)? {
+            let (_key, val) = result?;
+            if Event::get_kind_from_speedy_bytes(val) != Some(EventKind::Reporting) {
+                continue;
+            }
+            let event = Event::read_from_buffer(val)?;
+            self.write_person_reports1(&event, Some(txn))?;
+        }
+
+        Ok(())
+    }
+}
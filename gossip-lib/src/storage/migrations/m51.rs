@@ -0,0 +1,35 @@
+use crate::error::Error;
+use crate::storage::Storage;
+use heed::RwTxn;
+use nostr_types::{Event, EventKind};
+use speedy::Readable;
+
+impl Storage {
+    pub(super) fn m51_trigger(&self) -> Result<(), Error> {
+        let _ = self.db_events()?;
+        let _ = self.db_event_tci_index()?;
+        Ok(())
+    }
+
+    pub(super) fn m51_migrate<'a>(
+        &'a self,
+        prefix: &str,
+        txn: &mut RwTxn<'a>,
+    ) -> Result<(), Error> {
+        // Info message
+        tracing::info!("{prefix}: Indexing labels of people...");
+
+        // Label events now have their "p" tags indexed
+        let loop_txn = self.env.read_txn()?;
+        for result in self.db_events()?.iter(&loop_txn)? {
+            let (_key, val) = result?;
+            if Event::get_kind_from_speedy_bytes(val) != Some(EventKind::Label) {
+                continue;
+            }
+            let event = Event::read_from_buffer(val)?;
+            self.write_event_tci_index(&event, Some(txn))?;
+        }
+
+        Ok(())
+    }
+}
@@ -0,0 +1,71 @@
+use crate::error::Error;
+use crate::nostr_connect_server::{Approval, Nip46Policy, Nip46Server};
+use crate::storage::Storage;
+use heed::RwTxn;
+use nostr_types::{PublicKey, RelayUrl};
+use speedy::{Readable, Writable};
+
+// The layout of nip46server2 records before per-method approvals and policies
+#[derive(Debug, Clone, Readable, Writable)]
+struct Nip46ServerV1 {
+    name: String,
+    peer_pubkey: PublicKey,
+    relays: Vec<RelayUrl>,
+    sign_approval: Approval,
+    encrypt_approval: Approval,
+    decrypt_approval: Approval,
+}
+
+impl Storage {
+    pub(super) fn m52_trigger(&self) -> Result<(), Error> {
+        let _ = self.db_nip46servers2()?;
+        Ok(())
+    }
+
+    pub(super) fn m52_migrate<'a>(
+        &'a self,
+        prefix: &str,
+        txn: &mut RwTxn<'a>,
+    ) -> Result<(), Error> {
+        // Info message
+        tracing::info!("{prefix}: Adding policies to nostr-connect services...");
+
+        // Migrate
+        self.m52_migrate_nip46_servers(txn)?;
+
+        Ok(())
+    }
+
+    fn m52_migrate_nip46_servers<'a>(&'a self, txn: &mut RwTxn<'a>) -> Result<(), Error> {
+        let loop_txn = self.env.read_txn()?;
+        let mut servers: Vec<Nip46Server> = Vec::new();
+        for result in self.db_nip46servers2()?.iter(&loop_txn)? {
+            let (_key, val) = result?;
+            let old = Nip46ServerV1::read_from_buffer(val)?;
+
+            // Encryption and decryption approvals now apply per method
+            servers.push(Nip46Server {
+                name: old.name,
+                peer_pubkey: old.peer_pubkey,
+                relays: old.relays,
+                sign_approval: old.sign_approval,
+                nip04_encrypt_approval: old.encrypt_approval,
+                nip04_decrypt_approval: old.decrypt_approval,
+                nip44_encrypt_approval: old.encrypt_approval,
+                nip44_decrypt_approval: old.decrypt_approval,
+                policy: Nip46Policy::default(),
+            });
+        }
+        drop(loop_txn);
+
+        for server in &servers {
+            self.db_nip46servers2()?.put(
+                txn,
+                server.peer_pubkey.as_bytes(),
+                &server.write_to_vec()?,
+            )?;
+        }
+
+        Ok(())
+    }
+}
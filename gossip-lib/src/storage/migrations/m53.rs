@@ -0,0 +1,35 @@
+use crate::error::Error;
+use crate::storage::Storage;
+use heed::RwTxn;
+use nostr_types::Event;
+use speedy::Readable;
+
+impl Storage {
+    pub(super) fn m53_trigger(&self) -> Result<(), Error> {
+        let _ = self.db_events()?;
+        let _ = self.db_event_tci_index()?;
+        Ok(())
+    }
+
+    pub(super) fn m53_migrate<'a>(
+        &'a self,
+        prefix: &str,
+        txn: &mut RwTxn<'a>,
+    ) -> Result<(), Error> {
+        // Info message
+        tracing::info!("{prefix}: Indexing events posted into groups...");
+
+        // "h" tags (NIP-29 groups) are now indexed
+        let loop_txn = self.env.read_txn()?;
+        for result in self.db_events()?.iter(&loop_txn)? {
+            let (_key, val) = result?;
+            let event = Event::read_from_buffer(val)?;
+            if !event.tags.iter().any(|t| t.tagname() == "h") {
+                continue;
+            }
+            self.write_event_tci_index(&event, Some(txn))?;
+        }
+
+        Ok(())
+    }
+}
@@ -0,0 +1,39 @@
+use crate::error::Error;
+use crate::storage::Storage;
+use heed::RwTxn;
+use nostr_types::{Event, EventKind};
+use speedy::Readable;
+
+impl Storage {
+    pub(super) fn m54_trigger(&self) -> Result<(), Error> {
+        let _ = self.db_events()?;
+        let _ = self.db_event_kci_index()?;
+        Ok(())
+    }
+
+    pub(super) fn m54_migrate<'a>(
+        &'a self,
+        prefix: &str,
+        txn: &mut RwTxn<'a>,
+    ) -> Result<(), Error> {
+        // Info message
+        tracing::info!("{prefix}: Indexing public chat channels...");
+
+        // Channel creation and channel message kinds (NIP-28) are now indexed
+        let loop_txn = self.env.read_txn()?;
+        for result in self.db_events()?.iter(&loop_txn)? {
+            let (_key, val) = result?;
+            let kind = match Event::get_kind_from_speedy_bytes(val) {
+                Some(kind) => kind,
+                None => continue,
+            };
+            if kind != EventKind::ChannelCreation && kind != EventKind::ChannelMessage {
+                continue;
+            }
+            let event = Event::read_from_buffer(val)?;
+            self.write_event_kci_index(event.kind, event.created_at, event.id, Some(txn))?;
+        }
+
+        Ok(())
+    }
+}
@@ -31,6 +31,13 @@ mod m44;
 mod m45;
 mod m46;
 mod m47;
+mod m48;
+mod m49;
+mod m50;
+mod m51;
+mod m52;
+mod m53;
+mod m54;
 
 use super::Storage;
 use crate::error::{Error, ErrorKind};
@@ -38,7 +45,7 @@ use heed::RwTxn;
 
 impl Storage {
     const MIN_MIGRATION_LEVEL: u32 = 23;
-    const MAX_MIGRATION_LEVEL: u32 = 47;
+    const MAX_MIGRATION_LEVEL: u32 = 54;
 
     /// Initialize the database from empty
     pub(super) fn init_from_empty(&self) -> Result<(), Error> {
@@ -138,6 +145,13 @@ impl Storage {
             45 => self.m45_trigger()?,
             46 => self.m46_trigger()?,
             47 => self.m47_trigger()?,
+            48 => self.m48_trigger()?,
+            49 => self.m49_trigger()?,
+            50 => self.m50_trigger()?,
+            51 => self.m51_trigger()?,
+            52 => self.m52_trigger()?,
+            53 => self.m53_trigger()?,
+            54 => self.m54_trigger()?,
             _ => panic!("Unreachable migration level"),
         }
 
@@ -176,6 +190,13 @@ impl Storage {
             45 => self.m45_migrate(&prefix, txn)?,
             46 => self.m46_migrate(&prefix, txn)?,
             47 => self.m47_migrate(&prefix, txn)?,
+            48 => self.m48_migrate(&prefix, txn)?,
+            49 => self.m49_migrate(&prefix, txn)?,
+            50 => self.m50_migrate(&prefix, txn)?,
+            51 => self.m51_migrate(&prefix, txn)?,
+            52 => self.m52_migrate(&prefix, txn)?,
+            53 => self.m53_migrate(&prefix, txn)?,
+            54 => self.m54_migrate(&prefix, txn)?,
             _ => panic!("Unreachable migration level"),
         };
 
@@ -4,6 +4,7 @@ const MAX_LMDB_KEY: usize = 511;
 
 mod migrations;
 mod prune;
+mod search;
 
 // type implementations
 pub mod types;
@@ -24,6 +25,7 @@ pub mod handlers_table;
 pub use handlers_table::HandlersTable;
 
 // database implementations
+mod blossom_blobs1;
 mod configured_handlers;
 mod event_akci_index;
 use event_akci_index::AkciKey;
@@ -31,6 +33,7 @@ mod event_kci_index;
 use event_kci_index::KciKey;
 mod event_ek_c_index1;
 mod event_ek_pk_index1;
+mod event_expiration1;
 mod event_seen_on_relay1;
 mod event_tci_index;
 use event_tci_index::TciKey;
@@ -38,8 +41,12 @@ mod event_viewed1;
 mod events2;
 mod events3;
 mod fof;
+mod followed_channels1;
 mod general;
 mod hashtags1;
+mod invalid_zaps1;
+mod joined_groups1;
+mod nip46_audit1;
 mod nip46servers1;
 mod nip46servers2;
 mod people2;
@@ -49,27 +56,36 @@ mod person_lists_metadata2;
 mod person_lists_metadata3;
 mod person_relays1;
 mod person_relays2;
+mod person_reports1;
 mod relationships_by_addr1;
 mod relationships_by_addr2;
 mod relationships_by_addr3;
 mod relationships_by_id1;
 mod relationships_by_id2;
+mod relay_stats1;
 mod relays1;
 mod relays2;
 mod relays3;
+mod search_index1;
 mod unindexed_giftwraps1;
 mod versioned;
 
+use crate::blossom::BlobDescriptor;
+use crate::channels::FollowedChannel;
 use crate::dm_channel::{DmChannel, DmChannelData};
 use crate::error::{Error, ErrorKind};
 use crate::globals::GLOBALS;
+use crate::groups::JoinedGroup;
+use crate::labels::Label;
 use crate::misc::Private;
-use crate::nostr_connect_server::{Nip46Server, Nip46UnconnectedServer};
+use crate::nostr_connect_client::Nip46Bunker;
+use crate::nostr_connect_server::{Nip46AuditEntry, Nip46Server, Nip46UnconnectedServer};
 use crate::people::{PersonList, PersonListMetadata};
 use crate::person_relay::PersonRelay;
 use crate::profile::Profile;
 use crate::relationship::{RelationshipByAddr, RelationshipById};
 use crate::relay::Relay;
+use crate::relay_stats::RelayStats;
 use dashmap::DashMap;
 use filetime::FileTime;
 use heed::types::{Bytes, Unit};
@@ -83,6 +99,7 @@ use speedy::{Readable, Writable};
 use std::collections::{BTreeSet, HashMap};
 use std::fs;
 use std::ops::Bound;
+use std::path::Path;
 use std::time::SystemTime;
 
 use self::event_kci_index::INDEXED_KINDS;
@@ -103,6 +120,11 @@ pub struct Storage {
 
 impl Storage {
     fn new_env(rapid: bool) -> Result<Env, Error> {
+        let dir = Profile::lmdb_dir()?;
+        Self::open_env(&dir, rapid)
+    }
+
+    fn open_env(dir: &Path, rapid: bool) -> Result<Env, Error> {
         let mut builder = EnvOpenOptions::new();
 
         let flags = if rapid {
@@ -123,7 +145,7 @@ impl Storage {
         }
 
         // builder.max_readers(126); // this is the default
-        builder.max_dbs(32);
+        builder.max_dbs(48);
 
         // This has to be big enough for all the data.
         // Note that it is the size of the map in VIRTUAL address space,
@@ -134,9 +156,8 @@ impl Storage {
         //       after the database has been launched.
         builder.map_size(1048576 * 1024 * 24); // 24 GB
 
-        let dir = Profile::lmdb_dir()?;
         let env = unsafe {
-            match builder.open(&dir) {
+            match builder.open(dir) {
                 Ok(env) => env,
                 Err(e) => {
                     tracing::error!("Unable to open LMDB at {}", dir.display());
@@ -158,6 +179,23 @@ impl Storage {
         })
     }
 
+    /// A storage in a temporary directory for tests. There is only one, shared
+    /// by every test, because the table handles are process-wide statics.
+    #[cfg(test)]
+    pub(crate) fn for_tests() -> &'static Storage {
+        static STORAGE: std::sync::OnceLock<Storage> = std::sync::OnceLock::new();
+        STORAGE.get_or_init(|| {
+            let dir = tempdir::TempDir::new("gossip-test-lmdb")
+                .unwrap()
+                .into_path();
+            Storage {
+                env: Self::open_env(&dir, false).unwrap(),
+                volatile_events: DashMap::new(),
+                volatile_seen_on: DashMap::new(),
+            }
+        })
+    }
+
     pub(crate) fn compact() -> Result<(), Error> {
         let lmdb_dir = Profile::lmdb_dir()?;
 
@@ -247,18 +285,27 @@ impl Storage {
         //
         // old-version databases will be handled by their migration code and only
         // triggered into existence if their migration is necessary.
+        let _ = self.db_blossom_blobs()?;
         let _ = self.db_event_akci_index()?;
         let _ = self.db_event_kci_index()?;
         let _ = self.db_event_tci_index()?;
         let _ = self.db_events()?;
         let _ = self.db_event_seen_on_relay()?;
         let _ = self.db_event_viewed()?;
+        let _ = self.db_event_expiration()?;
+        let _ = self.db_followed_channels()?;
         let _ = self.db_hashtags()?;
+        let _ = self.db_invalid_zaps()?;
+        let _ = self.db_joined_groups()?;
+        let _ = self.db_nip46_audit()?;
         let _ = self.db_nip46servers()?;
         let _ = self.db_person_relays()?;
+        let _ = self.db_person_reports()?;
+        let _ = self.db_relay_stats()?;
         let _ = self.db_relationships_by_id()?;
         let _ = self.db_relationships_by_addr()?;
         let _ = self.db_relays()?;
+        let _ = self.db_search_index()?;
         let _ = self.db_unindexed_giftwraps()?;
         let _ = self.db_person_lists()?;
         let _ = self.db_person_lists_metadata()?;
@@ -302,6 +349,11 @@ impl Storage {
 
     // Database getters ---------------------------------
 
+    #[inline]
+    pub(crate) fn db_blossom_blobs(&self) -> Result<RawDatabase, Error> {
+        self.db_blossom_blobs1()
+    }
+
     #[inline]
     pub(crate) fn db_events(&self) -> Result<RawDatabase, Error> {
         self.db_events3()
@@ -317,11 +369,41 @@ impl Storage {
         self.db_event_viewed1()
     }
 
+    #[inline]
+    pub(crate) fn db_event_expiration(&self) -> Result<RawDatabase, Error> {
+        self.db_event_expiration1()
+    }
+
     #[inline]
     pub(crate) fn db_hashtags(&self) -> Result<RawDatabase, Error> {
         self.db_hashtags1()
     }
 
+    #[inline]
+    pub(crate) fn db_followed_channels(&self) -> Result<RawDatabase, Error> {
+        self.db_followed_channels1()
+    }
+
+    #[inline]
+    pub(crate) fn db_invalid_zaps(&self) -> Result<RawDatabase, Error> {
+        self.db_invalid_zaps1()
+    }
+
+    #[inline]
+    pub(crate) fn db_relay_stats(&self) -> Result<RawDatabase, Error> {
+        self.db_relay_stats1()
+    }
+
+    #[inline]
+    pub(crate) fn db_joined_groups(&self) -> Result<RawDatabase, Error> {
+        self.db_joined_groups1()
+    }
+
+    #[inline]
+    pub(crate) fn db_nip46_audit(&self) -> Result<RawDatabase, Error> {
+        self.db_nip46_audit1()
+    }
+
     #[inline]
     pub(crate) fn db_nip46servers(&self) -> Result<RawDatabase, Error> {
         self.db_nip46servers2()
@@ -332,6 +414,11 @@ impl Storage {
         self.db_person_relays2()
     }
 
+    #[inline]
+    pub(crate) fn db_person_reports(&self) -> Result<RawDatabase, Error> {
+        self.db_person_reports1()
+    }
+
     #[inline]
     pub(crate) fn db_relationships_by_addr(&self) -> Result<RawDatabase, Error> {
         self.db_relationships_by_addr3()
@@ -347,6 +434,11 @@ impl Storage {
         self.db_relays3()
     }
 
+    #[inline]
+    pub(crate) fn db_search_index(&self) -> Result<RawDatabase, Error> {
+        self.db_search_index1()
+    }
+
     #[inline]
     pub(crate) fn db_unindexed_giftwraps(&self) -> Result<RawDatabase, Error> {
         self.db_unindexed_giftwraps1()
@@ -382,12 +474,66 @@ impl Storage {
         self.get_event_viewed1_len()
     }
 
+    /// The number of records in the event_expiration table
+    pub fn get_event_expiration_len(&self) -> Result<u64, Error> {
+        let txn = self.env.read_txn()?;
+        Ok(self.db_event_expiration()?.len(&txn)?)
+    }
+
     /// The number of records in the hashtags table
     pub fn get_hashtags_len(&self) -> Result<u64, Error> {
         let txn = self.env.read_txn()?;
         Ok(self.db_hashtags()?.len(&txn)?)
     }
 
+    /// The number of records in the person_reports table
+    pub fn get_person_reports_len(&self) -> Result<u64, Error> {
+        let txn = self.env.read_txn()?;
+        Ok(self.db_person_reports()?.len(&txn)?)
+    }
+
+    /// The number of records in the search index table
+    pub fn get_search_index_len(&self) -> Result<u64, Error> {
+        let txn = self.env.read_txn()?;
+        Ok(self.db_search_index()?.len(&txn)?)
+    }
+
+    /// The number of records in the blossom_blobs table
+    pub fn get_blossom_blobs_len(&self) -> Result<u64, Error> {
+        let txn = self.env.read_txn()?;
+        Ok(self.db_blossom_blobs()?.len(&txn)?)
+    }
+
+    /// The number of records in the invalid_zaps table
+    pub fn get_invalid_zaps_len(&self) -> Result<u64, Error> {
+        let txn = self.env.read_txn()?;
+        Ok(self.db_invalid_zaps()?.len(&txn)?)
+    }
+
+    /// The number of records in the followed_channels table
+    pub fn get_followed_channels_len(&self) -> Result<u64, Error> {
+        let txn = self.env.read_txn()?;
+        Ok(self.db_followed_channels()?.len(&txn)?)
+    }
+
+    /// The number of records in the relay_stats table
+    pub fn get_relay_stats_len(&self) -> Result<u64, Error> {
+        let txn = self.env.read_txn()?;
+        Ok(self.db_relay_stats()?.len(&txn)?)
+    }
+
+    /// The number of records in the joined_groups table
+    pub fn get_joined_groups_len(&self) -> Result<u64, Error> {
+        let txn = self.env.read_txn()?;
+        Ok(self.db_joined_groups()?.len(&txn)?)
+    }
+
+    /// The number of records in the nip46_audit table
+    pub fn get_nip46_audit_len(&self) -> Result<u64, Error> {
+        let txn = self.env.read_txn()?;
+        Ok(self.db_nip46_audit()?.len(&txn)?)
+    }
+
     /// The number of records in the nip46servers table
     pub fn get_nip46servers_len(&self) -> Result<u64, Error> {
         let txn = self.env.read_txn()?;
@@ -575,6 +721,48 @@ impl Storage {
         Ok(())
     }
 
+    /// Write the remote signer (NIP-46 bunker) we sign with
+    pub fn write_nip46_bunker<'a>(
+        &'a self,
+        bunker: &Nip46Bunker,
+        rw_txn: Option<&mut RwTxn<'a>>,
+    ) -> Result<(), Error> {
+        let bytes = bunker.write_to_vec()?;
+
+        let mut local_txn = None;
+        let txn = maybe_local_txn!(self, rw_txn, local_txn);
+
+        self.db_general()?.put(txn, b"nip46_bunker", &bytes)?;
+
+        maybe_local_txn_commit!(local_txn);
+
+        Ok(())
+    }
+
+    /// Read the remote signer (NIP-46 bunker) we sign with
+    pub fn read_nip46_bunker(&self) -> Result<Option<Nip46Bunker>, Error> {
+        let txn = self.env.read_txn()?;
+        match self.db_general()?.get(&txn, b"nip46_bunker")? {
+            None => Ok(None),
+            Some(bytes) => {
+                let bunker = Nip46Bunker::read_from_buffer(bytes)?;
+                Ok(Some(bunker))
+            }
+        }
+    }
+
+    /// Delete the remote signer (NIP-46 bunker) we sign with
+    pub fn delete_nip46_bunker<'a>(&'a self, rw_txn: Option<&mut RwTxn<'a>>) -> Result<(), Error> {
+        let mut local_txn = None;
+        let txn = maybe_local_txn!(self, rw_txn, local_txn);
+
+        self.db_general()?.delete(txn, b"nip46_bunker")?;
+
+        maybe_local_txn_commit!(local_txn);
+
+        Ok(())
+    }
+
     // Flags ------------------------------------------------------------
 
     def_flag!(following_only, b"following_only", false);
@@ -592,6 +780,11 @@ impl Storage {
         true
     );
     def_flag!(rebuild_fof_needed, b"rebuild_fof_needed", true);
+    def_flag!(
+        rebuild_search_index_needed,
+        b"rebuild_search_index_needed",
+        false
+    );
 
     // Settings ----------------------------------------------------------
 
@@ -642,7 +835,21 @@ impl Storage {
     def_setting!(hide_mutes_entirely, b"hide_mutes_entirely", bool, false);
     def_setting!(reactions, b"reactions", bool, true);
     def_setting!(enable_zap_receipts, b"enable_zap_receipts", bool, true);
+    def_setting!(
+        check_zap_receipt_signers,
+        b"check_zap_receipt_signers",
+        bool,
+        false
+    );
+    def_setting!(
+        zap_payment_command,
+        b"zap_payment_command",
+        String,
+        "".to_owned()
+    );
     def_setting!(show_media, b"show_media", bool, true);
+    def_setting!(media_max_dimension, b"media_max_dimension", u32, 2400);
+    def_setting!(media_memory_cap_mb, b"media_memory_cap_mb", u64, 512);
     def_setting!(
         approve_content_warning,
         b"approve_content_warning",
@@ -780,6 +987,8 @@ impl Storage {
         u64,
         55
     );
+    def_setting!(socks5_proxy, b"socks5_proxy", String, "".to_owned());
+    def_setting!(proxy_only_onion, b"proxy_only_onion", bool, false);
     def_setting!(
         fetcher_connect_timeout_sec,
         b"fetcher_connect_timeout_sec",
@@ -813,6 +1022,7 @@ impl Storage {
     );
     def_setting!(prune_period_days, b"prune_period_days", u64, 90);
     def_setting!(cache_prune_period_days, b"cache_prune_period_days", u64, 90);
+    def_setting!(cache_max_size_mb, b"cache_max_size_mb", u64, 2048);
     def_setting!(
         avoid_spam_on_unsafe_relays,
         b"avoid_spam_on_unsafe_relays",
@@ -982,6 +1192,53 @@ impl Storage {
         self.is_event_viewed1(id)
     }
 
+    /// Record when an event expires (NIP-40)
+    #[inline]
+    pub fn add_event_expiration<'a>(
+        &'a self,
+        id: Id,
+        expiration: Unixtime,
+        rw_txn: Option<&mut RwTxn<'a>>,
+    ) -> Result<(), Error> {
+        self.add_event_expiration1(id, expiration, rw_txn)
+    }
+
+    /// Forget when an event expires
+    #[inline]
+    pub fn delete_event_expiration<'a>(
+        &'a self,
+        id: Id,
+        expiration: Unixtime,
+        rw_txn: Option<&mut RwTxn<'a>>,
+    ) -> Result<(), Error> {
+        self.delete_event_expiration1(id, expiration, rw_txn)
+    }
+
+    /// Find events that expire at or before `now`, with their expiration times
+    #[inline]
+    pub fn find_expired_events(&self, now: Unixtime) -> Result<Vec<(Id, Unixtime)>, Error> {
+        self.find_expired_events1(now)
+    }
+
+    /// Record the people reported (NIP-56) by a report event
+    #[inline]
+    pub(crate) fn write_person_reports<'a>(
+        &'a self,
+        event: &Event,
+        rw_txn: Option<&mut RwTxn<'a>>,
+    ) -> Result<(), Error> {
+        self.write_person_reports1(event, rw_txn)
+    }
+
+    /// Get reports of a person, as (report id, reporter, report type)
+    #[inline]
+    pub fn get_person_reports(
+        &self,
+        reported: PublicKey,
+    ) -> Result<Vec<(Id, PublicKey, String)>, Error> {
+        self.get_person_reports1(reported)
+    }
+
     /// Associate a hashtag to an event
     #[inline]
     pub fn add_hashtag<'a>(
@@ -1397,6 +1654,14 @@ impl Storage {
         let mut local_txn = None;
         let txn = maybe_local_txn!(self, rw_txn, local_txn);
 
+        // Delete from the search index and person reports (we need the event to know
+        // the keys)
+        if let Some(bytes) = self.db_events()?.get(txn, id.as_slice())? {
+            let event = Event::read_from_buffer(bytes)?;
+            self.delete_search_index1(&event, Some(txn))?;
+            self.delete_person_reports1(&event, Some(txn))?;
+        }
+
         // Delete from the events table
         self.delete_event3(id, Some(txn))?;
 
@@ -1758,52 +2023,34 @@ impl Storage {
         Ok(output.into_iter().rev().take(limit).collect())
     }
 
-    /// Search all events for the text, case insensitive. Both content and tags
-    /// are searched.
-    pub fn search_events(&self, text: &str) -> Result<Vec<Event>, Error> {
-        let event_kinds = crate::feed::feed_displayable_event_kinds(true);
-
-        let needle = regex::escape(text.to_lowercase().as_str());
-        let re = regex::RegexBuilder::new(needle.as_str())
-            .unicode(true)
-            .case_insensitive(true)
-            .build()?;
-
+    /// The (created_at, id) of the events we have of these kinds by these
+    /// authors, created within since..=until. This reads only the
+    /// event_akci_index, and is our side of a negentropy (NIP-77) sync.
+    pub fn negentropy_items(
+        &self,
+        authors: &[PublicKey],
+        kinds: &[EventKind],
+        since: Unixtime,
+        until: Unixtime,
+    ) -> Result<Vec<(Unixtime, Id)>, Error> {
         let txn = self.env.read_txn()?;
-        let iter = self.db_events()?.iter(&txn)?;
-        let mut events: Vec<Event> = Vec::new();
-        for result in iter {
-            let (_key, val) = result?;
-
-            // event kind must match
-            if let Some(kind) = Event::get_kind_from_speedy_bytes(val) {
-                if !event_kinds.contains(&kind) {
-                    continue;
-                }
-            } else {
-                continue;
-            }
-
-            if let Some(content) = Event::get_content_from_speedy_bytes(val) {
-                if re.is_match(content.as_ref()) {
-                    let event = Event::read_from_buffer(val)?;
-                    events.push(event);
-                    continue;
+        let mut output: Vec<(Unixtime, Id)> = Vec::new();
+        for author in authors {
+            for kind in kinds {
+                let start_prefix = AkciKey::from_parts(*author, *kind, until, Id([0; 32]));
+                let end_prefix = AkciKey::from_parts(*author, *kind, since, Id([255; 32]));
+                let range = (
+                    Bound::Included(start_prefix.as_slice()),
+                    Bound::Included(end_prefix.as_slice()),
+                );
+                for result in self.db_event_akci_index()?.range(&txn, &range)? {
+                    let (keybytes, _) = result?;
+                    let (_, _, created_at, id) = AkciKey::from_bytes(keybytes)?.into_parts()?;
+                    output.push((created_at, id));
                 }
             }
-
-            if Event::tag_search_in_speedy_bytes(val, &re)? {
-                let event = Event::read_from_buffer(val)?;
-                events.push(event);
-            }
         }
-
-        events.sort_by(|a, b| {
-            // ORDER created_at desc
-            b.created_at.cmp(&a.created_at).then(b.id.cmp(&a.id))
-        });
-
-        Ok(events)
+        Ok(output)
     }
 
     fn switch_to_rumor<'a>(
@@ -1867,6 +2114,16 @@ impl Storage {
         self.write_event3_tci_index(event, rw_txn)
     }
 
+    // We don't call this externally. Whenever we write an event, we do this
+    #[inline]
+    fn write_search_index<'a>(
+        &'a self,
+        event: &Event,
+        rw_txn: Option<&mut RwTxn<'a>>,
+    ) -> Result<(), Error> {
+        self.write_search_index1(event, rw_txn)
+    }
+
     #[inline]
     pub(crate) fn index_unindexed_giftwraps(&self) -> Result<(), Error> {
         self.index_unindexed_giftwraps1()
@@ -2084,28 +2341,148 @@ impl Storage {
         Ok((v, our_reaction))
     }
 
-    /// Get the zap total of a given event
+    /// Get the zap total of a given event, leaving out invalid zap receipts
     pub fn get_zap_total(&self, id: Id) -> Result<MilliSatoshi, Error> {
         let mut total = MilliSatoshi(0);
-        for (_, rel) in self.find_relationships_by_id(id)? {
+        let txn = self.env.read_txn()?;
+        for (receipt, rel) in self.find_relationships_by_id(id)? {
             if let RelationshipById::Zaps { by: _, amount } = rel {
-                total = total + amount;
+                if self.read_invalid_zap(receipt, Some(&txn))?.is_none() {
+                    total = total + amount;
+                }
             }
         }
         Ok(total)
     }
 
-    /// Get the zap events zapping a given event
+    /// Get the zap events zapping a given event, leaving out invalid zap receipts
     pub fn get_zap_data(&self, id: Id) -> Result<Vec<(PublicKey, MilliSatoshi)>, Error> {
         let mut output: Vec<(PublicKey, MilliSatoshi)> = Vec::new();
-        for (_, rel) in self.find_relationships_by_id(id)? {
+        let txn = self.env.read_txn()?;
+        for (receipt, rel) in self.find_relationships_by_id(id)? {
             if let RelationshipById::Zaps { by, amount } = rel {
-                output.push((by, amount));
+                if self.read_invalid_zap(receipt, Some(&txn))?.is_none() {
+                    output.push((by, amount));
+                }
+            }
+        }
+        Ok(output)
+    }
+
+    /// Get the NIP-56 reports made by people we follow about an event or its author,
+    /// as (reporter, report type)
+    pub fn get_reports_by_follows(
+        &self,
+        id: Id,
+        author: PublicKey,
+    ) -> Result<Vec<(PublicKey, String)>, Error> {
+        let mut output: Vec<(PublicKey, String)> = Vec::new();
+
+        for (report_id, rel) in self.find_relationships_by_id(id)? {
+            if let RelationshipById::Reports(report_type) = rel {
+                // We must have the report event to know who made it
+                if let Some(report) = self.read_event(report_id)? {
+                    if GLOBALS
+                        .people
+                        .is_person_in_list(&report.pubkey, PersonList::Followed)
+                    {
+                        output.push((report.pubkey, report_type));
+                    }
+                }
+            }
+        }
+
+        output.extend(self.get_person_reports_by_follows(author)?);
+
+        Ok(output)
+    }
+
+    /// Get the NIP-56 reports made by people we follow about a person,
+    /// as (reporter, report type)
+    pub fn get_person_reports_by_follows(
+        &self,
+        pubkey: PublicKey,
+    ) -> Result<Vec<(PublicKey, String)>, Error> {
+        Ok(self
+            .get_person_reports(pubkey)?
+            .drain(..)
+            .filter(|(_, reporter, _)| {
+                GLOBALS
+                    .people
+                    .is_person_in_list(reporter, PersonList::Followed)
+            })
+            .map(|(_, reporter, report_type)| (reporter, report_type))
+            .collect())
+    }
+
+    /// Get the labels (NIP-32) applied to an event
+    pub fn get_labels(&self, event: &Event) -> Result<Vec<Label>, Error> {
+        let mut label_ids = self.get_label_event_ids(event.id)?;
+
+        // Labels via 'a' tags
+        if let Some(parameter) = event.parameter() {
+            let addr = NAddr {
+                d: parameter,
+                relays: vec![],
+                kind: event.kind,
+                author: event.pubkey,
+            };
+            label_ids.extend(
+                self.find_relationships_by_addr(&addr)?
+                    .drain(..)
+                    .filter_map(|(id, rel)| match rel {
+                        RelationshipByAddr::Labels { .. } => Some(id),
+                        _ => None,
+                    }),
+            );
+        }
+
+        label_ids.sort();
+        label_ids.dedup();
+
+        self.read_labels(label_ids)
+    }
+
+    /// Get the labels (NIP-32) applied to an event by its id only. This misses labels
+    /// applied to the address of a parameterized replaceable event.
+    pub fn get_labels_by_id(&self, id: Id) -> Result<Vec<Label>, Error> {
+        self.read_labels(self.get_label_event_ids(id)?)
+    }
+
+    fn get_label_event_ids(&self, id: Id) -> Result<Vec<Id>, Error> {
+        Ok(self
+            .find_relationships_by_id(id)?
+            .drain(..)
+            .filter_map(|(id, rel)| match rel {
+                RelationshipById::Labels { .. } => Some(id),
+                _ => None,
+            })
+            .collect())
+    }
+
+    fn read_labels(&self, label_ids: Vec<Id>) -> Result<Vec<Label>, Error> {
+        let mut output: Vec<Label> = Vec::new();
+        for label_id in label_ids {
+            // We must have the label event to know who applied it
+            if let Some(label_event) = self.read_event(label_id)? {
+                output.extend(Label::from_event(&label_event));
             }
         }
         Ok(output)
     }
 
+    /// Get the labels (NIP-32) applied to a person
+    pub fn get_person_labels(&self, pubkey: PublicKey) -> Result<Vec<Label>, Error> {
+        let mut filter = Filter::new();
+        filter.kinds = vec![EventKind::Label];
+        filter.add_tag_value('p', pubkey.as_hex_string());
+        Ok(self
+            .find_events_by_filter(&filter, crate::labels::labels_people)?
+            .iter()
+            .flat_map(Label::from_event)
+            .collect())
+    }
+
     /// Get whether an event was deleted, and if so the optional reason
     pub fn get_deletions(&self, maybe_deleted_event: &Event) -> Result<Vec<String>, Error> {
         let mut reasons: Vec<String> = Vec::new();
@@ -2715,6 +3092,176 @@ impl Storage {
         self.delete_nip46server2(pubkey, rw_txn)
     }
 
+    pub(crate) fn write_nip46_audit_entry<'a>(
+        &'a self,
+        entry: &Nip46AuditEntry,
+        rw_txn: Option<&mut RwTxn<'a>>,
+    ) -> Result<(), Error> {
+        self.write_nip46_audit_entry1(entry, rw_txn)
+    }
+
+    /// The audit log of a NIP-46 client of our signer, oldest first
+    pub fn read_nip46_audit_log(
+        &self,
+        peer_pubkey: PublicKey,
+    ) -> Result<Vec<Nip46AuditEntry>, Error> {
+        self.read_nip46_audit_log1(peer_pubkey)
+    }
+
+    /// How many requests of a NIP-46 client we served since the given time
+    pub(crate) fn count_nip46_requests_served_since(
+        &self,
+        peer_pubkey: PublicKey,
+        since: Unixtime,
+    ) -> Result<usize, Error> {
+        self.count_nip46_requests_served_since1(peer_pubkey, since)
+    }
+
+    /// Delete NIP-46 audit log entries from before `from`
+    pub fn prune_nip46_audit_log(&self, from: Unixtime) -> Result<usize, Error> {
+        self.prune_nip46_audit_log1(from)
+    }
+
+    /// Remember a blob we uploaded, so that we keep it mirrored
+    pub(crate) fn write_blossom_blob<'a>(
+        &'a self,
+        blob: &BlobDescriptor,
+        rw_txn: Option<&mut RwTxn<'a>>,
+    ) -> Result<(), Error> {
+        self.write_blossom_blob1(blob, rw_txn)
+    }
+
+    /// The blobs we uploaded
+    pub fn read_blossom_blobs(&self) -> Result<Vec<BlobDescriptor>, Error> {
+        self.read_blossom_blobs1()
+    }
+
+    /// Forget a blob we uploaded, so that we no longer mirror it
+    pub(crate) fn delete_blossom_blob<'a>(
+        &'a self,
+        sha256: &str,
+        rw_txn: Option<&mut RwTxn<'a>>,
+    ) -> Result<(), Error> {
+        self.delete_blossom_blob1(sha256, rw_txn)
+    }
+
+    /// Flag a zap receipt as invalid, so that it is left out of zap totals
+    pub(crate) fn write_invalid_zap<'a>(
+        &'a self,
+        receipt: Id,
+        reason: &str,
+        rw_txn: Option<&mut RwTxn<'a>>,
+    ) -> Result<(), Error> {
+        self.write_invalid_zap1(receipt, reason, rw_txn)
+    }
+
+    /// Why a zap receipt is invalid, if it was flagged as invalid
+    pub fn read_invalid_zap(
+        &self,
+        receipt: Id,
+        txn: Option<&RoTxn<'_>>,
+    ) -> Result<Option<String>, Error> {
+        self.read_invalid_zap1(receipt, txn)
+    }
+
+    /// Clear the invalid flag of a zap receipt
+    pub(crate) fn delete_invalid_zap<'a>(
+        &'a self,
+        receipt: Id,
+        rw_txn: Option<&mut RwTxn<'a>>,
+    ) -> Result<(), Error> {
+        self.delete_invalid_zap1(receipt, rw_txn)
+    }
+
+    /// Remember a relay-based group (NIP-29) that we joined
+    pub(crate) fn write_joined_group<'a>(
+        &'a self,
+        group: &JoinedGroup,
+        rw_txn: Option<&mut RwTxn<'a>>,
+    ) -> Result<(), Error> {
+        self.write_joined_group1(group, rw_txn)
+    }
+
+    /// The relay-based groups (NIP-29) that we joined
+    pub fn read_joined_groups(&self) -> Result<Vec<JoinedGroup>, Error> {
+        self.read_joined_groups1()
+    }
+
+    /// Whether we joined this relay-based group (NIP-29)
+    pub fn is_joined_group(&self, relay: &RelayUrl, id: &str) -> Result<bool, Error> {
+        self.is_joined_group1(relay, id)
+    }
+
+    /// Forget a relay-based group (NIP-29) that we left
+    pub(crate) fn delete_joined_group<'a>(
+        &'a self,
+        relay: &RelayUrl,
+        id: &str,
+        rw_txn: Option<&mut RwTxn<'a>>,
+    ) -> Result<(), Error> {
+        self.delete_joined_group1(relay, id, rw_txn)
+    }
+
+    /// Remember a public chat channel (NIP-28) that we follow
+    pub(crate) fn write_followed_channel<'a>(
+        &'a self,
+        channel: &FollowedChannel,
+        rw_txn: Option<&mut RwTxn<'a>>,
+    ) -> Result<(), Error> {
+        self.write_followed_channel1(channel, rw_txn)
+    }
+
+    /// The public chat channels (NIP-28) that we follow
+    pub fn read_followed_channels(&self) -> Result<Vec<FollowedChannel>, Error> {
+        self.read_followed_channels1()
+    }
+
+    /// Whether we follow this public chat channel (NIP-28)
+    pub fn is_followed_channel(&self, id: Id) -> Result<bool, Error> {
+        self.is_followed_channel1(id)
+    }
+
+    /// Forget a public chat channel (NIP-28) that we no longer follow
+    pub(crate) fn delete_followed_channel<'a>(
+        &'a self,
+        id: Id,
+        rw_txn: Option<&mut RwTxn<'a>>,
+    ) -> Result<(), Error> {
+        self.delete_followed_channel1(id, rw_txn)
+    }
+
+    /// Add to the statistics kept for a relay on the day of the given stats
+    pub(crate) fn add_relay_stats<'a>(
+        &'a self,
+        url: &RelayUrl,
+        stats: &RelayStats,
+        rw_txn: Option<&mut RwTxn<'a>>,
+    ) -> Result<(), Error> {
+        self.add_relay_stats1(url, stats, rw_txn)
+    }
+
+    /// The daily statistics for a relay from the given day on, oldest first
+    pub fn read_relay_stats(
+        &self,
+        url: &RelayUrl,
+        since_day: u32,
+    ) -> Result<Vec<RelayStats>, Error> {
+        self.read_relay_stats1(url, since_day)
+    }
+
+    /// The daily statistics for every relay from the given day on, by relay then day
+    pub fn read_all_relay_stats(
+        &self,
+        since_day: u32,
+    ) -> Result<Vec<(RelayUrl, RelayStats)>, Error> {
+        self.read_all_relay_stats1(since_day)
+    }
+
+    /// Delete the statistics kept for days before the given day
+    pub fn prune_relay_stats(&self, before_day: u32) -> Result<usize, Error> {
+        self.prune_relay_stats1(before_day)
+    }
+
     pub fn url_is_banned(url: &RelayUrl) -> bool {
         let s = url.as_str();
 
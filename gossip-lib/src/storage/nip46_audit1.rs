@@ -0,0 +1,187 @@
+use crate::error::Error;
+use crate::nostr_connect_server::{Nip46AuditEntry, Nip46AuditOutcome};
+use crate::storage::{RawDatabase, Storage};
+use heed::types::Bytes;
+use heed::RwTxn;
+use nostr_types::{PublicKey, Unixtime};
+use sha2::{Digest, Sha256};
+use speedy::{Readable, Writable};
+use std::ops::Bound;
+use std::sync::Mutex;
+
+// Peer:When:RequestId -> Nip46AuditEntry
+//   key: peer pubkey (32 bytes) + when (8 bytes big endian) + sha256(request id) (32 bytes)
+//   val: nip46auditentry.write_to_vec() | Nip46AuditEntry::read_from_buffer(val)
+//
+// Every request a connected NIP-46 client made of our signer, and what became of it.
+
+static NIP46_AUDIT1_DB_CREATE_LOCK: Mutex<()> = Mutex::new(());
+static mut NIP46_AUDIT1_DB: Option<RawDatabase> = None;
+
+impl Storage {
+    pub(super) fn db_nip46_audit1(&self) -> Result<RawDatabase, Error> {
+        unsafe {
+            if let Some(db) = NIP46_AUDIT1_DB {
+                Ok(db)
+            } else {
+                // Lock.  This drops when anything returns.
+                let _lock = NIP46_AUDIT1_DB_CREATE_LOCK.lock();
+
+                // In case of a race, check again
+                if let Some(db) = NIP46_AUDIT1_DB {
+                    return Ok(db);
+                }
+
+                // Create it. We know that nobody else is doing this and that
+                // it cannot happen twice.
+                let mut txn = self.env.write_txn()?;
+                let db = self
+                    .env
+                    .database_options()
+                    .types::<Bytes, Bytes>()
+                    // no .flags needed
+                    .name("nip46_audit1")
+                    .create(&mut txn)?;
+                txn.commit()?;
+                NIP46_AUDIT1_DB = Some(db);
+                Ok(db)
+            }
+        }
+    }
+
+    pub(crate) fn write_nip46_audit_entry1<'a>(
+        &'a self,
+        entry: &Nip46AuditEntry,
+        rw_txn: Option<&mut RwTxn<'a>>,
+    ) -> Result<(), Error> {
+        let key = key(entry.peer_pubkey, entry.when, &entry.request_id);
+        let bytes = entry.write_to_vec()?;
+
+        let mut local_txn = None;
+        let txn = maybe_local_txn!(self, rw_txn, local_txn);
+
+        self.db_nip46_audit1()?.put(txn, &key, &bytes)?;
+
+        maybe_local_txn_commit!(local_txn);
+
+        Ok(())
+    }
+
+    /// The audit log of a client, oldest first
+    pub(crate) fn read_nip46_audit_log1(
+        &self,
+        peer_pubkey: PublicKey,
+    ) -> Result<Vec<Nip46AuditEntry>, Error> {
+        let txn = self.env.read_txn()?;
+        let mut output: Vec<Nip46AuditEntry> = Vec::new();
+        for result in self
+            .db_nip46_audit1()?
+            .prefix_iter(&txn, peer_pubkey.as_bytes())?
+        {
+            let (_key, val) = result?;
+            output.push(Nip46AuditEntry::read_from_buffer(val)?);
+        }
+        Ok(output)
+    }
+
+    pub(crate) fn count_nip46_requests_served_since1(
+        &self,
+        peer_pubkey: PublicKey,
+        since: Unixtime,
+    ) -> Result<usize, Error> {
+        let txn = self.env.read_txn()?;
+        let mut start_key: Vec<u8> = Vec::with_capacity(40);
+        start_key.extend(peer_pubkey.as_bytes());
+        start_key.extend(since.0.to_be_bytes());
+        let range = (Bound::Included(start_key.as_slice()), Bound::Unbounded);
+        let mut count: usize = 0;
+        for result in self.db_nip46_audit1()?.range(&txn, &range)? {
+            let (keybytes, val) = result?;
+            if !keybytes.starts_with(peer_pubkey.as_bytes()) {
+                break;
+            }
+            let entry = Nip46AuditEntry::read_from_buffer(val)?;
+            if entry.outcome == Nip46AuditOutcome::Served {
+                count += 1;
+            }
+        }
+        Ok(count)
+    }
+
+    /// Delete audit entries from before `from`, returning how many were deleted
+    pub(crate) fn prune_nip46_audit_log1(&self, from: Unixtime) -> Result<usize, Error> {
+        let mut txn = self.env.write_txn()?;
+
+        let mut deletions: Vec<Vec<u8>> = Vec::new();
+        for result in self.db_nip46_audit1()?.iter(&txn)? {
+            let (keybytes, _val) = result?;
+            if keybytes.len() < 40 {
+                continue;
+            }
+            let when = i64::from_be_bytes(keybytes[32..40].try_into().unwrap());
+            if when < from.0 {
+                deletions.push(keybytes.to_owned());
+            }
+        }
+
+        for deletion in &deletions {
+            self.db_nip46_audit1()?.delete(&mut txn, deletion)?;
+        }
+
+        txn.commit()?;
+
+        Ok(deletions.len())
+    }
+}
+
+// The request id is chosen by the client and can be any length, so it is hashed
+// to keep the key within LMDB's limit
+fn key(peer_pubkey: PublicKey, when: Unixtime, request_id: &str) -> Vec<u8> {
+    let mut key: Vec<u8> = Vec::with_capacity(72);
+    key.extend(peer_pubkey.as_bytes());
+    key.extend(when.0.to_be_bytes());
+    key.extend(Sha256::digest(request_id.as_bytes()));
+    key
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use nostr_types::PrivateKey;
+
+    #[test]
+    fn test_nip46_audit_long_request_id() {
+        let storage = Storage::for_tests();
+        let peer_pubkey = PrivateKey::generate().public_key();
+        let now = Unixtime::now();
+
+        // Clients pick the request id, and it may be longer than LMDB allows in a key
+        let entry = Nip46AuditEntry {
+            peer_pubkey,
+            request_id: "x".repeat(1000),
+            when: now,
+            method: "ping".to_owned(),
+            detail: "".to_owned(),
+            outcome: Nip46AuditOutcome::Served,
+        };
+        storage.write_nip46_audit_entry(&entry, None).unwrap();
+        assert_eq!(key(peer_pubkey, now, &entry.request_id).len(), 72);
+
+        let log = storage.read_nip46_audit_log(peer_pubkey).unwrap();
+        assert_eq!(log.len(), 1);
+        assert_eq!(log[0].request_id, entry.request_id);
+
+        assert_eq!(
+            storage
+                .count_nip46_requests_served_since(peer_pubkey, now)
+                .unwrap(),
+            1
+        );
+        assert_eq!(
+            storage
+                .count_nip46_requests_served_since(peer_pubkey, Unixtime(now.0 + 1))
+                .unwrap(),
+            0
+        );
+    }
+}
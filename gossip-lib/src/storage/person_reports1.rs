@@ -0,0 +1,141 @@
+use crate::error::Error;
+use crate::storage::{RawDatabase, Storage};
+use heed::types::Bytes;
+use heed::RwTxn;
+use nostr_types::{Event, EventKind, Id, PublicKey};
+use std::sync::Mutex;
+
+// Reported:ReportId -> Reporter:ReportType
+//   key: reported pubkey (32 bytes) + report event id (32 bytes)
+//   val: reporter pubkey (32 bytes) + report type (utf-8)
+//
+// NIP-56 reports of a person (a "p" tag with a report type). Reports of
+// events are kept as RelationshipById::Reports instead.
+
+static PERSON_REPORTS1_DB_CREATE_LOCK: Mutex<()> = Mutex::new(());
+static mut PERSON_REPORTS1_DB: Option<RawDatabase> = None;
+
+impl Storage {
+    pub(super) fn db_person_reports1(&self) -> Result<RawDatabase, Error> {
+        unsafe {
+            if let Some(db) = PERSON_REPORTS1_DB {
+                Ok(db)
+            } else {
+                // Lock.  This drops when anything returns.
+                let _lock = PERSON_REPORTS1_DB_CREATE_LOCK.lock();
+
+                // In case of a race, check again
+                if let Some(db) = PERSON_REPORTS1_DB {
+                    return Ok(db);
+                }
+
+                // Create it. We know that nobody else is doing this and that
+                // it cannot happen twice.
+                let mut txn = self.env.write_txn()?;
+                let db = self
+                    .env
+                    .database_options()
+                    .types::<Bytes, Bytes>()
+                    // no .flags needed
+                    .name("person_reports1")
+                    .create(&mut txn)?;
+                txn.commit()?;
+                PERSON_REPORTS1_DB = Some(db);
+                Ok(db)
+            }
+        }
+    }
+
+    pub(crate) fn write_person_reports1<'a>(
+        &'a self,
+        event: &Event,
+        rw_txn: Option<&mut RwTxn<'a>>,
+    ) -> Result<(), Error> {
+        if event.kind != EventKind::Reporting {
+            return Ok(());
+        }
+
+        let mut local_txn = None;
+        let txn = maybe_local_txn!(self, rw_txn, local_txn);
+
+        for (reported, report_type) in reported_people(event) {
+            let key = key(reported, event.id);
+            let mut val: Vec<u8> = Vec::with_capacity(32 + report_type.len());
+            val.extend(event.pubkey.as_bytes());
+            val.extend(report_type.as_bytes());
+            self.db_person_reports1()?.put(txn, &key, &val)?;
+        }
+
+        maybe_local_txn_commit!(local_txn);
+
+        Ok(())
+    }
+
+    pub(crate) fn delete_person_reports1<'a>(
+        &'a self,
+        event: &Event,
+        rw_txn: Option<&mut RwTxn<'a>>,
+    ) -> Result<(), Error> {
+        if event.kind != EventKind::Reporting {
+            return Ok(());
+        }
+
+        let mut local_txn = None;
+        let txn = maybe_local_txn!(self, rw_txn, local_txn);
+
+        for (reported, _) in reported_people(event) {
+            let key = key(reported, event.id);
+            let _ = self.db_person_reports1()?.delete(txn, &key);
+        }
+
+        maybe_local_txn_commit!(local_txn);
+
+        Ok(())
+    }
+
+    /// Get reports of a person, as (report id, reporter, report type)
+    pub(crate) fn get_person_reports1(
+        &self,
+        reported: PublicKey,
+    ) -> Result<Vec<(Id, PublicKey, String)>, Error> {
+        let txn = self.env.read_txn()?;
+        let mut output: Vec<(Id, PublicKey, String)> = Vec::new();
+        for result in self
+            .db_person_reports1()?
+            .prefix_iter(&txn, reported.as_bytes())?
+        {
+            let (keybytes, val) = result?;
+            if keybytes.len() != 64 || val.len() < 32 {
+                continue;
+            }
+            let id = Id(keybytes[32..64].try_into().unwrap());
+            let reporter = PublicKey::from_bytes(&val[..32], false)?;
+            let report_type = String::from_utf8_lossy(&val[32..]).into_owned();
+            output.push((id, reporter, report_type));
+        }
+        Ok(output)
+    }
+}
+
+// People reported by a report event, with the report type. Per NIP-56 the
+// report type is the 3rd entry of the "p" tag; "p" tags without one only
+// identify the author of a reported event.
+fn reported_people(event: &Event) -> Vec<(PublicKey, String)> {
+    let mut output: Vec<(PublicKey, String)> = Vec::new();
+    for tag in &event.tags {
+        if tag.tagname() != "p" || tag.get_index(2).is_empty() {
+            continue;
+        }
+        if let Ok(pubkey) = PublicKey::try_from_hex_string(tag.value(), false) {
+            output.push((pubkey, tag.get_index(2).to_owned()));
+        }
+    }
+    output
+}
+
+fn key(reported: PublicKey, report_id: Id) -> Vec<u8> {
+    let mut key: Vec<u8> = Vec::with_capacity(64);
+    key.extend(reported.as_bytes());
+    key.extend(report_id.as_slice());
+    key
+}
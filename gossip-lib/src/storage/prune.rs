@@ -3,6 +3,7 @@ use super::{PersonTable, Storage};
 use crate::error::Error;
 use crate::globals::GLOBALS;
 use nostr_types::{Event, Filter, Id, PublicKey, Unixtime};
+use speedy::Readable;
 use std::collections::HashSet;
 
 impl Storage {
@@ -99,6 +100,21 @@ impl Storage {
             self.db_relationships_by_id()?.delete(&mut txn, &deletion)?;
         }
 
+        // Delete from the search index and person reports
+        // (we need each event to know its index keys)
+        for id in &ids {
+            let event = match self.db_events()?.get(&txn, id.as_slice())? {
+                Some(bytes) => Event::read_from_buffer(bytes)?,
+                None => continue,
+            };
+            self.delete_search_index1(&event, Some(&mut txn))?;
+            self.delete_person_reports1(&event, Some(&mut txn))?;
+        }
+        tracing::info!(
+            "PRUNE: deleted search index records for {} events",
+            ids.len()
+        );
+
         // delete from events
         for id in &ids {
             let _ = self.db_events()?.delete(&mut txn, id.as_slice());
@@ -110,6 +126,33 @@ impl Storage {
         Ok(ids.len())
     }
 
+    /// Remove all events that have expired (NIP-40) as of `now`
+    ///
+    /// Returns the number of events deleted
+    pub fn prune_expired_events(&self, now: Unixtime) -> Result<usize, Error> {
+        let expired = self.find_expired_events(now)?;
+        if expired.is_empty() {
+            return Ok(0);
+        }
+
+        let mut txn = self.get_write_txn()?;
+
+        let mut count: usize = 0;
+        for (id, expiration) in &expired {
+            if self.db_events()?.get(&txn, id.as_slice())?.is_some() {
+                self.delete_event(*id, Some(&mut txn))?;
+                count += 1;
+            }
+            self.delete_event_expiration(*id, *expiration, Some(&mut txn))?;
+        }
+
+        txn.commit()?;
+
+        tracing::info!("PRUNE: deleted {} expired events", count);
+
+        Ok(count)
+    }
+
     /// Prune people that are not used:
     ///   * No feed related events
     ///   * less than 6 events
@@ -0,0 +1,234 @@
+use crate::error::Error;
+use crate::relay_stats::RelayStats;
+use crate::storage::{RawDatabase, Storage, MAX_LMDB_KEY};
+use heed::types::Bytes;
+use heed::RwTxn;
+use nostr_types::RelayUrl;
+use speedy::{Readable, Writable};
+use std::sync::Mutex;
+
+// Url:Day -> RelayStats
+//   key: key!(url.as_str().as_bytes()), stats.day.to_be_bytes()
+//   val: stats.write_to_vec() | RelayStats::read_from_buffer(val)
+//
+// Connection and bandwidth statistics for each relay, rolled up per day.
+
+static RELAY_STATS1_DB_CREATE_LOCK: Mutex<()> = Mutex::new(());
+static mut RELAY_STATS1_DB: Option<RawDatabase> = None;
+
+// The url part of the key, leaving room for the day
+fn url_key(url: &RelayUrl) -> Vec<u8> {
+    let mut key: Vec<u8> = url.as_str().as_bytes().to_owned();
+    key.truncate(MAX_LMDB_KEY - 4);
+    key
+}
+
+impl Storage {
+    pub(super) fn db_relay_stats1(&self) -> Result<RawDatabase, Error> {
+        unsafe {
+            if let Some(db) = RELAY_STATS1_DB {
+                Ok(db)
+            } else {
+                // Lock.  This drops when anything returns.
+                let _lock = RELAY_STATS1_DB_CREATE_LOCK.lock();
+
+                // In case of a race, check again
+                if let Some(db) = RELAY_STATS1_DB {
+                    return Ok(db);
+                }
+
+                // Create it. We know that nobody else is doing this and that
+                // it cannot happen twice.
+                let mut txn = self.env.write_txn()?;
+                let db = self
+                    .env
+                    .database_options()
+                    .types::<Bytes, Bytes>()
+                    // no .flags needed
+                    .name("relay_stats1")
+                    .create(&mut txn)?;
+                txn.commit()?;
+                RELAY_STATS1_DB = Some(db);
+                Ok(db)
+            }
+        }
+    }
+
+    pub(crate) fn add_relay_stats1<'a>(
+        &'a self,
+        url: &RelayUrl,
+        stats: &RelayStats,
+        rw_txn: Option<&mut RwTxn<'a>>,
+    ) -> Result<(), Error> {
+        let mut key = url_key(url);
+        key.extend(stats.day.to_be_bytes());
+
+        let mut local_txn = None;
+        let txn = maybe_local_txn!(self, rw_txn, local_txn);
+
+        let mut total = match self.db_relay_stats1()?.get(txn, &key)? {
+            Some(val) => RelayStats::read_from_buffer(val)?,
+            None => RelayStats::new(stats.day),
+        };
+        total.add(stats);
+        let bytes = total.write_to_vec()?;
+        self.db_relay_stats1()?.put(txn, &key, &bytes)?;
+
+        maybe_local_txn_commit!(local_txn);
+
+        Ok(())
+    }
+
+    pub(crate) fn read_relay_stats1(
+        &self,
+        url: &RelayUrl,
+        since_day: u32,
+    ) -> Result<Vec<RelayStats>, Error> {
+        let prefix = url_key(url);
+        let txn = self.env.read_txn()?;
+        let mut output: Vec<RelayStats> = Vec::new();
+        for result in self.db_relay_stats1()?.prefix_iter(&txn, &prefix)? {
+            let (key, val) = result?;
+
+            // Skip longer urls that start with this one
+            if key.len() != prefix.len() + 4 {
+                continue;
+            }
+
+            let stats = RelayStats::read_from_buffer(val)?;
+            if stats.day >= since_day {
+                output.push(stats);
+            }
+        }
+        Ok(output)
+    }
+
+    pub(crate) fn read_all_relay_stats1(
+        &self,
+        since_day: u32,
+    ) -> Result<Vec<(RelayUrl, RelayStats)>, Error> {
+        let txn = self.env.read_txn()?;
+        let mut output: Vec<(RelayUrl, RelayStats)> = Vec::new();
+        for result in self.db_relay_stats1()?.iter(&txn)? {
+            let (key, val) = result?;
+            if key.len() <= 4 {
+                continue;
+            }
+
+            let stats = RelayStats::read_from_buffer(val)?;
+            if stats.day < since_day {
+                continue;
+            }
+
+            // Extract off the Url (skipping any that were too long to store whole)
+            let url = match std::str::from_utf8(&key[..key.len() - 4])
+                .ok()
+                .and_then(|s| RelayUrl::try_from_str(s).ok())
+            {
+                Some(url) => url,
+                None => continue,
+            };
+            output.push((url, stats));
+        }
+        Ok(output)
+    }
+
+    /// Delete the statistics for days before `before_day`, returning how many
+    /// were deleted
+    pub(crate) fn prune_relay_stats1(&self, before_day: u32) -> Result<usize, Error> {
+        let mut txn = self.env.write_txn()?;
+
+        let mut deletions: Vec<Vec<u8>> = Vec::new();
+        for result in self.db_relay_stats1()?.iter(&txn)? {
+            let (key, _val) = result?;
+            if key.len() <= 4 {
+                continue;
+            }
+            let day = u32::from_be_bytes(key[key.len() - 4..].try_into().unwrap());
+            if day < before_day {
+                deletions.push(key.to_owned());
+            }
+        }
+
+        for deletion in &deletions {
+            self.db_relay_stats1()?.delete(&mut txn, deletion)?;
+        }
+
+        txn.commit()?;
+
+        Ok(deletions.len())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn stats(day: u32, bytes_sent: u64) -> RelayStats {
+        let mut stats = RelayStats::new(day);
+        stats.bytes_sent = bytes_sent;
+        stats
+    }
+
+    #[test]
+    fn test_url_key() {
+        let url = RelayUrl::try_from_str("wss://relay.example.com").unwrap();
+        assert_eq!(url_key(&url), url.as_str().as_bytes());
+
+        // Long urls are cut short, leaving room for the day
+        let long = format!("wss://relay.example.com/{}", "a".repeat(600));
+        let url = RelayUrl::try_from_str(&long).unwrap();
+        assert_eq!(url_key(&url).len(), MAX_LMDB_KEY - 4);
+        assert!(long.as_bytes().starts_with(&url_key(&url)));
+    }
+
+    #[test]
+    fn test_relay_stats1() {
+        let storage = Storage::for_tests();
+        let url = RelayUrl::try_from_str("wss://stats.example.com").unwrap();
+        let longer = RelayUrl::try_from_str("wss://stats.example.com/longer").unwrap();
+
+        storage
+            .add_relay_stats1(&url, &stats(1000, 10), None)
+            .unwrap();
+        storage
+            .add_relay_stats1(&url, &stats(1000, 5), None)
+            .unwrap();
+        storage
+            .add_relay_stats1(&url, &stats(1001, 7), None)
+            .unwrap();
+        storage
+            .add_relay_stats1(&longer, &stats(1001, 100), None)
+            .unwrap();
+
+        // Days add up, and a url that starts with this one is not included
+        let history = storage.read_relay_stats1(&url, 0).unwrap();
+        assert_eq!(history, vec![stats(1000, 15), stats(1001, 7)]);
+        let history = storage.read_relay_stats1(&url, 1001).unwrap();
+        assert_eq!(history, vec![stats(1001, 7)]);
+        let history = storage.read_relay_stats1(&longer, 0).unwrap();
+        assert_eq!(history, vec![stats(1001, 100)]);
+
+        let all: Vec<(RelayUrl, RelayStats)> = storage
+            .read_all_relay_stats1(0)
+            .unwrap()
+            .into_iter()
+            .filter(|(u, _)| *u == url || *u == longer)
+            .collect();
+        assert_eq!(
+            all,
+            vec![
+                (url.clone(), stats(1000, 15)),
+                (url.clone(), stats(1001, 7)),
+                (longer.clone(), stats(1001, 100)),
+            ]
+        );
+
+        // Pruning drops the old days only
+        assert!(storage.prune_relay_stats1(1001).unwrap() >= 1);
+        let history = storage.read_relay_stats1(&url, 0).unwrap();
+        assert_eq!(history, vec![stats(1001, 7)]);
+        let history = storage.read_relay_stats1(&longer, 0).unwrap();
+        assert_eq!(history, vec![stats(1001, 100)]);
+    }
+}
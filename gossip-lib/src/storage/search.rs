@@ -0,0 +1,334 @@
+use super::search_index1::{search_indexable, searchable_text, tokenize};
+use super::Storage;
+use crate::error::Error;
+use heed::RwTxn;
+use nostr_types::{Event, EventKind, Filter, Id, PublicKey, Unixtime};
+use speedy::Readable;
+use std::collections::HashMap;
+
+/// A parsed local search query.
+///
+/// Plain words must all appear in an event (AND). Other syntax:
+///
+/// * `"two words"` — the words must appear consecutively (a phrase)
+/// * `goss*` — matches any word starting with `goss`
+/// * `author:<npub|hex>` (or `by:`) — only events by this author
+/// * `kind:<number>` — only events of this kind
+/// * `since:<YYYY-MM-DD|unixtime>` and `until:<YYYY-MM-DD|unixtime>` — date range
+#[derive(Debug, Clone, Default, PartialEq)]
+pub(crate) struct SearchQuery {
+    pub words: Vec<String>,
+    pub prefixes: Vec<String>,
+    pub phrases: Vec<Vec<String>>,
+    pub authors: Vec<PublicKey>,
+    pub kinds: Vec<EventKind>,
+    pub since: Option<Unixtime>,
+    pub until: Option<Unixtime>,
+}
+
+impl SearchQuery {
+    pub fn parse(text: &str) -> SearchQuery {
+        let mut query = SearchQuery::default();
+
+        for part in split_query(text) {
+            if let Some(inner) = part.strip_prefix('"') {
+                query.add_phrase(tokenize(inner.trim_end_matches('"')));
+                continue;
+            }
+
+            if let Some((qualifier, value)) = part.split_once(':') {
+                match qualifier {
+                    "author" | "by" => {
+                        let value = value.strip_prefix("nostr:").unwrap_or(value);
+                        if let Ok(pk) = PublicKey::try_from_bech32_string(value, true) {
+                            query.authors.push(pk);
+                        } else if let Ok(pk) = PublicKey::try_from_hex_string(value, true) {
+                            query.authors.push(pk);
+                        }
+                        continue;
+                    }
+                    "kind" => {
+                        if let Ok(k) = value.parse::<u32>() {
+                            query.kinds.push(k.into());
+                        }
+                        continue;
+                    }
+                    "since" => {
+                        query.since = parse_date(value, false);
+                        continue;
+                    }
+                    "until" => {
+                        query.until = parse_date(value, true);
+                        continue;
+                    }
+                    _ => {} // not a qualifier, treat as words
+                }
+            }
+
+            if let Some(stem) = part.strip_suffix('*') {
+                let mut words = tokenize(stem);
+                if let Some(last) = words.pop() {
+                    query.prefixes.push(last);
+                }
+                query.words.extend(words);
+                continue;
+            }
+
+            // Punctuated runs like "foo-bar" are treated as phrases
+            query.add_phrase(tokenize(&part));
+        }
+
+        query
+    }
+
+    fn add_phrase(&mut self, words: Vec<String>) {
+        if words.len() > 1 {
+            self.phrases.push(words.clone());
+        }
+        self.words.extend(words);
+    }
+
+    /// If the query has no words to look up in the search index
+    pub fn has_no_terms(&self) -> bool {
+        self.words.is_empty() && self.prefixes.is_empty()
+    }
+
+    /// If the query has nothing at all to search on
+    pub fn is_empty(&self) -> bool {
+        self.has_no_terms()
+            && self.authors.is_empty()
+            && self.kinds.is_empty()
+            && self.since.is_none()
+            && self.until.is_none()
+    }
+}
+
+// Split on whitespace, keeping quoted phrases together (with their quotes)
+fn split_query(text: &str) -> Vec<String> {
+    let mut parts: Vec<String> = Vec::new();
+    let mut current = String::new();
+    let mut in_quotes = false;
+    for c in text.chars() {
+        if c == '"' {
+            if in_quotes {
+                current.push(c);
+                parts.push(std::mem::take(&mut current));
+            } else {
+                if !current.is_empty() {
+                    parts.push(std::mem::take(&mut current));
+                }
+                current.push(c);
+            }
+            in_quotes = !in_quotes;
+        } else if c.is_whitespace() && !in_quotes {
+            if !current.is_empty() {
+                parts.push(std::mem::take(&mut current));
+            }
+        } else {
+            current.push(c);
+        }
+    }
+    if !current.is_empty() {
+        parts.push(current);
+    }
+    parts
+}
+
+// Parse a unixtime or a YYYY-MM-DD date (UTC). If `end_of_day`, a date is
+// taken to mean the last second of that day.
+fn parse_date(s: &str, end_of_day: bool) -> Option<Unixtime> {
+    if !s.contains('-') {
+        return s.parse::<i64>().ok().map(Unixtime);
+    }
+
+    let mut parts = s.splitn(3, '-');
+    let y: i64 = parts.next()?.parse().ok()?;
+    let m: i64 = parts.next()?.parse().ok()?;
+    let d: i64 = parts.next().unwrap_or("1").parse().ok()?;
+    if !(1..=12).contains(&m) || !(1..=31).contains(&d) {
+        return None;
+    }
+
+    // Days from civil (Howard Hinnant's algorithm)
+    let y = if m <= 2 { y - 1 } else { y };
+    let era = (if y >= 0 { y } else { y - 399 }) / 400;
+    let yoe = y - era * 400;
+    let mp = (m + 9) % 12;
+    let doy = (153 * mp + 2) / 5 + d - 1;
+    let doe = yoe * 365 + yoe / 4 - yoe / 100 + doy;
+    let days = era * 146097 + doe - 719468;
+
+    let mut secs = days * 86400;
+    if end_of_day {
+        secs += 86399;
+    }
+    Some(Unixtime(secs))
+}
+
+// (1 + ln(tf)) * ln(1 + N/df)
+fn term_score(count: u32, doc_freq: usize, total: f64) -> f64 {
+    let tf = 1.0 + (count.max(1) as f64).ln();
+    let idf = (total / doc_freq.max(1) as f64).ln_1p();
+    tf * idf
+}
+
+fn contains_phrase(words: &[String], phrase: &[String]) -> bool {
+    words.windows(phrase.len()).any(|w| w == phrase)
+}
+
+impl Storage {
+    /// Search events using the local search index.
+    ///
+    /// Plain words must all match. Also supported are `"quoted phrases"`, `prefix*`
+    /// words, and the qualifiers `author:`, `kind:`, `since:` and `until:`.
+    /// Results are ranked by relevance, then by time (newest first).
+    pub fn search_events(&self, text: &str) -> Result<Vec<Event>, Error> {
+        let query = SearchQuery::parse(text);
+        self.search_events_by_query(&query)
+    }
+
+    pub(crate) fn search_events_by_query(&self, query: &SearchQuery) -> Result<Vec<Event>, Error> {
+        if query.is_empty() {
+            return Ok(vec![]);
+        }
+
+        let kinds: Vec<EventKind> = if query.kinds.is_empty() {
+            crate::feed::feed_displayable_event_kinds(false)
+                .into_iter()
+                .filter(|k| search_indexable(*k))
+                .collect()
+        } else {
+            query.kinds.clone()
+        };
+
+        // Only qualifiers: there is nothing to look up in the index
+        if query.has_no_terms() {
+            let mut filter = Filter::new();
+            filter.kinds = kinds;
+            filter.authors = query.authors.clone();
+            filter.since = query.since;
+            filter.until = query.until;
+            return self.find_events_by_filter(&filter, |_| true);
+        }
+
+        let since = query.since.unwrap_or(Unixtime(0));
+        let until = query.until.unwrap_or(Unixtime(i64::MAX));
+
+        let mut term_matches: Vec<HashMap<Id, (Unixtime, u32)>> = Vec::new();
+        for word in &query.words {
+            term_matches.push(self.search_index_lookup1(word, false, since, until)?);
+        }
+        for prefix in &query.prefixes {
+            term_matches.push(self.search_index_lookup1(prefix, true, since, until)?);
+        }
+
+        // Intersect starting from the rarest term
+        term_matches.sort_by_key(|m| m.len());
+        let (first, rest) = match term_matches.split_first() {
+            Some(x) => x,
+            None => return Ok(vec![]),
+        };
+
+        let total = self.get_event_len()?.max(1) as f64;
+
+        let mut candidates: Vec<(f64, Unixtime, Id)> = Vec::new();
+        'candidates: for (id, (created_at, count)) in first.iter() {
+            let mut score = term_score(*count, first.len(), total);
+            for other in rest {
+                match other.get(id) {
+                    Some((_, c)) => score += term_score(*c, other.len(), total),
+                    None => continue 'candidates,
+                }
+            }
+            candidates.push((score, *created_at, *id));
+        }
+
+        candidates.sort_by(|a, b| {
+            b.0.partial_cmp(&a.0)
+                .unwrap_or(std::cmp::Ordering::Equal)
+                .then(b.1.cmp(&a.1))
+                .then(b.2.cmp(&a.2))
+        });
+
+        let mut events: Vec<Event> = Vec::new();
+        for (_, _, id) in candidates.drain(..) {
+            let event = match self.read_event(id)? {
+                Some(event) => event,
+                None => continue, // stale index entry
+            };
+
+            if !kinds.contains(&event.kind) {
+                continue;
+            }
+
+            if !query.authors.is_empty() && !query.authors.contains(&event.pubkey) {
+                continue;
+            }
+
+            if !query.phrases.is_empty() {
+                let words = tokenize(&searchable_text(&event));
+                if !query.phrases.iter().all(|p| contains_phrase(&words, p)) {
+                    continue;
+                }
+            }
+
+            events.push(event);
+        }
+
+        Ok(events)
+    }
+
+    /// Rebuild the search index from all stored events
+    pub fn rebuild_search_index<'a>(&'a self, rw_txn: Option<&mut RwTxn<'a>>) -> Result<(), Error> {
+        let mut local_txn = None;
+        let txn = maybe_local_txn!(self, rw_txn, local_txn);
+
+        // Erase the index first
+        self.db_search_index()?.clear(txn)?;
+
+        let loop_txn = self.env.read_txn()?;
+        for result in self.db_events()?.iter(&loop_txn)? {
+            let (_key, val) = result?;
+            if let Some(kind) = Event::get_kind_from_speedy_bytes(val) {
+                if !search_indexable(kind) {
+                    continue;
+                }
+            }
+            let event = Event::read_from_buffer(val)?;
+            self.write_search_index(&event, Some(txn))?;
+        }
+        self.set_flag_rebuild_search_index_needed(false, Some(txn))?;
+
+        maybe_local_txn_commit!(local_txn);
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_search_query_parse() {
+        let q = SearchQuery::parse(r#"nostr "relay picker" goss* kind:1 since:2024-01-01"#);
+        assert_eq!(q.words, vec!["nostr", "relay", "picker"]);
+        assert_eq!(q.prefixes, vec!["goss"]);
+        assert_eq!(
+            q.phrases,
+            vec![vec!["relay".to_owned(), "picker".to_owned()]]
+        );
+        assert_eq!(q.kinds, vec![EventKind::TextNote]);
+        assert_eq!(q.since, Some(Unixtime(1704067200)));
+        assert_eq!(q.until, None);
+    }
+
+    #[test]
+    fn test_parse_date() {
+        assert_eq!(parse_date("1970-01-01", false), Some(Unixtime(0)));
+        assert_eq!(parse_date("1970-01-01", true), Some(Unixtime(86399)));
+        assert_eq!(parse_date("2000-03-01", false), Some(Unixtime(951868800)));
+        assert_eq!(parse_date("1700000000", false), Some(Unixtime(1700000000)));
+        assert_eq!(parse_date("2000-13-01", false), None);
+    }
+}
@@ -0,0 +1,278 @@
+use crate::error::{Error, ErrorKind};
+use crate::storage::{RawDatabase, Storage};
+use heed::types::Bytes;
+use heed::RwTxn;
+use nostr_types::{EventKind, EventV3, Id, Unixtime};
+use std::collections::HashMap;
+use std::ops::Bound;
+use std::sync::Mutex;
+
+// Word:NUL:Created(reversed):Id -> TermCount
+//   key: SearchKey
+//   val: u32::to_be_bytes(count)
+
+// Words longer than this many bytes are truncated before indexing
+const MAX_WORD_LEN: usize = 64;
+
+// Tags whose values are indexed alongside the event content
+const INDEXED_TEXT_TAGS: [&str; 5] = ["subject", "title", "summary", "t", "alt"];
+
+static SEARCH_INDEX1_DB_CREATE_LOCK: Mutex<()> = Mutex::new(());
+static mut SEARCH_INDEX1_DB: Option<RawDatabase> = None;
+
+impl Storage {
+    pub(super) fn db_search_index1(&self) -> Result<RawDatabase, Error> {
+        unsafe {
+            if let Some(db) = SEARCH_INDEX1_DB {
+                Ok(db)
+            } else {
+                // Lock.  This drops when anything returns.
+                let _lock = SEARCH_INDEX1_DB_CREATE_LOCK.lock();
+
+                // In case of a race, check again
+                if let Some(db) = SEARCH_INDEX1_DB {
+                    return Ok(db);
+                }
+
+                // Create it. We know that nobody else is doing this and that
+                // it cannot happen twice.
+                let mut txn = self.env.write_txn()?;
+                let db = self
+                    .env
+                    .database_options()
+                    .types::<Bytes, Bytes>()
+                    // no .flags needed
+                    .name("search_index1")
+                    .create(&mut txn)?;
+                txn.commit()?;
+                SEARCH_INDEX1_DB = Some(db);
+                Ok(db)
+            }
+        }
+    }
+
+    pub(crate) fn write_search_index1<'a>(
+        &'a self,
+        event: &EventV3,
+        rw_txn: Option<&mut RwTxn<'a>>,
+    ) -> Result<(), Error> {
+        if !search_indexable(event.kind) {
+            return Ok(());
+        }
+
+        let mut local_txn = None;
+        let txn = maybe_local_txn!(self, rw_txn, local_txn);
+
+        for (word, count) in word_counts(event) {
+            let key = SearchKey::from_parts(&word, event.created_at, event.id);
+            self.db_search_index1()?
+                .put(txn, key.as_slice(), &count.to_be_bytes())?;
+        }
+
+        maybe_local_txn_commit!(local_txn);
+
+        Ok(())
+    }
+
+    pub(crate) fn delete_search_index1<'a>(
+        &'a self,
+        event: &EventV3,
+        rw_txn: Option<&mut RwTxn<'a>>,
+    ) -> Result<(), Error> {
+        if !search_indexable(event.kind) {
+            return Ok(());
+        }
+
+        let mut local_txn = None;
+        let txn = maybe_local_txn!(self, rw_txn, local_txn);
+
+        for word in word_counts(event).keys() {
+            let key = SearchKey::from_parts(word, event.created_at, event.id);
+            let _ = self.db_search_index1()?.delete(txn, key.as_slice());
+        }
+
+        maybe_local_txn_commit!(local_txn);
+
+        Ok(())
+    }
+
+    /// Find events containing `word` (or any word starting with `word` if `prefix`
+    /// is set) that were created within [since, until].
+    ///
+    /// Returns a map from the event id to its created_at and the number of times
+    /// the matched word(s) appear in it.
+    pub(crate) fn search_index_lookup1(
+        &self,
+        word: &str,
+        prefix: bool,
+        since: Unixtime,
+        until: Unixtime,
+    ) -> Result<HashMap<Id, (Unixtime, u32)>, Error> {
+        let txn = self.env.read_txn()?;
+        let mut output: HashMap<Id, (Unixtime, u32)> = HashMap::new();
+
+        let word = truncate_word(word);
+        if word.is_empty() {
+            return Ok(output);
+        }
+
+        if prefix {
+            // Prefix matches span many words, so we have to check the time of each
+            for result in self
+                .db_search_index1()?
+                .prefix_iter(&txn, word.as_bytes())?
+            {
+                let (keybytes, val) = result?;
+                let (_, created_at, id) = SearchKey::from_bytes(keybytes)?.into_parts()?;
+                if created_at < since || created_at > until {
+                    continue;
+                }
+                output
+                    .entry(id)
+                    .and_modify(|(_, c)| *c += count_from_bytes(val))
+                    .or_insert((created_at, count_from_bytes(val)));
+            }
+        } else {
+            // Exact word matches are ordered by time, so we can range over them
+            let start_prefix = SearchKey::from_parts(word, until, Id([0; 32]));
+            let end_prefix = SearchKey::from_parts(word, since, Id([255; 32]));
+            let range = (
+                Bound::Included(start_prefix.as_slice()),
+                Bound::Included(end_prefix.as_slice()),
+            );
+            for result in self.db_search_index1()?.range(&txn, &range)? {
+                let (keybytes, val) = result?;
+                let (_, created_at, id) = SearchKey::from_bytes(keybytes)?.into_parts()?;
+                output.insert(id, (created_at, count_from_bytes(val)));
+            }
+        }
+
+        Ok(output)
+    }
+}
+
+fn count_from_bytes(val: &[u8]) -> u32 {
+    if val.len() >= 4 {
+        u32::from_be_bytes(val[..4].try_into().unwrap())
+    } else {
+        1
+    }
+}
+
+/// Whether events of this kind go into the search index.
+///
+/// Encrypted kinds are never indexed so that we do not leak plaintext into the index.
+pub(crate) fn search_indexable(kind: EventKind) -> bool {
+    kind.is_feed_displayable()
+        && kind != EventKind::EncryptedDirectMessage
+        && kind != EventKind::DmChat
+        && kind != EventKind::GiftWrap
+}
+
+/// The searchable text of an event: its content and some descriptive tags
+pub(crate) fn searchable_text(event: &EventV3) -> String {
+    let mut text = event.content.clone();
+    for tag in &event.tags {
+        if INDEXED_TEXT_TAGS.contains(&tag.tagname()) {
+            text.push(' ');
+            text.push_str(tag.value());
+        }
+    }
+    text
+}
+
+/// Split text into lowercased words suitable for the search index.
+///
+/// Words are runs of alphanumeric characters. Single-character words are dropped.
+pub(crate) fn tokenize(text: &str) -> Vec<String> {
+    text.split(|c: char| !c.is_alphanumeric())
+        .filter(|w| w.chars().nth(1).is_some())
+        .map(|w| truncate_word(&w.to_lowercase()).to_owned())
+        .collect()
+}
+
+fn word_counts(event: &EventV3) -> HashMap<String, u32> {
+    let mut counts: HashMap<String, u32> = HashMap::new();
+    for word in tokenize(&searchable_text(event)) {
+        counts.entry(word).and_modify(|c| *c += 1).or_insert(1);
+    }
+    counts
+}
+
+fn truncate_word(word: &str) -> &str {
+    if word.len() <= MAX_WORD_LEN {
+        return word;
+    }
+    let mut end = MAX_WORD_LEN;
+    while !word.is_char_boundary(end) {
+        end -= 1;
+    }
+    &word[..end]
+}
+
+pub struct SearchKey(Vec<u8>);
+
+impl SearchKey {
+    pub fn from_parts(word: &str, created_at: Unixtime, id: Id) -> Self {
+        let mut key: Vec<u8> = Vec::with_capacity(word.len() + 1 + std::mem::size_of::<i64>() + 32);
+        key.extend(word.as_bytes());
+        key.push(0);
+        key.extend((u64::MAX - created_at.0 as u64).to_be_bytes().as_slice());
+        key.extend(id.0.as_slice());
+        SearchKey(key)
+    }
+
+    pub fn into_parts(self) -> Result<(String, Unixtime, Id), Error> {
+        let mid = self.0.len() - 32 - std::mem::size_of::<i64>();
+        if self.0[mid - 1] != 0 {
+            return Err(ErrorKind::KeyInvalid.into());
+        }
+        let word = String::from_utf8_lossy(&self.0[..mid - 1]).into_owned();
+        let created_at = Unixtime(
+            (u64::MAX - u64::from_be_bytes(self.0[mid..mid + 8].try_into().unwrap())) as i64,
+        );
+        let id = Id(self.0[mid + 8..].try_into().unwrap());
+        Ok((word, created_at, id))
+    }
+
+    pub fn from_bytes(bytes: &[u8]) -> Result<SearchKey, Error> {
+        if bytes.len() < 1 + 1 + std::mem::size_of::<i64>() + 32 {
+            return Err(ErrorKind::KeySizeWrong.into());
+        }
+        Ok(SearchKey(bytes.to_owned()))
+    }
+
+    pub fn as_slice(&self) -> &[u8] {
+        &self.0
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_search_key() {
+        let word = "gossip";
+        let created_at = Unixtime::now();
+        let id = Id::try_from_hex_string(
+            "77f7653c67147a125cc624f695029d0557e3ab402e714680eb23dd2499f439a0",
+        )
+        .unwrap();
+
+        let key = SearchKey::from_parts(word, created_at, id);
+        let (word2, created_at2, id2) = key.into_parts().unwrap();
+
+        assert_eq!(word, word2);
+        assert_eq!(created_at, created_at2);
+        assert_eq!(id, id2);
+    }
+
+    #[test]
+    fn test_tokenize() {
+        assert_eq!(
+            tokenize("Hello, World! I'm using #Nostr via https://example.com"),
+            vec!["hello", "world", "using", "nostr", "via", "https", "example", "com"]
+        );
+    }
+}
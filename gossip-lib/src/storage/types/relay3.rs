@@ -1,6 +1,7 @@
 use crate::error::Error;
 use crate::globals::GLOBALS;
-use nostr_types::{RelayInformationDocument, RelayUrl, Unixtime};
+use crate::misc::pow_difficulty;
+use nostr_types::{Event, RelayInformationDocument, RelayLimitation, RelayUrl, Unixtime};
 use serde::{Deserialize, Serialize};
 
 // THIS IS HISTORICAL FOR MIGRATIONS AND THE STRUCTURES SHOULD NOT BE EDITED
@@ -195,6 +196,109 @@ impl Relay3 {
             || (self.rank > 0 && self.success_rate() > 0.50 && self.success_count > 15)
     }
 
+    /// Whether the relay's NIP-11 document lists the NIP as supported
+    pub fn supports_nip(&self, nip: u32) -> bool {
+        match &self.nip11 {
+            Some(nip11) => nip11.supported_nips.contains(&nip),
+            None => false,
+        }
+    }
+
+    /// The limitations the relay's NIP-11 document advertises
+    pub fn limitation(&self) -> Option<&RelayLimitation> {
+        self.nip11
+            .as_ref()
+            .and_then(|nip11| nip11.limitation.as_ref())
+    }
+
+    /// Why the relay would reject this event, going by its NIP-11 limitations
+    /// and by whether it already refused our events this session.
+    /// `message_length` is the length of the EVENT message we would send.
+    pub fn write_rejection(&self, event: &Event, message_length: usize) -> Option<String> {
+        if crate::relay_rejection::is_write_restricted(&self.url) {
+            return Some("the relay refused our events earlier".to_owned());
+        }
+
+        let limitation = self.limitation()?;
+
+        if let Some(max) = limitation.max_message_length {
+            if message_length > max {
+                return Some(format!(
+                    "the event is {} bytes but the relay takes at most {}",
+                    message_length, max
+                ));
+            }
+        }
+
+        if let Some(max) = limitation.max_event_tags {
+            if event.tags.len() > max {
+                return Some(format!(
+                    "the event has {} tags but the relay takes at most {}",
+                    event.tags.len(),
+                    max
+                ));
+            }
+        }
+
+        if let Some(min) = limitation.min_pow_difficulty {
+            let pow = pow_difficulty(event.id);
+            if (pow as usize) < min {
+                return Some(format!(
+                    "the relay wants proof of work of {} but the event has {}",
+                    min, pow
+                ));
+            }
+        }
+
+        // We presume the user has paid for the relays they write to
+        if limitation.payment_required == Some(true)
+            && !self.has_usage_bits(Self::WRITE)
+            && !self.has_usage_bits(Self::OUTBOX)
+        {
+            return Some("the relay requires payment".to_owned());
+        }
+
+        if limitation.auth_required == Some(true) && self.allow_auth == Some(false) {
+            return Some("the relay requires AUTH, which you declined".to_owned());
+        }
+
+        None
+    }
+
+    /// A description of each NIP-11 limitation the relay advertises that we honor
+    pub fn limitation_notes(&self) -> Vec<String> {
+        let mut notes: Vec<String> = Vec::new();
+        let limitation = match self.limitation() {
+            Some(limitation) => limitation,
+            None => return notes,
+        };
+        if let Some(max) = limitation.max_subscriptions {
+            notes.push(format!("At most {} subscriptions at a time", max));
+        }
+        if let Some(max) = limitation.max_filters {
+            notes.push(format!("At most {} filters per subscription", max));
+        }
+        if let Some(max) = limitation.max_limit {
+            notes.push(format!("Returns at most {} events per filter", max));
+        }
+        if let Some(max) = limitation.max_message_length {
+            notes.push(format!("Messages of at most {} bytes", max));
+        }
+        if let Some(max) = limitation.max_event_tags {
+            notes.push(format!("Events with at most {} tags", max));
+        }
+        if let Some(min) = limitation.min_pow_difficulty {
+            notes.push(format!("Proof of work of at least {}", min));
+        }
+        if limitation.auth_required == Some(true) {
+            notes.push("AUTH required".to_owned());
+        }
+        if limitation.payment_required == Some(true) {
+            notes.push("Payment required".to_owned());
+        }
+        notes
+    }
+
     /// This gives a pure score for the relay outside of context
     ///
     /// Output ranges from 0.0 (worst) to 1.0 (best)
@@ -295,3 +399,86 @@ impl ScoreFactors {
         success_count: true,
     };
 }
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use nostr_types::{EventKind, KeySigner, PreEvent, Signer, Tag};
+
+    fn relay_with_limitation(limitation: &str) -> Relay3 {
+        let mut relay = Relay3::new(RelayUrl::try_from_str("wss://relay.example.com").unwrap());
+        relay.nip11 =
+            Some(serde_json::from_str(&format!(r#"{{"limitation":{}}}"#, limitation)).unwrap());
+        relay
+    }
+
+    fn event(tags: usize) -> Event {
+        let signer = KeySigner::generate("test", 1).unwrap();
+        let pre_event = PreEvent {
+            pubkey: signer.public_key(),
+            created_at: Unixtime::now(),
+            kind: EventKind::TextNote,
+            tags: (0..tags)
+                .map(|n| Tag::new(&["t", &n.to_string()]))
+                .collect(),
+            content: "hello".to_owned(),
+        };
+        signer.sign_event(pre_event).unwrap()
+    }
+
+    #[test]
+    fn test_write_rejection() {
+        let event = event(3);
+
+        // Without limitations, anything goes
+        let relay = Relay3::new(RelayUrl::try_from_str("wss://relay.example.com").unwrap());
+        assert_eq!(relay.write_rejection(&event, 100_000), None);
+
+        let relay = relay_with_limitation(r#"{"max_message_length":1000}"#);
+        assert_eq!(relay.write_rejection(&event, 1000), None);
+        assert_eq!(
+            relay.write_rejection(&event, 1001),
+            Some("the event is 1001 bytes but the relay takes at most 1000".to_owned())
+        );
+
+        let relay = relay_with_limitation(r#"{"max_event_tags":2}"#);
+        assert_eq!(
+            relay.write_rejection(&event, 500),
+            Some("the event has 3 tags but the relay takes at most 2".to_owned())
+        );
+        let relay = relay_with_limitation(r#"{"max_event_tags":3}"#);
+        assert_eq!(relay.write_rejection(&event, 500), None);
+
+        // An unmined event will not have 64 bits of proof of work
+        let relay = relay_with_limitation(r#"{"min_pow_difficulty":64}"#);
+        let pow = pow_difficulty(event.id);
+        assert_eq!(
+            relay.write_rejection(&event, 500),
+            Some(format!(
+                "the relay wants proof of work of 64 but the event has {}",
+                pow
+            ))
+        );
+        let relay = relay_with_limitation(r#"{"min_pow_difficulty":0}"#);
+        assert_eq!(relay.write_rejection(&event, 500), None);
+
+        // Paid relays are presumed paid for if we write to them
+        let mut relay = relay_with_limitation(r#"{"payment_required":true}"#);
+        assert_eq!(
+            relay.write_rejection(&event, 500),
+            Some("the relay requires payment".to_owned())
+        );
+        relay.usage_bits = Relay3::OUTBOX;
+        assert_eq!(relay.write_rejection(&event, 500), None);
+        relay.usage_bits = Relay3::WRITE;
+        assert_eq!(relay.write_rejection(&event, 500), None);
+
+        let mut relay = relay_with_limitation(r#"{"auth_required":true}"#);
+        assert_eq!(relay.write_rejection(&event, 500), None);
+        relay.allow_auth = Some(false);
+        assert_eq!(
+            relay.write_rejection(&event, 500),
+            Some("the relay requires AUTH, which you declined".to_owned())
+        );
+    }
+}
@@ -58,17 +58,34 @@ impl Storage {
             ids.push(id);
         }
 
+        if ids.is_empty() {
+            return Ok(());
+        }
+
         let mut txn = self.env.write_txn()?;
         for id in ids {
             if let Some(event) = self.read_event(id)? {
+                // A remote signer answers later. Leave these for the next pass.
+                let rumor = match GLOBALS.identity.unwrap_giftwrap(&event) {
+                    Ok(rumor) => Some(rumor),
+                    Err(e) if matches!(e.kind, ErrorKind::Nip46Pending) => continue,
+                    Err(_) => None,
+                };
+
+                // Use the pubkey and created_at of the rumor, as when it is first stored
+                let (pubkey, created_at) = match rumor {
+                    Some(rumor) => (rumor.pubkey, rumor.created_at),
+                    None => (event.pubkey, event.created_at),
+                };
+
                 self.write_event_akci_index(
-                    event.pubkey,
+                    pubkey,
                     event.kind,
-                    event.created_at,
+                    created_at,
                     event.id,
                     Some(&mut txn),
                 )?;
-                self.write_event_kci_index(event.kind, event.created_at, event.id, Some(&mut txn))?;
+                self.write_event_kci_index(event.kind, created_at, event.id, Some(&mut txn))?;
                 self.write_event_tci_index(&event, Some(&mut txn))?;
             }
             self.db_unindexed_giftwraps1()?
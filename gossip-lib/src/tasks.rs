@@ -1,6 +1,7 @@
 use crate::error::ErrorKind;
 use crate::RunState;
 use crate::GLOBALS;
+use nostr_types::Unixtime;
 use std::sync::atomic::Ordering;
 use std::time::Duration;
 use tokio::time::Instant;
@@ -21,14 +22,28 @@ pub(crate) fn start_background_tasks() {
         tokio::pin!(sleep_future);
         let mut tick: usize = 0;
 
+        // Set when the remote signer has answered decryptions, so GiftWraps it could
+        // not unwrap before may now be indexed
+        let mut reindex_giftwraps = false;
+
         let recompute_bookmarks = GLOBALS.recompute_current_bookmarks.clone();
+        let remote_decrypted = GLOBALS.remote_decrypted.clone();
 
         loop {
             let recompute_bookmarks_future = recompute_bookmarks.notified();
+            let remote_decrypted_future = remote_decrypted.notified();
 
             tokio::select! {
                 _ = &mut sleep_future => {
-                    sleep_future.as_mut().reset(Instant::now() + Duration::from_millis(TICK))
+                    sleep_future.as_mut().reset(Instant::now() + Duration::from_millis(TICK));
+
+                    // Answers arrive in bursts, so index at most once a tick
+                    if reindex_giftwraps {
+                        reindex_giftwraps = false;
+                        if let Err(e) = GLOBALS.db().index_unindexed_giftwraps() {
+                            tracing::error!("{:?}", e);
+                        }
+                    }
                 },
                 _ = read_runstate.wait_for(|runstate| *runstate == RunState::ShuttingDown) => break,
                 _ = recompute_bookmarks_future => {
@@ -37,6 +52,15 @@ pub(crate) fn start_background_tasks() {
                         Err(e) => tracing::error!("{:?}", e),
                     }
                 }
+                _ = remote_decrypted_future => {
+                    // Pick up our private bookmarks once the remote signer has decrypted them
+                    if GLOBALS.bookmarks.read_arc().is_private_pending() {
+                        if let Err(e) = crate::bookmarks::rebuild_bookmarks() {
+                            tracing::error!("{:?}", e);
+                        }
+                    }
+                    reindex_giftwraps = true;
+                }
             }
 
             tick += 1;
@@ -75,6 +99,16 @@ async fn do_online_tasks(tick: usize) {
     if tick % 3 == 0 {
         GLOBALS.people.maybe_fetch_metadata().await;
     }
+
+    // Mirror our blossom blobs to servers missing them every 7200 ticks (an hour),
+    // starting a minute after startup
+    if tick % 7200 == 120 {
+        std::mem::drop(tokio::spawn(async move {
+            if let Err(e) = crate::blossom::repair_blobs().await {
+                tracing::error!("{:?}", e);
+            }
+        }));
+    }
 }
 
 async fn do_general_tasks(tick: usize) {
@@ -89,6 +123,28 @@ async fn do_general_tasks(tick: usize) {
         update_inbox_indicator().await;
     }
 
+    // Prune expired events (NIP-40) every 120 ticks
+    if tick % 120 == 0 {
+        if let Err(e) = GLOBALS.db().prune_expired_events(Unixtime::now()) {
+            tracing::error!("{:?}", e);
+        }
+    }
+
+    // Keep the disk cache under its maximum size every 1200 ticks (ten minutes),
+    // starting half a minute after startup
+    if tick % 1200 == 60 {
+        std::mem::drop(tokio::spawn(async move {
+            let max_bytes = GLOBALS.db().read_setting_cache_max_size_mb() * 1_048_576;
+            match GLOBALS.fetcher.trim_cache(max_bytes).await {
+                Ok((0, _)) => {}
+                Ok((count, freed)) => {
+                    tracing::info!("Trimmed the cache: {count} files removed, {freed} bytes freed")
+                }
+                Err(e) => tracing::error!("{:?}", e),
+            }
+        }));
+    }
+
     // Update handlers for quick menu rendering
     let _ = GLOBALS.update_handlers();
 }
@@ -0,0 +1,421 @@
+use crate::error::{Error, ErrorKind};
+use crate::globals::GLOBALS;
+use crate::storage::{PersonTable, Table};
+use bech32::primitives::decode::CheckedHrpstring;
+use bech32::{Bech32, Fe32, Fe32IterExt};
+use nostr_types::{Event, EventKind, Id, MilliSatoshi, PublicKey};
+use serde_json::Value;
+use sha2::{Digest, Sha256};
+use std::fmt;
+
+/// What is wrong with a zap receipt (kind 9735), per NIP-57 appendix F
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum InvalidZap {
+    /// The description tag is missing or is not a zap request event
+    NoZapRequest,
+
+    /// The zap request is not validly signed
+    BadZapRequestSignature,
+
+    /// The bolt11 tag is missing or is not a bolt11 invoice
+    BadInvoice,
+
+    /// The invoice does not commit to the zap request
+    DescriptionHashMismatch,
+
+    /// The invoice is not for the amount that was requested
+    AmountMismatch,
+
+    /// The receipt is for someone other than who the zap was requested for
+    WrongRecipient,
+
+    /// The zap was requested at a different lnurl than the recipient's
+    LnurlMismatch,
+
+    /// The receipt was not signed by the nostrPubkey of the recipient's lnurl
+    WrongSigner,
+}
+
+impl fmt::Display for InvalidZap {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match *self {
+            InvalidZap::NoZapRequest => write!(f, "No zap request"),
+            InvalidZap::BadZapRequestSignature => write!(f, "Zap request signature is invalid"),
+            InvalidZap::BadInvoice => write!(f, "No valid bolt11 invoice"),
+            InvalidZap::DescriptionHashMismatch => {
+                write!(f, "Invoice description hash does not match the zap request")
+            }
+            InvalidZap::AmountMismatch => {
+                write!(f, "Invoice amount does not match the zap request")
+            }
+            InvalidZap::WrongRecipient => write!(f, "Recipient does not match the zap request"),
+            InvalidZap::LnurlMismatch => {
+                write!(f, "Zap request lnurl is not the recipient's lnurl")
+            }
+            InvalidZap::WrongSigner => {
+                write!(f, "Not signed by the zapper of the recipient's lnurl")
+            }
+        }
+    }
+}
+
+/// Check a zap receipt against the zap request and the invoice embedded in it.
+///
+/// This does not check who signed the receipt, which needs the recipient's lnurl.
+/// See [check_zap_receipt_lnurl].
+pub fn check_zap_receipt(receipt: &Event) -> Option<InvalidZap> {
+    let description = match tag_value(receipt, "description") {
+        Some(d) => d,
+        None => return Some(InvalidZap::NoZapRequest),
+    };
+    let request: Event = match serde_json::from_str(description) {
+        Ok(e) => e,
+        Err(_) => return Some(InvalidZap::NoZapRequest),
+    };
+    if request.kind != EventKind::ZapRequest {
+        return Some(InvalidZap::NoZapRequest);
+    }
+    if request.verify(None).is_err() {
+        return Some(InvalidZap::BadZapRequestSignature);
+    }
+
+    let bolt11 = match tag_value(receipt, "bolt11").and_then(decode_bolt11) {
+        Some(b) => b,
+        None => return Some(InvalidZap::BadInvoice),
+    };
+
+    let description_hash: [u8; 32] = Sha256::digest(description.as_bytes()).into();
+    if bolt11.description_hash != Some(description_hash) {
+        return Some(InvalidZap::DescriptionHashMismatch);
+    }
+
+    if let Some(amount) = tag_value(&request, "amount") {
+        match amount.parse::<u64>() {
+            Ok(msats) if bolt11.amount.map(|a| a.0) == Some(msats) => {}
+            _ => return Some(InvalidZap::AmountMismatch),
+        }
+    }
+
+    if tag_value(&request, "p") != tag_value(receipt, "p") {
+        return Some(InvalidZap::WrongRecipient);
+    }
+
+    None
+}
+
+/// Check who signed a zap receipt, and the lnurl it was requested at, against the
+/// lnurl of the recipient. This may fetch the LNURL pay request data of the recipient.
+///
+/// Errors mean we could not tell, such as when we don't know the recipient's lnurl.
+pub async fn check_zap_receipt_lnurl(receipt: &Event) -> Result<Option<InvalidZap>, Error> {
+    let request: Event =
+        match tag_value(receipt, "description").and_then(|d| serde_json::from_str(d).ok()) {
+            Some(e) => e,
+            None => return Ok(Some(InvalidZap::NoZapRequest)),
+        };
+
+    let recipient =
+        match tag_value(receipt, "p").and_then(|p| PublicKey::try_from_hex_string(p, true).ok()) {
+            Some(pk) => pk,
+            None => return Ok(Some(InvalidZap::WrongRecipient)),
+        };
+
+    let lnurl = match PersonTable::read_record(recipient, None)? {
+        Some(person) => person.metadata().as_ref().and_then(|m| m.lnurl()),
+        None => None,
+    };
+    let lnurl = match lnurl {
+        Some(lnurl) => lnurl,
+        None => {
+            return Err(ErrorKind::General("The recipient has no lnurl".to_owned()).into());
+        }
+    };
+
+    // The lnurl tag is optional, and may be bech32 encoded
+    if let Some(requested) = tag_value(&request, "lnurl") {
+        if let Some(requested) = lnurl_to_url(requested) {
+            if !requested.eq_ignore_ascii_case(&lnurl) {
+                return Ok(Some(InvalidZap::LnurlMismatch));
+            }
+        }
+    }
+
+    match lnurl_nostr_pubkey(&lnurl).await? {
+        Some(pubkey) if pubkey == receipt.pubkey => Ok(None),
+        _ => Ok(Some(InvalidZap::WrongSigner)),
+    }
+}
+
+/// Fully check a zap receipt, flagging it as invalid (or clearing the flag) in storage.
+///
+/// If `online` is false, or the lnurl checks cannot be made, only the offline checks
+/// of [check_zap_receipt] decide.
+pub async fn verify_zap_receipt(
+    receipt: &Event,
+    online: bool,
+) -> Result<Option<InvalidZap>, Error> {
+    let mut invalid = check_zap_receipt(receipt);
+    if invalid.is_none() && online {
+        match check_zap_receipt_lnurl(receipt).await {
+            Ok(i) => invalid = i,
+            Err(e) => tracing::debug!("Could not check zap receipt {}: {}", receipt.id, e),
+        }
+    }
+
+    match invalid {
+        Some(i) => flag_invalid_zap(receipt, i)?,
+        None => GLOBALS.db().delete_invalid_zap(receipt.id, None)?,
+    }
+
+    Ok(invalid)
+}
+
+/// Check the invoice an lnurl server gave us for a zap before paying it. It must be
+/// for the amount we asked for, and commit to our zap request (the JSON we sent).
+///
+/// Returns the payment hash of the invoice.
+pub(crate) fn check_zap_invoice(
+    invoice: &str,
+    msats: MilliSatoshi,
+    zap_request: &str,
+) -> Result<[u8; 32], InvalidZap> {
+    let bolt11 = decode_bolt11(invoice).ok_or(InvalidZap::BadInvoice)?;
+
+    if bolt11.amount.map(|a| a.0) != Some(msats.0) {
+        return Err(InvalidZap::AmountMismatch);
+    }
+
+    let description_hash: [u8; 32] = Sha256::digest(zap_request.as_bytes()).into();
+    if bolt11.description_hash != Some(description_hash) {
+        return Err(InvalidZap::DescriptionHashMismatch);
+    }
+
+    bolt11.payment_hash.ok_or(InvalidZap::BadInvoice)
+}
+
+/// The payment hash of a bolt11 invoice
+pub(crate) fn invoice_payment_hash(invoice: &str) -> Option<[u8; 32]> {
+    decode_bolt11(invoice).and_then(|b| b.payment_hash)
+}
+
+/// Flag a zap receipt as invalid, and redraw the note it zapped
+pub(crate) fn flag_invalid_zap(receipt: &Event, invalid: InvalidZap) -> Result<(), Error> {
+    tracing::debug!("Zap receipt {} is invalid: {}", receipt.id, invalid);
+    GLOBALS
+        .db()
+        .write_invalid_zap(receipt.id, &invalid.to_string(), None)?;
+    if let Some(id) = tag_value(receipt, "e").and_then(|e| Id::try_from_hex_string(e).ok()) {
+        GLOBALS.ui_invalidate_note(id);
+    }
+    Ok(())
+}
+
+// The pubkey that signs zap receipts for the lnurl, if it supports nostr zaps
+async fn lnurl_nostr_pubkey(lnurl: &str) -> Result<Option<PublicKey>, Error> {
+    if let Some(pubkey) = GLOBALS.lnurl_nostr_pubkeys.get(lnurl) {
+        return Ok(*pubkey);
+    }
+
+    let client = crate::proxy::http_client_builder()?
+        .timeout(std::time::Duration::new(15, 0))
+        .gzip(true)
+        .brotli(true)
+        .deflate(true)
+        .build()?;
+    let response = client.get(lnurl).send().await?;
+    let value: Value = serde_json::from_str(&response.text().await?)?;
+
+    let pubkey = match value.get("nostrPubkey") {
+        Some(Value::String(s)) => PublicKey::try_from_hex_string(s, true).ok(),
+        _ => None,
+    };
+    GLOBALS.lnurl_nostr_pubkeys.insert(lnurl.to_owned(), pubkey);
+
+    Ok(pubkey)
+}
+
+fn tag_value<'a>(event: &'a Event, name: &str) -> Option<&'a str> {
+    event
+        .tags
+        .iter()
+        .find(|t| t.get_index(0) == name)
+        .map(|t| t.get_index(1))
+}
+
+// An lnurl may be a plain url, or bech32 encoded with the "lnurl" prefix
+fn lnurl_to_url(lnurl: &str) -> Option<String> {
+    if !lnurl.to_lowercase().starts_with("lnurl1") {
+        return Some(lnurl.to_owned());
+    }
+    let checked = CheckedHrpstring::new::<Bech32>(lnurl).ok()?;
+    String::from_utf8(checked.byte_iter().collect()).ok()
+}
+
+// What we need from a bolt11 invoice
+struct Bolt11 {
+    amount: Option<MilliSatoshi>,
+    payment_hash: Option<[u8; 32]>,
+    description_hash: Option<[u8; 32]>,
+}
+
+fn decode_bolt11(invoice: &str) -> Option<Bolt11> {
+    // Invoices are often longer than the 90 characters of segwit addresses, which
+    // the bech32 crate only enforces for those
+    let checked = CheckedHrpstring::new::<Bech32>(invoice.trim()).ok()?;
+    let words: Vec<Fe32> = checked
+        .data_part_ascii_no_checksum()
+        .iter()
+        .map(|c| Fe32::from_char(*c as char).ok())
+        .collect::<Option<Vec<Fe32>>>()?;
+
+    // "ln", the currency, and then the amount if there is one
+    let hrp = checked.hrp().to_lowercase();
+    let hrp = hrp.strip_prefix("ln")?;
+    let amount = match hrp.find(|c: char| c.is_ascii_digit()) {
+        Some(i) => Some(parse_bolt11_amount(&hrp[i..])?),
+        None => None,
+    };
+
+    // A timestamp (7 words), tagged fields, and a signature (104 words)
+    if words.len() < 7 + 104 {
+        return None;
+    }
+    let fields = &words[7..words.len() - 104];
+
+    let mut payment_hash: Option<[u8; 32]> = None;
+    let mut description_hash: Option<[u8; 32]> = None;
+    let mut pos: usize = 0;
+    while pos + 3 <= fields.len() {
+        let tag = fields[pos].to_u8();
+        let len = ((fields[pos + 1].to_u8() as usize) << 5) | fields[pos + 2].to_u8() as usize;
+        pos += 3;
+        if pos + len > fields.len() {
+            return None;
+        }
+        // 'p' is the payment hash and 'h' is the description hash. Both are 256 bits.
+        if (tag == 1 || tag == 23) && len == 52 {
+            let bytes: Vec<u8> = fields[pos..pos + len]
+                .iter()
+                .copied()
+                .fes_to_bytes()
+                .collect();
+            let hash: Option<[u8; 32]> = bytes[..32].try_into().ok();
+            if tag == 1 {
+                payment_hash = hash;
+            } else {
+                description_hash = hash;
+            }
+        }
+        pos += len;
+    }
+
+    Some(Bolt11 {
+        amount,
+        payment_hash,
+        description_hash,
+    })
+}
+
+// An amount in bitcoin, with an optional multiplier
+fn parse_bolt11_amount(s: &str) -> Option<MilliSatoshi> {
+    let (digits, multiplier) = match s.chars().last()? {
+        c if c.is_ascii_digit() => (s, None),
+        c => (&s[..s.len() - 1], Some(c)),
+    };
+    let n: u64 = digits.parse().ok()?;
+    let msats = match multiplier {
+        None => n.checked_mul(100_000_000_000)?,
+        Some('m') => n.checked_mul(100_000_000)?,
+        Some('u') => n.checked_mul(100_000)?,
+        Some('n') => n.checked_mul(100)?,
+        Some('p') if n % 10 == 0 => n / 10,
+        _ => return None,
+    };
+    Some(MilliSatoshi(msats))
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_decode_bolt11() {
+        // From the BOLT-11 examples: 20m with a description hash, and 2500u with a
+        // plain description
+        let bolt11 = decode_bolt11(
+            "lnbc20m1pvjluezpp5qqqsyqcyq5rqwzqfqqqsyqcyq5rqwzqfqqqsyqcyq5rqwzqfqypqhp58yjmdan79s6qqdhdzgynm4zwqd5d7xmw5fk98klysy043l2ahrqscc6gd6ql3jrc5yzme8v4ntcewwz5cnw92tz0pc8qcuufvq7khhr8wpald05e92xw006sq94mg8v2ndf4sefvf9sygkshp5zfem29trqq2yxxz7",
+        )
+        .unwrap();
+        assert_eq!(bolt11.amount.map(|a| a.0), Some(2_000_000_000));
+        let description = "One piece of chocolate cake, one icecream cone, one pickle, one slice of swiss cheese, one slice of salami, one lollypop, one piece of cherry pie, one sausage, one cupcake, and one slice of watermelon";
+        let description_hash: [u8; 32] = Sha256::digest(description.as_bytes()).into();
+        assert_eq!(bolt11.description_hash, Some(description_hash));
+        let payment_hash: [u8; 32] =
+            hex::decode("0001020304050607080900010203040506070809000102030405060708090102")
+                .unwrap()
+                .try_into()
+                .unwrap();
+        assert_eq!(bolt11.payment_hash, Some(payment_hash));
+
+        let bolt11 = decode_bolt11(
+            "lnbc2500u1pvjluezsp5zyg3zyg3zyg3zyg3zyg3zyg3zyg3zyg3zyg3zyg3zyg3zyg3zygspp5qqqsyqcyq5rqwzqfqqqsyqcyq5rqwzqfqqqsyqcyq5rqwzqfqypqdq5xysxxatsyp3k7enxv4jsxqzpu9qrsgquk0rl77nj30yxdy8j9vdx85fkpmdla2087ne0xh8nhedh8w27kyke0lp53ut353s06fv3qfegext0eh0ymjpf39tuven09sam30g4vgpfna3rh",
+        )
+        .unwrap();
+        assert_eq!(bolt11.amount.map(|a| a.0), Some(250_000_000));
+        assert_eq!(bolt11.description_hash, None);
+
+        // Bad checksum
+        assert!(decode_bolt11(
+            "lnbc2500u1pvjluezsp5zyg3zyg3zyg3zyg3zyg3zyg3zyg3zyg3zyg3zyg3zyg3zyg3zygspp5qqqsyqcyq5rqwzqfqqqsyqcyq5rqwzqfqqqsyqcyq5rqwzqfqypqdq5xysxxatsyp3k7enxv4jsxqzpu9qrsgquk0rl77nj30yxdy8j9vdx85fkpmdla2087ne0xh8nhedh8w27kyke0lp53ut353s06fv3qfegext0eh0ymjpf39tuven09sam30g4vgpfna3rq",
+        )
+        .is_none());
+    }
+
+    #[test]
+    fn test_check_zap_invoice() {
+        // The BOLT-11 example commits to this description as if it were a zap request
+        let invoice = "lnbc20m1pvjluezpp5qqqsyqcyq5rqwzqfqqqsyqcyq5rqwzqfqqqsyqcyq5rqwzqfqypqhp58yjmdan79s6qqdhdzgynm4zwqd5d7xmw5fk98klysy043l2ahrqscc6gd6ql3jrc5yzme8v4ntcewwz5cnw92tz0pc8qcuufvq7khhr8wpald05e92xw006sq94mg8v2ndf4sefvf9sygkshp5zfem29trqq2yxxz7";
+        let request = "One piece of chocolate cake, one icecream cone, one pickle, one slice of swiss cheese, one slice of salami, one lollypop, one piece of cherry pie, one sausage, one cupcake, and one slice of watermelon";
+
+        let payment_hash =
+            check_zap_invoice(invoice, MilliSatoshi(2_000_000_000), request).unwrap();
+        assert_eq!(invoice_payment_hash(invoice), Some(payment_hash));
+
+        assert_eq!(
+            check_zap_invoice(invoice, MilliSatoshi(21_000), request),
+            Err(InvalidZap::AmountMismatch)
+        );
+        assert_eq!(
+            check_zap_invoice(invoice, MilliSatoshi(2_000_000_000), "{}"),
+            Err(InvalidZap::DescriptionHashMismatch)
+        );
+        assert_eq!(
+            check_zap_invoice("lnbc1garbage", MilliSatoshi(2_000_000_000), request),
+            Err(InvalidZap::BadInvoice)
+        );
+    }
+
+    #[test]
+    fn test_lnurl_to_url() {
+        // From LUD-01
+        assert_eq!(
+            lnurl_to_url("LNURL1DP68GURN8GHJ7UM9WFMXJCM99E3K7MF0V9CXJ0M385EKVCENXC6R2C35XVUKXEFCV5MKVV34X5EKZD3EV56NYD3HXQURZEPEXEJXXEPNXSCRVWFNV9NXZCN9XQ6XYEFHVGCXXCMYXYMNSERXFQ5FNS"),
+            Some("https://service.com/api?q=3fc3645b439ce8e7f2553a69e5267081d96dcd340693afabe04be7b0ccd178df".to_owned())
+        );
+        assert_eq!(
+            lnurl_to_url("https://example.com/lnurlp/alice"),
+            Some("https://example.com/lnurlp/alice".to_owned())
+        );
+    }
+
+    #[test]
+    fn test_parse_bolt11_amount() {
+        assert_eq!(parse_bolt11_amount("1").map(|a| a.0), Some(100_000_000_000));
+        assert_eq!(parse_bolt11_amount("20m").map(|a| a.0), Some(2_000_000_000));
+        assert_eq!(parse_bolt11_amount("2500u").map(|a| a.0), Some(250_000_000));
+        assert_eq!(parse_bolt11_amount("210n").map(|a| a.0), Some(21_000));
+        assert_eq!(parse_bolt11_amount("10p").map(|a| a.0), Some(1));
+        assert_eq!(parse_bolt11_amount("15p").map(|a| a.0), None);
+        assert_eq!(parse_bolt11_amount("5x").map(|a| a.0), None);
+    }
+}